@@ -1,21 +1,22 @@
 use std::cell::RefCell;
 use std::mem;
+use std::ops::Bound;
 use std::rc::Rc;
 
-use crate::{InsertType, TreeNode};
+use crate::{InsertType, NodeRef, TreeNode};
 
 #[derive(Debug)]
 pub struct CommonNode<K, V> {
     degree: usize,
     keys: Vec<K>,
-    childs: Vec<Rc<RefCell<TreeNode<K, V>>>>,
+    childs: Vec<NodeRef<K, V>>,
 }
 
-impl<K: Ord + Copy, V: Clone> CommonNode<K, V> {
+impl<K: Ord + Clone, V: Clone> CommonNode<K, V> {
     pub fn new(
         degree: usize,
         keys: Option<Vec<K>>,
-        childs: Option<Vec<Rc<RefCell<TreeNode<K, V>>>>>,
+        childs: Option<Vec<NodeRef<K, V>>>,
     ) -> Self {
         let mut new_node = CommonNode {
             degree,
@@ -34,7 +35,7 @@ impl<K: Ord + Copy, V: Clone> CommonNode<K, V> {
         new_node
     }
 
-    fn split(&mut self) -> (K, Rc<RefCell<TreeNode<K, V>>>, Rc<RefCell<TreeNode<K, V>>>) {
+    fn split(&mut self) -> (K, NodeRef<K, V>, NodeRef<K, V>) {
         let median_idx = self.keys.len() / 2;
 
         let right_keys = self.keys.split_off(median_idx + 1); // [median_idx + 1, n]
@@ -55,8 +56,17 @@ impl<K: Ord + Copy, V: Clone> CommonNode<K, V> {
         )
     }
 
+    /// The method returns the index of the child which could contain the key.
+    /// Keys equal to a separator are stored in the right subtree
+    fn get_child_idx(&self, key: &K) -> usize {
+        match self.keys.binary_search(key) {
+            Ok(idx) => idx + 1,
+            Err(idx) => idx,
+        }
+    }
+
     pub fn insert(&mut self, new_key: &K, new_value: &V) -> InsertType<K, V> {
-        let new_key_idx = self.keys.binary_search(new_key).unwrap_or_else(|idx| idx);
+        let new_key_idx = self.get_child_idx(new_key);
 
         let insert_result = match *self.childs[new_key_idx].borrow_mut() {
             TreeNode::Common(ref mut borrowed_common_child) => {
@@ -110,11 +120,37 @@ impl<K: Ord + Copy, V: Clone> CommonNode<K, V> {
         }
     }
 
+    /// The method removes the key from the leaf level without rebalancing the tree.
+    /// The separators stay valid, so the search keeps working on underfilled leaves
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let child_idx = self.get_child_idx(key);
+
+        match *self.childs[child_idx].borrow_mut() {
+            TreeNode::Common(ref mut borrowed_common_child) => borrowed_common_child.remove(key),
+            TreeNode::Leaf(ref mut borrowed_leaf_child) => borrowed_leaf_child.remove(key),
+        }
+    }
+
+    /// The method finds the leaf node where a scan starting from the bound should begin
+    pub fn find_leaf(&self, start_bound: Bound<&K>) -> NodeRef<K, V> {
+        let child_idx = match start_bound {
+            Bound::Included(key) | Bound::Excluded(key) => self.get_child_idx(key),
+            Bound::Unbounded => 0,
+        };
+
+        let child_ref = &self.childs[child_idx];
+
+        match *child_ref.borrow() {
+            TreeNode::Common(ref common_child) => common_child.find_leaf(start_bound),
+            TreeNode::Leaf(_) => Rc::clone(child_ref),
+        }
+    }
+
     pub fn get_keys(&self) -> &Vec<K> {
         &self.keys
     }
 
-    pub fn get_childs(&self) -> &Vec<Rc<RefCell<TreeNode<K, V>>>> {
+    pub fn get_childs(&self) -> &Vec<NodeRef<K, V>> {
         &self.childs
     }
 }
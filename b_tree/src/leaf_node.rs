@@ -1,19 +1,20 @@
 use std::cell::RefCell;
 use std::mem;
+use std::ops::{Bound, RangeBounds};
 use std::rc::Rc;
 
-use crate::{InsertType, TreeNode};
+use crate::{InsertType, NodeRef, TreeNode};
 
 #[derive(Debug)]
 pub struct LeafNode<K, V> {
     degree: usize,
     keys: Vec<K>,
     values: Vec<V>,
-    left_node: Option<Rc<RefCell<TreeNode<K, V>>>>,
-    right_node: Option<Rc<RefCell<TreeNode<K, V>>>>,
+    left_node: Option<NodeRef<K, V>>,
+    right_node: Option<NodeRef<K, V>>,
 }
 
-impl<K: Ord + Copy, V: Clone> LeafNode<K, V> {
+impl<K: Ord + Clone, V: Clone> LeafNode<K, V> {
     pub fn new(degree: usize, keys: Option<Vec<K>>, values: Option<Vec<V>>) -> Self {
         let mut new_node = LeafNode {
             degree,
@@ -34,11 +35,11 @@ impl<K: Ord + Copy, V: Clone> LeafNode<K, V> {
         new_node
     }
 
-    fn split(&mut self) -> (K, Rc<RefCell<TreeNode<K, V>>>, Rc<RefCell<TreeNode<K, V>>>) {
+    fn split(&mut self) -> (K, NodeRef<K, V>, NodeRef<K, V>) {
         let cur_num_values = self.values.len();
 
         let median_idx = cur_num_values / 2;
-        let median_key = self.keys[median_idx];
+        let median_key = self.keys[median_idx].clone();
 
         let right_keys = self.keys.split_off(median_idx);
         let right_values = self.values.split_off(median_idx);
@@ -79,8 +80,17 @@ impl<K: Ord + Copy, V: Clone> LeafNode<K, V> {
     }
 
     pub fn insert(&mut self, new_key: &K, new_value: &V) -> InsertType<K, V> {
-        let new_key_pos = self.keys.binary_search(new_key).unwrap_or_else(|idx| idx);
-        self.keys.insert(new_key_pos, *new_key);
+        let new_key_pos = match self.keys.binary_search(new_key) {
+            Ok(existing_key_pos) => {
+                // The key is already stored, so only the value is replaced
+                self.values[existing_key_pos] = new_value.clone();
+
+                return InsertType::Normal;
+            }
+            Err(new_key_pos) => new_key_pos,
+        };
+
+        self.keys.insert(new_key_pos, new_key.clone());
         self.values.insert(new_key_pos, new_value.clone());
 
         let values_len = self.values.len();
@@ -119,6 +129,44 @@ impl<K: Ord + Copy, V: Clone> LeafNode<K, V> {
         InsertType::Split(median_key, new_left_leaf_ref, new_right_leaf_ref)
     }
 
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let key_pos = self.keys.binary_search(key).ok()?;
+
+        self.keys.remove(key_pos);
+
+        Some(self.values.remove(key_pos))
+    }
+
+    /// The method copies the pairs from the range to the result.
+    /// Returns false if the end of the range was reached inside the node
+    pub fn collect_range<R: RangeBounds<K>>(&self, range: &R, result: &mut Vec<(K, V)>) -> bool {
+        let start_pos = match range.start_bound() {
+            Bound::Included(key) => self.keys.partition_point(|cur_key| cur_key < key),
+            Bound::Excluded(key) => self.keys.partition_point(|cur_key| cur_key <= key),
+            Bound::Unbounded => 0,
+        };
+
+        for (key, value) in self.keys.iter().zip(self.values.iter()).skip(start_pos) {
+            let is_in_range = match range.end_bound() {
+                Bound::Included(end_key) => key <= end_key,
+                Bound::Excluded(end_key) => key < end_key,
+                Bound::Unbounded => true,
+            };
+
+            if !is_in_range {
+                return false;
+            }
+
+            result.push((key.clone(), value.clone()));
+        }
+
+        true
+    }
+
+    pub fn get_right_node(&self) -> Option<NodeRef<K, V>> {
+        self.right_node.as_ref().map(Rc::clone)
+    }
+
     pub fn get_keys(&self) -> &Vec<K> {
         &self.keys
     }
@@ -3,9 +3,11 @@ mod errors;
 mod leaf_node;
 mod types;
 
+use std::ops::RangeBounds;
+
 use common_node::CommonNode;
 use leaf_node::LeafNode;
-use types::{InsertType, TreeNode};
+use types::{InsertType, NodeRef, TreeNode};
 
 pub use errors::BTreeCreateError;
 
@@ -16,7 +18,7 @@ pub struct BTree<K, V> {
     root: TreeNode<K, V>,
 }
 
-impl<K: Ord + Copy, V: Clone> BTree<K, V> {
+impl<K: Ord + Clone, V: Clone> BTree<K, V> {
     pub fn new(leaf_degree: usize, common_degree: usize) -> Result<Self, BTreeCreateError> {
         if leaf_degree == 0 {
             return Err(BTreeCreateError::ZeroLeafNodeDegree);
@@ -40,7 +42,7 @@ impl<K: Ord + Copy, V: Clone> BTree<K, V> {
         };
 
         match insert_result {
-            InsertType::Normal => return,
+            InsertType::Normal => {}
             InsertType::Split(new_median, new_left_ref, new_right_ref) => {
                 self.root = TreeNode::Common(CommonNode::new(
                     self.common_degree,
@@ -58,7 +60,7 @@ impl<K: Ord + Copy, V: Clone> BTree<K, V> {
             TreeNode::Leaf(leaf_node) => leaf_node.get_keys(),
         };
 
-        if keys.len() == 0 {
+        if keys.is_empty() {
             return None;
         }
 
@@ -87,6 +89,43 @@ impl<K: Ord + Copy, V: Clone> BTree<K, V> {
         self.find_value(&self.root, key)
     }
 
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        match self.root {
+            TreeNode::Leaf(ref mut leaf_node) => leaf_node.remove(key),
+            TreeNode::Common(ref mut common_node) => common_node.remove(key),
+        }
+    }
+
+    /// The method returns all pairs with keys from the range in the ascending order
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Vec<(K, V)> {
+        let mut result = vec![];
+
+        let mut next_leaf = match self.root {
+            TreeNode::Leaf(ref leaf_node) => {
+                leaf_node.collect_range(&range, &mut result);
+
+                return result;
+            }
+            TreeNode::Common(ref common_node) => Some(common_node.find_leaf(range.start_bound())),
+        };
+
+        // Walk through the linked leaves until the end of the range is reached
+        while let Some(leaf_ref) = next_leaf {
+            next_leaf = match *leaf_ref.borrow() {
+                TreeNode::Leaf(ref leaf_node) => {
+                    if leaf_node.collect_range(&range, &mut result) {
+                        leaf_node.get_right_node()
+                    } else {
+                        None
+                    }
+                }
+                TreeNode::Common(_) => unreachable!("Only leaf nodes are linked together"),
+            };
+        }
+
+        result
+    }
+
     pub fn get_settings(&self) -> (usize, usize) {
         (self.common_degree, self.leaf_degree)
     }
@@ -161,6 +200,117 @@ mod tests {
         }
     }
 
+    mod tree_remove {
+        use super::*;
+
+        #[test]
+        fn it_should_remove_value_from_leaf_root() {
+            let mut b_tree: BTree<i32, i32> = BTree::new(2, 3).unwrap();
+
+            b_tree.insert(&10, &110);
+            b_tree.insert(&20, &120);
+
+            assert_eq!(b_tree.remove(&10), Some(110));
+            assert_eq!(b_tree.remove(&10), None);
+            assert_eq!(b_tree.search(&10), None);
+            assert_eq!(b_tree.search(&20), Some(120));
+        }
+
+        #[test]
+        fn it_should_find_reinserted_separator_key() {
+            let mut b_tree: BTree<i32, i32> = BTree::new(2, 3).unwrap();
+
+            for key in 1..=10 {
+                b_tree.insert(&key, &(key + 100));
+            }
+
+            for key in 1..=10 {
+                assert_eq!(b_tree.remove(&key), Some(key + 100));
+                assert_eq!(b_tree.search(&key), None);
+            }
+
+            for key in 1..=10 {
+                b_tree.insert(&key, &(key + 200));
+            }
+
+            for key in 1..=10 {
+                assert_eq!(b_tree.search(&key), Some(key + 200));
+            }
+        }
+
+        #[test]
+        fn it_should_replace_value_of_existing_key() {
+            let mut b_tree: BTree<i32, i32> = BTree::new(2, 3).unwrap();
+
+            b_tree.insert(&10, &110);
+            b_tree.insert(&10, &210);
+
+            assert_eq!(b_tree.range(..), vec![(10, 210)]);
+        }
+    }
+
+    mod tree_range {
+        use super::*;
+
+        #[test]
+        fn it_should_return_empty_range_for_empty_tree() {
+            let b_tree: BTree<i32, i32> = BTree::new(2, 3).unwrap();
+
+            assert_eq!(b_tree.range(..), vec![]);
+        }
+
+        #[test]
+        fn it_should_return_values_in_ascending_order() {
+            let mut b_tree: BTree<i32, i32> = BTree::new(2, 3).unwrap();
+
+            for key in [10, 20, 5, 6, 12, 30, 7, 4] {
+                b_tree.insert(&key, &(key + 100));
+            }
+
+            let keys: Vec<i32> = b_tree.range(..).into_iter().map(|(key, _)| key).collect();
+            assert_eq!(keys, vec![4, 5, 6, 7, 10, 12, 20, 30]);
+
+            assert_eq!(b_tree.range(6..12), vec![(6, 106), (7, 107), (10, 110)]);
+            assert_eq!(b_tree.range(11..=20), vec![(12, 112), (20, 120)]);
+            assert_eq!(b_tree.range(..5), vec![(4, 104)]);
+            assert_eq!(b_tree.range(21..), vec![(30, 130)]);
+        }
+
+        #[test]
+        fn it_should_skip_removed_values() {
+            let mut b_tree: BTree<i32, i32> = BTree::new(2, 2).unwrap();
+
+            for key in 1..=20 {
+                b_tree.insert(&key, &key);
+            }
+
+            for key in (1..=20).filter(|key| key % 3 != 0) {
+                b_tree.remove(&key);
+            }
+
+            let keys: Vec<i32> = b_tree.range(2..).into_iter().map(|(key, _)| key).collect();
+            assert_eq!(keys, vec![3, 6, 9, 12, 15, 18]);
+        }
+
+        #[test]
+        fn it_should_support_not_copyable_keys() {
+            let mut b_tree: BTree<String, u32> = BTree::new(2, 3).unwrap();
+
+            for (idx, name) in ["delta", "alpha", "charlie", "bravo", "echo"].iter().enumerate() {
+                b_tree.insert(&name.to_string(), &(idx as u32));
+            }
+
+            assert_eq!(b_tree.search(&"charlie".to_string()), Some(2));
+
+            let keys: Vec<String> = b_tree
+                .range("b".to_string().."d".to_string())
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect();
+            assert_eq!(keys, vec!["bravo", "charlie"]);
+        }
+    }
+
     mod tree_topology {
         use super::*;
 
@@ -3,6 +3,8 @@ use crate::leaf_node::LeafNode;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+pub type NodeRef<K, V> = Rc<RefCell<TreeNode<K, V>>>;
+
 #[derive(Debug)]
 pub enum TreeNode<K, V> {
     Common(CommonNode<K, V>),
@@ -16,7 +18,7 @@ pub enum InsertType<K, V> {
     // First value is a new median
     // Second is a left new node
     // Third is a right new node
-    Split(K, Rc<RefCell<TreeNode<K, V>>>, Rc<RefCell<TreeNode<K, V>>>),
+    Split(K, NodeRef<K, V>, NodeRef<K, V>),
     // We put the value into the leaf node,
     // it got full,
     // so we also updated the minimum value of the current leaf node and
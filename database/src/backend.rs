@@ -1,14 +1,23 @@
+mod database;
 mod table;
 mod pager;
 mod cursor;
+mod foreign_key;
+mod sorter;
+mod operator;
+mod query;
+mod rename;
+mod trigger;
+mod view;
+mod vm;
 
 use crate::backend::pager::Pager;
-pub use crate::backend::table::Table;
+pub use crate::backend::database::Database;
 
-pub fn db_open(file_name: Option<&str>) -> Table {
+pub fn db_open(file_name: Option<&str>) -> Database {
     let pager = Pager::new(
-        file_name.unwrap_or_else(|| "./database"),
+        file_name.unwrap_or("./database"),
     );
 
-    Table::new(pager)
+    Database::new(pager)
 }
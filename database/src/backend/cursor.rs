@@ -1,70 +1,126 @@
 use std::io;
 use super::pager::Pager;
+use super::pager::page::Page;
+use super::pager::page::row_data::RowData;
 
 pub struct Cursor<'pager_lifetime> {
     pager: &'pager_lifetime Pager,
+    table_id: u32,
     page_num: usize,
     cell_num: usize,
+    // The cursor points to the position after the last row or before the first one
     end_of_table: bool,
 }
 
 pub enum CursorPosition {
-    START,
-    POS(usize),
-    END,
+    Start,
+    End,
 }
 
-impl Cursor{
-    pub fn new(pager: &Pager, cursor_position: CursorPosition) -> io::Result<Self> {
+impl<'pager_lifetime> Cursor<'pager_lifetime> {
+    pub fn new(pager: &'pager_lifetime Pager, table_id: u32, cursor_position: CursorPosition) -> io::Result<Self> {
         match cursor_position {
-            CursorPosition::START => Ok(Cursor {
-                pager,
-                row_num: 0,
-                end_of_table: false,
-            }),
-            CursorPosition::POS(row_num) => {
-                let total_rows_number = pager.get_total_num_rows();
-
-                if row_num >= total_rows_number {
-                    Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "The row number is too big",
-                    ))
-                } else {
-                    Ok(Cursor {
-                        pager,
-                        row_num,
-                        end_of_table: row_num == total_rows_number - 1,
-                    })
-                }
-            },
-            CursorPosition::END => {
-                let total_rows_number = pager.get_total_num_rows();
+            CursorPosition::Start => {
+                let mut cursor = Cursor {
+                    pager,
+                    table_id,
+                    page_num: 0,
+                    cell_num: 0,
+                    end_of_table: false,
+                };
+                cursor.skip_empty_pages();
 
-                Ok(Cursor {
+                Ok(cursor)
+            },
+            CursorPosition::End => {
+                let mut cursor = Cursor {
                     pager,
-                    row_num: total_rows_number - 1,
-                    end_of_table: true,
-                })
+                    table_id,
+                    page_num: pager.get_num_pages(table_id),
+                    cell_num: 0,
+                    end_of_table: false,
+                };
+                cursor.move_to_previous_row();
+
+                Ok(cursor)
             },
         }
     }
 
-    pub fn cursor_advance(&mut self) -> io::Result<usize> {
-        let total_rows_number = self.pager.get_total_num_rows();
+    /// The method places the cursor at the row with the id, there is no cursor if there is no such row
+    pub fn seek(pager: &'pager_lifetime Pager, table_id: u32, row_id: i64) -> Option<Self> {
+        let (page_num, cell_num) = pager.find_row(table_id, row_id)?;
+
+        Some(Cursor {
+            pager,
+            table_id,
+            page_num,
+            cell_num,
+            end_of_table: false,
+        })
+    }
+
+    /// The method moves the cursor to the next non-empty page if the current one is over
+    fn skip_empty_pages(&mut self) {
+        while let Some(page) = self.pager.get_page(self.table_id, self.page_num) {
+            if self.cell_num < page.get_num_rows() {
+                return;
+            }
+
+            self.page_num += 1;
+            self.cell_num = 0;
+        }
+
+        self.end_of_table = true;
+    }
+
+    /// The method moves the cursor to the row before the current position, the empty pages are skipped
+    fn move_to_previous_row(&mut self) {
+        while self.cell_num == 0 {
+            if self.page_num == 0 {
+                self.end_of_table = true;
+                return;
+            }
+
+            self.page_num -= 1;
+            self.cell_num = self.pager.get_page(self.table_id, self.page_num).map_or(0, Page::get_num_rows);
+        }
+
+        self.cell_num -= 1;
+    }
+
+    pub fn get_row(&self) -> Option<RowData> {
+        if self.end_of_table {
+            return None;
+        }
 
-        // If we already at the end
-        // Also handling the case when the total rows number was increased after we got to the end
-        if (self.end_of_table && self.row_num >= total_rows_number - 1) {
+        self.pager.get_row(self.table_id, self.page_num, self.cell_num)
+    }
+
+    pub fn cursor_advance(&mut self) -> io::Result<()> {
+        if self.end_of_table {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "The cursor is already at the end of table",
             ));
         }
 
-        self.row_num += 1;
-        self.end_of_table = self.row_num == total_rows_number - 1;
+        self.cell_num += 1;
+        self.skip_empty_pages();
+
+        Ok(())
+    }
+
+    pub fn cursor_retreat(&mut self) -> io::Result<()> {
+        if self.end_of_table {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The cursor is already at the start of table",
+            ));
+        }
+
+        self.move_to_previous_row();
 
-        Ok(self.row_num)
+        Ok(())
     }
 }
@@ -0,0 +1,3432 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+use std::{io, process};
+use super::foreign_key::{constraint_error, get_key_values, key_condition, mismatch_error};
+use super::pager::Pager;
+use super::pager::journal::JournalMode;
+use super::pager::page::row_data::RowData;
+use super::table::{Table, ROW_ID_COLUMN};
+use super::table::index::Index;
+use super::table::statistics::{ColumnStatistics, TableStatistics};
+use super::operator::Source;
+use super::query::{conjoin, evaluate_constant, explain_table_scan, find_table, get_result_columns, Query};
+use super::query::subquery::with_query;
+use super::rename::Rename;
+use super::trigger::TriggerRows;
+use super::view::expand_views;
+use super::vm::{Program, VirtualMachine};
+use super::vm::compiler::Compiler;
+use crate::statement::{
+    Statement,
+    create_table_statement::{ColumnDefinition, CreateTableStatement, ForeignKey, ForeignKeyAction, TableConstraint},
+    drop_table_statement::DropTableStatement,
+    alter_table_statement::{AlterTableAction, AlterTableStatement},
+    create_index_statement::CreateIndexStatement,
+    drop_index_statement::DropIndexStatement,
+    create_view_statement::CreateViewStatement,
+    drop_view_statement::DropViewStatement,
+    create_trigger_statement::{CreateTriggerStatement, TriggerEvent, TriggerTiming},
+    drop_trigger_statement::DropTriggerStatement,
+    analyze_statement::AnalyzeStatement,
+    pragma_statement::PragmaStatement,
+    explain_statement::{ExplainMode, ExplainStatement},
+    transaction_statement::TransactionStatement,
+    expression::{BinaryOperator, Expression},
+    select_statement::{ResultColumn, SelectStatement},
+    insert_statement::{ConflictResolution, InsertSource, InsertStatement, Upsert, UpsertAction},
+    update_statement::UpdateStatement,
+    delete_statement::DeleteStatement,
+};
+use crate::enums::Statements;
+use crate::parser::ParsedStatement;
+use crate::parser::token_stream::TokenStream;
+use crate::session::Session;
+use crate::value::Value;
+
+/// The catalog table which stores the SQL of all the tables, indexes, views and triggers.
+/// The views have no table, so their table id is the id of the catalog table
+const SCHEMA_TABLE_ID: u32 = 0;
+const SCHEMA_TABLE_SQL: &str = "CREATE TABLE rustqlight_schema (\
+    type TEXT NOT NULL, name TEXT NOT NULL, tbl_name TEXT NOT NULL, table_id INTEGER NOT NULL, sql TEXT NOT NULL\
+)";
+
+/// The table with the biggest used row ids of the AUTOINCREMENT tables.
+/// It's created together with the first AUTOINCREMENT table
+const SEQUENCE_TABLE_NAME: &str = "rustqlight_sequence";
+const SEQUENCE_TABLE_SQL: &str = "CREATE TABLE rustqlight_sequence (name TEXT NOT NULL, seq INTEGER NOT NULL)";
+
+/// The table with the statistics gathered by ANALYZE. The row without the column
+/// has the number of the rows of the table. It's created by the first ANALYZE
+const STAT_TABLE_NAME: &str = "rustqlight_stat";
+const STAT_TABLE_SQL: &str = "CREATE TABLE rustqlight_stat (\
+    tbl TEXT NOT NULL, col TEXT, nrow INTEGER, ndistinct INTEGER, nnull INTEGER, histogram TEXT\
+)";
+
+/// The number of the prepared programs kept by the database
+const MAX_PROGRAMS: usize = 100;
+
+/// The names with this prefix are used by the database itself
+const INTERNAL_NAME_PREFIX: &str = "rustqlight_";
+
+pub struct Database {
+    pager: Pager,
+    // The schema table is always the first one
+    tables: Vec<Table>,
+    views: Vec<CreateViewStatement>,
+    triggers: Vec<CreateTriggerStatement>,
+    // The triggers which are running, they are not fired again by their own statements
+    firing_triggers: Vec<String>,
+    // The deferred foreign keys of the child tables which have to be checked at the end of the statement,
+    // or at the end of the transaction if there is one
+    deferred_foreign_keys: Vec<(String, ForeignKey)>,
+    // The names of the savepoints of the transaction from the outermost one, BEGIN starts the unnamed one
+    savepoints: Vec<Option<String>>,
+    // The compiled programs by the SQL of their statements, they are dropped when the schema changes
+    programs: BTreeMap<String, Rc<Program>>,
+    session: Session,
+}
+
+impl Database {
+    pub fn new(pager: Pager) -> Self {
+        let schema_table = Self::create_schema_table(&pager).expect("The schema table definition is invalid");
+
+        let mut database = Database {
+            pager,
+            tables: vec![schema_table],
+            views: Vec::new(),
+            triggers: Vec::new(),
+            firing_triggers: Vec::new(),
+            deferred_foreign_keys: Vec::new(),
+            savepoints: Vec::new(),
+            programs: BTreeMap::new(),
+            session: Session::default(),
+        };
+
+        if let Err(err) = database.load_schema() {
+            println!("Unable to load the schema: {}", err);
+        }
+
+        database
+    }
+
+    fn create_schema_table(pager: &Pager) -> io::Result<Table> {
+        let mut tokens = TokenStream::new(SCHEMA_TABLE_SQL)?;
+
+        Table::new(SCHEMA_TABLE_ID, CreateTableStatement::new(&mut tokens)?, pager)
+    }
+
+    /// The tables, the indexes, the views and the triggers are rebuilt from the SQL stored in the schema table.
+    /// The tables go first, since the indexes refer to them
+    fn load_schema(&mut self) -> io::Result<()> {
+        let schema_rows = self.tables[0].get_all_rows(&self.pager)?;
+        let mut index_sqls = Vec::new();
+
+        for row_data in schema_rows {
+            let [Value::Text(object_type), _, _, Value::Integer(table_id), Value::Text(sql)] = row_data.values.as_slice() else {
+                println!("The schema row {} is corrupted", row_data.id);
+                continue;
+            };
+
+            if object_type == "index" {
+                index_sqls.push(sql.clone());
+                continue;
+            }
+
+            if object_type == "view" {
+                match TokenStream::new(sql).and_then(|mut tokens| CreateViewStatement::new(&mut tokens)) {
+                    Ok(view) => self.views.push(view),
+                    Err(err) => println!("Unable to restore the view {}: {}", sql, err),
+                }
+
+                continue;
+            }
+
+            if object_type == "trigger" {
+                match TokenStream::new(sql).and_then(|mut tokens| CreateTriggerStatement::new(&mut tokens)) {
+                    Ok(trigger) => self.triggers.push(trigger),
+                    Err(err) => println!("Unable to restore the trigger {}: {}", sql, err),
+                }
+
+                continue;
+            }
+
+            let restored_table = TokenStream::new(sql)
+                .and_then(|mut tokens| CreateTableStatement::new(&mut tokens))
+                .and_then(|definition| Table::new(*table_id as u32, definition, &self.pager));
+
+            match restored_table {
+                Ok(table) => {
+                    let table_name = table.get_name().to_string();
+                    self.tables.push(table);
+                    self.set_default_values(&table_name)?;
+                },
+                Err(err) => println!("Unable to restore the table {}: {}", sql, err),
+            }
+        }
+
+        for index_sql in index_sqls {
+            let restored_index = TokenStream::new(&index_sql)
+                .and_then(|mut tokens| CreateIndexStatement::new(&mut tokens))
+                .and_then(|statement| {
+                    let table = Self::find_table_mut(&mut self.tables, &statement.table_name)?;
+                    table.create_index(&self.pager, &statement)
+                });
+
+            if let Err(err) = restored_index {
+                println!("Unable to restore the index {}: {}", index_sql, err);
+            }
+        }
+
+        self.load_sequences()?;
+        self.load_statistics()
+    }
+
+    fn load_sequences(&mut self) -> io::Result<()> {
+        let Ok(sequence_table) = Self::find_table_mut(&mut self.tables, SEQUENCE_TABLE_NAME) else {
+            return Ok(());
+        };
+
+        for row_data in sequence_table.get_all_rows(&self.pager)? {
+            if let [Value::Text(table_name), Value::Integer(sequence)] = row_data.values.as_slice() {
+                if let Ok(table) = Self::find_table_mut(&mut self.tables, table_name) {
+                    table.set_sequence(*sequence);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_statistics(&mut self) -> io::Result<()> {
+        let Ok(stat_table) = Self::find_table_mut(&mut self.tables, STAT_TABLE_NAME) else {
+            return Ok(());
+        };
+
+        let mut statistics: BTreeMap<String, TableStatistics> = BTreeMap::new();
+
+        for row_data in stat_table.get_all_rows(&self.pager)? {
+            match row_data.values.as_slice() {
+                [Value::Text(table_name), Value::Null, Value::Integer(row_count), ..] => {
+                    statistics.entry(table_name.clone())
+                        .or_insert_with(|| TableStatistics { row_count: 0, columns: BTreeMap::new() })
+                        .row_count = *row_count as usize;
+                },
+                [Value::Text(table_name), Value::Text(column_name), _, Value::Integer(distinct_count), Value::Integer(null_count), Value::Text(histogram)] => {
+                    let Ok(histogram) = ColumnStatistics::parse_histogram(histogram) else {
+                        println!("The histogram of the column {}.{} is corrupted", table_name, column_name);
+                        continue;
+                    };
+
+                    statistics.entry(table_name.clone())
+                        .or_insert_with(|| TableStatistics { row_count: 0, columns: BTreeMap::new() })
+                        .columns
+                        .insert(column_name.clone(), ColumnStatistics {
+                            distinct_count: *distinct_count as usize,
+                            null_count: *null_count as usize,
+                            histogram,
+                        });
+                },
+                _ => println!("The statistics row {} is corrupted", row_data.id),
+            }
+        }
+
+        for (table_name, table_statistics) in statistics {
+            if let Ok(table) = Self::find_table_mut(&mut self.tables, &table_name) {
+                table.set_statistics(table_statistics);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The method stores the biggest used row id of the AUTOINCREMENT table
+    fn save_sequence(&mut self, table_name: &str) -> io::Result<()> {
+        let Some(sequence) = Self::find_table_mut(&mut self.tables, table_name)?.get_sequence() else {
+            return Ok(());
+        };
+
+        let sequence_table = Self::find_table_mut(&mut self.tables, SEQUENCE_TABLE_NAME)?;
+
+        sequence_table.delete(&mut self.pager, &self.session, &Some(Self::name_condition("name", table_name)))?;
+        sequence_table.insert_row(
+            &mut self.pager,
+            &self.session,
+            Value::Null,
+            vec![Value::Text(table_name.to_string()), Value::Integer(sequence)],
+        )?;
+
+        Ok(())
+    }
+
+    /// The condition selecting the rows of the catalog table which belong to the table
+    fn name_condition(column_name: &str, table_name: &str) -> Expression {
+        Expression::Binary(
+            Box::new(Expression::Column {
+                table_name: None,
+                column_name: column_name.to_string(),
+            }),
+            BinaryOperator::Equal,
+            Box::new(Expression::Literal(Value::Text(table_name.to_string()))),
+        )
+    }
+
+    /// The method rewrites the schema table with the current tables, indexes, views and triggers
+    fn save_schema(&mut self) -> io::Result<()> {
+        self.pager.drop_table(SCHEMA_TABLE_ID);
+        self.tables[0] = Self::create_schema_table(&self.pager)?;
+
+        let mut schema_rows = Vec::new();
+
+        for table in self.tables[1..].iter() {
+            schema_rows.push(vec![
+                Value::Text("table".to_string()),
+                Value::Text(table.get_name().to_string()),
+                Value::Text(table.get_name().to_string()),
+                Value::Integer(table.get_id() as i64),
+                Value::Text(table.get_definition().to_sql()),
+            ]);
+
+            // The indexes of the constraints are created together with the table
+            for index in table.get_indexes().iter().filter(|index| !index.is_auto_index()) {
+                schema_rows.push(vec![
+                    Value::Text("index".to_string()),
+                    Value::Text(index.get_name().to_string()),
+                    Value::Text(table.get_name().to_string()),
+                    Value::Integer(table.get_id() as i64),
+                    Value::Text(index.to_sql()),
+                ]);
+            }
+        }
+
+        for view in self.views.iter() {
+            schema_rows.push(vec![
+                Value::Text("view".to_string()),
+                Value::Text(view.view_name.clone()),
+                Value::Text(view.view_name.clone()),
+                Value::Integer(SCHEMA_TABLE_ID as i64),
+                Value::Text(view.to_sql()),
+            ]);
+        }
+
+        for trigger in self.triggers.iter() {
+            let table_id = find_table(&self.tables, &trigger.table_name).map_or(SCHEMA_TABLE_ID, Table::get_id);
+
+            schema_rows.push(vec![
+                Value::Text("trigger".to_string()),
+                Value::Text(trigger.trigger_name.clone()),
+                Value::Text(trigger.table_name.clone()),
+                Value::Integer(table_id as i64),
+                Value::Text(trigger.to_sql()),
+            ]);
+        }
+
+        for values in schema_rows {
+            self.tables[0].insert_row(&mut self.pager, &self.session, Value::Null, values)?;
+        }
+
+        Ok(())
+    }
+
+    fn find_table_mut<'a>(tables: &'a mut [Table], table_name: &str) -> io::Result<&'a mut Table> {
+        tables
+            .iter_mut()
+            .find(|table| table.get_name() == table_name)
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No such table: {}", table_name),
+            ))
+    }
+
+    /// The method returns the table which could be changed by the user statements
+    fn get_writable_table<'a>(tables: &'a mut [Table], table_name: &str) -> io::Result<&'a mut Table> {
+        let table = Self::find_table_mut(tables, table_name)?;
+
+        if table.get_id() == SCHEMA_TABLE_ID {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("The table {} may not be modified", table_name),
+            ));
+        }
+
+        Ok(table)
+    }
+
+    /// Tables, indexes, views and triggers share the same namespace
+    fn check_new_name(&self, name: &str) -> io::Result<()> {
+        if name.starts_with(INTERNAL_NAME_PREFIX) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The name {} is reserved for internal use", name),
+            ));
+        }
+
+        if self.tables.iter().any(|table| table.get_name() == name) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("The table {} already exists", name),
+            ));
+        }
+
+        if self.tables.iter().any(|table| table.find_index(name).is_some()) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("The index {} already exists", name),
+            ));
+        }
+
+        if self.find_view(name).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("The view {} already exists", name),
+            ));
+        }
+
+        if self.triggers.iter().any(|trigger| trigger.trigger_name == name) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("The trigger {} already exists", name),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn create_table(&mut self, create_table_statement: &CreateTableStatement) -> io::Result<usize> {
+        if let Err(err) = self.check_new_name(&create_table_statement.table_name) {
+            if create_table_statement.if_not_exists && err.kind() == io::ErrorKind::AlreadyExists {
+                return Ok(0);
+            }
+
+            return Err(err);
+        }
+
+        let table_id = self.add_table(create_table_statement.clone())?;
+
+        let is_autoincrement = self.tables.last().is_some_and(|table| table.get_sequence().is_some());
+
+        if is_autoincrement && Self::find_table_mut(&mut self.tables, SEQUENCE_TABLE_NAME).is_err() {
+            let mut tokens = TokenStream::new(SEQUENCE_TABLE_SQL)?;
+
+            if let Err(err) = self.add_table(CreateTableStatement::new(&mut tokens)?) {
+                self.tables.retain(|table| table.get_id() != table_id);
+                return Err(err);
+            }
+        }
+
+        self.save_schema()?;
+
+        Ok(0)
+    }
+
+    /// The method creates the table with the next free id, returns the id
+    fn add_table(&mut self, create_table_statement: CreateTableStatement) -> io::Result<u32> {
+        let table_id = self.tables.iter().map(Table::get_id).max().unwrap_or(SCHEMA_TABLE_ID) + 1;
+        let table_name = create_table_statement.table_name.clone();
+        let table = Table::new(table_id, create_table_statement, &self.pager)?;
+
+        self.tables.push(table);
+        self.set_default_values(&table_name)?;
+
+        Ok(table_id)
+    }
+
+    fn drop_table(&mut self, drop_table_statement: &DropTableStatement) -> io::Result<usize> {
+        let table_name = &drop_table_statement.table_name;
+
+        if self.find_view(table_name).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Use DROP VIEW to drop the view {}", table_name),
+            ));
+        }
+
+        let table_id = match Self::find_table_mut(&mut self.tables, table_name) {
+            Ok(table) => table.get_id(),
+            Err(err) if drop_table_statement.if_exists && err.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err),
+        };
+
+        if table_name.starts_with(INTERNAL_NAME_PREFIX) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("The table {} may not be dropped", table_name),
+            ));
+        }
+
+        // The rows of the parent table are deleted first, so the actions of the foreign keys are applied
+        if self.get_child_foreign_keys(table_name).iter().any(|(child_table_name, _)| child_table_name != table_name) {
+            self.delete_rows(&DeleteStatement { table_name: table_name.clone(), where_clause: None, returning: Vec::new() })?;
+        }
+
+        self.tables.retain(|table| table.get_id() != table_id);
+        self.triggers.retain(|trigger| trigger.table_name != *table_name);
+        self.pager.drop_table(table_id);
+
+        if let Ok(sequence_table) = Self::find_table_mut(&mut self.tables, SEQUENCE_TABLE_NAME) {
+            sequence_table.delete(&mut self.pager, &self.session, &Some(Self::name_condition("name", table_name)))?;
+        }
+
+        if let Ok(stat_table) = Self::find_table_mut(&mut self.tables, STAT_TABLE_NAME) {
+            stat_table.delete(&mut self.pager, &self.session, &Some(Self::name_condition("tbl", table_name)))?;
+        }
+
+        self.save_schema()?;
+
+        Ok(0)
+    }
+
+    fn create_index(&mut self, create_index_statement: &CreateIndexStatement) -> io::Result<usize> {
+        if let Err(err) = self.check_new_name(&create_index_statement.index_name) {
+            if create_index_statement.if_not_exists && err.kind() == io::ErrorKind::AlreadyExists {
+                return Ok(0);
+            }
+
+            return Err(err);
+        }
+
+        let table = Self::get_writable_table(&mut self.tables, &create_index_statement.table_name)?;
+        table.create_index(&self.pager, create_index_statement)?;
+
+        self.save_schema()?;
+
+        Ok(0)
+    }
+
+    fn drop_index(&mut self, drop_index_statement: &DropIndexStatement) -> io::Result<usize> {
+        let index_name = &drop_index_statement.index_name;
+
+        let table = self.tables.iter_mut().find(|table| table.find_index(index_name).is_some());
+
+        match table {
+            Some(table) => {
+                if table.find_index(index_name).is_some_and(|index| index.is_auto_index()) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        format!("The index {} belongs to a PRIMARY KEY or UNIQUE constraint and can't be dropped", index_name),
+                    ));
+                }
+
+                table.drop_index(index_name);
+                self.save_schema()?;
+
+                Ok(0)
+            },
+            None if drop_index_statement.if_exists => Ok(0),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No such index: {}", index_name),
+            )),
+        }
+    }
+
+    /// The rows written before the columns were added have fewer values, they are completed
+    /// by the defaults of the columns. Only the constant defaults may be used by ADD COLUMN
+    fn set_default_values(&mut self, table_name: &str) -> io::Result<()> {
+        let table = find_table(&self.tables, table_name)?;
+        let table_id = table.get_id();
+
+        let default_values = table.get_definition().columns
+            .iter()
+            .map(|column| match &column.default {
+                Some(default) => evaluate_constant(&self.tables, &self.pager, &self.session, default),
+                None => Ok(Value::Null),
+            })
+            .collect::<io::Result<Vec<Value>>>()?;
+
+        self.pager.set_default_values(table_id, default_values);
+
+        Ok(())
+    }
+
+    /// The method changes the table, the indexes, the views, the triggers and the foreign keys
+    /// referring to the table or to its columns are changed together with it
+    fn alter_table(&mut self, alter_table_statement: &AlterTableStatement) -> io::Result<usize> {
+        let table_name = &alter_table_statement.table_name;
+
+        if self.find_view(table_name).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The view {} can't be altered", table_name),
+            ));
+        }
+
+        let table = find_table(&self.tables, table_name)?;
+
+        if table.get_id() == SCHEMA_TABLE_ID || table_name.starts_with(INTERNAL_NAME_PREFIX) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("The table {} may not be altered", table_name),
+            ));
+        }
+
+        match &alter_table_statement.action {
+            AlterTableAction::RenameTable(new_name) => self.rename_table(table_name, new_name)?,
+            AlterTableAction::RenameColumn { old_name, new_name } => self.rename_column(table_name, old_name, new_name)?,
+            AlterTableAction::AddColumn(column) => self.add_column(table_name, column)?,
+            AlterTableAction::DropColumn(column_name) => self.drop_column(table_name, column_name)?,
+        }
+
+        self.save_schema()?;
+
+        Ok(0)
+    }
+
+    /// The method creates the table again with the changed definition and the indexes, the rows are kept.
+    /// The statistics are dropped since the columns could be changed
+    fn replace_table(&mut self, table_name: &str, definition: CreateTableStatement, indexes: Vec<CreateIndexStatement>) -> io::Result<()> {
+        let table_position = self.tables
+            .iter()
+            .position(|table| table.get_name() == table_name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("No such table: {}", table_name)))?;
+
+        let old_table = &self.tables[table_position];
+        let new_table_name = definition.table_name.clone();
+        let mut table = Table::new(old_table.get_id(), definition, &self.pager)?;
+
+        if let Some(sequence) = old_table.get_sequence() {
+            table.set_sequence(sequence);
+        }
+
+        for create_index_statement in indexes {
+            table.create_index(&self.pager, &create_index_statement)?;
+        }
+
+        self.tables[table_position] = table;
+
+        if let Ok(stat_table) = Self::find_table_mut(&mut self.tables, STAT_TABLE_NAME) {
+            stat_table.delete(&mut self.pager, &self.session, &Some(Self::name_condition("tbl", table_name)))?;
+        }
+
+        self.set_default_values(&new_table_name)
+    }
+
+    /// The statements of the indexes created by the user, the indexes of the constraints
+    /// are created together with the table
+    fn get_index_statements(&self, table_name: &str) -> io::Result<Vec<CreateIndexStatement>> {
+        Ok(find_table(&self.tables, table_name)?
+            .get_indexes()
+            .iter()
+            .filter(|index| !index.is_auto_index())
+            .map(Index::get_statement)
+            .collect())
+    }
+
+    /// The method renames the table or the column in the definitions of the other tables,
+    /// in the views and in the triggers
+    fn rename_references(&mut self, rename: &Rename, table_name: &str) {
+        for table in self.tables.iter_mut().filter(|table| table.get_name() != table_name) {
+            rename.rename_definition(table.get_definition_mut());
+        }
+
+        for view in self.views.iter_mut() {
+            rename.rename_select(&mut view.select_statement);
+        }
+
+        for trigger in self.triggers.iter_mut() {
+            rename.rename_trigger(trigger);
+        }
+    }
+
+    fn rename_table(&mut self, table_name: &str, new_name: &str) -> io::Result<()> {
+        self.check_new_name(new_name)?;
+
+        let rename = Rename::Table { old_name: table_name, new_name };
+        let mut definition = find_table(&self.tables, table_name)?.get_definition().clone();
+        rename.rename_definition(&mut definition);
+
+        let mut indexes = self.get_index_statements(table_name)?;
+
+        for create_index_statement in indexes.iter_mut() {
+            create_index_statement.table_name = new_name.to_string();
+        }
+
+        self.rename_references(&rename, table_name);
+        self.replace_table(table_name, definition, indexes)?;
+
+        // The sequence of the table is stored by its name
+        if find_table(&self.tables, SEQUENCE_TABLE_NAME).is_ok() {
+            self.update(&UpdateStatement {
+                table_name: SEQUENCE_TABLE_NAME.to_string(),
+                assignments: vec![("name".to_string(), Expression::Literal(Value::Text(new_name.to_string())))],
+                where_clause: Some(Self::name_condition("name", table_name)),
+                returning: Vec::new(),
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn rename_column(&mut self, table_name: &str, old_name: &str, new_name: &str) -> io::Result<()> {
+        let table = find_table(&self.tables, table_name)?;
+
+        if table.find_column(old_name)?.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The row id of the table {} can't be renamed", table_name),
+            ));
+        }
+
+        if table.has_column(new_name) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Duplicate column name: {}", new_name),
+            ));
+        }
+
+        let rename = Rename::Column { table_name, old_name, new_name };
+        let mut definition = table.get_definition().clone();
+        rename.rename_definition(&mut definition);
+
+        let mut indexes = self.get_index_statements(table_name)?;
+
+        for create_index_statement in indexes.iter_mut() {
+            for column_name in create_index_statement.columns.iter_mut().filter(|column_name| *column_name == old_name) {
+                *column_name = new_name.to_string();
+            }
+        }
+
+        self.rename_references(&rename, table_name);
+        self.replace_table(table_name, definition, indexes)
+    }
+
+    /// The rows are not changed, the old rows get the default value of the column when they are read
+    fn add_column(&mut self, table_name: &str, column: &ColumnDefinition) -> io::Result<()> {
+        let default = match &column.default {
+            Some(default) => evaluate_constant(&self.tables, &self.pager, &self.session, default)?,
+            None => Value::Null,
+        };
+
+        let error_message = if column.primary_key || column.unique {
+            Some("Cannot add a PRIMARY KEY or UNIQUE column")
+        } else if column.not_null && default.is_null() {
+            Some("Cannot add a NOT NULL column with default value NULL")
+        } else if column.references.is_some() && self.session.foreign_keys && !default.is_null() {
+            Some("Cannot add a REFERENCES column with non-NULL default value")
+        } else {
+            None
+        };
+
+        if let Some(error_message) = error_message {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, error_message));
+        }
+
+        let mut definition = find_table(&self.tables, table_name)?.get_definition().clone();
+        definition.columns.push(column.clone());
+
+        let indexes = self.get_index_statements(table_name)?;
+        self.replace_table(table_name, definition, indexes)
+    }
+
+    /// The column is removed from every row, it can't be dropped while the constraints,
+    /// the indexes or the foreign keys use it
+    fn drop_column(&mut self, table_name: &str, column_name: &str) -> io::Result<()> {
+        let table = find_table(&self.tables, table_name)?;
+        let definition = table.get_definition();
+
+        let Some(column_idx) = table.find_column(column_name)? else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The row id of the table {} can't be dropped", table_name),
+            ));
+        };
+
+        let column = &definition.columns[column_idx];
+
+        let is_checked_column = definition.columns
+            .iter()
+            .filter_map(|column| column.check.as_ref())
+            .any(|check| check.get_column_names().contains(&column_name));
+
+        let is_constraint_column = definition.constraints.iter().any(|constraint| match constraint {
+            TableConstraint::PrimaryKey(column_names) | TableConstraint::Unique(column_names) => column_names.iter().any(|name| name == column_name),
+            TableConstraint::Check(check) => check.get_column_names().contains(&column_name),
+            TableConstraint::ForeignKey(foreign_key) => foreign_key.columns.iter().any(|name| name == column_name),
+        });
+
+        let is_parent_column = self.tables
+            .iter()
+            .flat_map(|table| table.get_definition().get_foreign_keys())
+            .filter(|foreign_key| foreign_key.parent_table == table_name)
+            .any(|foreign_key| foreign_key.parent_columns.iter().any(|name| name == column_name));
+
+        let reason = if definition.columns.len() == 1 {
+            Some("it's the only column of the table")
+        } else if column.primary_key || column.unique || is_constraint_column || is_checked_column {
+            Some("it's used by the constraints of the table")
+        } else if table.get_indexes().iter().any(|index| index.get_columns().iter().any(|name| name == column_name)) {
+            Some("it's indexed")
+        } else if column.references.is_some() || is_parent_column {
+            Some("it's used by the foreign key")
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Cannot drop the column {}: {}", column_name, reason),
+            ));
+        }
+
+        let mut definition = definition.clone();
+        definition.columns.remove(column_idx);
+
+        let table_id = table.get_id();
+
+        for mut row_data in table.get_all_rows(&self.pager)? {
+            row_data.values.remove(column_idx);
+            self.pager.update_row(table_id, &row_data)?;
+        }
+
+        let indexes = self.get_index_statements(table_name)?;
+        self.replace_table(table_name, definition, indexes)
+    }
+
+    fn find_view(&self, view_name: &str) -> Option<&CreateViewStatement> {
+        self.views.iter().find(|view| view.view_name == view_name)
+    }
+
+    /// The SELECT of the view is planned to check it, the tables it reads must exist
+    fn create_view(&mut self, create_view_statement: &CreateViewStatement) -> io::Result<usize> {
+        if let Err(err) = self.check_new_name(&create_view_statement.view_name) {
+            if create_view_statement.if_not_exists && err.kind() == io::ErrorKind::AlreadyExists {
+                return Ok(0);
+            }
+
+            return Err(err);
+        }
+
+        let mut select_statement = create_view_statement.get_select_statement();
+        expand_views(&self.views, &mut select_statement)?;
+        self.with_query(&select_statement, |_| Ok(()))?;
+
+        self.views.push(create_view_statement.clone());
+        self.save_schema()?;
+
+        Ok(0)
+    }
+
+    /// The views which read the dropped view are kept, they fail when they are used
+    fn drop_view(&mut self, drop_view_statement: &DropViewStatement) -> io::Result<usize> {
+        let view_name = &drop_view_statement.view_name;
+
+        match self.find_view(view_name) {
+            Some(_) => {
+                self.views.retain(|view| view.view_name != *view_name);
+                self.save_schema()?;
+
+                Ok(0)
+            },
+            None if drop_view_statement.if_exists => Ok(0),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No such view: {}", view_name),
+            )),
+        }
+    }
+
+    /// The method replaces the views read by the statement with their SELECTs, the views can't be changed
+    fn expand_views(&self, statement: &mut Statement) -> io::Result<()> {
+        let changed_table_name = match statement {
+            Statement::Insert(insert_statement) => Some(&insert_statement.table_name),
+            Statement::Update(update_statement) => Some(&update_statement.table_name),
+            Statement::Delete(delete_statement) => Some(&delete_statement.table_name),
+            _ => None,
+        };
+
+        if let Some(view) = changed_table_name.and_then(|table_name| self.find_view(table_name)) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("The view {} may not be modified", view.view_name),
+            ));
+        }
+
+        if let Statement::Explain(explain_statement) = statement {
+            return self.expand_views(&mut explain_statement.statement);
+        }
+
+        if let Some(select_statement) = statement.get_select_mut() {
+            expand_views(&self.views, select_statement)?;
+        }
+
+        for expression in statement.get_expressions_mut() {
+            for subquery in expression.get_subqueries_mut() {
+                expand_views(&self.views, subquery)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The statements of the trigger are checked against the NEW and OLD rows of NULLs
+    fn create_trigger(&mut self, create_trigger_statement: &CreateTriggerStatement) -> io::Result<usize> {
+        if let Err(err) = self.check_new_name(&create_trigger_statement.trigger_name) {
+            if create_trigger_statement.if_not_exists && err.kind() == io::ErrorKind::AlreadyExists {
+                return Ok(0);
+            }
+
+            return Err(err);
+        }
+
+        let table_name = &create_trigger_statement.table_name;
+
+        if self.find_view(table_name).is_some() || table_name.starts_with(INTERNAL_NAME_PREFIX) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("The trigger can't be created on {}", table_name),
+            ));
+        }
+
+        let table = Self::get_writable_table(&mut self.tables, table_name)?;
+
+        if let TriggerEvent::Update(column_names) = &create_trigger_statement.event {
+            for column_name in column_names.iter() {
+                table.find_column(column_name)?;
+            }
+        }
+
+        let null_row = RowData::new(0, vec![Value::Null; table.get_definition().columns.len()]);
+        let event = &create_trigger_statement.event;
+
+        let rows = TriggerRows {
+            table,
+            old_row: (*event != TriggerEvent::Insert).then_some(&null_row),
+            new_row: (*event != TriggerEvent::Delete).then_some(&null_row),
+        };
+
+        if let Some(when) = &create_trigger_statement.when {
+            rows.substitute_expression(&mut when.clone())?;
+        }
+
+        for statement in create_trigger_statement.statements.iter() {
+            rows.substitute(&mut statement.clone())?;
+        }
+
+        self.triggers.push(create_trigger_statement.clone());
+        self.save_schema()?;
+
+        Ok(0)
+    }
+
+    fn drop_trigger(&mut self, drop_trigger_statement: &DropTriggerStatement) -> io::Result<usize> {
+        let trigger_name = &drop_trigger_statement.trigger_name;
+
+        match self.triggers.iter().position(|trigger| trigger.trigger_name == *trigger_name) {
+            Some(trigger_num) => {
+                self.triggers.remove(trigger_num);
+                self.save_schema()?;
+
+                Ok(0)
+            },
+            None if drop_trigger_statement.if_exists => Ok(0),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No such trigger: {}", trigger_name),
+            )),
+        }
+    }
+
+    fn has_triggers(&self, table_name: &str) -> bool {
+        self.triggers.iter().any(|trigger| trigger.table_name == table_name)
+    }
+
+    /// The method runs the triggers of the table fired by the change of the row,
+    /// the columns of NEW and OLD are replaced by the values of the rows
+    fn fire_triggers(
+        &mut self,
+        timing: TriggerTiming,
+        event: &TriggerEvent,
+        table_name: &str,
+        old_row: Option<&RowData>,
+        new_row: Option<&RowData>,
+    ) -> io::Result<()> {
+        let triggers: Vec<CreateTriggerStatement> = self.triggers
+            .iter()
+            .filter(|trigger| trigger.table_name == table_name && trigger.timing == timing && trigger.event.is_fired_by(event))
+            .filter(|trigger| !self.firing_triggers.contains(&trigger.trigger_name))
+            .cloned()
+            .collect();
+
+        for trigger in triggers {
+            let rows = TriggerRows { table: find_table(&self.tables, table_name)?, old_row, new_row };
+
+            if let Some(mut when) = trigger.when {
+                rows.substitute_expression(&mut when)?;
+
+                if !evaluate_constant(&self.tables, &self.pager, &self.session, &when)?.is_true() {
+                    continue;
+                }
+            }
+
+            let mut statements = trigger.statements;
+
+            for statement in statements.iter_mut() {
+                rows.substitute(statement)?;
+            }
+
+            self.firing_triggers.push(trigger.trigger_name);
+            let result = statements.iter_mut().try_for_each(|statement| self.run_trigger_statement(statement));
+            self.firing_triggers.pop();
+
+            result?;
+        }
+
+        Ok(())
+    }
+
+    /// The rows of the SELECT run by the trigger are not printed
+    fn run_trigger_statement(&mut self, statement: &mut Statement) -> io::Result<()> {
+        self.expand_views(statement)?;
+
+        match statement {
+            Statement::Select(select_statement) => self.select_rows(select_statement).map(|_| ()),
+            statement => self.execute_statement(statement).map(|_| ()),
+        }
+    }
+
+    /// The foreign keys of the table, they are enforced when PRAGMA foreign_keys is on
+    fn get_foreign_keys(&self, table_name: &str) -> Vec<ForeignKey> {
+        match find_table(&self.tables, table_name) {
+            Ok(table) if self.session.foreign_keys => table.get_definition().get_foreign_keys().into_iter().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The foreign keys referring to the table with the names of their child tables
+    fn get_child_foreign_keys(&self, table_name: &str) -> Vec<(String, ForeignKey)> {
+        if !self.session.foreign_keys {
+            return Vec::new();
+        }
+
+        self.tables
+            .iter()
+            .flat_map(|table| table.get_definition()
+                .get_foreign_keys()
+                .into_iter()
+                .filter(|foreign_key| foreign_key.parent_table == table_name)
+                .map(|foreign_key| (table.get_name().to_string(), foreign_key.clone())))
+            .collect()
+    }
+
+    /// The rows are changed one by one if the triggers or the foreign keys have to see every row
+    fn has_row_actions(&self, table_name: &str) -> bool {
+        self.has_triggers(table_name)
+            || !self.get_foreign_keys(table_name).is_empty()
+            || !self.get_child_foreign_keys(table_name).is_empty()
+    }
+
+    /// The columns of the parent table referred to by the foreign key of the child table
+    fn get_parent_key(&self, table_name: &str, foreign_key: &ForeignKey) -> io::Result<Vec<String>> {
+        find_table(&self.tables, &foreign_key.parent_table)
+            .map_err(|_| mismatch_error(table_name, foreign_key))?
+            .get_parent_key(foreign_key)
+            .ok_or_else(|| mismatch_error(table_name, foreign_key))
+    }
+
+    /// The method returns the rows of the table with the values of the key columns
+    fn find_key_rows(&self, table_name: &str, column_names: &[String], values: &[Value]) -> io::Result<Vec<RowData>> {
+        self.select_rows(&SelectStatement::from_table(table_name, vec![ResultColumn::All], key_condition(column_names, values)))
+    }
+
+    /// The method checks whether the parent row of the child row exists, the row could refer to itself
+    fn has_parent_row(&self, table_name: &str, foreign_key: &ForeignKey, row_data: &RowData, values: &[Value]) -> io::Result<bool> {
+        let parent_key = self.get_parent_key(table_name, foreign_key)?;
+
+        if foreign_key.parent_table == table_name {
+            let table = find_table(&self.tables, table_name)?;
+
+            if get_key_values(table, row_data, &parent_key)?.as_deref() == Some(values) {
+                return Ok(true);
+            }
+        }
+
+        Ok(!self.find_key_rows(&foreign_key.parent_table, &parent_key, values)?.is_empty())
+    }
+
+    fn defer_foreign_key(&mut self, table_name: &str, foreign_key: ForeignKey) {
+        let deferred_foreign_key = (table_name.to_string(), foreign_key);
+
+        if !self.deferred_foreign_keys.contains(&deferred_foreign_key) {
+            self.deferred_foreign_keys.push(deferred_foreign_key);
+        }
+    }
+
+    /// The method checks that the new row of the child table refers to the existing parent rows,
+    /// the keys not changed by UPDATE and the keys with NULL aren't checked
+    fn check_parent_rows(&mut self, table_name: &str, new_row: &RowData, old_row: Option<&RowData>) -> io::Result<()> {
+        let mut new_row = new_row.clone();
+        find_table(&self.tables, table_name)?.apply_affinities(&mut new_row)?;
+
+        for foreign_key in self.get_foreign_keys(table_name) {
+            let table = find_table(&self.tables, table_name)?;
+
+            let Some(values) = get_key_values(table, &new_row, &foreign_key.columns)? else {
+                continue;
+            };
+
+            if let Some(old_row) = old_row {
+                if get_key_values(table, old_row, &foreign_key.columns)?.as_ref() == Some(&values) {
+                    continue;
+                }
+            }
+
+            if foreign_key.deferred {
+                self.defer_foreign_key(table_name, foreign_key);
+            } else if !self.has_parent_row(table_name, &foreign_key, &new_row, &values)? {
+                return Err(constraint_error(table_name, &foreign_key));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The values of the parent key of the row if the row is deleted or its key is changed
+    fn get_changed_key(
+        &self,
+        table_name: &str,
+        child_table_name: &str,
+        foreign_key: &ForeignKey,
+        old_row: &RowData,
+        new_row: Option<&RowData>,
+    ) -> io::Result<Option<Vec<Value>>> {
+        let parent_key = self.get_parent_key(child_table_name, foreign_key)?;
+        let table = find_table(&self.tables, table_name)?;
+        let old_values = get_key_values(table, old_row, &parent_key)?;
+
+        let Some(new_row) = new_row else {
+            return Ok(old_values);
+        };
+
+        let mut new_row = new_row.clone();
+        table.apply_affinities(&mut new_row)?;
+
+        match get_key_values(table, &new_row, &parent_key)? == old_values {
+            true => Ok(None),
+            false => Ok(old_values),
+        }
+    }
+
+    /// The method is called before the row of the parent table is deleted or changed,
+    /// RESTRICT and NO ACTION forbid the change while the child rows refer to the row
+    fn check_child_rows(&mut self, table_name: &str, old_row: &RowData, new_row: Option<&RowData>) -> io::Result<()> {
+        for (child_table_name, foreign_key) in self.get_child_foreign_keys(table_name) {
+            let action = if new_row.is_some() { foreign_key.on_update } else { foreign_key.on_delete };
+
+            if !matches!(action, ForeignKeyAction::Restrict | ForeignKeyAction::NoAction) {
+                continue;
+            }
+
+            let Some(values) = self.get_changed_key(table_name, &child_table_name, &foreign_key, old_row, new_row)? else {
+                continue;
+            };
+
+            // The deferred NO ACTION allows the child rows to refer to the parent row inserted later
+            if foreign_key.deferred && action == ForeignKeyAction::NoAction {
+                self.defer_foreign_key(&child_table_name, foreign_key);
+                continue;
+            }
+
+            let has_child_rows = self.find_key_rows(&child_table_name, &foreign_key.columns, &values)?
+                .iter()
+                .any(|row_data| child_table_name != table_name || row_data.id != old_row.id);
+
+            if has_child_rows {
+                return Err(constraint_error(&child_table_name, &foreign_key));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The method is called after the row of the parent table is deleted or changed,
+    /// CASCADE and SET NULL are applied by the statements changing the child rows
+    fn update_child_rows(&mut self, table_name: &str, old_row: &RowData, new_row: Option<&RowData>) -> io::Result<()> {
+        for (child_table_name, foreign_key) in self.get_child_foreign_keys(table_name) {
+            let action = if new_row.is_some() { foreign_key.on_update } else { foreign_key.on_delete };
+
+            if !matches!(action, ForeignKeyAction::Cascade | ForeignKeyAction::SetNull) {
+                continue;
+            }
+
+            let Some(old_values) = self.get_changed_key(table_name, &child_table_name, &foreign_key, old_row, new_row)? else {
+                continue;
+            };
+
+            let where_clause = key_condition(&foreign_key.columns, &old_values);
+
+            let new_values = match (action, new_row) {
+                (ForeignKeyAction::Cascade, None) => {
+                    self.execute_statement(&Statement::Delete(DeleteStatement { table_name: child_table_name, where_clause, returning: Vec::new() }))?;
+                    continue;
+                },
+                (ForeignKeyAction::Cascade, Some(new_row)) => {
+                    let parent_key = self.get_parent_key(&child_table_name, &foreign_key)?;
+                    let table = find_table(&self.tables, table_name)?;
+
+                    parent_key
+                        .iter()
+                        .map(|column_name| table.get_column_value(new_row, column_name))
+                        .collect::<io::Result<Vec<Value>>>()?
+                },
+                _ => vec![Value::Null; foreign_key.columns.len()],
+            };
+
+            let assignments = foreign_key.columns
+                .iter()
+                .cloned()
+                .zip(new_values.into_iter().map(Expression::Literal))
+                .collect();
+
+            self.execute_statement(&Statement::Update(UpdateStatement { table_name: child_table_name, assignments, where_clause, returning: Vec::new() }))?;
+        }
+
+        Ok(())
+    }
+
+    /// Every statement is committed on its own, so the deferred foreign keys are checked
+    /// at the end of the statement. All the rows of the child tables are checked
+    fn check_deferred_foreign_keys(&mut self) -> io::Result<()> {
+        for (table_name, foreign_key) in self.deferred_foreign_keys.clone() {
+            for row_data in self.find_key_rows(&table_name, &[], &[])? {
+                let table = find_table(&self.tables, &table_name)?;
+
+                let Some(values) = get_key_values(table, &row_data, &foreign_key.columns)? else {
+                    continue;
+                };
+
+                if !self.has_parent_row(&table_name, &foreign_key, &row_data, &values)? {
+                    return Err(constraint_error(&table_name, &foreign_key));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// PRAGMA foreign_keys shows or changes whether the foreign keys are enforced,
+    /// PRAGMA journal_mode shows or changes the way the database file is saved
+    fn pragma(&mut self, pragma_statement: &PragmaStatement) -> io::Result<usize> {
+        match (pragma_statement.name.as_str(), &pragma_statement.value) {
+            ("foreign_keys", None) => {
+                println!("foreign_keys: {}", self.session.foreign_keys as i64);
+                Ok(1)
+            },
+            ("foreign_keys", Some(value)) => {
+                self.session.foreign_keys = pragma_statement.parse_flag(value)?;
+                Ok(0)
+            },
+            ("journal_mode", None) => {
+                println!("journal_mode: {}", self.pager.get_journal_mode());
+                Ok(1)
+            },
+            ("journal_mode", Some(value)) => {
+                self.pager.set_journal_mode(JournalMode::parse(value)?);
+                Ok(0)
+            },
+            (name, _) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown pragma: {}", name),
+            )),
+        }
+    }
+
+    /// The method gathers the statistics of the table or of all the tables and stores them in the catalog
+    fn analyze(&mut self, analyze_statement: &AnalyzeStatement) -> io::Result<usize> {
+        let table_names: Vec<String> = match &analyze_statement.table_name {
+            Some(table_name) => vec![Self::get_writable_table(&mut self.tables, table_name)?.get_name().to_string()],
+            None => self.tables[1..]
+                .iter()
+                .map(|table| table.get_name().to_string())
+                .filter(|table_name| !table_name.starts_with(INTERNAL_NAME_PREFIX))
+                .collect(),
+        };
+
+        if Self::find_table_mut(&mut self.tables, STAT_TABLE_NAME).is_err() {
+            let mut tokens = TokenStream::new(STAT_TABLE_SQL)?;
+
+            self.add_table(CreateTableStatement::new(&mut tokens)?)?;
+            self.save_schema()?;
+        }
+
+        for table_name in table_names.iter() {
+            let table = Self::find_table_mut(&mut self.tables, table_name)?;
+            table.analyze(&self.pager)?;
+
+            let statistics = table.get_statistics().cloned().unwrap();
+
+            let mut stat_rows = vec![vec![
+                Value::Text(table_name.clone()),
+                Value::Null,
+                Value::Integer(statistics.row_count as i64),
+                Value::Null,
+                Value::Null,
+                Value::Null,
+            ]];
+
+            for (column_name, column) in statistics.columns.iter() {
+                stat_rows.push(vec![
+                    Value::Text(table_name.clone()),
+                    Value::Text(column_name.clone()),
+                    Value::Null,
+                    Value::Integer(column.distinct_count as i64),
+                    Value::Integer(column.null_count as i64),
+                    Value::Text(column.histogram_to_sql()),
+                ]);
+            }
+
+            let stat_table = Self::find_table_mut(&mut self.tables, STAT_TABLE_NAME)?;
+            stat_table.delete(&mut self.pager, &self.session, &Some(Self::name_condition("tbl", table_name)))?;
+
+            for values in stat_rows {
+                stat_table.insert_row(&mut self.pager, &self.session, Value::Null, values)?;
+            }
+        }
+
+        Ok(0)
+    }
+
+    /// The method compiles the statement to the program of the virtual machine,
+    /// the tables changed by the statement must be writable
+    fn compile(&mut self, statement: &Statement) -> io::Result<Program> {
+        match statement {
+            Statement::Insert(insert_statement) => {
+                Self::get_writable_table(&mut self.tables, &insert_statement.table_name)?;
+            },
+            Statement::Delete(delete_statement) => {
+                Self::get_writable_table(&mut self.tables, &delete_statement.table_name)?;
+            },
+            _ => {},
+        }
+
+        Compiler::new(&self.tables, &self.pager, &self.session).compile(statement)
+    }
+
+    /// The method returns the program of the statement, the program is compiled
+    /// once and run many times until the schema changes
+    pub fn prepare(&mut self, statement: &Statement) -> io::Result<Rc<Program>> {
+        // The statement is written back with the identifiers and the literals quoted,
+        // so the different statements never share the key
+        let sql = match statement {
+            Statement::Select(select_statement) => select_statement.to_string(),
+            Statement::Insert(insert_statement) => insert_statement.to_string(),
+            Statement::Delete(delete_statement) => delete_statement.to_string(),
+            _ => return self.compile(statement).map(Rc::new),
+        };
+
+        if let Some(program) = self.programs.get(&sql) {
+            return Ok(Rc::clone(program));
+        }
+
+        let program = Rc::new(self.compile(statement)?);
+
+        // The programs of the statements which are run once shouldn't fill the memory
+        if self.programs.len() >= MAX_PROGRAMS {
+            self.programs.clear();
+        }
+
+        self.programs.insert(sql, Rc::clone(&program));
+
+        Ok(program)
+    }
+
+    /// The method runs the program and passes its result rows to the sink, the program
+    /// could be run many times. Returns the number of the result rows or the changed rows
+    pub fn run_program(&mut self, program: &Program, mut sink: impl FnMut(RowData)) -> io::Result<usize> {
+        let mut vm = VirtualMachine::new(program, &mut self.pager, &mut self.tables, &mut self.session);
+        let mut num_rows = 0;
+
+        while let Some(row_data) = vm.step()? {
+            sink(row_data);
+            num_rows += 1;
+        }
+
+        let num_changes = vm.get_num_changes();
+
+        if let Some(table_name) = &program.changed_table_name {
+            self.save_sequence(table_name)?;
+        }
+
+        Ok(if program.column_names.is_empty() { num_changes } else { num_rows })
+    }
+
+    /// The method plans the SELECT run by the operators and passes it to the function,
+    /// the tables of WITH and the subqueries in FROM are materialized first
+    fn with_query<T>(&self, select_statement: &SelectStatement, f: impl FnOnce(&Query) -> io::Result<T>) -> io::Result<T> {
+        with_query(&self.tables, &self.pager, &self.session, select_statement, None, None, f)
+    }
+
+    /// The method returns all the rows of the SELECT run by the operators
+    fn select_rows(&self, select_statement: &SelectStatement) -> io::Result<Vec<RowData>> {
+        self.with_query(select_statement, |query| {
+            let mut rows = Vec::new();
+            query.run(|row_data| rows.push(row_data))?;
+
+            Ok(rows)
+        })
+    }
+
+    /// The method evaluates the values of the inserted rows or runs the SELECT of INSERT
+    fn get_insert_rows(&self, insert_statement: &InsertStatement) -> io::Result<Vec<Vec<Value>>> {
+        match &insert_statement.source {
+            InsertSource::Values(rows) => rows
+                .iter()
+                .map(|values| values
+                    .iter()
+                    .map(|value| evaluate_constant(&self.tables, &self.pager, &self.session, value))
+                    .collect())
+                .collect(),
+            InsertSource::Select(select_statement) => Ok(self.select_rows(select_statement)?
+                .into_iter()
+                .map(|row_data| row_data.values)
+                .collect()),
+            InsertSource::DefaultValues => Ok(vec![Vec::new()]),
+        }
+    }
+
+    /// The values of the rows are evaluated by the operators, then the rows are inserted one by one,
+    /// so the triggers could be fired, the foreign keys checked and the conflicts resolved around every row.
+    /// Returns the inserted rows and the rows updated by the upserts
+    fn insert_rows(&mut self, insert_statement: &InsertStatement) -> io::Result<Vec<RowData>> {
+        let table_name = &insert_statement.table_name;
+        let rows = self.get_insert_rows(insert_statement)?;
+
+        let table = Self::get_writable_table(&mut self.tables, table_name)?;
+        let num_columns = table.get_definition().columns.len();
+
+        for upsert in insert_statement.upserts.iter() {
+            if !upsert.target.is_empty() && !table.is_conflict_target(&upsert.target) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("ON CONFLICT ({}) doesn't match any PRIMARY KEY or UNIQUE constraint of the table {}", upsert.target.join(", "), table_name),
+                ));
+            }
+        }
+
+        if let (Some(values), false) = (rows.first(), matches!(insert_statement.source, InsertSource::DefaultValues)) {
+            table.check_num_values(&insert_statement.columns, values.len())?;
+        }
+
+        let positions = match &insert_statement.columns {
+            Some(column_names) => table.get_insert_positions(column_names)?,
+            None => (0..num_columns).map(Some).collect(),
+        };
+
+        let mut changed_rows = Vec::new();
+
+        for values in rows {
+            match self.insert_row(insert_statement, &positions, values) {
+                Ok(rows) => changed_rows.extend(rows),
+                // The rows inserted before the failed one are kept
+                Err(err) if insert_statement.conflict_resolution == ConflictResolution::Fail => {
+                    self.save_sequence(table_name)?;
+                    return Err(err);
+                },
+                Err(err) => return Err(err),
+            }
+        }
+
+        self.save_sequence(table_name)?;
+
+        Ok(changed_rows)
+    }
+
+    /// The missing columns get their defaults. NEW.rowid is -1 before the row id is generated.
+    /// Returns the inserted row or the rows updated by the upsert, there are none if the row is skipped
+    fn insert_row(&mut self, insert_statement: &InsertStatement, positions: &[Option<usize>], row_values: Vec<Value>) -> io::Result<Vec<RowData>> {
+        let table_name = &insert_statement.table_name;
+        let num_columns = find_table(&self.tables, table_name)?.get_definition().columns.len();
+
+        let mut row_id = Value::Null;
+        let mut values = vec![None; num_columns];
+
+        for (value, position) in row_values.into_iter().zip(positions) {
+            match position {
+                Some(column_idx) => values[*column_idx] = Some(value),
+                None => row_id = value,
+            }
+        }
+
+        let table = find_table(&self.tables, table_name)?;
+
+        let values = table.get_definition().columns
+            .iter()
+            .zip(values)
+            .map(|(column, value)| match (value, &column.default) {
+                (Some(value), _) => Ok(value),
+                (None, Some(default)) => evaluate_constant(&self.tables, &self.pager, &self.session, default),
+                (None, None) => Ok(Value::Null),
+            })
+            .collect::<io::Result<Vec<Value>>>()?;
+
+        let new_row = RowData::new(if let Value::Integer(row_id) = row_id { row_id } else { -1 }, values.clone());
+        self.fire_triggers(TriggerTiming::Before, &TriggerEvent::Insert, table_name, None, Some(&new_row))?;
+
+        let conflict_resolution = insert_statement.conflict_resolution;
+        let table = find_table(&self.tables, table_name)?;
+        let prepared_row = table.prepare_row(&self.pager, row_id, values)?;
+
+        match table.check_constraints(&self.session, &prepared_row) {
+            Err(err) if conflict_resolution == ConflictResolution::Ignore && err.kind() == io::ErrorKind::InvalidInput => {
+                return Ok(Vec::new());
+            },
+            result => result?,
+        }
+
+        // The first clause with the target of the conflicting key or without the target handles the conflict
+        for (column_names, conflicting_row_id) in table.find_conflicts(&self.pager, &prepared_row) {
+            let upsert = insert_statement.upserts.iter().find(|upsert| {
+                upsert.target.is_empty()
+                    || upsert.target.len() == column_names.len() && column_names.iter().all(|column_name| upsert.target.contains(column_name))
+            });
+
+            match (upsert, conflict_resolution) {
+                (Some(upsert), _) => return self.upsert_row(table_name, upsert, &prepared_row, conflicting_row_id),
+                (None, ConflictResolution::Ignore) => return Ok(Vec::new()),
+                (None, ConflictResolution::Replace) => self.replace_row(table_name, conflicting_row_id)?,
+                // The conflict is reported when the row is written
+                (None, _) => break,
+            }
+        }
+
+        self.check_parent_rows(table_name, &prepared_row, None)?;
+
+        let table = Self::get_writable_table(&mut self.tables, table_name)?;
+        let row_id = table.write_row(&mut self.pager, prepared_row)?;
+        let table_id = table.get_id();
+
+        self.session.last_insert_rowid = row_id;
+
+        let new_row = self.pager.get_row_by_id(table_id, row_id).unwrap_or(new_row);
+        self.fire_triggers(TriggerTiming::After, &TriggerEvent::Insert, table_name, None, Some(&new_row))?;
+
+        Ok(vec![new_row])
+    }
+
+    /// DO UPDATE changes the conflicting row by the UPDATE statement, the columns
+    /// of "excluded" are replaced by the values of the row which wasn't inserted
+    fn upsert_row(&mut self, table_name: &str, upsert: &Upsert, excluded_row: &RowData, conflicting_row_id: i64) -> io::Result<Vec<RowData>> {
+        let UpsertAction::Update { assignments, where_clause } = &upsert.action else {
+            return Ok(Vec::new());
+        };
+
+        let table = find_table(&self.tables, table_name)?;
+        let mut assignments = assignments.clone();
+        let mut where_clause = where_clause.clone();
+
+        for expression in assignments.iter_mut().map(|(_, expression)| expression).chain(where_clause.iter_mut()) {
+            Self::substitute_excluded(table, excluded_row, expression)?;
+        }
+
+        let row_id_condition = key_condition(&[ROW_ID_COLUMN.to_string()], &[Value::Integer(conflicting_row_id)]);
+
+        self.update(&UpdateStatement {
+            table_name: table_name.to_string(),
+            assignments,
+            where_clause: conjoin(row_id_condition.into_iter().chain(where_clause).collect()),
+            returning: Vec::new(),
+        })
+    }
+
+    fn substitute_excluded(table: &Table, excluded_row: &RowData, expression: &mut Expression) -> io::Result<()> {
+        match expression {
+            Expression::Column { table_name: Some(table_name), column_name } if table_name == "excluded" => {
+                *expression = Expression::Literal(table.get_column_value(excluded_row, column_name)?);
+                Ok(())
+            },
+            _ => expression
+                .get_children_mut()
+                .into_iter()
+                .try_for_each(|child| Self::substitute_excluded(table, excluded_row, child)),
+        }
+    }
+
+    /// REPLACE deletes the conflicting row before the new row is inserted,
+    /// the foreign keys referring to it are handled as by DELETE
+    fn replace_row(&mut self, table_name: &str, row_id: i64) -> io::Result<()> {
+        let table_id = find_table(&self.tables, table_name)?.get_id();
+
+        let Some(row_data) = self.pager.get_row_by_id(table_id, row_id) else {
+            return Ok(());
+        };
+
+        self.check_child_rows(table_name, &row_data, None)?;
+        Self::get_writable_table(&mut self.tables, table_name)?.delete_row(&mut self.pager, &row_data)?;
+        self.update_child_rows(table_name, &row_data, None)
+    }
+
+    /// The rows are found by the operators if the compiler doesn't support the condition,
+    /// or the triggers and the foreign keys have to see every deleted row. Returns the deleted rows
+    fn delete_rows(&mut self, delete_statement: &DeleteStatement) -> io::Result<Vec<RowData>> {
+        let table_name = &delete_statement.table_name;
+        let rows = self.select_rows(&delete_statement.get_select_statement())?;
+        let mut deleted_rows = Vec::new();
+
+        for row_data in rows {
+            let table_id = find_table(&self.tables, table_name)?.get_id();
+
+            // The row could be already deleted by the trigger or by the foreign key of its own table
+            if self.pager.find_row(table_id, row_data.id).is_none() {
+                continue;
+            }
+
+            self.fire_triggers(TriggerTiming::Before, &TriggerEvent::Delete, table_name, Some(&row_data), None)?;
+            self.check_child_rows(table_name, &row_data, None)?;
+            Self::get_writable_table(&mut self.tables, table_name)?.delete_row(&mut self.pager, &row_data)?;
+            self.update_child_rows(table_name, &row_data, None)?;
+            self.fire_triggers(TriggerTiming::After, &TriggerEvent::Delete, table_name, Some(&row_data), None)?;
+
+            deleted_rows.push(row_data);
+        }
+
+        Ok(deleted_rows)
+    }
+
+    /// The rows are found and the new values are evaluated by the operators,
+    /// then all the rows are changed at once. Returns the new rows
+    fn update(&mut self, update_statement: &UpdateStatement) -> io::Result<Vec<RowData>> {
+        let table = Self::get_writable_table(&mut self.tables, &update_statement.table_name)?;
+        let positions = table.get_update_positions(update_statement)?;
+        let num_columns = table.get_definition().columns.len();
+
+        let changes = self.select_rows(&update_statement.get_select_statement())?
+            .into_iter()
+            .map(|mut row_data| {
+                let values = row_data.values.split_off(num_columns);
+                (row_data, values)
+            })
+            .collect();
+
+        let new_rows = match self.has_row_actions(&update_statement.table_name) {
+            true => self.update_row_by_row(update_statement, &positions, changes)?,
+            false => {
+                let table = Self::get_writable_table(&mut self.tables, &update_statement.table_name)?;
+                table.update(&mut self.pager, &self.session, &positions, changes)?
+            },
+        };
+
+        self.save_sequence(&update_statement.table_name)?;
+
+        Ok(new_rows)
+    }
+
+    /// The rows are changed one by one, so the triggers could be fired and the foreign keys checked around every row
+    fn update_row_by_row(&mut self, update_statement: &UpdateStatement, positions: &[Option<usize>], changes: Vec<(RowData, Vec<Value>)>) -> io::Result<Vec<RowData>> {
+        let table_name = &update_statement.table_name;
+        let event = TriggerEvent::Update(update_statement.assignments.iter().map(|(column_name, _)| column_name.clone()).collect());
+        let mut new_rows = Vec::new();
+
+        for (old_row, values) in changes {
+            let table = find_table(&self.tables, table_name)?;
+            let table_id = table.get_id();
+
+            // The row could be deleted by the trigger or by the foreign key of its own table
+            if self.pager.find_row(table_id, old_row.id).is_none() {
+                continue;
+            }
+
+            let new_row = table.get_updated_row(positions, &old_row, values.clone())?;
+            self.fire_triggers(TriggerTiming::Before, &event, table_name, Some(&old_row), Some(&new_row))?;
+            self.check_parent_rows(table_name, &new_row, Some(&old_row))?;
+            self.check_child_rows(table_name, &old_row, Some(&new_row))?;
+
+            let table = Self::get_writable_table(&mut self.tables, table_name)?;
+            table.update(&mut self.pager, &self.session, positions, vec![(old_row.clone(), values)])?;
+
+            let new_row = self.pager.get_row_by_id(table_id, new_row.id).unwrap_or(new_row);
+            self.update_child_rows(table_name, &old_row, Some(&new_row))?;
+            self.fire_triggers(TriggerTiming::After, &event, table_name, Some(&old_row), Some(&new_row))?;
+
+            new_rows.push(new_row);
+        }
+
+        Ok(new_rows)
+    }
+
+    /// The method runs INSERT, UPDATE or DELETE without the compiler and evaluates RETURNING against
+    /// the inserted rows, the new values of the updated rows and the old values of the deleted rows.
+    /// Returns the names of the result columns and the result rows
+    fn execute_returning(&mut self, statement: &Statement) -> io::Result<(Vec<String>, Vec<RowData>)> {
+        let table_name = match statement {
+            Statement::Insert(insert_statement) => &insert_statement.table_name,
+            Statement::Update(update_statement) => &update_statement.table_name,
+            Statement::Delete(delete_statement) => &delete_statement.table_name,
+            _ => return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "RETURNING could be used with INSERT, UPDATE and DELETE only",
+            )),
+        };
+
+        let table = find_table(&self.tables, table_name)?;
+        let source = Source { table, pager: &self.pager, name: table_name, merged_columns: Vec::new() };
+        let (column_names, expressions): (Vec<String>, Vec<Expression>) = get_result_columns(&[source], statement.get_returning())?
+            .into_iter()
+            .unzip();
+
+        // The expressions are checked against the row of NULLs before any row is changed
+        let empty_row = RowData::new(0, vec![Value::Null; table.get_definition().columns.len()]);
+
+        for expression in expressions.iter() {
+            table.evaluate(&self.session, &empty_row, expression)?;
+        }
+
+        let changed_rows = match statement {
+            Statement::Insert(insert_statement) => self.insert_rows(insert_statement)?,
+            Statement::Update(update_statement) => self.update(update_statement)?,
+            Statement::Delete(delete_statement) => self.delete_rows(delete_statement)?,
+            _ => Vec::new(),
+        };
+
+        let table = find_table(&self.tables, table_name)?;
+
+        let rows = changed_rows
+            .into_iter()
+            .map(|row_data| {
+                let values = expressions
+                    .iter()
+                    .map(|expression| table.evaluate(&self.session, &row_data, expression))
+                    .collect::<io::Result<Vec<Value>>>()?;
+
+                Ok(RowData::new(row_data.id, values))
+            })
+            .collect::<io::Result<Vec<RowData>>>()?;
+
+        Ok((column_names, rows))
+    }
+
+    /// The method describes the plan of the statement, EXPLAIN ANALYZE also runs it
+    fn explain(&mut self, explain_statement: &ExplainStatement) -> io::Result<Vec<String>> {
+        match (explain_statement.mode, explain_statement.statement.as_ref()) {
+            (ExplainMode::Bytecode, statement) => {
+                Ok(Compiler::new(&self.tables, &self.pager, &self.session).compile(statement)?.explain())
+            },
+            (mode, Statement::Select(select_statement)) => self.with_query(select_statement, |query| match mode {
+                ExplainMode::Analyze => query.analyze(),
+                _ => query.explain(),
+            }),
+            (ExplainMode::QueryPlan, Statement::Update(update_statement)) => {
+                let table = find_table(&self.tables, &update_statement.table_name)?;
+                let description = format!("UPDATE {}", table.get_name());
+
+                Ok(explain_table_scan(description, &self.tables, table, &self.pager, &self.session, &update_statement.where_clause))
+            },
+            (ExplainMode::QueryPlan, Statement::Delete(delete_statement)) => {
+                let table = find_table(&self.tables, &delete_statement.table_name)?;
+                let description = format!("DELETE FROM {}", table.get_name());
+
+                Ok(explain_table_scan(description, &self.tables, table, &self.pager, &self.session, &delete_statement.where_clause))
+            },
+            (ExplainMode::QueryPlan, _) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "EXPLAIN QUERY PLAN supports SELECT, UPDATE and DELETE statements only",
+            )),
+            (ExplainMode::Analyze, _) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "EXPLAIN ANALYZE supports SELECT statements only",
+            )),
+        }
+    }
+
+    fn print_row(column_names: &[String], row_data: &RowData, with_row_id: bool) {
+        let mut values = Vec::new();
+
+        if with_row_id {
+            values.push(format!("Id: {}", row_data.id));
+        }
+
+        for (column_name, value) in column_names.iter().zip(row_data.values.iter()) {
+            values.push(format!("{}: {}", column_name, value));
+        }
+
+        println!("{}", values.join(", "));
+    }
+
+    /// The statement runs within its own savepoint, so the changes of the failed statement are rolled back
+    /// except the rows inserted by INSERT OR FAIL before the failed one. INSERT OR ROLLBACK rolls back
+    /// the whole transaction. Outside of the transaction the deferred foreign keys are checked at the end
+    /// of the statement
+    fn execute_atomically(&mut self, statement: &Statement) -> io::Result<usize> {
+        self.pager.begin_savepoint();
+
+        let result = self.execute_statement(statement).and_then(|num_rows| {
+            if self.savepoints.is_empty() {
+                self.check_deferred_foreign_keys()?;
+            }
+
+            Ok(num_rows)
+        });
+
+        let conflict_resolution = match statement {
+            Statement::Insert(insert_statement) => insert_statement.conflict_resolution,
+            _ => ConflictResolution::Abort,
+        };
+
+        if result.is_ok() || conflict_resolution == ConflictResolution::Fail {
+            self.pager.release_savepoint();
+        } else {
+            let table_ids = self.pager.rollback_savepoint()?;
+            self.restore_tables(&table_ids)?;
+        }
+
+        if result.is_err() && conflict_resolution == ConflictResolution::Rollback {
+            self.rollback_savepoints(0)?;
+        }
+
+        if self.savepoints.is_empty() {
+            self.deferred_foreign_keys.clear();
+        }
+
+        result
+    }
+
+    /// BEGIN starts the transaction, SAVEPOINT starts it too if there is none. COMMIT and RELEASE
+    /// of the outermost savepoint end the transaction, the deferred foreign keys are checked first
+    fn execute_transaction(&mut self, transaction_statement: &TransactionStatement) -> io::Result<usize> {
+        match transaction_statement {
+            TransactionStatement::Begin if !self.savepoints.is_empty() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Cannot start a transaction within a transaction",
+                ));
+            },
+            TransactionStatement::Commit | TransactionStatement::Rollback(None) if self.savepoints.is_empty() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "There is no active transaction",
+                ));
+            },
+            TransactionStatement::Begin => self.begin_savepoint(None),
+            TransactionStatement::Savepoint(name) => self.begin_savepoint(Some(name.clone())),
+            TransactionStatement::Commit => self.release_savepoints(0)?,
+            TransactionStatement::Release(name) => {
+                let position = self.find_savepoint(name)?;
+                self.release_savepoints(position)?;
+            },
+            TransactionStatement::Rollback(None) => {
+                self.rollback_savepoints(0)?;
+                self.deferred_foreign_keys.clear();
+            },
+            TransactionStatement::Rollback(Some(name)) => {
+                // The savepoint stays after the rollback to it
+                let position = self.find_savepoint(name)?;
+                let name = self.savepoints[position].clone();
+
+                self.rollback_savepoints(position)?;
+                self.begin_savepoint(name);
+            },
+        }
+
+        Ok(0)
+    }
+
+    fn begin_savepoint(&mut self, name: Option<String>) {
+        self.pager.begin_savepoint();
+        self.savepoints.push(name);
+    }
+
+    /// The method returns the position of the innermost savepoint with the name
+    fn find_savepoint(&self, name: &str) -> io::Result<usize> {
+        self.savepoints
+            .iter()
+            .rposition(|savepoint| savepoint.as_ref().is_some_and(|savepoint| savepoint.eq_ignore_ascii_case(name)))
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No such savepoint: {}", name),
+            ))
+    }
+
+    /// The method releases the savepoint at the position and the ones started after it.
+    /// The transaction is committed with its outermost savepoint
+    fn release_savepoints(&mut self, position: usize) -> io::Result<()> {
+        if position == 0 {
+            self.check_deferred_foreign_keys()?;
+            self.deferred_foreign_keys.clear();
+        }
+
+        while self.savepoints.len() > position {
+            self.savepoints.pop();
+            self.pager.release_savepoint();
+        }
+
+        Ok(())
+    }
+
+    /// The method rolls back the changes made after the savepoint at the position
+    /// and removes it together with the ones started after it
+    fn rollback_savepoints(&mut self, position: usize) -> io::Result<()> {
+        let mut table_ids = BTreeSet::new();
+
+        while self.savepoints.len() > position {
+            self.savepoints.pop();
+            table_ids.extend(self.pager.rollback_savepoint()?);
+        }
+
+        self.restore_tables(&table_ids)
+    }
+
+    /// The tables are rebuilt after their rows are rolled back, so the indexes match the rows.
+    /// The whole schema is loaded again if the schema table is rolled back
+    fn restore_tables(&mut self, table_ids: &BTreeSet<u32>) -> io::Result<()> {
+        if table_ids.is_empty() {
+            return Ok(());
+        }
+
+        self.programs.clear();
+
+        if table_ids.contains(&SCHEMA_TABLE_ID) {
+            self.tables = vec![Self::create_schema_table(&self.pager)?];
+            self.views.clear();
+            self.triggers.clear();
+
+            return self.load_schema();
+        }
+
+        for table_position in 0..self.tables.len() {
+            let table = &self.tables[table_position];
+
+            if !table_ids.contains(&table.get_id()) {
+                continue;
+            }
+
+            let indexes = self.get_index_statements(table.get_name())?;
+            let mut restored_table = Table::new(table.get_id(), table.get_definition().clone(), &self.pager)?;
+
+            for create_index_statement in indexes {
+                restored_table.create_index(&self.pager, &create_index_statement)?;
+            }
+
+            self.tables[table_position] = restored_table;
+        }
+
+        // The sequences and the statistics of the rebuilt tables are read again
+        self.load_sequences()?;
+        self.load_statistics()
+    }
+
+    fn execute_statement(&mut self, statement: &Statement) -> io::Result<usize> {
+        match statement {
+            // The changed rows are returned by execute_returning
+            _ if !statement.get_returning().is_empty() => {
+                let (column_names, rows) = self.execute_returning(statement)?;
+
+                for row_data in rows.iter() {
+                    Self::print_row(&column_names, row_data, false);
+                }
+
+                Ok(rows.len())
+            },
+            // The statements with the subqueries, the triggers or the foreign keys are run without the compiler
+            Statement::Insert(insert_statement) if self.has_row_actions(&insert_statement.table_name) => {
+                Ok(self.insert_rows(insert_statement)?.len())
+            },
+            Statement::Delete(delete_statement) if self.has_row_actions(&delete_statement.table_name) => {
+                Ok(self.delete_rows(delete_statement)?.len())
+            },
+            Statement::Insert(insert_statement) => match self.prepare(statement) {
+                Ok(program) => self.run_program(&program, |_| {}),
+                Err(err) if err.kind() == io::ErrorKind::Unsupported => Ok(self.insert_rows(insert_statement)?.len()),
+                Err(err) => Err(err),
+            },
+            Statement::Delete(delete_statement) => match self.prepare(statement) {
+                Ok(program) => self.run_program(&program, |_| {}),
+                Err(err) if err.kind() == io::ErrorKind::Unsupported => Ok(self.delete_rows(delete_statement)?.len()),
+                Err(err) => Err(err),
+            },
+            Statement::Select(select_statement) => {
+                // The row id is printed for the whole rows of one table only
+                let with_row_id = matches!(select_statement.columns[..], [ResultColumn::All])
+                    && !select_statement.from.is_derived()
+                    && select_statement.joins.is_empty()
+                    && select_statement.compound.is_empty()
+                    && !select_statement.is_aggregate()?;
+
+                // The queries the compiler doesn't support are run by the operators
+                match self.prepare(statement) {
+                    Ok(program) => {
+                        let column_names = program.column_names.clone();
+                        self.run_program(&program, |row_data| Self::print_row(&column_names, &row_data, with_row_id))
+                    },
+                    Err(err) if err.kind() == io::ErrorKind::Unsupported => self.with_query(select_statement, |query| {
+                        let column_names = query.get_column_names();
+
+                        query.run(|row_data| Self::print_row(&column_names, &row_data, with_row_id))
+                    }),
+                    Err(err) => Err(err),
+                }
+            },
+            Statement::Update(update_statement) => Ok(self.update(update_statement)?.len()),
+            Statement::CreateTable(create_table_statement) => self.create_table(create_table_statement),
+            Statement::DropTable(drop_table_statement) => self.drop_table(drop_table_statement),
+            Statement::AlterTable(alter_table_statement) => self.alter_table(alter_table_statement),
+            Statement::CreateIndex(create_index_statement) => self.create_index(create_index_statement),
+            Statement::DropIndex(drop_index_statement) => self.drop_index(drop_index_statement),
+            Statement::CreateView(create_view_statement) => self.create_view(create_view_statement),
+            Statement::DropView(drop_view_statement) => self.drop_view(drop_view_statement),
+            Statement::CreateTrigger(create_trigger_statement) => self.create_trigger(create_trigger_statement),
+            Statement::DropTrigger(drop_trigger_statement) => self.drop_trigger(drop_trigger_statement),
+            Statement::Analyze(analyze_statement) => self.analyze(analyze_statement),
+            Statement::Pragma(pragma_statement) => self.pragma(pragma_statement),
+            Statement::Transaction(transaction_statement) => self.execute_transaction(transaction_statement),
+            Statement::Explain(explain_statement) => {
+                let lines = self.explain(explain_statement)?;
+
+                if explain_statement.mode != ExplainMode::Bytecode {
+                    println!("QUERY PLAN");
+                }
+
+                for line in lines {
+                    println!("{}", line);
+                }
+
+                Ok(0)
+            },
+        }
+    }
+
+    /// The method dumps the database to the file and stops the process
+    /// The changes of the unfinished transaction are rolled back before the database is saved
+    pub fn exit(&mut self) -> ! {
+        if let Err(err) = self.rollback_savepoints(0) {
+            println!("Can't roll back the transaction: {:?}", err);
+        }
+
+        if let Err(err) = self.pager.save() {
+            println!("Can't dump database to the file: {:?}", err);
+        }
+
+        process::exit(0);
+    }
+
+    fn execute_meta_command(
+        &mut self,
+        parsed_meta_command: &Option<String>,
+    ) -> io::Result<usize> {
+        let meta_command = parsed_meta_command.as_ref().unwrap();
+
+        match meta_command.as_str() {
+            ".exit" => self.exit(),
+            _ => {
+                println!("The meta command was not implemented yet");
+                Ok(0)
+            }
+        }
+    }
+
+    pub fn execute(&mut self, parsed_statement: ParsedStatement) -> io::Result<usize> {
+        match parsed_statement.statement_type {
+            Statements::Statement => {
+                let mut statement = parsed_statement.statement.unwrap();
+                self.expand_views(&mut statement)?;
+
+                // The programs are compiled against the schema, so they are dropped by the statements which could change it
+                if !matches!(statement, Statement::Select(_) | Statement::Insert(_) | Statement::Update(_) | Statement::Delete(_)) {
+                    self.programs.clear();
+                }
+
+                match statement {
+                    Statement::Transaction(_) => self.execute_statement(&statement),
+                    _ => self.execute_atomically(&statement),
+                }
+            },
+            Statements::MetaCommand => {
+                self.execute_meta_command(&parsed_statement.meta_command)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statement::expression::Expression;
+    use crate::backend::table::ScanPlan;
+
+    fn create_database() -> Database {
+        Database::new(Pager::new("./not_existing_test_database"))
+    }
+
+    fn execute(database: &mut Database, statement_str: &str) -> io::Result<usize> {
+        database.execute(ParsedStatement::new(statement_str)?)
+    }
+
+    fn select_rows(database: &mut Database, statement_str: &str) -> Vec<RowData> {
+        let mut parsed_statement = ParsedStatement::new(statement_str).unwrap();
+        let Some(Statement::Select(select_statement)) = &mut parsed_statement.statement else {
+            panic!("The statement {} is not a select", statement_str);
+        };
+
+        expand_views(&database.views, select_statement).unwrap();
+
+        database.select_rows(select_statement).unwrap()
+    }
+
+    fn select_ids(database: &mut Database, statement_str: &str) -> Vec<i64> {
+        select_rows(database, statement_str).into_iter().map(|row_data| row_data.id).collect()
+    }
+
+    fn select_values(database: &mut Database, statement_str: &str) -> Vec<Vec<Value>> {
+        select_rows(database, statement_str).into_iter().map(|row_data| row_data.values).collect()
+    }
+
+    fn returning_values(database: &mut Database, statement_str: &str) -> Vec<Vec<Value>> {
+        let statement = ParsedStatement::new(statement_str).unwrap().statement.unwrap();
+        let (_, rows) = database.execute_returning(&statement).unwrap();
+
+        rows.into_iter().map(|row_data| row_data.values).collect()
+    }
+
+    fn fill_database(database: &mut Database) {
+        execute(database, "create table users (email text, user_name text)").unwrap();
+
+        for (email, user_name) in [("a@x.com", "alice"), ("b@x.com", "bob"), ("c@x.com", "carol")] {
+            execute(database, &format!("insert into users values ('{}', '{}')", email, user_name)).unwrap();
+        }
+    }
+
+    #[test]
+    fn it_should_find_rows_using_index() {
+        let mut database = create_database();
+        fill_database(&mut database);
+
+        execute(&mut database, "create index users_name on users (user_name)").unwrap();
+
+        // The index is cheaper than the table scan only if the table has enough rows
+        for user_num in 0..20 {
+            execute(&mut database, &format!("insert into users values ('{}@y.com', 'user{}')", user_num, user_num)).unwrap();
+        }
+
+        let where_clause = Some(Expression::parse(&mut TokenStream::new("user_name >= 'b' and user_name < 'c'").unwrap()).unwrap());
+        let table = Database::find_table_mut(&mut database.tables, "users").unwrap();
+        let ScanPlan::Index(index, _) = table.plan_scan(&database.pager, &where_clause, &[]).scan_plan else {
+            panic!("The index should be used");
+        };
+        assert_eq!(index.get_name(), "users_name");
+
+        assert_eq!(execute(&mut database, "select * from users where user_name = 'bob'").unwrap(), 1);
+        assert_eq!(execute(&mut database, "select * from users where user_name > 'alice'").unwrap(), 22);
+        assert_eq!(execute(&mut database, "select * from users where 'bob' >= user_name").unwrap(), 2);
+    }
+
+    #[test]
+    fn it_should_reject_duplicates_in_unique_index() {
+        let mut database = create_database();
+        fill_database(&mut database);
+
+        execute(&mut database, "create unique index users_email on users (email)").unwrap();
+
+        let err = execute(&mut database, "insert into users values ('a@x.com', 'dup')").unwrap_err();
+        assert_eq!(err.to_string(), "UNIQUE constraint failed: users.email = 'a@x.com'");
+
+        let err = execute(&mut database, "update users set email = 'b@x.com' where rowid = 1").unwrap_err();
+        assert_eq!(err.to_string(), "UNIQUE constraint failed: users.email = 'b@x.com'");
+
+        // The values could be swapped in one statement
+        assert_eq!(execute(&mut database, "update users set email = user_name where rowid <= 2").unwrap(), 2);
+        assert_eq!(execute(&mut database, "select * from users where email = 'alice'").unwrap(), 1);
+    }
+
+    #[test]
+    fn it_should_maintain_index_on_delete() {
+        let mut database = create_database();
+        fill_database(&mut database);
+
+        execute(&mut database, "create unique index users_email on users (email)").unwrap();
+
+        assert_eq!(execute(&mut database, "delete from users where email = 'b@x.com'").unwrap(), 1);
+        assert_eq!(execute(&mut database, "select * from users where email = 'b@x.com'").unwrap(), 0);
+        assert_eq!(execute(&mut database, "insert into users values ('b@x.com', 'bob')").unwrap(), 1);
+        assert_eq!(execute(&mut database, "select * from users where email = 'b@x.com'").unwrap(), 1);
+    }
+
+    #[test]
+    fn it_should_drop_index() {
+        let mut database = create_database();
+        fill_database(&mut database);
+
+        execute(&mut database, "create index users_name on users (user_name)").unwrap();
+        assert!(execute(&mut database, "create index users_name on users (email)").is_err());
+        assert!(execute(&mut database, "create index if not exists users_name on users (email)").is_ok());
+
+        execute(&mut database, "drop index users_name").unwrap();
+        assert!(execute(&mut database, "drop index users_name").is_err());
+        assert!(execute(&mut database, "drop index if exists users_name").is_ok());
+    }
+
+    #[test]
+    fn it_should_use_quoted_identifiers() {
+        let mut database = create_database();
+
+        // The quoted keywords are identifiers
+        execute(&mut database, "create table t (\"select\" text, `from` integer)").unwrap();
+        execute(&mut database, "insert into t (\"select\", \"from\") values ('a', 2)").unwrap();
+        execute(&mut database, "create index \"index\" on t (\"from\")").unwrap();
+
+        assert_eq!(
+            select_values(&mut database, "select \"select\" \"order\", t.\"from\" from t where \"from\" = 2 order by \"order\""),
+            vec![vec![Value::Text("a".to_string()), Value::Integer(2)]],
+        );
+        assert!(execute(&mut database, "create table u (select text)").is_err());
+    }
+
+    #[test]
+    fn it_should_restore_quoted_identifiers() {
+        use std::fs;
+
+        let file_name = std::env::temp_dir().join("rql_quoted_identifiers_test_database");
+        let file_name = file_name.to_str().unwrap();
+        let _ = fs::remove_file(file_name);
+
+        let mut database = Database::new(Pager::new(file_name));
+        execute(&mut database, "create table \"my table\" (\"the col\" text, \"select\" integer check (\"select\" > 0))").unwrap();
+        execute(&mut database, "create unique index \"my index\" on \"my table\" (\"the col\")").unwrap();
+        execute(&mut database, "create view \"my view\" (\"a b\") as select \"the col\" from \"my table\" where \"select\" > 1").unwrap();
+        execute(&mut database, "create table log (\"the col\" text)").unwrap();
+        execute(&mut database, "create trigger \"my trigger\" after insert on \"my table\" begin insert into log values (new.\"the col\"); end").unwrap();
+        execute(&mut database, "insert into \"my table\" values ('a', 1), ('b', 2)").unwrap();
+        database.pager.save().unwrap();
+
+        let mut database = Database::new(Pager::new(file_name));
+        fs::remove_file(file_name).unwrap();
+
+        let text = |value: &str| Value::Text(value.to_string());
+
+        assert_eq!(
+            select_values(&mut database, "select \"the col\", \"select\" from \"my table\""),
+            vec![vec![text("a"), Value::Integer(1)], vec![text("b"), Value::Integer(2)]],
+        );
+        assert_eq!(select_values(&mut database, "select \"a b\" from \"my view\""), vec![vec![text("b")]]);
+        assert_eq!(select_values(&mut database, "select * from log"), vec![vec![text("a")], vec![text("b")]]);
+        assert!(execute(&mut database, "insert into \"my table\" values ('a', 3)").is_err());
+        assert!(execute(&mut database, "insert into \"my table\" values ('c', 0)").is_err());
+    }
+
+    #[test]
+    fn it_should_reject_corrupt_files() {
+        use std::fs;
+        use crate::backend::pager::page::PAGE_SIZE;
+
+        let file_name = std::env::temp_dir().join("rql_corrupt_file_test_database");
+        let file_name = file_name.to_str().unwrap();
+        let _ = fs::remove_file(file_name);
+
+        let mut database = Database::new(Pager::new(file_name));
+        execute(&mut database, "create table t (a text)").unwrap();
+        execute(&mut database, "insert into t values ('x')").unwrap();
+        database.pager.save().unwrap();
+        drop(database);
+
+        let saved_data = fs::read(file_name).unwrap();
+        let last_page = saved_data.len() - PAGE_SIZE;
+        let open_error = |data: &[u8]| {
+            fs::write(file_name, data).unwrap();
+            Pager::open(file_name).err().unwrap().to_string()
+        };
+
+        // The file written before the header was added
+        assert!(open_error(&saved_data[PAGE_SIZE..PAGE_SIZE + 100]).contains("isn't a multiple of the page size"));
+        assert!(open_error(&saved_data[PAGE_SIZE..]).contains("isn't a database file"));
+
+        let mut data = saved_data.clone();
+        data[8..12].copy_from_slice(&2u32.to_be_bytes());
+        assert!(open_error(&data).contains("format version 2"));
+
+        // The number of cells and the value tag of the first cell of the table page
+        let mut data = saved_data.clone();
+        data[last_page + 4..last_page + 6].copy_from_slice(&u16::MAX.to_be_bytes());
+        assert!(open_error(&data).contains("The page is corrupt"));
+
+        let mut data = saved_data.clone();
+        data[last_page + 16] = 9;
+        assert!(open_error(&data).contains("Unknown value tag 9"));
+
+        fs::write(file_name, &saved_data).unwrap();
+        let mut database = Database::new(Pager::open(file_name).unwrap());
+        fs::remove_file(file_name).unwrap();
+
+        assert_eq!(select_values(&mut database, "select a from t"), vec![vec![Value::Text("x".to_string())]]);
+    }
+
+    #[test]
+    fn it_should_check_column_constraints() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (\
+            email text primary key, \
+            user_name text not null, \
+            age integer check (age > 0) default 18\
+        )").unwrap();
+
+        execute(&mut database, "insert into users (email, user_name) values ('a@x.com', 'alice')").unwrap();
+        assert_eq!(execute(&mut database, "select * from users where age = 18").unwrap(), 1);
+
+        let err = execute(&mut database, "insert into users values ('a@x.com', 'bob', 20)").unwrap_err();
+        assert_eq!(err.to_string(), "UNIQUE constraint failed: users.email = 'a@x.com'");
+
+        let err = execute(&mut database, "insert into users values (null, 'bob', 20)").unwrap_err();
+        assert_eq!(err.to_string(), "NOT NULL constraint failed: users.email = NULL");
+
+        let err = execute(&mut database, "update users set age = -1").unwrap_err();
+        assert_eq!(err.to_string(), "CHECK constraint failed: users (age > 0): users.age = -1");
+
+        let err = execute(&mut database, "insert into users values ('b@x.com', 'bob', 'old')").unwrap_err();
+        assert_eq!(err.to_string(), "datatype mismatch: users.age = 'old'");
+
+        // The text is converted to the column type
+        execute(&mut database, "insert into users values ('b@x.com', 'bob', '20')").unwrap();
+        assert_eq!(execute(&mut database, "select * from users where age = 20").unwrap(), 1);
+    }
+
+    #[test]
+    fn it_should_check_table_constraints() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table grades (\
+            student text, course text, grade integer, \
+            unique (student, course), \
+            constraint valid_grade check (grade between 1 and 5)\
+        )").unwrap();
+
+        execute(&mut database, "insert into grades values ('alice', 'math', 5)").unwrap();
+        execute(&mut database, "insert into grades values ('alice', 'art', 4)").unwrap();
+
+        // NULLs are never equal, so they don't violate the unique constraint
+        execute(&mut database, "insert into grades values (null, 'art', 3)").unwrap();
+        execute(&mut database, "insert into grades values (null, 'art', 3)").unwrap();
+
+        let err = execute(&mut database, "insert into grades values ('alice', 'math', 3)").unwrap_err();
+        assert_eq!(err.to_string(), "UNIQUE constraint failed: grades.student = 'alice', grades.course = 'math'");
+
+        let err = execute(&mut database, "update grades set grade = 6 where course = 'math'").unwrap_err();
+        assert_eq!(err.to_string(), "CHECK constraint failed: grades ((grade >= 1) AND (grade <= 5)): grades.grade = 6");
+
+        let err = execute(&mut database, "drop index rustqlight_autoindex_grades_1").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn it_should_manage_tables() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (email text)").unwrap();
+        assert!(execute(&mut database, "create table users (name text)").is_err());
+        assert!(execute(&mut database, "create table if not exists users (name text)").is_ok());
+        assert!(execute(&mut database, "create table rustqlight_users (name text)").is_err());
+        assert!(execute(&mut database, "create table pairs (a text primary key, b text primary key)").is_err());
+        assert!(execute(&mut database, "insert into rustqlight_schema values ('a', 'b', 'c', 1, 'd')").is_err());
+
+        assert_eq!(execute(&mut database, "select * from rustqlight_schema").unwrap(), 1);
+
+        execute(&mut database, "drop table users").unwrap();
+        assert!(execute(&mut database, "select * from users").is_err());
+        assert!(execute(&mut database, "drop table if exists users").is_ok());
+        assert_eq!(execute(&mut database, "select * from rustqlight_schema").unwrap(), 0);
+    }
+
+    #[test]
+    fn it_should_use_explicit_row_ids() {
+        let mut database = create_database();
+        fill_database(&mut database);
+
+        execute(&mut database, "insert into users (rowid, email) values (10, 'd@x.com')").unwrap();
+        assert_eq!(database.session.last_insert_rowid, 10);
+        assert_eq!(select_values(&mut database, "select last_insert_rowid()"), vec![vec![Value::Integer(10)]]);
+
+        execute(&mut database, "insert into users values ('e@x.com', 'eve')").unwrap();
+        assert_eq!(database.session.last_insert_rowid, 11);
+        assert_eq!(select_values(&mut database, "select last_insert_rowid()"), vec![vec![Value::Integer(11)]]);
+
+        let err = execute(&mut database, "insert into users (rowid, email) values (2, 'f@x.com')").unwrap_err();
+        assert_eq!(err.to_string(), "UNIQUE constraint failed: users.rowid = 2");
+
+        let err = execute(&mut database, "update users set rowid = 1 where rowid = 2").unwrap_err();
+        assert_eq!(err.to_string(), "UNIQUE constraint failed: users.rowid = 1");
+
+        execute(&mut database, "update users set rowid = 20 where rowid = 11").unwrap();
+        assert_eq!(execute(&mut database, "select * from users where rowid = 20 and email = 'e@x.com'").unwrap(), 1);
+    }
+
+    #[test]
+    fn it_should_alias_integer_primary_key() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table items (id integer primary key, name text)").unwrap();
+        execute(&mut database, "insert into items (name) values ('first')").unwrap();
+        execute(&mut database, "insert into items values (5, 'fifth')").unwrap();
+        execute(&mut database, "insert into items values (null, 'sixth')").unwrap();
+
+        assert_eq!(execute(&mut database, "select * from items where rowid = 6 and id = 6").unwrap(), 1);
+        assert_eq!(execute(&mut database, "select * from items where id = last_insert_rowid()").unwrap(), 1);
+
+        let err = execute(&mut database, "insert into items values (5, 'duplicate')").unwrap_err();
+        assert_eq!(err.to_string(), "UNIQUE constraint failed: items.id = 5");
+
+        let err = execute(&mut database, "insert into items values ('five', 'text')").unwrap_err();
+        assert_eq!(err.to_string(), "datatype mismatch: items.id = 'five'");
+
+        execute(&mut database, "update items set id = 7 where id = 6").unwrap();
+        assert_eq!(execute(&mut database, "select * from items where rowid = 7 and name = 'sixth'").unwrap(), 1);
+
+        // The table without AUTOINCREMENT reuses the biggest id after it was deleted
+        execute(&mut database, "delete from items where id = 7").unwrap();
+        execute(&mut database, "insert into items (name) values ('again')").unwrap();
+        assert_eq!(database.session.last_insert_rowid, 6);
+    }
+
+    #[test]
+    fn it_should_not_reuse_autoincrement_ids() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table events (id integer primary key autoincrement, name text)").unwrap();
+        execute(&mut database, "insert into events (name) values ('a')").unwrap();
+        execute(&mut database, "insert into events values (100, 'b')").unwrap();
+        execute(&mut database, "delete from events where id = 100").unwrap();
+        execute(&mut database, "insert into events (name) values ('c')").unwrap();
+        assert_eq!(database.session.last_insert_rowid, 101);
+
+        assert_eq!(execute(&mut database, "select * from rustqlight_sequence where name = 'events' and seq = 101").unwrap(), 1);
+        assert!(execute(&mut database, "drop table rustqlight_sequence").is_err());
+        assert!(execute(&mut database, "create table bad (name text primary key autoincrement)").is_err());
+
+        execute(&mut database, "drop table events").unwrap();
+        assert_eq!(execute(&mut database, "select * from rustqlight_sequence").unwrap(), 0);
+    }
+
+    #[test]
+    fn it_should_find_null_values() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table profiles (name text not null, bio text check (bio != ''))").unwrap();
+        execute(&mut database, "create index profiles_bio on profiles (bio)").unwrap();
+
+        execute(&mut database, "insert into profiles values ('alice', 'likes rust')").unwrap();
+        execute(&mut database, "insert into profiles (name) values ('bob')").unwrap();
+        execute(&mut database, "insert into profiles values ('carol', null)").unwrap();
+
+        // The CHECK constraint is satisfied by NULL, but not by the empty text
+        assert!(execute(&mut database, "insert into profiles values ('dave', '')").is_err());
+
+        assert_eq!(execute(&mut database, "select * from profiles where bio is null").unwrap(), 2);
+        assert_eq!(execute(&mut database, "select * from profiles where bio is not null").unwrap(), 1);
+        assert_eq!(execute(&mut database, "select * from profiles where bio = null").unwrap(), 0);
+        assert_eq!(execute(&mut database, "select * from profiles where not bio = 'likes rust'").unwrap(), 0);
+        assert_eq!(execute(&mut database, "select * from profiles where coalesce(bio, 'none') = 'none'").unwrap(), 2);
+    }
+
+    #[test]
+    fn it_should_order_and_limit_rows() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (name text, age integer)").unwrap();
+
+        for (name, age) in [("dave", "30"), ("alice", "null"), ("carol", "25"), ("bob", "30"), ("eve", "null")] {
+            execute(&mut database, &format!("insert into users values ('{}', {})", name, age)).unwrap();
+        }
+
+        assert_eq!(select_ids(&mut database, "select * from users order by age"), vec![2, 5, 3, 1, 4]);
+        assert_eq!(select_ids(&mut database, "select * from users order by age desc, name"), vec![4, 1, 3, 2, 5]);
+        assert_eq!(select_ids(&mut database, "select * from users order by age nulls last, name desc"), vec![3, 1, 4, 5, 2]);
+        assert_eq!(select_ids(&mut database, "select * from users order by rowid desc limit 2"), vec![5, 4]);
+        assert_eq!(select_ids(&mut database, "select * from users order by name limit 2 offset 1"), vec![4, 3]);
+        assert_eq!(select_ids(&mut database, "select * from users order by name limit 3, 10"), vec![1, 5]);
+        assert_eq!(select_ids(&mut database, "select * from users where age is not null order by name limit -1 offset 1"), vec![3, 1]);
+
+        assert!(execute(&mut database, "select * from users limit 'ten'").is_err());
+    }
+
+    #[test]
+    fn it_should_order_rows_using_index() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (name text, age integer)").unwrap();
+        execute(&mut database, "create index users_age_name on users (age, name)").unwrap();
+
+        for (name, age) in [("dave", 30), ("alice", 20), ("carol", 25), ("bob", 30)] {
+            execute(&mut database, &format!("insert into users values ('{}', {})", name, age)).unwrap();
+        }
+
+        let table = Database::find_table_mut(&mut database.tables, "users").unwrap();
+
+        let order_by = |order_by_str: &str| {
+            let statement_str = format!("select * from users order by {}", order_by_str);
+            let Some(Statement::Select(select_statement)) = ParsedStatement::new(&statement_str).unwrap().statement else {
+                unreachable!();
+            };
+
+            select_statement.order_by
+        };
+
+        let where_clause = Some(Expression::parse(&mut TokenStream::new("age = 30").unwrap()).unwrap());
+
+        assert_eq!(table.plan_scan(&database.pager, &None, &order_by("age, name")).order, Some(false));
+        assert_eq!(table.plan_scan(&database.pager, &None, &order_by("age desc")).order, Some(true));
+        assert_eq!(table.plan_scan(&database.pager, &where_clause, &order_by("name desc")).order, Some(true));
+        assert_eq!(table.plan_scan(&database.pager, &None, &order_by("name")).order, None);
+        assert_eq!(table.plan_scan(&database.pager, &None, &order_by("age nulls last")).order, None);
+
+        assert_eq!(select_ids(&mut database, "select * from users order by age desc, name desc"), vec![1, 4, 3, 2]);
+        assert_eq!(select_ids(&mut database, "select * from users where age = 30 order by name"), vec![4, 1]);
+    }
+
+    #[test]
+    fn it_should_scan_rows_in_reverse_across_pages() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table notes (id integer primary key, text text)").unwrap();
+
+        for _ in 0..200 {
+            execute(&mut database, "insert into notes (text) values ('the note which is long enough to fill several pages')").unwrap();
+        }
+
+        let table_id = Database::find_table_mut(&mut database.tables, "notes").unwrap().get_id();
+        assert!(database.pager.get_num_pages(table_id) > 1);
+
+        assert_eq!(select_ids(&mut database, "select * from notes order by id desc limit 3"), vec![200, 199, 198]);
+        assert_eq!(select_ids(&mut database, "select * from notes order by id desc limit 2 offset 197"), vec![3, 2]);
+        assert_eq!(select_ids(&mut database, "select * from notes where id > 2 order by rowid desc").len(), 198);
+    }
+
+    #[test]
+    fn it_should_aggregate_rows() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (email text, domain text, age integer)").unwrap();
+
+        for (email, domain, age) in [("a@x.com", "x.com", "30"), ("b@x.com", "x.com", "20"), ("c@y.com", "y.com", "null"), ("d@z.com", "z.com", "41")] {
+            execute(&mut database, &format!("insert into users values ('{}', '{}', {})", email, domain, age)).unwrap();
+        }
+
+        let text = |value: &str| Value::Text(value.to_string());
+
+        assert_eq!(
+            select_values(&mut database, "select domain, count(*), count(age), sum(age), min(age), max(email) from users group by domain order by count(*) desc, domain"),
+            vec![
+                vec![text("x.com"), Value::Integer(2), Value::Integer(2), Value::Integer(50), Value::Integer(20), text("b@x.com")],
+                vec![text("y.com"), Value::Integer(1), Value::Integer(0), Value::Null, Value::Null, text("c@y.com")],
+                vec![text("z.com"), Value::Integer(1), Value::Integer(1), Value::Integer(41), Value::Integer(41), text("d@z.com")],
+            ],
+        );
+
+        assert_eq!(
+            select_values(&mut database, "select count(distinct domain), avg(age), group_concat(email, ';') from users where age is not null"),
+            vec![vec![Value::Integer(2), Value::Real(91.0 / 3.0), text("a@x.com;b@x.com;d@z.com")]],
+        );
+
+        assert_eq!(
+            select_values(&mut database, "select domain from users group by domain having count(*) > 1 or max(age) > 40 order by domain desc"),
+            vec![vec![text("z.com")], vec![text("x.com")]],
+        );
+
+        assert_eq!(
+            select_values(&mut database, "select count(*), sum(age), avg(age) from users where age > 100"),
+            vec![vec![Value::Integer(0), Value::Null, Value::Null]],
+        );
+        assert!(select_values(&mut database, "select count(*) from users where age > 100 group by domain").is_empty());
+
+        assert!(execute(&mut database, "select email from users where count(*) > 1").is_err());
+        assert!(execute(&mut database, "select count(max(age)) from users").is_err());
+        assert!(execute(&mut database, "select email from users having age > 1").is_err());
+
+        // GROUP BY refers to the result columns by their positions and aliases like ORDER BY,
+        // the columns of the table are preferred to the aliases
+        assert_eq!(
+            select_values(&mut database, "select age >= 30 as senior, count(*) from users group by senior order by 1"),
+            vec![vec![Value::Null, Value::Integer(1)], vec![Value::Integer(0), Value::Integer(1)], vec![Value::Integer(1), Value::Integer(2)]],
+        );
+        assert_eq!(
+            select_values(&mut database, "select domain, count(*) from users group by 1 order by 2 desc, 1 limit 1"),
+            vec![vec![text("x.com"), Value::Integer(2)]],
+        );
+        assert_eq!(
+            select_values(&mut database, "select count(*) as domain from users group by domain order by 1"),
+            vec![vec![Value::Integer(1)], vec![Value::Integer(1)], vec![Value::Integer(2)]],
+        );
+
+        for group_by in ["0", "3"] {
+            let err = execute(&mut database, &format!("select domain, count(*) from users group by {}", group_by)).unwrap_err();
+            assert_eq!(err.to_string(), "GROUP BY term 1 out of range - should be between 1 and 2");
+        }
+
+        assert!(execute(&mut database, "select domain, count(*) as total from users group by total").is_err());
+        assert!(execute(&mut database, "select domain, count(*) from users group by 2").is_err());
+    }
+
+    #[test]
+    fn it_should_select_expressions() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (email text, age integer, score real)").unwrap();
+        execute(&mut database, "insert into users values ('a@x.com', 30, 1.5)").unwrap();
+        execute(&mut database, "insert into users values ('b@x.com', 20, '2')").unwrap();
+
+        let text = |value: &str| Value::Text(value.to_string());
+
+        assert_eq!(
+            select_values(&mut database, "select email, age * 2 as doubled, score from users order by doubled"),
+            vec![
+                vec![text("b@x.com"), Value::Integer(40), Value::Real(2.0)],
+                vec![text("a@x.com"), Value::Integer(60), Value::Real(1.5)],
+            ],
+        );
+
+        assert_eq!(
+            select_values(&mut database, "select rowid || ':' || email name from users order by 1 desc"),
+            vec![vec![text("2:b@x.com")], vec![text("1:a@x.com")]],
+        );
+
+        assert_eq!(
+            select_values(&mut database, "select * from users order by 3 limit 1"),
+            vec![vec![text("a@x.com"), Value::Integer(30), Value::Real(1.5)]],
+        );
+
+        // The columns of SELECT without FROM are evaluated once
+        assert_eq!(
+            select_values(&mut database, "select 1 + 2, 'a' || 'b' as ab"),
+            vec![vec![Value::Integer(3), text("ab")]],
+        );
+        assert!(select_values(&mut database, "select 1 where 0").is_empty());
+        assert!(execute(&mut database, "select *").is_err());
+
+        assert!(execute(&mut database, "select email from users order by 2").is_err());
+        assert!(execute(&mut database, "insert into users values ('c@x.com', 1, 'many')").is_err());
+    }
+
+    #[test]
+    fn it_should_join_tables() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (id integer primary key, name text)").unwrap();
+        execute(&mut database, "create table orders (user_id integer, amount integer)").unwrap();
+        execute(&mut database, "insert into users (name) values ('ann')").unwrap();
+        execute(&mut database, "insert into users (name) values ('bob')").unwrap();
+        execute(&mut database, "insert into orders values (1, 10)").unwrap();
+        execute(&mut database, "insert into orders values (1, 20)").unwrap();
+        execute(&mut database, "insert into orders values (3, 5)").unwrap();
+
+        let text = |value: &str| Value::Text(value.to_string());
+
+        let expected_rows = vec![
+            vec![text("ann"), Value::Integer(10)],
+            vec![text("ann"), Value::Integer(20)],
+        ];
+
+        // The hash join, the nested loop join and the index nested loop join give the same rows
+        assert_eq!(select_values(&mut database, "select name, amount from users u join orders o on o.user_id = u.id"), expected_rows);
+        assert_eq!(select_values(&mut database, "select name, amount from users, orders where user_id > id - 1 and user_id < id + 1"), expected_rows);
+        assert_eq!(select_values(&mut database, "select name, amount from orders join users on id = user_id order by amount"), expected_rows);
+
+        assert_eq!(
+            select_values(&mut database, "select u.name, sum(o.amount) from users u left join orders o on u.id = o.user_id group by u.name"),
+            vec![vec![text("ann"), Value::Integer(30)], vec![text("bob"), Value::Null]],
+        );
+
+        assert_eq!(
+            select_values(&mut database, "select o.* from orders o left join users u on u.id = o.user_id where u.id is null"),
+            vec![vec![Value::Integer(3), Value::Integer(5)]],
+        );
+
+        assert!(execute(&mut database, "select name from users cross join users").is_err());
+    }
+
+    #[test]
+    fn it_should_analyze_tables() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (id integer primary key, name text)").unwrap();
+        execute(&mut database, "create table orders (user_id integer, amount integer)").unwrap();
+
+        for user_num in 1..=30 {
+            execute(&mut database, &format!("insert into users (name) values ('user{}')", user_num)).unwrap();
+            execute(&mut database, &format!("insert into orders values ({}, {})", user_num % 10, user_num)).unwrap();
+        }
+
+        execute(&mut database, "insert into orders values (null, 100)").unwrap();
+        execute(&mut database, "analyze").unwrap();
+
+        // One row has the number of the rows, the others describe the columns
+        assert_eq!(execute(&mut database, "select * from rustqlight_stat where tbl = 'orders'").unwrap(), 3);
+        assert_eq!(execute(&mut database, "select * from rustqlight_stat where tbl = 'orders' and col is null and nrow = 31").unwrap(), 1);
+        assert_eq!(execute(&mut database, "select * from rustqlight_stat where col = 'user_id' and ndistinct = 10 and nnull = 1").unwrap(), 1);
+
+        let table = Database::find_table_mut(&mut database.tables, "orders").unwrap();
+        let statistics = table.get_statistics().cloned().unwrap();
+        table.set_statistics(TableStatistics { row_count: 0, columns: BTreeMap::new() });
+
+        database.load_statistics().unwrap();
+        assert_eq!(Database::find_table_mut(&mut database.tables, "orders").unwrap().get_statistics(), Some(&statistics));
+
+        // The joined rows don't depend on the order the tables are joined in
+        assert_eq!(
+            select_values(&mut database, "select u.name, o.amount from users u join orders o on o.user_id = u.id where o.amount > 25 order by o.amount"),
+            vec![
+                vec![Value::Text("user6".to_string()), Value::Integer(26)],
+                vec![Value::Text("user7".to_string()), Value::Integer(27)],
+                vec![Value::Text("user8".to_string()), Value::Integer(28)],
+                vec![Value::Text("user9".to_string()), Value::Integer(29)],
+            ],
+        );
+
+        execute(&mut database, "drop table orders").unwrap();
+        assert_eq!(execute(&mut database, "select * from rustqlight_stat where tbl = 'orders'").unwrap(), 0);
+        assert!(execute(&mut database, "analyze orders").is_err());
+    }
+
+    #[test]
+    fn it_should_explain_queries() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (id integer primary key, name text, age integer)").unwrap();
+        execute(&mut database, "create table orders (user_id integer, amount integer)").unwrap();
+        execute(&mut database, "create index users_age on users (age)").unwrap();
+
+        for (name, age) in [("ann", 30), ("bob", 25), ("cid", 40)] {
+            execute(&mut database, &format!("insert into users (name, age) values ('{}', {})", name, age)).unwrap();
+        }
+
+        for (user_id, amount) in [(1, 10), (1, 20), (2, 5)] {
+            execute(&mut database, &format!("insert into orders values ({}, {})", user_id, amount)).unwrap();
+        }
+
+        let mut explain = |statement_str: &str| {
+            let Some(Statement::Explain(explain_statement)) = ParsedStatement::new(statement_str).unwrap().statement else {
+                unreachable!();
+            };
+
+            database.explain(&explain_statement)
+        };
+
+        assert_eq!(
+            explain("explain query plan select * from users where age = 30 order by age desc").unwrap(),
+            vec![
+                "PROJECT users.id, users.name, users.age (estimated rows=1)",
+                "`--FILTER age = 30 (estimated rows=1)",
+                "   `--SEARCH users USING INDEX users_age (age=30) IN REVERSE ORDER",
+            ],
+        );
+
+        assert_eq!(
+            explain("explain query plan delete from users where id = 2").unwrap(),
+            vec!["DELETE FROM users", "`--FILTER id = 2 (estimated rows=1)", "   `--SEARCH users USING ROWID (rowid=2)"],
+        );
+
+        // Every operator reports the number of the rows it returned
+        let lines = explain("explain analyze select name, amount from users u join orders o on o.user_id = u.id limit 2").unwrap();
+        assert!(lines[0].starts_with("LIMIT 2 ") && lines[0].contains("actual rows=2 loops=1"));
+        assert!(lines.iter().any(|line| line.contains("JOIN") && line.contains("actual rows=2")));
+        assert_eq!(lines.last().unwrap().split(" in ").next(), Some("Returned 2 rows"));
+
+        assert!(explain("explain analyze delete from users").is_err());
+        assert!(ParsedStatement::new("explain explain query plan select * from users").is_err());
+    }
+
+    #[test]
+    fn it_should_run_compiled_programs() {
+        let mut database = create_database();
+        fill_database(&mut database);
+
+        execute(&mut database, "create index user_name_index on users (user_name)").unwrap();
+
+        let compile = |database: &mut Database, statement_str: &str| {
+            let parsed_statement = ParsedStatement::new(statement_str).unwrap();
+            database.compile(parsed_statement.statement.as_ref().unwrap())
+        };
+
+        // The program is compiled once and gives the same rows as the operators every time it's run
+        for statement_str in [
+            "select * from users",
+            "select user_name, rowid from users where email != 'b@x.com' order by user_name desc",
+            "select email from users where user_name >= 'bob' order by 1 limit 1 offset 1",
+            "select case rowid when 1 then 'one' else coalesce(null, user_name) end from users order by rowid desc",
+            "select cast(rowid as text) || email, -rowid from users where rowid = 2",
+        ] {
+            let program = compile(&mut database, statement_str).unwrap();
+            let expected_rows: Vec<(i64, Vec<Value>)> = select_rows(&mut database, statement_str)
+                .into_iter()
+                .map(|row_data| (row_data.id, row_data.values))
+                .collect();
+
+            for _ in 0..2 {
+                let mut rows = Vec::new();
+                database.run_program(&program, |row_data| rows.push((row_data.id, row_data.values))).unwrap();
+
+                assert_eq!(rows, expected_rows, "{}", statement_str);
+            }
+        }
+
+        let insert_program = compile(&mut database, "insert into users (user_name) values ('dave')").unwrap();
+        assert_eq!(database.run_program(&insert_program, |_| {}).unwrap(), 1);
+        assert_eq!(database.run_program(&insert_program, |_| {}).unwrap(), 1);
+        assert_eq!(database.session.last_insert_rowid, 5);
+
+        let delete_program = compile(&mut database, "delete from users where user_name = 'dave'").unwrap();
+        assert_eq!(database.run_program(&delete_program, |_| {}).unwrap(), 2);
+        assert_eq!(select_ids(&mut database, "select * from users where user_name = 'dave'"), Vec::<i64>::new());
+
+        let explain = |database: &mut Database, statement_str: &str| -> Vec<String> {
+            let opcodes = compile(database, statement_str).unwrap().explain();
+            opcodes[2..].iter().map(|line| line.split_whitespace().nth(1).unwrap().to_string()).collect()
+        };
+
+        assert_eq!(
+            explain(&mut database, "select email from users where rowid > 1"),
+            vec!["OpenRead", "Rewind", "RowId", "Integer", "Binary", "IfNot", "RowId", "Column", "ResultRow", "Next", "Halt"],
+        );
+        assert_eq!(
+            explain(&mut database, "insert into users (email) values ('e@x.com')"),
+            vec!["OpenWrite", "Null", "String", "Null", "Insert", "Halt"],
+        );
+
+        let err = compile(&mut database, "select count(*) from users").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn it_should_reuse_prepared_programs() {
+        let mut database = create_database();
+        fill_database(&mut database);
+
+        let prepare = |database: &mut Database, statement_str: &str| {
+            let parsed_statement = ParsedStatement::new(statement_str).unwrap();
+            database.prepare(parsed_statement.statement.as_ref().unwrap()).unwrap()
+        };
+
+        let run = |database: &mut Database, program: &Program| {
+            let mut rows = Vec::new();
+            database.run_program(program, |row_data| rows.push(row_data.values)).unwrap();
+
+            rows
+        };
+
+        let text = |value: &str| Value::Text(value.to_string());
+
+        let insert_program = prepare(&mut database, "insert into users (email) values ('d@x.com')");
+        let select_program = prepare(&mut database, "select email, user_name from users where email = 'd@x.com'");
+
+        // The programs are found by the SQL of their statements
+        assert!(Rc::ptr_eq(&insert_program, &prepare(&mut database, "INSERT INTO users (email) VALUES ('d@x.com')")));
+
+
+        for num_rows in 1..=3 {
+            assert_eq!(database.run_program(&insert_program, |_| {}).unwrap(), 1);
+            assert_eq!(run(&mut database, &select_program), vec![vec![text("d@x.com"), Value::Null]; num_rows]);
+        }
+
+        // execute() runs the prepared programs
+        let num_programs = database.programs.len();
+        execute(&mut database, "insert into users (email) values ('d@x.com')").unwrap();
+        execute(&mut database, "select email, user_name from users where email = 'd@x.com'").unwrap();
+        assert_eq!(database.programs.len(), num_programs);
+        assert_eq!(run(&mut database, &select_program).len(), 4);
+
+        // The programs compiled against the dropped table are not run on the new one
+        execute(&mut database, "drop table users").unwrap();
+        assert!(database.programs.is_empty());
+        execute(&mut database, "create table users (user_name text, email text unique)").unwrap();
+
+        execute(&mut database, "insert into users (email) values ('d@x.com')").unwrap();
+        let select_program = prepare(&mut database, "select email, user_name from users where email = 'd@x.com'");
+        assert_eq!(run(&mut database, &select_program), vec![vec![text("d@x.com"), Value::Null]]);
+        assert_eq!(select_values(&mut database, "select * from users"), vec![vec![Value::Null, text("d@x.com")]]);
+
+        // The programs are dropped when the failed statement restores the table
+        assert!(execute(&mut database, "insert into users values ('eve', 'e@x.com'), ('dan', 'd@x.com')").is_err());
+        assert!(database.programs.is_empty());
+
+        // The quoted column and the literal with the same text are different statements
+        execute(&mut database, "create table numbers (\"1\" text)").unwrap();
+        execute(&mut database, "insert into numbers values ('one')").unwrap();
+        let column_program = prepare(&mut database, "select \"1\" from numbers");
+        let literal_program = prepare(&mut database, "select 1 from numbers");
+        assert!(!Rc::ptr_eq(&column_program, &literal_program));
+        assert_eq!(run(&mut database, &column_program), vec![vec![text("one")]]);
+        assert_eq!(run(&mut database, &literal_program), vec![vec![Value::Integer(1)]]);
+    }
+
+    #[test]
+    fn it_should_run_subqueries() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (id integer primary key, name text)").unwrap();
+        execute(&mut database, "create table orders (user_id integer, amount integer)").unwrap();
+
+        for name in ["ann", "bob", "cid", "dan"] {
+            execute(&mut database, &format!("insert into users (name) values ('{}')", name)).unwrap();
+        }
+
+        for (user_id, amount) in [("1", 10), ("1", 20), ("3", 5), ("null", 7)] {
+            execute(&mut database, &format!("insert into orders values ({}, {})", user_id, amount)).unwrap();
+        }
+
+        let text = |value: &str| Value::Text(value.to_string());
+
+        assert_eq!(
+            select_values(&mut database, "select name, (select sum(amount) from orders where user_id = users.id) from users where id < 3"),
+            vec![vec![text("ann"), Value::Integer(30)], vec![text("bob"), Value::Null]],
+        );
+        assert_eq!(select_ids(&mut database, "select * from users where id in (select user_id from orders)"), vec![1, 3]);
+        assert_eq!(select_ids(&mut database, "select * from users where id in (2, null, 4)"), vec![2, 4]);
+
+        // The NULL in the subquery makes NOT IN unknown for the values which are not found
+        assert_eq!(select_ids(&mut database, "select * from users where id not in (select user_id from orders)"), Vec::<i64>::new());
+        assert_eq!(
+            select_ids(&mut database, "select * from users u where not exists (select 1 from orders o where o.user_id = u.id)"),
+            vec![2, 4],
+        );
+        assert_eq!(
+            select_values(&mut database, "select t.name, t.total from (select name, sum(amount) as total from users join orders on user_id = id group by name) t where t.total > 5"),
+            vec![vec![text("ann"), Value::Integer(30)]],
+        );
+
+        assert!(execute(&mut database, "select (select id, name from users) from users").is_err());
+
+        execute(&mut database, "update users set name = (select count(*) from orders where user_id = users.id) where id in (select user_id from orders)").unwrap();
+        execute(&mut database, "insert into users values ((select max(id) from users) + 1, 'eve')").unwrap();
+
+        assert_eq!(execute(&mut database, "delete from users where id not in (select user_id from orders where user_id is not null)").unwrap(), 3);
+        assert_eq!(
+            select_values(&mut database, "select * from users"),
+            vec![vec![Value::Integer(1), text("2")], vec![Value::Integer(3), text("1")]],
+        );
+    }
+
+    #[test]
+    fn it_should_run_common_table_expressions() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table org (id integer primary key, name text, manager_id integer)").unwrap();
+
+        for (id, name, manager_id) in [(1, "ceo", "null"), (2, "cto", "1"), (3, "cfo", "1"), (4, "dev", "2"), (5, "ops", "4")] {
+            execute(&mut database, &format!("insert into org values ({}, '{}', {})", id, name, manager_id)).unwrap();
+        }
+
+        let text = |value: &str| Value::Text(value.to_string());
+
+        assert_eq!(
+            select_values(&mut database, "with recursive chain(id, name, depth) as (select id, name, 0 from org where id = 1 union all select org.id, org.name, depth + 1 from org join chain on org.manager_id = chain.id) select name, depth from chain where depth > 1"),
+            vec![vec![text("dev"), Value::Integer(2)], vec![text("ops"), Value::Integer(3)]],
+        );
+        assert_eq!(
+            select_values(&mut database, "with recursive chain(id, depth) as (select id, 0 from org where id = 1 union all select org.id, depth + 1 from org join chain on org.manager_id = chain.id) select depth, count(*) from chain group by depth"),
+            vec![
+                vec![Value::Integer(0), Value::Integer(1)],
+                vec![Value::Integer(1), Value::Integer(2)],
+                vec![Value::Integer(2), Value::Integer(1)],
+                vec![Value::Integer(3), Value::Integer(1)],
+            ],
+        );
+
+        // The anchor without FROM seeds the table with a single row
+        assert_eq!(
+            select_values(&mut database, "with recursive c(x) as (select 1 union all select x + 1 from c where x < 10) select x from c"),
+            (1..=10).map(|x| vec![Value::Integer(x)]).collect::<Vec<_>>(),
+        );
+
+        // LIMIT stops the table which would grow forever, UNION stops when no new rows are found
+        assert_eq!(
+            select_values(&mut database, "with recursive numbers(x) as (select 1 union all select x + 1 from numbers limit 10) select sum(x) from numbers"),
+            vec![vec![Value::Integer(55)]],
+        );
+        assert_eq!(
+            select_values(&mut database, "with recursive numbers(x) as (select 1 union select x % 3 + 1 from numbers) select count(*) from numbers"),
+            vec![vec![Value::Integer(3)]],
+        );
+
+        assert_eq!(
+            select_values(&mut database, "with managers as (select manager_id from org), names as (select name from org where id in (select * from managers)) select * from names"),
+            vec![vec![text("ceo")], vec![text("cto")], vec![text("dev")]],
+        );
+        assert_eq!(
+            select_ids(&mut database, "select * from org where id in (with org as (select 3 as id from org) select id from org)"),
+            vec![3],
+        );
+
+        assert!(execute(&mut database, "with a(x, y) as (select id from org) select * from a").is_err());
+        assert!(execute(&mut database, "with a as (select id from org), a as (select id from org) select * from a").is_err());
+    }
+
+    #[test]
+    fn it_should_compute_window_functions() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table employees (id integer primary key, department text, salary integer)").unwrap();
+
+        for (id, department, salary) in [(1, "a", "100"), (2, "a", "200"), (3, "a", "200"), (4, "b", "50"), (5, "b", "null"), (6, "c", "300")] {
+            execute(&mut database, &format!("insert into employees values ({}, '{}', {})", id, department, salary)).unwrap();
+        }
+
+        let integers = |values: &[i64]| values.iter().map(|&value| Value::Integer(value)).collect::<Vec<Value>>();
+
+        assert_eq!(
+            select_values(&mut database, "select row_number() over (partition by department order by salary desc), rank() over (partition by department order by salary desc), dense_rank() over (order by salary desc) from employees order by id"),
+            [[3, 3, 3], [1, 1, 2], [2, 1, 2], [1, 1, 4], [2, 2, 5], [1, 1, 1]].iter().map(|values| integers(values)).collect::<Vec<Vec<Value>>>(),
+        );
+
+        // The default frame ends with the last peer of the row, the whole partition is the frame without ORDER BY
+        assert_eq!(
+            select_values(&mut database, "select sum(salary) over (order by department), sum(salary) over (), count(salary) over (partition by department) from employees order by id"),
+            [[500, 850, 3], [500, 850, 3], [500, 850, 3], [550, 850, 1], [550, 850, 1], [850, 850, 1]].iter().map(|values| integers(values)).collect::<Vec<Vec<Value>>>(),
+        );
+
+        assert_eq!(
+            select_values(&mut database, "select lag(id) over (order by id), lead(id, 2, 0) over (order by id), first_value(id) over (partition by department order by id desc), last_value(id) over (partition by department rows between current row and unbounded following) from employees where id < 5 order by id"),
+            vec![
+                vec![Value::Null, Value::Integer(3), Value::Integer(3), Value::Integer(3)],
+                integers(&[1, 4, 3, 3]),
+                integers(&[2, 0, 3, 3]),
+                integers(&[3, 0, 4, 4]),
+            ],
+        );
+
+        assert_eq!(
+            select_values(&mut database, "select sum(salary) over (order by id rows between 1 preceding and 1 following), count(*) over (order by salary range between 100 preceding and current row) from employees order by id"),
+            [[300, 2], [500, 3], [450, 3], [250, 1], [350, 1], [300, 3]].iter().map(|values| integers(values)).collect::<Vec<Vec<Value>>>(),
+        );
+
+        assert_eq!(
+            select_values(&mut database, "select department, rank() over (order by sum(salary) desc) from employees group by department order by department"),
+            vec![
+                vec![Value::Text("a".to_string()), Value::Integer(1)],
+                vec![Value::Text("b".to_string()), Value::Integer(3)],
+                vec![Value::Text("c".to_string()), Value::Integer(2)],
+            ],
+        );
+
+        assert!(execute(&mut database, "select id from employees where row_number() over (order by id) > 1").is_err());
+        assert!(execute(&mut database, "select row_number() from employees").is_err());
+        assert!(execute(&mut database, "select sum(salary) over (order by id rows between current row and 1 preceding) from employees").is_err());
+        assert!(execute(&mut database, "select sum(salary) over (order by id rows between -1 preceding and current row) from employees").is_err());
+
+        // The offset of lag() and lead() is rejected the same way as the frame offset
+        for offset in ["-1", "1.5", "'a'", "null"] {
+            let err = execute(&mut database, &format!("select lag(id, {}) over (order by id) from employees", offset)).unwrap_err();
+            assert!(err.to_string().contains("must be a non-negative integer"));
+            assert!(execute(&mut database, &format!("select lead(id, {}) over (order by id) from employees", offset)).is_err());
+        }
+
+        assert_eq!(
+            select_values(&mut database, "select lag(id, 0) over (order by id), lead(id, 9223372036854775807, -1) over (order by id) from employees where id < 3 order by id"),
+            vec![integers(&[1, -1]), integers(&[2, -1])],
+        );
+    }
+
+    #[test]
+    fn it_should_run_compound_queries() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table a (x integer, y text)").unwrap();
+        execute(&mut database, "create table b (x integer, y text)").unwrap();
+
+        for (table, x, y) in [("a", 1, "one"), ("a", 2, "two"), ("a", 2, "two"), ("b", 2, "two"), ("b", 3, "three")] {
+            execute(&mut database, &format!("insert into {} values ({}, '{}')", table, x, y)).unwrap();
+        }
+
+        let integers = |values: &[i64]| values.iter().map(|&value| vec![Value::Integer(value)]).collect::<Vec<Vec<Value>>>();
+
+        assert_eq!(select_values(&mut database, "select x from a union select x from b"), integers(&[1, 2, 3]));
+        assert_eq!(select_values(&mut database, "select x from a union all select x from b"), integers(&[1, 2, 2, 2, 3]));
+        assert_eq!(select_values(&mut database, "select x from a intersect select x from b"), integers(&[2]));
+        assert_eq!(select_values(&mut database, "select x from a except select x from b"), integers(&[1]));
+        assert_eq!(
+            select_values(&mut database, "select x from a except select x from b union all select x from b"),
+            integers(&[1, 2, 3]),
+        );
+
+        // ORDER BY and LIMIT are applied to the combined rows, the columns are named by the first select
+        assert_eq!(
+            select_values(&mut database, "select x as n from a union all select x from b order by n desc limit 2 offset 1"),
+            integers(&[2, 2]),
+        );
+        assert_eq!(
+            select_values(&mut database, "select count(*) from (select x, y from a union select x, y from b)"),
+            integers(&[3]),
+        );
+        assert_eq!(
+            select_values(&mut database, "select x from a where x in (select x from b intersect select x from a)"),
+            integers(&[2, 2]),
+        );
+
+        assert!(execute(&mut database, "select x from a union select x, y from b").is_err());
+        assert!(execute(&mut database, "with recursive c(n) as (select x from a except select n from c) select n from c").is_err());
+    }
+
+    #[test]
+    fn it_should_expand_views() {
+        let mut database = create_database();
+        fill_database(&mut database);
+
+        let text = |value: &str| vec![Value::Text(value.to_string())];
+
+        execute(&mut database, "create view b_users as select user_name, email from users where user_name >= 'b'").unwrap();
+        execute(&mut database, "create view names (name) as select user_name from b_users").unwrap();
+
+        assert_eq!(select_values(&mut database, "select name from names"), vec![text("bob"), text("carol")]);
+        assert_eq!(
+            select_values(&mut database, "select b_users.email from b_users join users on users.email = b_users.email where users.user_name = 'carol'"),
+            vec![text("c@x.com")],
+        );
+        assert_eq!(
+            select_values(&mut database, "select email from users where user_name not in (select name from names)"),
+            vec![text("a@x.com")],
+        );
+
+        // The table of WITH hides the view
+        assert_eq!(
+            select_values(&mut database, "with names as (select email from users where user_name = 'alice') select * from names"),
+            vec![text("a@x.com")],
+        );
+
+        assert!(execute(&mut database, "create view names as select email from users").is_err());
+        assert!(execute(&mut database, "create view if not exists names as select email from users").is_ok());
+        assert!(execute(&mut database, "create table names (name text)").is_err());
+        assert!(execute(&mut database, "create view emails as select email from missing").is_err());
+        assert!(execute(&mut database, "create view emails (a, b) as select email from users").is_err());
+        assert!(execute(&mut database, "insert into names values ('dave')").is_err());
+        assert!(execute(&mut database, "drop table names").is_err());
+
+        execute(&mut database, "drop view b_users").unwrap();
+        assert!(execute(&mut database, "select * from names").is_err());
+        assert!(execute(&mut database, "drop view b_users").is_err());
+        assert!(execute(&mut database, "drop view if exists b_users").is_ok());
+        assert_eq!(execute(&mut database, "select * from rustqlight_schema where type = 'view'").unwrap(), 1);
+    }
+
+    #[test]
+    fn it_should_fire_triggers() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table customers (id integer primary key, orders integer default 0)").unwrap();
+        execute(&mut database, "create table orders (id integer primary key, customer_id integer, amount integer)").unwrap();
+        execute(&mut database, "create table audit (action text, amount integer)").unwrap();
+        execute(&mut database, "insert into customers (id) values (1)").unwrap();
+
+        execute(&mut database, "create trigger count_orders after insert on orders begin \
+            update customers set orders = orders + 1 where id = new.customer_id; \
+            insert into audit values ('insert', new.amount); \
+        end").unwrap();
+        execute(&mut database, "create trigger uncount_orders after delete on orders for each row begin \
+            update customers set orders = orders - 1 where id = old.customer_id; \
+        end").unwrap();
+        execute(&mut database, "create trigger audit_amount before update of amount on orders when new.amount > old.amount begin \
+            insert into audit values ('raise', new.amount - old.amount); \
+        end").unwrap();
+
+        execute(&mut database, "insert into orders (customer_id, amount) values (1, 10)").unwrap();
+        execute(&mut database, "insert into orders (customer_id, amount) values (1, 20)").unwrap();
+        assert_eq!(execute(&mut database, "update orders set amount = 15").unwrap(), 2);
+        assert_eq!(execute(&mut database, "update orders set customer_id = 2").unwrap(), 2);
+        assert_eq!(execute(&mut database, "delete from orders where amount = 15 and id = 1").unwrap(), 1);
+
+        assert_eq!(select_values(&mut database, "select orders from customers"), vec![vec![Value::Integer(2)]]);
+        assert_eq!(
+            select_values(&mut database, "select action, amount from audit"),
+            vec![
+                vec![Value::Text("insert".to_string()), Value::Integer(10)],
+                vec![Value::Text("insert".to_string()), Value::Integer(20)],
+                vec![Value::Text("raise".to_string()), Value::Integer(5)],
+            ],
+        );
+
+        // The trigger isn't fired by its own statements
+        execute(&mut database, "create trigger copy_audit after insert on audit begin insert into audit values ('copy', new.amount); end").unwrap();
+        execute(&mut database, "insert into audit values ('manual', 1)").unwrap();
+        assert_eq!(execute(&mut database, "select * from audit where amount = 1").unwrap(), 2);
+
+        assert!(execute(&mut database, "create trigger bad after delete on orders begin insert into audit values ('x', new.amount); end").is_err());
+        assert!(execute(&mut database, "create trigger bad after insert on orders begin insert into audit values ('x', new.price); end").is_err());
+        assert!(execute(&mut database, "create trigger bad after insert on orders begin create table x (a integer); end").is_err());
+        assert!(execute(&mut database, "create trigger count_orders after insert on orders begin select 1 from audit; end").is_err());
+
+        execute(&mut database, "drop trigger copy_audit").unwrap();
+        assert!(execute(&mut database, "drop trigger copy_audit").is_err());
+        assert!(execute(&mut database, "drop trigger if exists copy_audit").is_ok());
+
+        execute(&mut database, "drop table orders").unwrap();
+        assert_eq!(execute(&mut database, "select * from rustqlight_schema where type = 'trigger'").unwrap(), 0);
+    }
+
+    #[test]
+    fn it_should_enforce_foreign_keys() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (id integer primary key, name text unique)").unwrap();
+        execute(&mut database, "create table sessions (id integer primary key, \
+            user_id integer references users (id) on delete cascade on update cascade)").unwrap();
+        execute(&mut database, "create table notes (user_name text, foreign key (user_name) references users (name) on delete set null)").unwrap();
+        execute(&mut database, "create table audits (user_id integer references users on delete restrict)").unwrap();
+
+        // The foreign keys are not checked until they are turned on
+        execute(&mut database, "insert into sessions values (1, 9)").unwrap();
+        execute(&mut database, "pragma foreign_keys = on").unwrap();
+        assert!(execute(&mut database, "insert into sessions values (2, 9)").is_err());
+
+        execute(&mut database, "insert into users values (1, 'ann')").unwrap();
+        execute(&mut database, "insert into users values (2, 'bob')").unwrap();
+        execute(&mut database, "insert into sessions values (2, 1)").unwrap();
+        execute(&mut database, "insert into sessions values (3, null)").unwrap();
+        execute(&mut database, "insert into notes values ('ann')").unwrap();
+        execute(&mut database, "insert into audits values (2)").unwrap();
+        assert!(execute(&mut database, "update sessions set user_id = 5 where id = 2").is_err());
+
+        execute(&mut database, "update users set id = 10 where id = 1").unwrap();
+        assert_eq!(select_values(&mut database, "select user_id from sessions where id = 2"), vec![vec![Value::Integer(10)]]);
+
+        execute(&mut database, "delete from users where id = 10").unwrap();
+        assert_eq!(select_ids(&mut database, "select * from sessions"), vec![1, 3]);
+        assert_eq!(select_values(&mut database, "select user_name from notes"), vec![vec![Value::Null]]);
+
+        assert!(execute(&mut database, "delete from users where id = 2").is_err());
+        assert!(execute(&mut database, "drop table users").is_err());
+
+        execute(&mut database, "create table bad (x integer references notes (user_name))").unwrap();
+        assert!(execute(&mut database, "insert into bad values (1)").is_err());
+        assert!(execute(&mut database, "pragma unknown").is_err());
+    }
+
+    #[test]
+    fn it_should_alter_tables() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (id integer primary key, name text unique, age integer)").unwrap();
+        execute(&mut database, "create index users_age on users (age)").unwrap();
+        execute(&mut database, "create table sessions (user_id integer references users (id))").unwrap();
+        execute(&mut database, "create view adults as select name from users where age > 18").unwrap();
+        execute(&mut database, "insert into users values (1, 'ann', 30)").unwrap();
+
+        // The old rows get the default value of the added column
+        execute(&mut database, "alter table users add column email text default 'none'").unwrap();
+        execute(&mut database, "insert into users values (2, 'bob', 40, 'bob@mail')").unwrap();
+        assert_eq!(
+            select_values(&mut database, "select email from users"),
+            vec![vec![Value::Text("none".to_string())], vec![Value::Text("bob@mail".to_string())]],
+        );
+        assert!(execute(&mut database, "alter table users add column code integer not null").is_err());
+        assert!(execute(&mut database, "alter table users add column code integer unique").is_err());
+
+        execute(&mut database, "alter table users rename column age to years").unwrap();
+        assert_eq!(select_ids(&mut database, "select * from users where years > 35"), vec![2]);
+        assert_eq!(execute(&mut database, "select * from adults").unwrap(), 2);
+        assert!(execute(&mut database, "alter table users rename column name to email").is_err());
+
+        execute(&mut database, "alter table users rename to people").unwrap();
+        assert!(execute(&mut database, "select * from users").is_err());
+        assert_eq!(execute(&mut database, "select * from adults").unwrap(), 2);
+        assert!(database.tables[1..].iter().any(|table| table.find_index("users_age").is_some_and(|index| index.get_columns() == &["years"])));
+        assert_eq!(find_table(&database.tables, "sessions").unwrap().get_definition().get_foreign_keys()[0].parent_table, "people");
+
+        execute(&mut database, "alter table people drop column email").unwrap();
+        assert_eq!(select_values(&mut database, "select * from people where id = 1"), vec![vec![
+            Value::Integer(1),
+            Value::Text("ann".to_string()),
+            Value::Integer(30),
+        ]]);
+        assert!(execute(&mut database, "alter table people drop column name").is_err());
+        assert!(execute(&mut database, "alter table people drop column years").is_err());
+        assert!(execute(&mut database, "alter table rustqlight_schema add column x integer").is_err());
+    }
+
+    #[test]
+    fn it_should_insert_several_rows() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table users (id integer primary key, name text unique, age integer default 18)").unwrap();
+        assert_eq!(execute(&mut database, "insert into users (name, age) values ('ann', 30), ('bob', 40), ('cy', 50)").unwrap(), 3);
+        assert_eq!(execute(&mut database, "insert into users default values").unwrap(), 1);
+        assert_eq!(select_values(&mut database, "select age from users where id = 4"), vec![vec![Value::Integer(18)]]);
+
+        // The rows inserted before the failed one are removed
+        assert!(execute(&mut database, "insert into users (name) values ('dee'), ('ann')").is_err());
+        assert_eq!(select_ids(&mut database, "select * from users"), vec![1, 2, 3, 4]);
+        assert!(execute(&mut database, "insert into users (name, age) values ('eve', 1), ('fay')").is_err());
+
+        execute(&mut database, "create table archive (name text, age integer)").unwrap();
+        assert_eq!(execute(&mut database, "insert into archive select name, age from users where age > 35").unwrap(), 2);
+        assert_eq!(execute(&mut database, "insert into archive (name) select name from users where id = 1").unwrap(), 1);
+        assert_eq!(
+            select_values(&mut database, "select name, age from archive"),
+            vec![
+                vec![Value::Text("bob".to_string()), Value::Integer(40)],
+                vec![Value::Text("cy".to_string()), Value::Integer(50)],
+                vec![Value::Text("ann".to_string()), Value::Null],
+            ],
+        );
+        assert!(execute(&mut database, "insert into archive select * from users").is_err());
+    }
+
+    #[test]
+    fn it_should_upsert_rows() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table stock (item text primary key, amount integer not null, updates integer default 0)").unwrap();
+        execute(&mut database, "insert into stock (item, amount) values ('apple', 5), ('pear', 3)").unwrap();
+
+        let upsert = "insert into stock (item, amount) values ('apple', 2), ('plum', 7) \
+            on conflict (item) do update set amount = amount + excluded.amount, updates = updates + 1";
+        assert_eq!(execute(&mut database, upsert).unwrap(), 2);
+        assert_eq!(execute(&mut database, "insert into stock (item, amount) values ('pear', 1) on conflict do nothing").unwrap(), 0);
+        assert_eq!(
+            execute(&mut database, "insert into stock (item, amount) values ('pear', 1) on conflict (item) do update set amount = 0 where excluded.amount > 1").unwrap(),
+            0,
+        );
+        assert!(execute(&mut database, "insert into stock (item, amount) values ('fig', 1) on conflict (amount) do nothing").is_err());
+        assert_eq!(
+            select_values(&mut database, "select item, amount, updates from stock"),
+            vec![
+                vec![Value::Text("apple".to_string()), Value::Integer(7), Value::Integer(1)],
+                vec![Value::Text("pear".to_string()), Value::Integer(3), Value::Integer(0)],
+                vec![Value::Text("plum".to_string()), Value::Integer(7), Value::Integer(0)],
+            ],
+        );
+
+        // IGNORE skips the rows violating the constraints
+        assert_eq!(execute(&mut database, "insert or ignore into stock (item, amount) values ('apple', 1), ('fig', null), ('kiwi', 4)").unwrap(), 1);
+        assert_eq!(select_ids(&mut database, "select * from stock"), vec![1, 2, 3, 4]);
+
+        // REPLACE deletes the conflicting row
+        assert_eq!(execute(&mut database, "replace into stock (item, amount) values ('pear', 9)").unwrap(), 1);
+        assert_eq!(select_ids(&mut database, "select * from stock"), vec![1, 3, 4, 5]);
+
+        // FAIL keeps the rows inserted before the failed one and ABORT removes them
+        assert!(execute(&mut database, "insert or fail into stock (item, amount) values ('lime', 1), ('kiwi', 1)").is_err());
+        assert!(execute(&mut database, "insert or abort into stock (item, amount) values ('date', 1), ('kiwi', 1)").is_err());
+        assert_eq!(select_ids(&mut database, "select * from stock"), vec![1, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn it_should_return_changed_rows() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table orders (id integer primary key, item text unique, amount integer default 1)").unwrap();
+        assert_eq!(
+            returning_values(&mut database, "insert into orders (item) values ('tea'), ('jam') returning id, amount * 10 as total"),
+            vec![vec![Value::Integer(1), Value::Integer(10)], vec![Value::Integer(2), Value::Integer(10)]],
+        );
+        assert_eq!(
+            returning_values(&mut database, "insert into orders (item, amount) values ('tea', 3) on conflict (item) do update set amount = excluded.amount returning *"),
+            vec![vec![Value::Integer(1), Value::Text("tea".to_string()), Value::Integer(3)]],
+        );
+        assert_eq!(
+            returning_values(&mut database, "update orders set amount = amount + 1 where id = 2 returning orders.amount"),
+            vec![vec![Value::Integer(2)]],
+        );
+
+        // The deleted rows are returned with their old values
+        assert_eq!(
+            returning_values(&mut database, "delete from orders where amount > 2 returning item"),
+            vec![vec![Value::Text("tea".to_string())]],
+        );
+        assert_eq!(execute(&mut database, "delete from orders returning id").unwrap(), 1);
+
+        // The statement with the wrong RETURNING changes no rows
+        execute(&mut database, "insert into orders (item) values ('oil')").unwrap();
+        assert!(execute(&mut database, "update orders set amount = 5 returning price").is_err());
+        assert_eq!(select_values(&mut database, "select amount from orders"), vec![vec![Value::Integer(1)]]);
+        assert!(execute(&mut database, "create trigger log after insert on orders begin delete from orders returning *; end").is_err());
+    }
+
+    #[test]
+    fn it_should_roll_back_to_savepoints() {
+        let mut database = create_database();
+
+        execute(&mut database, "create table imports (id integer primary key, code text unique)").unwrap();
+        execute(&mut database, "begin").unwrap();
+        execute(&mut database, "insert into imports (code) values ('a'), ('b')").unwrap();
+
+        // The failed batch is skipped and the import goes on
+        execute(&mut database, "savepoint batch").unwrap();
+        execute(&mut database, "insert into imports (code) values ('c')").unwrap();
+        execute(&mut database, "update imports set code = 'z' where code = 'a'").unwrap();
+        assert!(execute(&mut database, "insert into imports (code) values ('b')").is_err());
+        execute(&mut database, "rollback to batch").unwrap();
+        assert_eq!(select_ids(&mut database, "select * from imports"), vec![1, 2]);
+        assert_eq!(select_ids(&mut database, "select * from imports where code = 'a'"), vec![1]);
+
+        execute(&mut database, "insert into imports (code) values ('d')").unwrap();
+        execute(&mut database, "release batch").unwrap();
+        assert!(execute(&mut database, "rollback to batch").is_err());
+        execute(&mut database, "commit").unwrap();
+        assert!(execute(&mut database, "commit").is_err());
+
+        // The whole transaction is rolled back together with the schema changes
+        execute(&mut database, "savepoint cleanup").unwrap();
+        execute(&mut database, "delete from imports where id > 1").unwrap();
+        execute(&mut database, "create index imports_code on imports (code)").unwrap();
+        assert!(execute(&mut database, "begin").is_err());
+        execute(&mut database, "rollback").unwrap();
+        assert_eq!(select_ids(&mut database, "select * from imports"), vec![1, 2, 3]);
+        assert!(database.tables[1].find_index("imports_code").is_none());
+
+        // The deferred foreign keys are checked by COMMIT
+        execute(&mut database, "pragma foreign_keys = on").unwrap();
+        execute(&mut database, "create table lines (import_id integer references imports (id) deferrable initially deferred)").unwrap();
+        execute(&mut database, "begin").unwrap();
+        execute(&mut database, "insert into lines values (4)").unwrap();
+        assert!(execute(&mut database, "commit").is_err());
+        execute(&mut database, "insert into imports (code) values ('e')").unwrap();
+        execute(&mut database, "commit").unwrap();
+        assert_eq!(select_values(&mut database, "select import_id from lines"), vec![vec![Value::Integer(4)]]);
+    }
+
+    #[test]
+    fn it_should_roll_back_hot_journal() {
+        use std::fs;
+        use crate::backend::pager::journal::{encode_header, get_journal_file_name, JOURNAL_HEADER_SIZE, PAGE_NUM_SIZE};
+        use crate::backend::pager::page::PAGE_SIZE;
+
+        let file_name = std::env::temp_dir().join("rql_hot_journal_test_database");
+        let file_name = file_name.to_str().unwrap();
+        let journal_file_name = get_journal_file_name(file_name);
+        let _ = fs::remove_file(file_name);
+        let _ = fs::remove_file(&journal_file_name);
+
+        let mut database = Database::new(Pager::new(file_name));
+        assert_eq!(database.pager.get_journal_mode(), JournalMode::Delete);
+        execute(&mut database, "pragma journal_mode = persist").unwrap();
+        execute(&mut database, "create table orders (id integer primary key, total integer)").unwrap();
+        execute(&mut database, "insert into orders (total) values (10), (20)").unwrap();
+        database.pager.save().unwrap();
+        let original_size = fs::metadata(file_name).unwrap().len();
+
+        // The persisted journal has the zeroed header and isn't hot
+        execute(&mut database, "update orders set total = 0").unwrap();
+        execute(&mut database, "insert into orders (total) values (30)").unwrap();
+        database.pager.save().unwrap();
+        let mut journal = fs::read(&journal_file_name).unwrap();
+        assert_eq!(journal[..JOURNAL_HEADER_SIZE], [0; JOURNAL_HEADER_SIZE]);
+
+        // The journal mode isn't stored in the file
+        let database = Database::new(Pager::new(file_name));
+        assert_eq!(database.pager.get_num_rows(1), 3);
+        assert_eq!(database.pager.get_journal_mode(), JournalMode::Delete);
+        drop(database);
+
+        // The saving interrupted while the journal was written hasn't changed the database file,
+        // the journal without the header is left as it is
+        let saved_data = fs::read(file_name).unwrap();
+        fs::write(&journal_file_name, &journal[..JOURNAL_HEADER_SIZE + PAGE_NUM_SIZE + PAGE_SIZE / 2]).unwrap();
+
+        let database = Database::new(Pager::new(file_name));
+        assert_eq!(database.pager.get_num_rows(1), 3);
+        assert!(fs::metadata(&journal_file_name).is_ok());
+        drop(database);
+        assert_eq!(fs::read(file_name).unwrap(), saved_data);
+
+        let num_pages = (journal.len() - JOURNAL_HEADER_SIZE) / (PAGE_NUM_SIZE + PAGE_SIZE);
+        journal.splice(..JOURNAL_HEADER_SIZE, encode_header(original_size, num_pages));
+
+        // The journal with the complete header and the truncated page images can't be rolled back,
+        // the database is opened as it is
+        fs::write(&journal_file_name, &journal[..journal.len() - PAGE_SIZE / 2]).unwrap();
+
+        let database = Database::new(Pager::open(file_name).unwrap());
+        assert_eq!(database.pager.get_num_rows(1), 3);
+        drop(database);
+        assert_eq!(fs::read(file_name).unwrap(), saved_data);
+
+        // The saving interrupted after the journal was written is rolled back on open
+        fs::write(&journal_file_name, journal).unwrap();
+
+        let mut database = Database::new(Pager::new(file_name));
+        assert!(fs::metadata(&journal_file_name).is_err());
+        assert_eq!(select_values(&mut database, "select total from orders"), vec![vec![Value::Integer(10)], vec![Value::Integer(20)]]);
+
+        fs::remove_file(file_name).unwrap();
+    }
+}
@@ -0,0 +1,51 @@
+use std::io;
+use super::pager::page::row_data::RowData;
+use super::query::conjoin;
+use super::table::Table;
+use crate::statement::create_table_statement::ForeignKey;
+use crate::statement::expression::{BinaryOperator, Expression};
+use crate::value::Value;
+
+/// The method returns the values of the key columns of the row,
+/// the key with NULL doesn't refer to any row
+pub fn get_key_values(table: &Table, row_data: &RowData, column_names: &[String]) -> io::Result<Option<Vec<Value>>> {
+    let values = column_names
+        .iter()
+        .map(|column_name| table.get_column_value(row_data, column_name))
+        .collect::<io::Result<Vec<Value>>>()?;
+
+    Ok(Some(values).filter(|values| !values.iter().any(Value::is_null)))
+}
+
+/// The condition selecting the rows with the values of the key columns
+pub fn key_condition(column_names: &[String], values: &[Value]) -> Option<Expression> {
+    conjoin(
+        column_names
+            .iter()
+            .zip(values)
+            .map(|(column_name, value)| Expression::Binary(
+                Box::new(Expression::Column {
+                    table_name: None,
+                    column_name: column_name.clone(),
+                }),
+                BinaryOperator::Equal,
+                Box::new(Expression::Literal(value.clone())),
+            ))
+            .collect(),
+    )
+}
+
+pub fn constraint_error(table_name: &str, foreign_key: &ForeignKey) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("FOREIGN KEY constraint failed: {} ({}) REFERENCES {}", table_name, foreign_key.columns.join(", "), foreign_key.parent_table),
+    )
+}
+
+/// The parent key must be the primary key or have the unique constraint
+pub fn mismatch_error(table_name: &str, foreign_key: &ForeignKey) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Foreign key mismatch: {} referencing {}", table_name, foreign_key.parent_table),
+    )
+}
@@ -0,0 +1,358 @@
+pub mod table_scan;
+pub mod index_scan;
+pub mod filter;
+pub mod join;
+pub mod aggregate;
+pub mod project;
+pub mod sort;
+pub mod limit;
+pub mod profile;
+pub mod window;
+
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::rc::Rc;
+use std::time::Duration;
+use super::pager::Pager;
+use super::pager::page::row_data::RowData;
+use super::query::cte::CteScope;
+use super::query::subquery::run_subquery;
+use super::table::{ScanPlan, Table};
+use crate::session::Session;
+use crate::statement::expression::{aggregate_misuse_error, window_misuse_error, Expression, RowSource, SubqueryResult};
+use crate::statement::select_statement::SelectStatement;
+use crate::value::Value;
+use filter::Filter;
+use index_scan::IndexScan;
+use table_scan::TableScan;
+
+/// The pull-based operator of the query plan: it's opened, then the rows are pulled
+/// one by one until there are no more rows, then it's closed
+pub trait Operator<T> {
+    /// The method prepares the operator and its children to produce the rows
+    fn open(&mut self) -> io::Result<()>;
+
+    /// The method returns the next row or None if there are no more rows
+    fn next(&mut self) -> io::Result<Option<T>>;
+
+    /// The method releases the rows kept by the operator and its children
+    fn close(&mut self);
+
+    /// The method describes the operator and its children for EXPLAIN
+    fn explain(&self) -> PlanNode;
+}
+
+pub type BoxedOperator<'a, T> = Box<dyn Operator<T> + 'a>;
+
+/// The measurements of the operator made by EXPLAIN ANALYZE
+#[derive(Debug, Clone, Default)]
+pub struct OperatorProfile {
+    pub num_rows: usize,
+    // The number of times the operator was opened
+    pub num_loops: usize,
+    // The time spent in the operator together with its children
+    pub elapsed: Duration,
+}
+
+/// The node of the operator tree printed by EXPLAIN
+#[derive(Debug, Clone)]
+pub struct PlanNode {
+    pub description: String,
+    pub estimated_rows: Option<f64>,
+    pub profile: Option<OperatorProfile>,
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    pub fn new(description: String, children: Vec<PlanNode>) -> Self {
+        PlanNode {
+            description,
+            estimated_rows: None,
+            profile: None,
+            children,
+        }
+    }
+
+    fn describe(&self) -> String {
+        let mut description = self.description.clone();
+
+        if let Some(estimated_rows) = self.estimated_rows {
+            description.push_str(&format!(" (estimated rows={})", estimated_rows.ceil()));
+        }
+
+        if let Some(profile) = &self.profile {
+            description.push_str(&format!(
+                " (actual rows={} loops={} time={:.3} ms)",
+                profile.num_rows,
+                profile.num_loops,
+                profile.elapsed.as_secs_f64() * 1000.0,
+            ));
+        }
+
+        description
+    }
+
+    /// The method draws the tree of the operators, the children are placed below their parent
+    pub fn to_lines(&self) -> Vec<String> {
+        let mut lines = vec![self.describe()];
+
+        for (child_num, child) in self.children.iter().enumerate() {
+            let is_last = child_num + 1 == self.children.len();
+
+            for (line_num, line) in child.to_lines().into_iter().enumerate() {
+                let prefix = match (line_num, is_last) {
+                    (0, false) => "|--",
+                    (0, true) => "`--",
+                    (_, false) => "|  ",
+                    (_, true) => "   ",
+                };
+
+                lines.push(format!("{}{}", prefix, line));
+            }
+        }
+
+        lines
+    }
+}
+
+/// The table of the FROM clause
+pub struct Source<'a> {
+    pub table: &'a Table,
+    // The rows of the derived tables are kept in their own pagers
+    pub pager: &'a Pager,
+    // The alias or the name of the table
+    pub name: &'a str,
+    // The columns merged with the columns of the previous tables by USING,
+    // they could be used by the qualified names only
+    pub merged_columns: Vec<String>,
+}
+
+impl Source<'_> {
+    /// The name of the table followed by the alias
+    pub fn describe(&self) -> String {
+        match self.table.get_name() == self.name {
+            true => self.name.to_string(),
+            false => format!("{} AS {}", self.table.get_name(), self.name),
+        }
+    }
+}
+
+/// The rows of the sources, there is no row if the source is not joined yet
+/// or the LEFT JOIN found no matching row
+pub type JoinedRowData = Vec<Option<RowData>>;
+
+/// The row produced by the operators before the projection
+#[derive(Clone)]
+pub struct Row {
+    pub sources: JoinedRowData,
+    // The values of the query aggregates, they are computed for the groups only
+    pub aggregate_values: Vec<Value>,
+    // The values of the window functions of the query, they are computed after the rows are grouped
+    pub window_values: Vec<Value>,
+}
+
+impl Row {
+    /// The row with the row of one source only
+    pub fn new(num_sources: usize, source_num: usize, row_data: RowData) -> Self {
+        let mut sources = vec![None; num_sources];
+        sources[source_num] = Some(row_data);
+
+        Row {
+            sources,
+            aggregate_values: Vec::new(),
+            window_values: Vec::new(),
+        }
+    }
+
+    /// The method adds the rows of the sources of another row
+    pub fn merge(&mut self, row: &Row) {
+        for (source, row_data) in self.sources.iter_mut().zip(row.sources.iter()) {
+            if row_data.is_some() {
+                source.clone_from(row_data);
+            }
+        }
+    }
+
+    /// The row id of the result row is the row id of the first table
+    pub fn get_row_id(&self) -> i64 {
+        self.sources
+            .first()
+            .and_then(Option::as_ref)
+            .map_or(0, |row_data| row_data.id)
+    }
+}
+
+/// The method finds the source of the column
+pub fn find_column_source(sources: &[Source], table_name: Option<&str>, column_name: &str) -> io::Result<usize> {
+    let mut source_nums = sources
+        .iter()
+        .enumerate()
+        .filter(|(_, source)| match table_name {
+            Some(table_name) => source.name == table_name && source.table.has_column(column_name),
+            None => source.table.has_column(column_name) && !source.merged_columns.iter().any(|merged_column| merged_column == column_name),
+        })
+        .map(|(source_num, _)| source_num);
+
+    match (source_nums.next(), source_nums.next()) {
+        (Some(source_num), None) => Ok(source_num),
+        (Some(_), Some(_)) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Ambiguous column name: {}", column_name),
+        )),
+        (None, _) => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            match table_name {
+                Some(table_name) => format!("No such column: {}.{}", table_name, column_name),
+                None => format!("No such column: {}", column_name),
+            },
+        )),
+    }
+}
+
+/// The data shared by the operators of one query
+pub struct QueryContext<'a> {
+    // The tables and the pager are used by the subqueries
+    pub tables: &'a [Table],
+    pub pager: &'a Pager,
+    pub session: &'a Session,
+    // The tables of WITH the subqueries could read
+    pub ctes: Option<&'a CteScope<'a>>,
+    pub sources: Vec<Source<'a>>,
+    // The aggregates computed by the Aggregate operator
+    pub aggregates: Vec<Expression>,
+    // The window functions computed by the Window operator
+    pub windows: Vec<Expression>,
+    // The row of the outer query for the subquery, its columns are used if the sources don't have them
+    outer: Option<&'a dyn RowSource>,
+    // The subquery uses the columns of the outer query, so its result depends on the outer row
+    is_correlated: Cell<bool>,
+    // The results of the subqueries which don't depend on the rows
+    subquery_results: RefCell<Vec<(SelectStatement, Rc<SubqueryResult>)>>,
+}
+
+/// The row the expressions are evaluated against
+pub struct BoundRow<'a> {
+    context: &'a QueryContext<'a>,
+    row: &'a Row,
+}
+
+impl RowSource for BoundRow<'_> {
+    fn get_column_value(&self, table_name: Option<&str>, column_name: &str) -> io::Result<Value> {
+        let source_num = match (find_column_source(&self.context.sources, table_name, column_name), self.context.outer) {
+            (Err(err), Some(outer)) if err.kind() == io::ErrorKind::NotFound => {
+                self.context.is_correlated.set(true);
+                return outer.get_column_value(table_name, column_name);
+            },
+            (result, _) => result?,
+        };
+
+        match &self.row.sources[source_num] {
+            Some(row_data) => self.context.sources[source_num].table.get_column_value(row_data, column_name),
+            None => Ok(Value::Null),
+        }
+    }
+
+    fn get_session(&self) -> &Session {
+        self.context.session
+    }
+
+    /// The aggregates are known after the rows are grouped only
+    fn get_aggregate_value(&self, expression: &Expression) -> io::Result<Value> {
+        self.context.aggregates
+            .iter()
+            .position(|aggregate| aggregate == expression)
+            .and_then(|aggregate_num| self.row.aggregate_values.get(aggregate_num))
+            .cloned()
+            .ok_or_else(|| aggregate_misuse_error(expression))
+    }
+
+    /// The window functions are known after all the rows are read only
+    fn get_window_value(&self, expression: &Expression) -> io::Result<Value> {
+        self.context.windows
+            .iter()
+            .position(|window| window == expression)
+            .and_then(|window_num| self.row.window_values.get(window_num))
+            .cloned()
+            .ok_or_else(|| window_misuse_error(expression))
+    }
+
+    /// The subqueries which don't use the columns of the row are run once
+    fn get_subquery_result(&self, subquery: &SelectStatement) -> io::Result<Rc<SubqueryResult>> {
+        let subquery_results = &self.context.subquery_results;
+
+        if let Some((_, result)) = subquery_results.borrow().iter().find(|(cached_subquery, _)| cached_subquery == subquery) {
+            return Ok(result.clone());
+        }
+
+        let context = self.context;
+        let (result, is_correlated) = run_subquery(context.tables, context.pager, context.session, subquery, context.ctes, self)?;
+        let result = Rc::new(result);
+
+        if !is_correlated {
+            subquery_results.borrow_mut().push((subquery.clone(), result.clone()));
+        }
+
+        Ok(result)
+    }
+}
+
+impl<'a> QueryContext<'a> {
+    pub fn new(
+        tables: &'a [Table],
+        pager: &'a Pager,
+        session: &'a Session,
+        ctes: Option<&'a CteScope<'a>>,
+        sources: Vec<Source<'a>>,
+        aggregates: Vec<Expression>,
+        outer: Option<&'a dyn RowSource>,
+    ) -> Self {
+        QueryContext {
+            tables,
+            pager,
+            session,
+            ctes,
+            sources,
+            aggregates,
+            windows: Vec::new(),
+            outer,
+            is_correlated: Cell::new(false),
+            subquery_results: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The method checks whether the outer row was used by the query
+    pub fn is_correlated(&self) -> bool {
+        self.is_correlated.get()
+    }
+
+    pub fn set_correlated(&self) {
+        self.is_correlated.set(true);
+    }
+
+    pub fn get_num_sources(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn bind<'r>(&'r self, row: &'r Row) -> BoundRow<'r> {
+        BoundRow { context: self, row }
+    }
+
+    pub fn evaluate(&self, expression: &Expression, row: &Row) -> io::Result<Value> {
+        expression.evaluate(&self.bind(row))
+    }
+
+    /// The method creates the operator reading the rows of the source by the plan,
+    /// the rows are filtered by the condition
+    pub fn create_scan(&'a self, source_num: usize, scan_plan: ScanPlan<'a>, reverse: bool, condition: Option<Expression>) -> BoxedOperator<'a, Row> {
+        let scan: BoxedOperator<'a, Row> = match scan_plan {
+            ScanPlan::RowId(row_id) => Box::new(TableScan::seek(self, source_num, row_id)),
+            ScanPlan::Index(index, index_range) => Box::new(IndexScan::new(self, source_num, index, index_range, reverse)),
+            ScanPlan::Table => Box::new(TableScan::new(self, source_num, reverse)),
+        };
+
+        match condition {
+            Some(condition) => Box::new(Filter::new(self, scan, condition)),
+            None => scan,
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::vec::IntoIter;
+use super::{BoxedOperator, Operator, PlanNode, QueryContext, Row};
+use crate::statement::expression::{AggregateFunction, Expression, RowSource};
+use crate::value::Value;
+
+/// The running state of one aggregate function
+enum AccumulatorState {
+    Count(i64),
+    Sum(Option<Value>),
+    Avg {
+        sum: f64,
+        count: i64,
+    },
+    Min(Option<Value>),
+    Max(Option<Value>),
+    GroupConcat(Option<String>),
+}
+
+pub struct Accumulator<'a> {
+    arguments: &'a [Expression],
+    // The values which were already aggregated, it's used by the DISTINCT aggregates only
+    distinct_values: Option<HashSet<Value>>,
+    state: AccumulatorState,
+}
+
+impl<'a> Accumulator<'a> {
+    pub fn new(aggregate: &'a Expression) -> Self {
+        let Expression::Aggregate { function, arguments, distinct } = aggregate else {
+            unreachable!("The expression {} is not an aggregate", aggregate);
+        };
+
+        let state = match function {
+            AggregateFunction::Count => AccumulatorState::Count(0),
+            AggregateFunction::Sum => AccumulatorState::Sum(None),
+            AggregateFunction::Avg => AccumulatorState::Avg { sum: 0.0, count: 0 },
+            AggregateFunction::Min => AccumulatorState::Min(None),
+            AggregateFunction::Max => AccumulatorState::Max(None),
+            AggregateFunction::GroupConcat => AccumulatorState::GroupConcat(None),
+        };
+
+        Accumulator {
+            arguments,
+            distinct_values: distinct.then(HashSet::new),
+            state,
+        }
+    }
+
+    pub fn update(&mut self, row: &dyn RowSource) -> io::Result<()> {
+        // COUNT(*) counts all the rows
+        let Some(argument) = self.arguments.first() else {
+            if let AccumulatorState::Count(count) = &mut self.state {
+                *count += 1;
+            }
+
+            return Ok(());
+        };
+
+        // The aggregates ignore NULLs
+        let value = argument.evaluate(row)?;
+
+        if value.is_null() {
+            return Ok(());
+        }
+
+        if let Some(distinct_values) = &mut self.distinct_values {
+            if !distinct_values.insert(value.clone()) {
+                return Ok(());
+            }
+        }
+
+        match &mut self.state {
+            AccumulatorState::Count(count) => *count += 1,
+            AccumulatorState::Sum(sum) => {
+                // The sum stays an integer while all the values are integers
+                *sum = Some(match (sum.take(), value) {
+                    (None, Value::Integer(value)) => Value::Integer(value),
+                    (Some(Value::Integer(sum)), Value::Integer(value)) => {
+                        Value::Integer(sum.checked_add(value).ok_or_else(|| {
+                            io::Error::new(io::ErrorKind::InvalidData, "Integer overflow")
+                        })?)
+                    },
+                    (None, value) => Value::Real(value.to_real()),
+                    (Some(sum), value) => Value::Real(sum.to_real() + value.to_real()),
+                });
+            },
+            AccumulatorState::Avg { sum, count } => {
+                *sum += value.to_real();
+                *count += 1;
+            },
+            AccumulatorState::Min(min) => {
+                if min.as_ref().is_none_or(|min| value < *min) {
+                    *min = Some(value);
+                }
+            },
+            AccumulatorState::Max(max) => {
+                if max.as_ref().is_none_or(|max| value > *max) {
+                    *max = Some(value);
+                }
+            },
+            AccumulatorState::GroupConcat(text) => {
+                let separator = match self.arguments.get(1) {
+                    Some(separator) => match separator.evaluate(row)? {
+                        Value::Null => String::new(),
+                        separator => separator.to_string(),
+                    },
+                    None => ",".to_string(),
+                };
+
+                match text {
+                    Some(text) => {
+                        text.push_str(&separator);
+                        text.push_str(&value.to_string());
+                    },
+                    None => *text = Some(value.to_string()),
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// The value of the aggregate for the rows added so far, the window functions
+    /// read it after every row
+    pub fn get_value(&self) -> Value {
+        match &self.state {
+            AccumulatorState::Count(count) => Value::Integer(*count),
+            AccumulatorState::Avg { count: 0, .. } => Value::Null,
+            AccumulatorState::Avg { sum, count } => Value::Real(sum / *count as f64),
+            AccumulatorState::Sum(value) | AccumulatorState::Min(value) | AccumulatorState::Max(value) => {
+                value.clone().unwrap_or(Value::Null)
+            },
+            AccumulatorState::GroupConcat(text) => text.clone().map_or(Value::Null, Value::Text),
+        }
+    }
+}
+
+/// The operator distributing the rows into the groups by the values of the GROUP BY expressions
+/// in the hash table. The aggregates of every group are updated while the rows are read,
+/// the groups are returned in the order of their first rows
+pub struct Aggregate<'a> {
+    context: &'a QueryContext<'a>,
+    child: BoxedOperator<'a, Row>,
+    group_by: Vec<Expression>,
+    groups: IntoIter<Row>,
+}
+
+impl<'a> Aggregate<'a> {
+    pub fn new(context: &'a QueryContext<'a>, child: BoxedOperator<'a, Row>, group_by: Vec<Expression>) -> Self {
+        Aggregate {
+            context,
+            child,
+            group_by,
+            groups: Vec::new().into_iter(),
+        }
+    }
+
+    fn create_accumulators(&self) -> Vec<Accumulator<'a>> {
+        self.context.aggregates.iter().map(Accumulator::new).collect()
+    }
+}
+
+impl Operator<Row> for Aggregate<'_> {
+    fn open(&mut self) -> io::Result<()> {
+        self.child.open()?;
+
+        let mut group_nums: HashMap<Vec<Value>, usize> = HashMap::new();
+        // The first row of the group, the columns which are not aggregated are taken from it
+        let mut groups: Vec<(Row, Vec<Accumulator>)> = Vec::new();
+
+        while let Some(row) = self.child.next()? {
+            let key = self.group_by
+                .iter()
+                .map(|expression| self.context.evaluate(expression, &row))
+                .collect::<io::Result<Vec<Value>>>()?;
+
+            let group_num = match group_nums.get(&key) {
+                Some(&group_num) => group_num,
+                None => {
+                    groups.push((row.clone(), self.create_accumulators()));
+                    group_nums.insert(key, groups.len() - 1);
+
+                    groups.len() - 1
+                },
+            };
+
+            let row_source = self.context.bind(&row);
+
+            for accumulator in groups[group_num].1.iter_mut() {
+                accumulator.update(&row_source)?;
+            }
+        }
+
+        // Without GROUP BY all the rows are one group, even if there are no rows.
+        // The columns of the empty group are NULLs
+        if groups.is_empty() && self.group_by.is_empty() {
+            let row = Row {
+                sources: vec![None; self.context.get_num_sources()],
+                aggregate_values: Vec::new(),
+                window_values: Vec::new(),
+            };
+
+            groups.push((row, self.create_accumulators()));
+        }
+
+        self.groups = groups
+            .into_iter()
+            .map(|(mut row, accumulators)| {
+                row.aggregate_values = accumulators.iter().map(Accumulator::get_value).collect();
+                row
+            })
+            .collect::<Vec<Row>>()
+            .into_iter();
+
+        Ok(())
+    }
+
+    fn next(&mut self) -> io::Result<Option<Row>> {
+        Ok(self.groups.next())
+    }
+
+    fn close(&mut self) {
+        self.groups = Vec::new().into_iter();
+        self.child.close();
+    }
+
+    fn explain(&self) -> PlanNode {
+        let mut description = "AGGREGATE".to_string();
+
+        if !self.context.aggregates.is_empty() {
+            let aggregates: Vec<String> = self.context.aggregates.iter().map(Expression::to_string).collect();
+            description.push_str(&format!(" {}", aggregates.join(", ")));
+        }
+
+        if !self.group_by.is_empty() {
+            let group_by: Vec<String> = self.group_by.iter().map(Expression::to_string).collect();
+            description.push_str(&format!(" GROUP BY {}", group_by.join(", ")));
+        }
+
+        PlanNode::new(description, vec![self.child.explain()])
+    }
+}
@@ -0,0 +1,44 @@
+use std::io;
+use super::{BoxedOperator, Operator, PlanNode, QueryContext, Row};
+use crate::statement::expression::Expression;
+
+/// The operator passing the rows for which the condition is true
+pub struct Filter<'a> {
+    context: &'a QueryContext<'a>,
+    child: BoxedOperator<'a, Row>,
+    condition: Expression,
+}
+
+impl<'a> Filter<'a> {
+    pub fn new(context: &'a QueryContext<'a>, child: BoxedOperator<'a, Row>, condition: Expression) -> Self {
+        Filter {
+            context,
+            child,
+            condition,
+        }
+    }
+}
+
+impl Operator<Row> for Filter<'_> {
+    fn open(&mut self) -> io::Result<()> {
+        self.child.open()
+    }
+
+    fn next(&mut self) -> io::Result<Option<Row>> {
+        while let Some(row) = self.child.next()? {
+            if self.context.evaluate(&self.condition, &row)?.is_true() {
+                return Ok(Some(row));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn close(&mut self) {
+        self.child.close();
+    }
+
+    fn explain(&self) -> PlanNode {
+        PlanNode::new(format!("FILTER {}", self.condition), vec![self.child.explain()])
+    }
+}
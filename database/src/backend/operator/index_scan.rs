@@ -0,0 +1,72 @@
+use std::io;
+use std::vec::IntoIter;
+use super::{Operator, PlanNode, QueryContext, Row};
+use crate::backend::table::index::{Index, IndexRange};
+
+/// The operator reading the rows of the table found in the index, the rows are read in the index order
+pub struct IndexScan<'a> {
+    context: &'a QueryContext<'a>,
+    source_num: usize,
+    index: &'a Index,
+    index_range: IndexRange,
+    reverse: bool,
+    row_ids: IntoIter<i64>,
+}
+
+impl<'a> IndexScan<'a> {
+    pub fn new(context: &'a QueryContext<'a>, source_num: usize, index: &'a Index, index_range: IndexRange, reverse: bool) -> Self {
+        IndexScan {
+            context,
+            source_num,
+            index,
+            index_range,
+            reverse,
+            row_ids: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl Operator<Row> for IndexScan<'_> {
+    fn open(&mut self) -> io::Result<()> {
+        let mut row_ids = self.index.scan(&self.index_range);
+
+        if self.reverse {
+            row_ids.reverse();
+        }
+
+        self.row_ids = row_ids.into_iter();
+
+        Ok(())
+    }
+
+    fn next(&mut self) -> io::Result<Option<Row>> {
+        let table_id = self.context.sources[self.source_num].table.get_id();
+
+        for row_id in self.row_ids.by_ref() {
+            if let Some(row_data) = self.context.sources[self.source_num].pager.get_row_by_id(table_id, row_id) {
+                return Ok(Some(Row::new(self.context.get_num_sources(), self.source_num, row_data)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn close(&mut self) {
+        self.row_ids = Vec::new().into_iter();
+    }
+
+    fn explain(&self) -> PlanNode {
+        let source = self.context.sources[self.source_num].describe();
+
+        let mut description = match self.index.describe_range(&self.index_range) {
+            Some(range) => format!("SEARCH {} USING INDEX {} ({})", source, self.index.get_name(), range),
+            None => format!("SCAN {} USING INDEX {}", source, self.index.get_name()),
+        };
+
+        if self.reverse {
+            description.push_str(" IN REVERSE ORDER");
+        }
+
+        PlanNode::new(description, Vec::new())
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::io;
+use std::vec::IntoIter;
+use super::{BoxedOperator, Operator, PlanNode, QueryContext, Row};
+use crate::statement::expression::{BinaryOperator, Expression};
+use crate::statement::select_statement::JoinType;
+use crate::value::Value;
+
+/// The way the rows of the new table are matched with the left rows
+pub enum JoinAlgorithm<'a> {
+    // The matching rows are found by the index or the row id for every left row,
+    // the found rows are filtered by the condition
+    IndexNestedLoop {
+        column_name: String,
+        left: Expression,
+        condition: Option<Expression>,
+    },
+    // The right rows are put into the hash table by the values of the right expression
+    Hash {
+        left: Expression,
+        right: Expression,
+        right_rows: BoxedOperator<'a, Row>,
+    },
+    // Every left row is combined with every right row
+    NestedLoop(BoxedOperator<'a, Row>),
+}
+
+/// The operator joining the rows of the next source to the left rows, the order of the left rows is kept
+pub struct Join<'a> {
+    context: &'a QueryContext<'a>,
+    left: BoxedOperator<'a, Row>,
+    source_num: usize,
+    join_type: JoinType,
+    algorithm: JoinAlgorithm<'a>,
+    // The condition could use all the joined sources
+    condition: Option<Expression>,
+    // The rows of the new source, they are read once unless they are looked up by the index
+    right_rows: Vec<Row>,
+    hash_table: HashMap<Value, Vec<usize>>,
+    left_row: Option<Row>,
+    // The numbers of the right rows which could match the left row
+    candidate_nums: IntoIter<usize>,
+    is_matched: bool,
+}
+
+impl<'a> Join<'a> {
+    pub fn new(
+        context: &'a QueryContext<'a>,
+        left: BoxedOperator<'a, Row>,
+        source_num: usize,
+        join_type: JoinType,
+        algorithm: JoinAlgorithm<'a>,
+        condition: Option<Expression>,
+    ) -> Self {
+        Join {
+            context,
+            left,
+            source_num,
+            join_type,
+            algorithm,
+            condition,
+            right_rows: Vec::new(),
+            hash_table: HashMap::new(),
+            left_row: None,
+            candidate_nums: Vec::new().into_iter(),
+            is_matched: false,
+        }
+    }
+
+    fn read_rows(operator: &mut BoxedOperator<'a, Row>) -> io::Result<Vec<Row>> {
+        let mut rows = Vec::new();
+
+        operator.open()?;
+
+        while let Some(row) = operator.next()? {
+            rows.push(row);
+        }
+
+        operator.close();
+
+        Ok(rows)
+    }
+
+    fn find_candidates(&mut self, left_row: &Row) -> io::Result<Vec<usize>> {
+        match &self.algorithm {
+            JoinAlgorithm::IndexNestedLoop { column_name, left, condition } => {
+                let value = self.context.evaluate(left, left_row)?;
+
+                // NULL is never equal to anything
+                if value.is_null() {
+                    return Ok(Vec::new());
+                }
+
+                let lookup_condition = Some(Expression::Binary(
+                    Box::new(Expression::Column { table_name: None, column_name: column_name.clone() }),
+                    BinaryOperator::Equal,
+                    Box::new(Expression::Literal(value)),
+                ));
+
+                let source = &self.context.sources[self.source_num];
+                let access_path = source.table.plan_scan(source.pager, &lookup_condition, &[]);
+                let mut scan = self.context.create_scan(self.source_num, access_path.scan_plan, false, condition.clone());
+
+                self.right_rows = Self::read_rows(&mut scan)?;
+
+                Ok((0..self.right_rows.len()).collect())
+            },
+            JoinAlgorithm::Hash { left, .. } => match self.context.evaluate(left, left_row)? {
+                Value::Null => Ok(Vec::new()),
+                value => Ok(self.hash_table.get(&value).cloned().unwrap_or_default()),
+            },
+            JoinAlgorithm::NestedLoop(_) => Ok((0..self.right_rows.len()).collect()),
+        }
+    }
+}
+
+impl Operator<Row> for Join<'_> {
+    fn open(&mut self) -> io::Result<()> {
+        match &mut self.algorithm {
+            JoinAlgorithm::IndexNestedLoop { .. } => {},
+            JoinAlgorithm::Hash { right, right_rows, .. } => {
+                self.right_rows = Self::read_rows(right_rows)?;
+
+                for (row_num, row) in self.right_rows.iter().enumerate() {
+                    let value = self.context.evaluate(right, row)?;
+
+                    if !value.is_null() {
+                        self.hash_table.entry(value).or_default().push(row_num);
+                    }
+                }
+            },
+            JoinAlgorithm::NestedLoop(right_rows) => self.right_rows = Self::read_rows(right_rows)?,
+        }
+
+        self.left.open()
+    }
+
+    fn next(&mut self) -> io::Result<Option<Row>> {
+        loop {
+            if let Some(left_row) = &self.left_row {
+                for candidate_num in self.candidate_nums.by_ref() {
+                    let mut row = left_row.clone();
+                    row.merge(&self.right_rows[candidate_num]);
+
+                    let is_joined = match &self.condition {
+                        Some(condition) => self.context.evaluate(condition, &row)?.is_true(),
+                        None => true,
+                    };
+
+                    if is_joined {
+                        self.is_matched = true;
+                        return Ok(Some(row));
+                    }
+                }
+
+                let left_row = self.left_row.take().unwrap();
+
+                // The LEFT JOIN keeps the rows without the matching rows
+                if !self.is_matched && self.join_type == JoinType::Left {
+                    return Ok(Some(left_row));
+                }
+            }
+
+            let Some(left_row) = self.left.next()? else {
+                return Ok(None);
+            };
+
+            self.candidate_nums = self.find_candidates(&left_row)?.into_iter();
+            self.is_matched = false;
+            self.left_row = Some(left_row);
+        }
+    }
+
+    fn close(&mut self) {
+        self.left.close();
+        self.right_rows.clear();
+        self.hash_table.clear();
+        self.left_row = None;
+        self.candidate_nums = Vec::new().into_iter();
+    }
+
+    fn explain(&self) -> PlanNode {
+        let join_type = match self.join_type {
+            JoinType::Left => "LEFT ",
+            JoinType::Inner | JoinType::Cross => "",
+        };
+        let source = self.context.sources[self.source_num].describe();
+
+        let (mut description, right_rows) = match &self.algorithm {
+            JoinAlgorithm::IndexNestedLoop { column_name, left, condition } => {
+                let mut description = format!("{}INDEX NESTED LOOP JOIN {} LOOKUP {} = {}", join_type, source, column_name, left);
+
+                if let Some(condition) = condition {
+                    description.push_str(&format!(" FILTER {}", condition));
+                }
+
+                (description, None)
+            },
+            JoinAlgorithm::Hash { left, right, right_rows } => {
+                (format!("{}HASH JOIN {} BY {} = {}", join_type, source, left, right), Some(right_rows))
+            },
+            JoinAlgorithm::NestedLoop(right_rows) => (format!("{}NESTED LOOP JOIN {}", join_type, source), Some(right_rows)),
+        };
+
+        if let Some(condition) = &self.condition {
+            description.push_str(&format!(" ON {}", condition));
+        }
+
+        let mut children = vec![self.left.explain()];
+        children.extend(right_rows.map(|right_rows| right_rows.explain()));
+
+        PlanNode::new(description, children)
+    }
+}
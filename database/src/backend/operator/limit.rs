@@ -0,0 +1,63 @@
+use std::io;
+use super::{BoxedOperator, Operator, PlanNode};
+
+/// The operator skipping the first rows and stopping after the limit
+pub struct Limit<'a, T> {
+    child: BoxedOperator<'a, T>,
+    limit: usize,
+    offset: usize,
+    num_rows: usize,
+}
+
+impl<'a, T> Limit<'a, T> {
+    pub fn new(child: BoxedOperator<'a, T>, limit: usize, offset: usize) -> Self {
+        Limit {
+            child,
+            limit,
+            offset,
+            num_rows: 0,
+        }
+    }
+}
+
+impl<T> Operator<T> for Limit<'_, T> {
+    fn open(&mut self) -> io::Result<()> {
+        self.num_rows = 0;
+        self.child.open()
+    }
+
+    fn next(&mut self) -> io::Result<Option<T>> {
+        while self.num_rows < self.offset {
+            if self.child.next()?.is_none() {
+                return Ok(None);
+            }
+
+            self.num_rows += 1;
+        }
+
+        if self.num_rows - self.offset >= self.limit {
+            return Ok(None);
+        }
+
+        self.num_rows += 1;
+        self.child.next()
+    }
+
+    fn close(&mut self) {
+        self.child.close();
+    }
+
+    fn explain(&self) -> PlanNode {
+        let mut description = Vec::new();
+
+        if self.limit != usize::MAX {
+            description.push(format!("LIMIT {}", self.limit));
+        }
+
+        if self.offset != 0 {
+            description.push(format!("OFFSET {}", self.offset));
+        }
+
+        PlanNode::new(description.join(" "), vec![self.child.explain()])
+    }
+}
@@ -0,0 +1,66 @@
+use std::io;
+use std::time::Instant;
+use super::{BoxedOperator, Operator, OperatorProfile, PlanNode};
+
+/// The operator passing the rows of the child, it adds the estimated number of the rows
+/// to the description of the child and measures it if the query is analyzed
+pub struct Profile<'a, T> {
+    child: BoxedOperator<'a, T>,
+    estimated_rows: f64,
+    profile: Option<OperatorProfile>,
+}
+
+impl<'a, T> Profile<'a, T> {
+    pub fn new(child: BoxedOperator<'a, T>, estimated_rows: f64, is_measured: bool) -> Self {
+        Profile {
+            child,
+            estimated_rows,
+            profile: is_measured.then(OperatorProfile::default),
+        }
+    }
+}
+
+impl<T> Operator<T> for Profile<'_, T> {
+    fn open(&mut self) -> io::Result<()> {
+        let Some(profile) = &mut self.profile else {
+            return self.child.open();
+        };
+
+        let start = Instant::now();
+        let result = self.child.open();
+
+        profile.num_loops += 1;
+        profile.elapsed += start.elapsed();
+
+        result
+    }
+
+    fn next(&mut self) -> io::Result<Option<T>> {
+        let Some(profile) = &mut self.profile else {
+            return self.child.next();
+        };
+
+        let start = Instant::now();
+        let result = self.child.next();
+
+        profile.elapsed += start.elapsed();
+
+        if matches!(result, Ok(Some(_))) {
+            profile.num_rows += 1;
+        }
+
+        result
+    }
+
+    fn close(&mut self) {
+        self.child.close();
+    }
+
+    fn explain(&self) -> PlanNode {
+        PlanNode {
+            estimated_rows: Some(self.estimated_rows),
+            profile: self.profile.clone(),
+            ..self.child.explain()
+        }
+    }
+}
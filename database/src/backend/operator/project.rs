@@ -0,0 +1,51 @@
+use std::io;
+use super::{BoxedOperator, Operator, PlanNode, QueryContext, Row};
+use crate::backend::pager::page::row_data::RowData;
+use crate::statement::expression::Expression;
+use crate::value::Value;
+
+/// The operator evaluating the expressions against the rows, the row id of the first table is kept
+pub struct Project<'a> {
+    context: &'a QueryContext<'a>,
+    child: BoxedOperator<'a, Row>,
+    expressions: Vec<Expression>,
+}
+
+impl<'a> Project<'a> {
+    pub fn new(context: &'a QueryContext<'a>, child: BoxedOperator<'a, Row>, expressions: Vec<Expression>) -> Self {
+        Project {
+            context,
+            child,
+            expressions,
+        }
+    }
+}
+
+impl Operator<RowData> for Project<'_> {
+    fn open(&mut self) -> io::Result<()> {
+        self.child.open()
+    }
+
+    fn next(&mut self) -> io::Result<Option<RowData>> {
+        let Some(row) = self.child.next()? else {
+            return Ok(None);
+        };
+
+        let values = self.expressions
+            .iter()
+            .map(|expression| self.context.evaluate(expression, &row))
+            .collect::<io::Result<Vec<Value>>>()?;
+
+        Ok(Some(RowData::new(row.get_row_id(), values)))
+    }
+
+    fn close(&mut self) {
+        self.child.close();
+    }
+
+    fn explain(&self) -> PlanNode {
+        let expressions: Vec<String> = self.expressions.iter().map(Expression::to_string).collect();
+
+        PlanNode::new(format!("PROJECT {}", expressions.join(", ")), vec![self.child.explain()])
+    }
+}
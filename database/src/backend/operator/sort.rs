@@ -0,0 +1,58 @@
+use std::io;
+use super::{BoxedOperator, Operator, PlanNode};
+use crate::backend::pager::page::row_data::RowData;
+use crate::backend::sorter::{SortOrder, SortedRows, Sorter, SORT_MEMORY_LIMIT};
+
+/// The operator sorting the rows by the last values, one value for every sort order.
+/// The sort values are removed from the sorted rows
+pub struct Sort<'a> {
+    child: BoxedOperator<'a, RowData>,
+    sort_orders: Vec<SortOrder>,
+    sorted_rows: Option<SortedRows>,
+}
+
+impl<'a> Sort<'a> {
+    pub fn new(child: BoxedOperator<'a, RowData>, sort_orders: Vec<SortOrder>) -> Self {
+        Sort {
+            child,
+            sort_orders,
+            sorted_rows: None,
+        }
+    }
+}
+
+impl Operator<RowData> for Sort<'_> {
+    fn open(&mut self) -> io::Result<()> {
+        self.child.open()?;
+
+        let mut sorter = Sorter::new(self.sort_orders.clone(), SORT_MEMORY_LIMIT);
+
+        while let Some(mut row_data) = self.child.next()? {
+            let keys = row_data.values.split_off(row_data.values.len() - self.sort_orders.len());
+            sorter.push(keys, row_data)?;
+        }
+
+        self.sorted_rows = Some(sorter.finish()?);
+
+        Ok(())
+    }
+
+    fn next(&mut self) -> io::Result<Option<RowData>> {
+        self.sorted_rows
+            .as_mut()
+            .and_then(Iterator::next)
+            .transpose()
+    }
+
+    fn close(&mut self) {
+        // The temporary files are removed with the sorted rows
+        self.sorted_rows = None;
+        self.child.close();
+    }
+
+    fn explain(&self) -> PlanNode {
+        let sort_orders: Vec<String> = self.sort_orders.iter().map(SortOrder::to_string).collect();
+
+        PlanNode::new(format!("SORT ({})", sort_orders.join(", ")), vec![self.child.explain()])
+    }
+}
@@ -0,0 +1,94 @@
+use std::io;
+use super::{Operator, PlanNode, QueryContext, Row};
+use crate::backend::cursor::{Cursor, CursorPosition};
+use crate::backend::pager::page::row_data::RowData;
+
+/// The operator reading the rows of the table in the order of the row ids
+pub struct TableScan<'a> {
+    context: &'a QueryContext<'a>,
+    source_num: usize,
+    reverse: bool,
+    // The only row which is read if its id is known
+    row_id: Option<i64>,
+    cursor: Option<Cursor<'a>>,
+    found_row: Option<RowData>,
+}
+
+impl<'a> TableScan<'a> {
+    pub fn new(context: &'a QueryContext<'a>, source_num: usize, reverse: bool) -> Self {
+        TableScan {
+            context,
+            source_num,
+            reverse,
+            row_id: None,
+            cursor: None,
+            found_row: None,
+        }
+    }
+
+    /// The scan which reads the row with the id only
+    pub fn seek(context: &'a QueryContext<'a>, source_num: usize, row_id: i64) -> Self {
+        TableScan {
+            row_id: Some(row_id),
+            ..Self::new(context, source_num, false)
+        }
+    }
+
+    fn get_table_id(&self) -> u32 {
+        self.context.sources[self.source_num].table.get_id()
+    }
+}
+
+impl Operator<Row> for TableScan<'_> {
+    fn open(&mut self) -> io::Result<()> {
+        let pager = self.context.sources[self.source_num].pager;
+
+        match self.row_id {
+            Some(row_id) => self.found_row = pager.get_row_by_id(self.get_table_id(), row_id),
+            None => {
+                let cursor_position = if self.reverse { CursorPosition::End } else { CursorPosition::Start };
+                self.cursor = Some(Cursor::new(pager, self.get_table_id(), cursor_position)?);
+            },
+        }
+
+        Ok(())
+    }
+
+    fn next(&mut self) -> io::Result<Option<Row>> {
+        let row_data = match &mut self.cursor {
+            Some(cursor) => {
+                let row_data = cursor.get_row();
+
+                if row_data.is_some() {
+                    if self.reverse {
+                        cursor.cursor_retreat()?;
+                    } else {
+                        cursor.cursor_advance()?;
+                    }
+                }
+
+                row_data
+            },
+            None => self.found_row.take(),
+        };
+
+        Ok(row_data.map(|row_data| Row::new(self.context.get_num_sources(), self.source_num, row_data)))
+    }
+
+    fn close(&mut self) {
+        self.cursor = None;
+        self.found_row = None;
+    }
+
+    fn explain(&self) -> PlanNode {
+        let source = self.context.sources[self.source_num].describe();
+
+        let description = match self.row_id {
+            Some(row_id) => format!("SEARCH {} USING ROWID (rowid={})", source, row_id),
+            None if self.reverse => format!("SCAN {} IN REVERSE ORDER", source),
+            None => format!("SCAN {}", source),
+        };
+
+        PlanNode::new(description, Vec::new())
+    }
+}
@@ -0,0 +1,406 @@
+use std::io;
+use std::mem;
+use std::vec::IntoIter;
+use super::{BoxedOperator, Operator, PlanNode, QueryContext, Row};
+use super::aggregate::Accumulator;
+use crate::backend::pager::page::row_data::RowData;
+use crate::backend::sorter::{SortOrder, Sorter, SORT_MEMORY_LIMIT};
+use crate::statement::expression::{EmptyRow, Expression};
+use crate::statement::window::{FrameBound, FrameUnits, WindowDefinition, WindowFrame, WindowFunction};
+use crate::value::Value;
+
+/// The rows of one partition in the order of the window
+struct Partition {
+    row_nums: Vec<usize>,
+    // The values of the ORDER BY of the window for every row
+    order_keys: Vec<Vec<Value>>,
+    // The positions of the first peer and after the last peer of every row,
+    // the peers have the same ORDER BY values
+    peer_starts: Vec<usize>,
+    peer_ends: Vec<usize>,
+}
+
+impl Partition {
+    fn new(row_nums: Vec<usize>, order_keys: Vec<Vec<Value>>) -> Self {
+        let mut peer_starts = Vec::with_capacity(row_nums.len());
+
+        for position in 0..order_keys.len() {
+            match position > 0 && order_keys[position] == order_keys[position - 1] {
+                true => peer_starts.push(peer_starts[position - 1]),
+                false => peer_starts.push(position),
+            }
+        }
+
+        let mut peer_ends = vec![order_keys.len(); order_keys.len()];
+
+        for position in (0..order_keys.len().saturating_sub(1)).rev() {
+            if order_keys[position] == order_keys[position + 1] {
+                peer_ends[position] = peer_ends[position + 1];
+            } else {
+                peer_ends[position] = position + 1;
+            }
+        }
+
+        Partition {
+            row_nums,
+            order_keys,
+            peer_starts,
+            peer_ends,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.row_nums.len()
+    }
+}
+
+/// The frame bound with the evaluated offset
+#[derive(Clone, Copy)]
+enum Bound {
+    Unbounded,
+    CurrentRow,
+    // The offset is negative for PRECEDING
+    Offset(f64),
+}
+
+/// The operator computing the window functions. All the rows are read first, then the rows
+/// are sorted by the PARTITION BY and ORDER BY values of every window.
+/// The rows are returned in the order of the last window
+pub struct Window<'a> {
+    context: &'a QueryContext<'a>,
+    child: BoxedOperator<'a, Row>,
+    rows: IntoIter<Row>,
+}
+
+impl<'a> Window<'a> {
+    pub fn new(context: &'a QueryContext<'a>, child: BoxedOperator<'a, Row>) -> Self {
+        Window {
+            context,
+            child,
+            rows: Vec::new().into_iter(),
+        }
+    }
+
+    /// The method sorts the rows by the sorter and splits them into the partitions
+    fn get_partitions(&self, window: &WindowDefinition, rows: &[Row]) -> io::Result<Vec<Partition>> {
+        let mut sort_orders = vec![SortOrder { descending: false, nulls_first: true }; window.partition_by.len()];
+
+        sort_orders.extend(window.order_by.iter().map(|ordering_term| SortOrder {
+            descending: ordering_term.descending,
+            nulls_first: ordering_term.is_nulls_first(),
+        }));
+
+        let mut sorter = Sorter::new(sort_orders, SORT_MEMORY_LIMIT);
+
+        for (row_num, row) in rows.iter().enumerate() {
+            let keys = window.partition_by
+                .iter()
+                .chain(window.order_by.iter().map(|ordering_term| &ordering_term.expression))
+                .map(|expression| self.context.evaluate(expression, row))
+                .collect::<io::Result<Vec<Value>>>()?;
+
+            // The keys are kept in the row to find the partitions and the peers
+            sorter.push(keys.clone(), RowData::new(row_num as i64, keys))?;
+        }
+
+        let mut partitions = Vec::new();
+        let mut partition_key: Option<Vec<Value>> = None;
+        let mut row_nums = Vec::new();
+        let mut order_keys = Vec::new();
+
+        for row_data in sorter.finish()? {
+            let mut row_data = row_data?;
+            let keys = row_data.values.split_off(window.partition_by.len());
+
+            if partition_key.as_ref().is_some_and(|partition_key| *partition_key != row_data.values) {
+                partitions.push(Partition::new(mem::take(&mut row_nums), mem::take(&mut order_keys)));
+            }
+
+            partition_key = Some(row_data.values);
+            row_nums.push(row_data.id as usize);
+            order_keys.push(keys);
+        }
+
+        if !row_nums.is_empty() {
+            partitions.push(Partition::new(row_nums, order_keys));
+        }
+
+        Ok(partitions)
+    }
+
+    /// The offset of the frame must be the same for all the rows
+    fn evaluate_bound(&self, bound: &FrameBound, units: FrameUnits) -> io::Result<Bound> {
+        let (offset, sign) = match bound {
+            FrameBound::UnboundedPreceding | FrameBound::UnboundedFollowing => return Ok(Bound::Unbounded),
+            FrameBound::CurrentRow => return Ok(Bound::CurrentRow),
+            FrameBound::Preceding(offset) => (offset, -1.0),
+            FrameBound::Following(offset) => (offset, 1.0),
+        };
+
+        let value = offset.evaluate(&EmptyRow { session: self.context.session })?;
+
+        match (units, value) {
+            (_, Value::Integer(offset)) if offset >= 0 => Ok(Bound::Offset(sign * offset as f64)),
+            (FrameUnits::Range, Value::Real(offset)) if offset >= 0.0 => Ok(Bound::Offset(sign * offset)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The frame offset must be a non-negative number: {}", offset),
+            )),
+        }
+    }
+
+    /// The method returns the positions of the first row of the frame and after its last row,
+    /// the frame is empty if the first position is not less than the second one
+    fn get_frame(partition: &Partition, position: usize, units: FrameUnits, bounds: (Bound, Bound), descending: bool) -> io::Result<(usize, usize)> {
+        let len = partition.len();
+        let (start, end) = bounds;
+
+        let (start, end) = match units {
+            FrameUnits::Rows => {
+                let get_position = |offset: f64| (position as f64 + offset).clamp(0.0, len as f64) as usize;
+
+                let start = match start {
+                    Bound::Unbounded => 0,
+                    Bound::CurrentRow => position,
+                    Bound::Offset(offset) => get_position(offset),
+                };
+
+                let end = match end {
+                    Bound::Unbounded => len,
+                    Bound::CurrentRow => position + 1,
+                    Bound::Offset(offset) => get_position(offset + 1.0),
+                };
+
+                (start, end)
+            },
+            FrameUnits::Range => {
+                let start = match start {
+                    Bound::Unbounded => 0,
+                    Bound::CurrentRow => partition.peer_starts[position],
+                    Bound::Offset(offset) => Self::find_range_position(partition, position, offset, descending, false)?,
+                };
+
+                let end = match end {
+                    Bound::Unbounded => len,
+                    Bound::CurrentRow => partition.peer_ends[position],
+                    Bound::Offset(offset) => Self::find_range_position(partition, position, offset, descending, true)?,
+                };
+
+                (start, end)
+            },
+        };
+
+        Ok((start, end.max(start)))
+    }
+
+    /// The method finds the first row whose ORDER BY value is not less than the value of the row
+    /// moved by the offset, or the first greater value for the end of the frame. The NULLs are peers of each other only
+    fn find_range_position(partition: &Partition, position: usize, offset: f64, descending: bool, is_end: bool) -> io::Result<usize> {
+        let get_number = |keys: &[Value]| -> io::Result<Option<f64>> {
+            match &keys[0] {
+                Value::Null => Ok(None),
+                Value::Integer(value) => Ok(Some(*value as f64)),
+                Value::Real(value) => Ok(Some(*value)),
+                value => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("RANGE with an offset requires the numeric ORDER BY values, got {}", value.to_sql()),
+                )),
+            }
+        };
+
+        let Some(value) = get_number(&partition.order_keys[position])? else {
+            return Ok(match is_end {
+                true => partition.peer_ends[position],
+                false => partition.peer_starts[position],
+            });
+        };
+
+        // The values are compared as if they were sorted ascending
+        let direction = if descending { -1.0 } else { 1.0 };
+        let target = direction * value + offset;
+
+        let mut result = 0;
+        let mut has_numbers = false;
+
+        for keys in partition.order_keys.iter() {
+            match get_number(keys)? {
+                Some(key) if direction * key < target || (is_end && direction * key == target) => has_numbers = true,
+                Some(_) => break,
+                // The NULLs are either before the numbers or after them
+                None if !has_numbers => {},
+                None => break,
+            }
+
+            result += 1;
+        }
+
+        Ok(result)
+    }
+
+    /// The method computes the values of the window function for all the rows, the values are in the order of the rows
+    fn compute(&self, window_expression: &Expression, rows: &[Row], partitions: &[Partition]) -> io::Result<Vec<Value>> {
+        let Expression::Window { function, arguments, window } = window_expression else {
+            unreachable!("The expression {} is not a window function", window_expression);
+        };
+
+        let default_frame = WindowFrame::default_frame();
+        let frame = window.frame.as_ref().unwrap_or(&default_frame);
+        let bounds = (self.evaluate_bound(&frame.start, frame.units)?, self.evaluate_bound(&frame.end, frame.units)?);
+
+        let has_range_offset = matches!(bounds, (Bound::Offset(_), _) | (_, Bound::Offset(_)));
+
+        if frame.units == FrameUnits::Range && has_range_offset && window.order_by.len() != 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "RANGE with an offset PRECEDING or FOLLOWING requires exactly one ORDER BY term",
+            ));
+        }
+
+        let descending = window.order_by.first().is_some_and(|ordering_term| ordering_term.descending);
+        let aggregate = match function {
+            WindowFunction::Aggregate(function) => Some(Expression::Aggregate {
+                function: *function,
+                arguments: arguments.clone(),
+                distinct: false,
+            }),
+            _ => None,
+        };
+
+        let evaluate = |argument_num: usize, row_num: usize| -> io::Result<Value> {
+            self.context.evaluate(&arguments[argument_num], &rows[row_num])
+        };
+
+        let mut values = vec![Value::Null; rows.len()];
+
+        for partition in partitions.iter() {
+            // The aggregate of the frame starting at the partition start is updated by the new rows only
+            let mut running_accumulator = aggregate.as_ref().map(Accumulator::new);
+            let mut num_added_rows = 0;
+            let mut dense_rank = 0;
+
+            for (position, &row_num) in partition.row_nums.iter().enumerate() {
+                if partition.peer_starts[position] == position {
+                    dense_rank += 1;
+                }
+
+                values[row_num] = match function {
+                    WindowFunction::RowNumber => Value::Integer(position as i64 + 1),
+                    WindowFunction::Rank => Value::Integer(partition.peer_starts[position] as i64 + 1),
+                    WindowFunction::DenseRank => Value::Integer(dense_rank),
+                    WindowFunction::Lag | WindowFunction::Lead => {
+                        let offset = match arguments.get(1) {
+                            Some(_) => match evaluate(1, row_num)? {
+                                Value::Integer(offset) if offset >= 0 => offset,
+                                value => return Err(io::Error::new(
+                                    io::ErrorKind::InvalidInput,
+                                    format!("The offset of {}() must be a non-negative integer, got {}", function, value.to_sql()),
+                                )),
+                            },
+                            None => 1,
+                        };
+
+                        let target = match function {
+                            WindowFunction::Lag => (position as i64).saturating_sub(offset),
+                            _ => (position as i64).saturating_add(offset),
+                        };
+
+                        match usize::try_from(target).ok().and_then(|target| partition.row_nums.get(target)) {
+                            Some(&target_row_num) => evaluate(0, target_row_num)?,
+                            None if arguments.len() > 2 => evaluate(2, row_num)?,
+                            None => Value::Null,
+                        }
+                    },
+                    WindowFunction::FirstValue | WindowFunction::LastValue | WindowFunction::Aggregate(_) => {
+                        let (start, end) = Self::get_frame(partition, position, frame.units, bounds, descending)?;
+
+                        match (function, &mut running_accumulator) {
+                            (WindowFunction::FirstValue, _) if start < end => evaluate(0, partition.row_nums[start])?,
+                            (WindowFunction::LastValue, _) if start < end => evaluate(0, partition.row_nums[end - 1])?,
+                            (WindowFunction::FirstValue | WindowFunction::LastValue, _) => Value::Null,
+                            (_, Some(accumulator)) if matches!(bounds.0, Bound::Unbounded) => {
+                                for &added_row_num in partition.row_nums[num_added_rows.min(end)..end].iter() {
+                                    accumulator.update(&self.context.bind(&rows[added_row_num]))?;
+                                }
+
+                                num_added_rows = num_added_rows.max(end);
+                                accumulator.get_value()
+                            },
+                            (_, _) => {
+                                let mut accumulator = Accumulator::new(aggregate.as_ref().expect("The aggregate window function has the aggregate"));
+
+                                for &frame_row_num in partition.row_nums[start..end].iter() {
+                                    accumulator.update(&self.context.bind(&rows[frame_row_num]))?;
+                                }
+
+                                accumulator.get_value()
+                            },
+                        }
+                    },
+                };
+            }
+        }
+
+        Ok(values)
+    }
+}
+
+impl Operator<Row> for Window<'_> {
+    fn open(&mut self) -> io::Result<()> {
+        self.child.open()?;
+
+        let mut rows = Vec::new();
+
+        while let Some(row) = self.child.next()? {
+            rows.push(row);
+        }
+
+        let mut window_values = vec![Vec::with_capacity(self.context.windows.len()); rows.len()];
+        let mut row_nums: Vec<usize> = (0..rows.len()).collect();
+
+        for window_expression in self.context.windows.iter() {
+            let Expression::Window { window, .. } = window_expression else {
+                unreachable!("The expression {} is not a window function", window_expression);
+            };
+
+            let partitions = self.get_partitions(window, &rows)?;
+
+            for (row_values, value) in window_values.iter_mut().zip(self.compute(window_expression, &rows, &partitions)?) {
+                row_values.push(value);
+            }
+
+            row_nums = partitions.into_iter().flat_map(|partition| partition.row_nums).collect();
+        }
+
+        let mut rows: Vec<Option<Row>> = rows
+            .into_iter()
+            .zip(window_values)
+            .map(|(mut row, values)| {
+                row.window_values = values;
+                Some(row)
+            })
+            .collect();
+
+        self.rows = row_nums
+            .into_iter()
+            .filter_map(|row_num| rows[row_num].take())
+            .collect::<Vec<Row>>()
+            .into_iter();
+
+        Ok(())
+    }
+
+    fn next(&mut self) -> io::Result<Option<Row>> {
+        Ok(self.rows.next())
+    }
+
+    fn close(&mut self) {
+        self.rows = Vec::new().into_iter();
+        self.child.close();
+    }
+
+    fn explain(&self) -> PlanNode {
+        let windows: Vec<String> = self.context.windows.iter().map(Expression::to_string).collect();
+
+        PlanNode::new(format!("WINDOW {}", windows.join(", ")), vec![self.child.explain()])
+    }
+}
@@ -1,132 +1,378 @@
 pub mod page;
+pub mod journal;
+pub mod file_header;
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::io;
 use std::process;
 use std::fs::{File, metadata, remove_file, rename};
 use std::io::{Read, Write};
+use file_header::{check_file_header, encode_file_header};
+use journal::{rollback_hot_journal, write_pages, JournalMode};
 use page::row_data::RowData;
 use page::{Page, PAGE_SIZE};
+use crate::value::Value;
+
+/// The change of the rows which is undone by the rollback to the savepoint,
+/// the changed rows are kept as they were stored
+enum UndoEntry {
+    Insert { table_id: u32, row_id: i64 },
+    Update { table_id: u32, old_row_data: RowData },
+    Delete { table_id: u32, old_row_data: RowData },
+    DropTable { table_id: u32, pages: Vec<Page> },
+}
 
 pub struct Pager {
     file_name: String,
     file: Option<File>,
-    pages: Vec<Page>,
-    total_num_rows: usize,
+    // The pages of every table sorted by the row ids
+    tables_pages: BTreeMap<u32, Vec<Page>>,
+    // The values of the columns of every table, the rows written before ALTER TABLE ADD COLUMN
+    // have fewer values and they are completed by these ones
+    default_values: BTreeMap<u32, Vec<Value>>,
+    // The changes made after the outermost savepoint
+    undo_log: Vec<UndoEntry>,
+    // The lengths of the undo log when the savepoints were started, the innermost one is the last
+    savepoints: Vec<usize>,
+    journal_mode: JournalMode,
 }
 
 impl Pager {
+    /// The hot journal left by the interrupted saving is rolled back before the file is read
     pub fn new(file_name: &str) -> Self {
-        if !metadata(file_name).is_ok() {
-            println!("The db dump file {} doesn't exist", file_name);
-            return Pager {
-                file: None,
-                file_name: file_name.to_string(),
-                pages: Vec::new(),
-                total_num_rows: 0,
-            };
-        }
-
-        match File::open(file_name) {
-            Ok(mut file) => {
-                let saved_data = Self::read_file(&mut file);
-
-                if let Ok(pages) = saved_data {
-                    let total_num_rows = pages
-                        .iter()
-                        .fold(0, | acc, page | acc + page.get_num_rows());
-
-                    Pager {
-                        file: Some(file),
-                        file_name: file_name.to_string(),
-                        pages,
-                        total_num_rows,
-                    }
-                } else {
-                    println!("Unable to read file metadata {}", file_name);
-                    process::exit(1);
-                }
-            },
+        match Self::open(file_name) {
+            Ok(pager) => pager,
             Err(err) => {
-                println!("Unable to open file {}: {}", file_name, err);
+                println!("Unable to open the database {}: {}", file_name, err);
                 process::exit(1);
             },
         }
     }
 
-    fn read_file(file: &mut File) -> io::Result<Vec<Page>> {
-        let file_metadata = file.metadata()?;
-        let file_size = file_metadata.len();
+    /// The method reads the database file, the corrupt file or the file
+    /// written in the unsupported format is reported as the error
+    pub fn open(file_name: &str) -> io::Result<Self> {
+        if rollback_hot_journal(file_name)? {
+            println!("The hot journal of {} was rolled back", file_name);
+        }
+
+        let mut pager = Self::temporary();
+        pager.file_name = file_name.to_string();
 
-        if file_size == 0 {
-            return Ok(Vec::new());
+        if metadata(file_name).is_err() {
+            println!("The db dump file {} doesn't exist", file_name);
+            return Ok(pager);
         }
 
-        let mut pages= Vec::new();
-        // Read the last id (4 bytes)
+        let mut file = File::open(file_name)?;
+        pager.tables_pages = Self::read_file(file_name, &mut file)?;
+        pager.file = Some(file);
 
-        let mut buffer = vec![0u8; PAGE_SIZE];
+        Ok(pager)
+    }
 
-        loop {
-            let bytes_read = file.read(&mut buffer)?;
+    /// The pager which keeps the pages in memory only, it's never saved
+    pub fn temporary() -> Self {
+        Pager {
+            file: None,
+            file_name: String::new(),
+            tables_pages: BTreeMap::new(),
+            default_values: BTreeMap::new(),
+            undo_log: Vec::new(),
+            savepoints: Vec::new(),
+            journal_mode: JournalMode::default(),
+        }
+    }
 
-            if bytes_read == 0 {
-                break;
-            }
+    /// The first page is the file header, the table pages follow it
+    fn read_file(file_name: &str, file: &mut File) -> io::Result<BTreeMap<u32, Vec<Page>>> {
+        let mut tables_pages: BTreeMap<u32, Vec<Page>> = BTreeMap::new();
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
 
-            pages.push(Page::new(Some(&buffer)));
+        // The file is empty if the first saving was interrupted before anything was written
+        if data.is_empty() {
+            return Ok(tables_pages);
         }
 
-        Ok(pages)
+        if data.len() % PAGE_SIZE != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "The size of the file {} is {} bytes, it isn't a multiple of the page size {}. \
+                     It isn't a database file or it was written by an older version",
+                    file_name, data.len(), PAGE_SIZE,
+                ),
+            ));
+        }
+
+        let mut pages = data.chunks(PAGE_SIZE);
+        check_file_header(file_name, pages.next().unwrap_or_default())?;
+
+        for (page_num, data) in pages.enumerate() {
+            let page = Page::from_bytes(data).map_err(|err| io::Error::new(
+                err.kind(),
+                format!("Unable to read the page {} of the file {}: {}", page_num + 1, file_name, err),
+            ))?;
+
+            tables_pages.entry(page.get_table_id()).or_default().push(page);
+        }
+
+        Ok(tables_pages)
+    }
+
+    fn get_pages(&self, table_id: u32) -> &[Page] {
+        self.tables_pages.get(&table_id).map_or(&[], |pages| pages.as_slice())
+    }
+
+    pub fn get_last_row_id(&self, table_id: u32) -> Option<i64> {
+        self.get_pages(table_id).last().and_then(Page::get_last_idx)
     }
 
-    pub fn get_last_saved_index(&self) -> Option<u32> {
-        if let Some(last_page) = self.pages.last() {
-            Some(last_page.get_last_idx())
-        } else {
-            None
+    /// The method returns the number of the page where the row with the id is or should be
+    fn find_page(pages: &[Page], id: i64) -> usize {
+        // The ids are growing from page to page, so the row is on the first page
+        // with the last id not less than the required one
+        pages
+            .partition_point(|page| page.get_last_idx().is_some_and(|last_idx| last_idx < id))
+            .min(pages.len().saturating_sub(1))
+    }
+
+    /// The changes are recorded only if they could be rolled back
+    fn record(&mut self, undo_entry: UndoEntry) {
+        if !self.savepoints.is_empty() {
+            self.undo_log.push(undo_entry);
         }
     }
 
-    /// The method inserts new row to the last page
-    pub fn insert_new_row(&mut self, new_row_data: &RowData) -> io::Result<usize> {
-        let cur_page = match self.pages.last_mut() {
-            Some(last_page) => if last_page.get_num_rows() % PAGE_SIZE == 0 { // The last page is full
-                self.pages.push(Page::new(None));
+    /// The savepoint marks the state of the pager the changes made after it could be rolled back to
+    pub fn begin_savepoint(&mut self) {
+        self.savepoints.push(self.undo_log.len());
+    }
 
-                self.pages.last_mut().unwrap()
-            } else { // There is still place in the current page
-                last_page
-            },
-            // No pages
-            None => {
-                self.pages.push(Page::new(None));
+    /// The method keeps the changes made after the innermost savepoint, they are rolled back
+    /// together with the enclosing savepoint if there is one
+    pub fn release_savepoint(&mut self) {
+        self.savepoints.pop();
 
-                self.pages.last_mut().unwrap()
-            }
+        if self.savepoints.is_empty() {
+            self.undo_log.clear();
+        }
+    }
+
+    /// The method undoes the changes made after the innermost savepoint and removes it.
+    /// Returns the ids of the tables whose rows were restored
+    pub fn rollback_savepoint(&mut self) -> io::Result<BTreeSet<u32>> {
+        let Some(undo_log_len) = self.savepoints.pop() else {
+            return Ok(BTreeSet::new());
         };
 
-        cur_page.insert_row(new_row_data)?;
+        let mut table_ids = BTreeSet::new();
+
+        for undo_entry in self.undo_log.split_off(undo_log_len).into_iter().rev() {
+            match undo_entry {
+                UndoEntry::Insert { table_id, row_id } => {
+                    self.remove_row(table_id, row_id)?;
+                    table_ids.insert(table_id);
+                },
+                UndoEntry::Update { table_id, old_row_data } => {
+                    self.remove_row(table_id, old_row_data.id)?;
+                    self.put_row(table_id, &old_row_data)?;
+                    table_ids.insert(table_id);
+                },
+                UndoEntry::Delete { table_id, old_row_data } => {
+                    self.put_row(table_id, &old_row_data)?;
+                    table_ids.insert(table_id);
+                },
+                UndoEntry::DropTable { table_id, pages } => {
+                    self.tables_pages.insert(table_id, pages);
+                    table_ids.insert(table_id);
+                },
+            }
+        }
+
+        Ok(table_ids)
+    }
+
+    /// The row as it's stored, without the default values of the columns added later
+    fn get_stored_row(&self, table_id: u32, id: i64) -> io::Result<RowData> {
+        let (page_num, cell_num) = self
+            .find_row(table_id, id)
+            .ok_or_else(|| Self::row_not_found_error(id))?;
+
+        Ok(self.get_pages(table_id)[page_num].get_row(cell_num))
+    }
+
+    pub fn insert_row(&mut self, table_id: u32, new_row_data: &RowData) -> io::Result<()> {
+        self.put_row(table_id, new_row_data)?;
+        self.record(UndoEntry::Insert { table_id, row_id: new_row_data.id });
+
+        Ok(())
+    }
+
+    /// The method inserts new row keeping the rows sorted by the id.
+    /// The full page is split into two halves
+    fn put_row(&mut self, table_id: u32, new_row_data: &RowData) -> io::Result<()> {
+        let pages = self.tables_pages.entry(table_id).or_default();
+
+        if pages.is_empty() {
+            pages.push(Page::new(table_id));
+        }
+
+        let mut page_num = Self::find_page(pages, new_row_data.id);
+
+        while !pages[page_num].insert_row(new_row_data)? {
+            let cur_page = &mut pages[page_num];
+
+            // The only row in the page is too big to share it, so the new row gets its own page
+            if cur_page.get_num_rows() <= 1 {
+                let is_before = cur_page.get_first_idx().is_some_and(|first_idx| new_row_data.id < first_idx);
+                let new_page_num = if is_before { page_num } else { page_num + 1 };
+
+                pages.insert(new_page_num, Page::new(table_id));
+                page_num = new_page_num;
+
+                continue;
+            }
+
+            let new_page = cur_page.split();
+            let is_in_new_page = new_page.get_first_idx().is_some_and(|first_idx| new_row_data.id >= first_idx);
+            pages.insert(page_num + 1, new_page);
+
+            if is_in_new_page {
+                page_num += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The method returns the page number and the cell number of the row with the id
+    pub fn find_row(&self, table_id: u32, id: i64) -> Option<(usize, usize)> {
+        let pages = self.get_pages(table_id);
+
+        if pages.is_empty() {
+            return None;
+        }
+
+        let page_num = Self::find_page(pages, id);
+        let cell_num = pages[page_num].find_row(id)?;
+
+        Some((page_num, cell_num))
+    }
+
+    pub fn get_row(&self, table_id: u32, page_num: usize, cell_num: usize) -> Option<RowData> {
+        let page = self.get_pages(table_id).get(page_num)?;
+
+        if cell_num >= page.get_num_rows() {
+            return None;
+        }
+
+        let mut row_data = page.get_row(cell_num);
+
+        if let Some(default_values) = self.default_values.get(&table_id) {
+            row_data.values.extend(default_values.iter().skip(row_data.values.len()).cloned());
+        }
+
+        Some(row_data)
+    }
+
+    pub fn set_default_values(&mut self, table_id: u32, default_values: Vec<Value>) {
+        self.default_values.insert(table_id, default_values);
+    }
+
+    pub fn get_row_by_id(&self, table_id: u32, id: i64) -> Option<RowData> {
+        let (page_num, cell_num) = self.find_row(table_id, id)?;
+
+        self.get_row(table_id, page_num, cell_num)
+    }
+
+    pub fn update_row(&mut self, table_id: u32, row_data: &RowData) -> io::Result<()> {
+        let old_row_data = self.get_stored_row(table_id, row_data.id)?;
+        let (page_num, cell_num) = self
+            .find_row(table_id, row_data.id)
+            .ok_or_else(|| Self::row_not_found_error(row_data.id))?;
+
+        let pages = self.tables_pages.get_mut(&table_id).unwrap();
+
+        if !pages[page_num].update_row(cell_num, row_data)? {
+            // The new row doesn't fit into the page, so it's moved to the split page
+            self.remove_row(table_id, row_data.id)?;
+            self.put_row(table_id, row_data)?;
+        }
+
+        self.record(UndoEntry::Update { table_id, old_row_data });
 
-        self.total_num_rows += 1;
+        Ok(())
+    }
+
+    pub fn delete_row(&mut self, table_id: u32, id: i64) -> io::Result<()> {
+        let old_row_data = self.get_stored_row(table_id, id)?;
+        self.remove_row(table_id, id)?;
+        self.record(UndoEntry::Delete { table_id, old_row_data });
+
+        Ok(())
+    }
+
+    fn remove_row(&mut self, table_id: u32, id: i64) -> io::Result<()> {
+        let (page_num, cell_num) = self
+            .find_row(table_id, id)
+            .ok_or_else(|| Self::row_not_found_error(id))?;
 
-        Ok(self.total_num_rows)
+        let pages = self.tables_pages.get_mut(&table_id).unwrap();
+
+        pages[page_num].remove_row(cell_num);
+
+        if pages[page_num].get_num_rows() == 0 {
+            pages.remove(page_num);
+        }
+
+        Ok(())
     }
 
-    pub fn select_all(&self) {
-        for page in self.pages.iter() {
-            page.print();
+    /// The method removes all the pages of the table
+    pub fn drop_table(&mut self, table_id: u32) {
+        if let Some(pages) = self.tables_pages.remove(&table_id) {
+            self.record(UndoEntry::DropTable { table_id, pages });
         }
+
+        self.default_values.remove(&table_id);
     }
 
+    fn row_not_found_error(id: i64) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("The row with id {} doesn't exist", id),
+        )
+    }
+
+    pub fn get_journal_mode(&self) -> JournalMode {
+        self.journal_mode
+    }
+
+    pub fn set_journal_mode(&mut self, journal_mode: JournalMode) {
+        self.journal_mode = journal_mode;
+    }
+
+    /// The pages are written to the temporary file replacing the database file,
+    /// or to the database file in place guarded by the rollback journal
     pub fn save(&mut self) -> io::Result<()> {
+        if self.journal_mode != JournalMode::Rename {
+            let pages: Vec<Vec<u8>> = std::iter::once(encode_file_header())
+                .chain(self.tables_pages.values().flatten().map(Page::to_bytes))
+                .collect();
+            drop(self.file.take());
+
+            return write_pages(&self.file_name, &pages, self.journal_mode);
+        }
+
         let tmp_file_name = format!("{}.tmp", self.file_name);
 
         let mut tmp_file = File::create(&tmp_file_name)?;
+        tmp_file.write_all(&encode_file_header())?;
 
-        for page in self.pages.iter() {
-            tmp_file.write_all(
-                page.get_destination()
-            )?;
+        for page in self.tables_pages.values().flatten() {
+            tmp_file.write_all(&page.to_bytes())?;
         }
 
         if metadata(&self.file_name).is_ok() {
@@ -139,7 +385,15 @@ impl Pager {
         Ok(())
     }
 
-    pub fn get_total_num_rows(&self) -> usize {
-        self.total_num_rows
+    pub fn get_page(&self, table_id: u32, page_num: usize) -> Option<&Page> {
+        self.get_pages(table_id).get(page_num)
+    }
+
+    pub fn get_num_pages(&self, table_id: u32) -> usize {
+        self.get_pages(table_id).len()
+    }
+
+    pub fn get_num_rows(&self, table_id: u32) -> usize {
+        self.get_pages(table_id).iter().map(Page::get_num_rows).sum()
     }
 }
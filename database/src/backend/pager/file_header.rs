@@ -0,0 +1,48 @@
+use std::io;
+use super::page::PAGE_SIZE;
+
+/// The database file starts with these bytes, the files without them
+/// were written before the header was added or aren't database files
+const FILE_MAGIC: &[u8; 8] = b"rqlitedb";
+
+/// The version is changed when the page or the record layout is changed
+pub const FILE_FORMAT_VERSION: u32 = 1;
+
+const FORMAT_VERSION_SIZE: usize = size_of::<u32>();
+
+/// The header takes the whole first page, so the table pages stay aligned:
+/// the magic and the format version followed by zeros
+pub fn encode_file_header() -> Vec<u8> {
+    let mut header = Vec::with_capacity(PAGE_SIZE);
+    header.extend(FILE_MAGIC);
+    header.extend(FILE_FORMAT_VERSION.to_be_bytes());
+    header.resize(PAGE_SIZE, 0);
+
+    header
+}
+
+/// The method checks the first page of the file
+pub fn check_file_header(file_name: &str, header: &[u8]) -> io::Result<()> {
+    if !header.starts_with(FILE_MAGIC) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "The file {} isn't a database file or it was written by an older version without the file header, \
+                 it can't be read. Move it away to start with an empty database",
+                file_name,
+            ),
+        ));
+    }
+
+    let version_bytes = &header[FILE_MAGIC.len()..FILE_MAGIC.len() + FORMAT_VERSION_SIZE];
+    let version = u32::from_be_bytes(version_bytes.try_into().unwrap());
+
+    if version != FILE_FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("The file {} has the format version {}, the supported version is {}", file_name, version, FILE_FORMAT_VERSION),
+        ));
+    }
+
+    Ok(())
+}
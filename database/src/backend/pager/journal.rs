@@ -0,0 +1,190 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs::{remove_file, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use super::page::PAGE_SIZE;
+
+/// The journal is rolled back only if its header starts with these bytes
+const JOURNAL_MAGIC: &[u8; 8] = b"rqljrnl1";
+
+const ORIGINAL_SIZE_SIZE: usize = size_of::<u64>();
+const NUM_PAGES_SIZE: usize = size_of::<u32>();
+pub(crate) const PAGE_NUM_SIZE: usize = size_of::<u32>();
+
+/// The header layout: the magic, the size of the database file before the changes
+/// and the number of the page images following the header
+pub(crate) const JOURNAL_HEADER_SIZE: usize = JOURNAL_MAGIC.len() + ORIGINAL_SIZE_SIZE + NUM_PAGES_SIZE;
+
+/// PRAGMA journal_mode, the way the database file is changed when the pages are saved
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum JournalMode {
+    // The pages are written to the temporary file which replaces the database file
+    Rename,
+    // The original images of the changed pages are written to the journal, then the database file
+    // is changed in place. At the end the journal is deleted, truncated or its header is zeroed.
+    // As in SQLite, the mode isn't stored in the file, every connection starts with DELETE
+    #[default]
+    Delete,
+    Truncate,
+    Persist,
+}
+
+impl JournalMode {
+    /// RENAME | DELETE | TRUNCATE | PERSIST
+    pub fn parse(value: &str) -> io::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "rename" => Ok(JournalMode::Rename),
+            "delete" => Ok(JournalMode::Delete),
+            "truncate" => Ok(JournalMode::Truncate),
+            "persist" => Ok(JournalMode::Persist),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The journal mode must be RENAME, DELETE, TRUNCATE or PERSIST, got {}", value),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for JournalMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalMode::Rename => write!(f, "rename"),
+            JournalMode::Delete => write!(f, "delete"),
+            JournalMode::Truncate => write!(f, "truncate"),
+            JournalMode::Persist => write!(f, "persist"),
+        }
+    }
+}
+
+pub fn get_journal_file_name(file_name: &str) -> String {
+    format!("{}-journal", file_name)
+}
+
+/// The method writes the pages to the database file in place. The original images of the pages
+/// which are changed or cut off are written to the journal first, so the file could be restored
+/// if the writing is interrupted
+pub fn write_pages(file_name: &str, pages: &[Vec<u8>], journal_mode: JournalMode) -> io::Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(file_name)?;
+
+    let mut original_data = Vec::new();
+    file.read_to_end(&mut original_data)?;
+
+    let original_pages: Vec<(usize, &[u8])> = original_data
+        .chunks(PAGE_SIZE)
+        .enumerate()
+        .filter(|(page_num, original_page)| pages.get(*page_num).is_none_or(|page| page != original_page))
+        .collect();
+
+    let journal_file_name = get_journal_file_name(file_name);
+    write_journal(&journal_file_name, original_data.len() as u64, &original_pages)?;
+
+    let changed_page_nums: BTreeSet<usize> = original_pages.iter().map(|(page_num, _)| *page_num).collect();
+    let num_original_pages = original_data.len().div_ceil(PAGE_SIZE);
+
+    for (page_num, page) in pages.iter().enumerate() {
+        if page_num < num_original_pages && !changed_page_nums.contains(&page_num) {
+            continue;
+        }
+
+        file.seek(SeekFrom::Start((page_num * PAGE_SIZE) as u64))?;
+        file.write_all(page)?;
+    }
+
+    file.set_len((pages.len() * PAGE_SIZE) as u64)?;
+    file.sync_all()?;
+
+    finish_journal(&journal_file_name, journal_mode)
+}
+
+/// The header is written after the page images are stored, so the journal
+/// interrupted before it's complete is never rolled back
+fn write_journal(journal_file_name: &str, original_size: u64, original_pages: &[(usize, &[u8])]) -> io::Result<()> {
+    let mut journal = File::create(journal_file_name)?;
+    journal.write_all(&[0; JOURNAL_HEADER_SIZE])?;
+
+    for (page_num, original_page) in original_pages {
+        journal.write_all(&(*page_num as u32).to_be_bytes())?;
+        journal.write_all(original_page)?;
+        journal.write_all(&[0; PAGE_SIZE][original_page.len()..])?;
+    }
+
+    journal.sync_all()?;
+
+    journal.seek(SeekFrom::Start(0))?;
+    journal.write_all(&encode_header(original_size, original_pages.len()))?;
+    journal.sync_all()
+}
+
+/// The header of the complete journal, the journal with this header is hot
+pub(crate) fn encode_header(original_size: u64, num_pages: usize) -> Vec<u8> {
+    let mut header = Vec::with_capacity(JOURNAL_HEADER_SIZE);
+    header.extend(JOURNAL_MAGIC);
+    header.extend(original_size.to_be_bytes());
+    header.extend((num_pages as u32).to_be_bytes());
+
+    header
+}
+
+/// The journal is made invalid once the database file is written
+fn finish_journal(journal_file_name: &str, journal_mode: JournalMode) -> io::Result<()> {
+    match journal_mode {
+        JournalMode::Rename | JournalMode::Delete => remove_file(journal_file_name),
+        JournalMode::Truncate => OpenOptions::new().write(true).open(journal_file_name)?.set_len(0),
+        JournalMode::Persist => {
+            let mut journal = OpenOptions::new().write(true).open(journal_file_name)?;
+            journal.write_all(&[0; JOURNAL_HEADER_SIZE])?;
+            journal.sync_all()
+        },
+    }
+}
+
+/// The hot journal is left when the writing of the database file is interrupted, the original
+/// pages are written back from it. Returns whether there was the hot journal
+pub fn rollback_hot_journal(file_name: &str) -> io::Result<bool> {
+    let journal_file_name = get_journal_file_name(file_name);
+
+    let Ok(mut journal) = File::open(&journal_file_name) else {
+        return Ok(false);
+    };
+
+    let mut header = [0; JOURNAL_HEADER_SIZE];
+
+    // The empty, truncated or zeroed journal isn't hot
+    if journal.read_exact(&mut header).is_err() || !header.starts_with(JOURNAL_MAGIC) {
+        return Ok(false);
+    }
+
+    let size_offset = JOURNAL_MAGIC.len();
+    let num_pages_offset = size_offset + ORIGINAL_SIZE_SIZE;
+    let original_size = u64::from_be_bytes(header[size_offset..num_pages_offset].try_into().unwrap());
+    let num_pages = u32::from_be_bytes(header[num_pages_offset..].try_into().unwrap());
+
+    // The header is written after the page images, so the journal shorter than the header says
+    // was damaged after it was written, the database file is opened as it is
+    let journal_size = JOURNAL_HEADER_SIZE as u64 + num_pages as u64 * (PAGE_NUM_SIZE + PAGE_SIZE) as u64;
+
+    if journal.metadata()?.len() < journal_size {
+        println!("The journal {} is shorter than its header says, it isn't rolled back", journal_file_name);
+        return Ok(false);
+    }
+
+    let mut file = OpenOptions::new().write(true).create(true).truncate(false).open(file_name)?;
+    let mut page_num = [0; PAGE_NUM_SIZE];
+    let mut original_page = vec![0; PAGE_SIZE];
+
+    for _ in 0..num_pages {
+        journal.read_exact(&mut page_num)?;
+        journal.read_exact(&mut original_page)?;
+
+        file.seek(SeekFrom::Start(u32::from_be_bytes(page_num) as u64 * PAGE_SIZE as u64))?;
+        file.write_all(&original_page)?;
+    }
+
+    // The last original page could be shorter than the page image
+    file.set_len(original_size)?;
+    file.sync_all()?;
+
+    remove_file(&journal_file_name)?;
+
+    Ok(true)
+}
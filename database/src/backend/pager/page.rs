@@ -4,137 +4,223 @@ mod deserializer;
 use std::io;
 use deserializer::{
     Deserializer,
+    I64Deserializer,
+    U16Deserializer,
     U32Deserializer,
-    UTF8Deserializer,
 };
 
-use row_data::{
-    RowData,
-    COLUMN_ID_SIZE,
-    COLUMN_EMAIL_SIZE,
-    COLUMN_USERNAME_SIZE,
-};
+use row_data::{RowData, ROW_ID_SIZE};
 
 pub const PAGE_SIZE: usize = 4096;
-pub const ROW_SIZE: usize = COLUMN_ID_SIZE + COLUMN_EMAIL_SIZE + COLUMN_USERNAME_SIZE;
-const EMAIL_OFFSET: usize = COLUMN_ID_SIZE;
-const USERNAME_OFFSET: usize = EMAIL_OFFSET + COLUMN_EMAIL_SIZE;
 
+const TABLE_ID_SIZE: usize = size_of::<u32>();
+const NUM_CELLS_SIZE: usize = size_of::<u16>();
+const PAGE_HEADER_SIZE: usize = TABLE_ID_SIZE + NUM_CELLS_SIZE;
+
+const CELL_LENGTH_SIZE: usize = size_of::<u16>();
+const CELL_HEADER_SIZE: usize = ROW_ID_SIZE + CELL_LENGTH_SIZE;
+
+/// The biggest record which could be stored in an empty page
+pub const MAX_RECORD_SIZE: usize = PAGE_SIZE - PAGE_HEADER_SIZE - CELL_HEADER_SIZE;
+
+/// The serialized row with its id
+struct Cell {
+    row_id: i64,
+    record: Vec<u8>,
+}
+
+impl Cell {
+    fn get_size(&self) -> usize {
+        CELL_HEADER_SIZE + self.record.len()
+    }
+}
 
+/// The page layout: the header with the table id and the number of cells,
+/// then the cells, each one is the row id, the record length and the record.
+/// The cells are sorted by the row id
 pub struct Page {
-    num_rows: usize,
-    destination: Vec<u8>,
-    last_idx: u32,
+    table_id: u32,
+    cells: Vec<Cell>,
+    used_size: usize,
 }
 
 impl Page {
-    pub fn new(data: Option<&[u8]>) -> Self {
-        match data {
-            Some(rows) => {
-                let (last_idx, num_rows, destination_rows) = Self::parse_raw_data(&rows);
-
-                Page {
-                    num_rows,
-                    last_idx,
-                    destination: destination_rows.to_vec(),
-                }
-            },
-            None => Page {
-                num_rows: 0,
-                destination: Vec::new(),
-                last_idx: 0,
+    pub fn new(table_id: u32) -> Self {
+        Page {
+            table_id,
+            cells: Vec::new(),
+            used_size: PAGE_HEADER_SIZE,
+        }
+    }
+
+    /// The method parses raw bytes data. The data is read from the file, so the number of cells,
+    /// the record lengths and the values are checked before they are used
+    pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        if data.len() != PAGE_SIZE {
+            return Err(Self::corrupt_page_error(format!("the page size is {} bytes, expected {}", data.len(), PAGE_SIZE)));
+        }
+
+        let table_id = U32Deserializer::deserialize(data, 0, TABLE_ID_SIZE)?;
+        let num_cells = U16Deserializer::deserialize(data, TABLE_ID_SIZE, NUM_CELLS_SIZE)?;
+
+        let mut page = Page::new(table_id);
+        let mut offset = PAGE_HEADER_SIZE;
+
+        for cell_num in 0..num_cells {
+            let row_id = I64Deserializer::deserialize(data, offset, ROW_ID_SIZE)?;
+            offset += ROW_ID_SIZE;
+
+            let record_length = U16Deserializer::deserialize(data, offset, CELL_LENGTH_SIZE)? as usize;
+            offset += CELL_LENGTH_SIZE;
+
+            let record = data.get(offset..offset + record_length).ok_or_else(|| Self::corrupt_page_error(
+                format!("the cell {} of {} cells is out of the page bounds", cell_num, num_cells),
+            ))?;
+            offset += record_length;
+
+            if page.get_last_idx().is_some_and(|last_idx| last_idx >= row_id) {
+                return Err(Self::corrupt_page_error(format!("the row ids aren't sorted at the cell {}", cell_num)));
             }
+
+            RowData::deserialize(row_id, record).map_err(|err| Self::corrupt_page_error(err.to_string()))?;
+
+            let cell = Cell {
+                row_id,
+                record: record.to_vec(),
+            };
+
+            page.used_size += cell.get_size();
+            page.cells.push(cell);
         }
+
+        Ok(page)
     }
 
-    /// The method parses raw bytes data
-    fn parse_raw_data(data: &[u8]) -> (u32, usize, &[u8]) {
-        // Get the index of the last non-nullable row
-        let last_non_empty_row_idx = (0..PAGE_SIZE / ROW_SIZE)
-            .rev()
-            .find(|&i| {
-                let start = i * ROW_SIZE;
-                let end = start + ROW_SIZE;
-                data[start..end].iter().any(|&byte| byte != 0)
-            })
-            .map(|i| i + 1)
-            .unwrap_or(0);
-
-        (
-            U32Deserializer::deserialize(
-                &data[(last_non_empty_row_idx - 1) * ROW_SIZE..last_non_empty_row_idx * ROW_SIZE],
-                0,
-                COLUMN_ID_SIZE,
-            ),
-            last_non_empty_row_idx,
-            &data[..last_non_empty_row_idx * ROW_SIZE],
+    fn corrupt_page_error(reason: String) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("The page is corrupt: {}", reason),
         )
     }
 
-    fn get_value_padded(&self, value_str: &String, length: usize) -> Vec<u8> {
-        let value_bytes = value_str.as_bytes();
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(PAGE_SIZE);
 
-        // Create a Vec with predefined capacity without allocation
-        let mut result: Vec<u8> = Vec::with_capacity(length);
-        // file by bytes from slice
-        result.extend_from_slice(value_bytes);
-        // Fill by zeroes
-        result.resize(length, 0);
+        result.extend(self.table_id.to_be_bytes());
+        result.extend((self.cells.len() as u16).to_be_bytes());
+
+        for cell in self.cells.iter() {
+            result.extend(cell.row_id.to_be_bytes());
+            result.extend((cell.record.len() as u16).to_be_bytes());
+            result.extend(&cell.record);
+        }
+
+        // Every page takes exactly PAGE_SIZE bytes in the file
+        result.resize(PAGE_SIZE, 0);
 
         result
     }
 
-    // TODO Need to rewrite it by preparing the data and then copying it to the destination
-    pub fn insert_row(&mut self, row_data: &RowData) -> io::Result<usize> {
-        self.destination.extend(
-            row_data.id.to_be_bytes(),
-        );
-        self.destination.extend(
-            self.get_value_padded(&row_data.email, COLUMN_EMAIL_SIZE),
-        );
-        self.destination.extend(
-            self.get_value_padded(&row_data.user_name, COLUMN_USERNAME_SIZE),
-        );
-
-        self.num_rows += 1;
-        self.last_idx = row_data.id;
-
-        Ok(self.num_rows)
-    }
-
-    fn deserialize_row(&self, row_num: usize) -> RowData {
-        let offset = row_num * ROW_SIZE;
-
-        RowData::new(
-            U32Deserializer::deserialize(&self.destination, offset, COLUMN_ID_SIZE),
-            &UTF8Deserializer::deserialize(
-                &self.destination,
-                offset + EMAIL_OFFSET,
-                COLUMN_EMAIL_SIZE,
-            ),
-            &UTF8Deserializer::deserialize(
-                &self.destination,
-                offset + USERNAME_OFFSET,
-                COLUMN_USERNAME_SIZE,
-            ),
-        ).unwrap()
-    }
-
-    pub fn print(&self) {
-        for i in 0..self.num_rows {
-            self.deserialize_row(i).print();
+    fn create_cell(row_data: &RowData) -> io::Result<Cell> {
+        let record = row_data.serialize();
+
+        if record.len() > MAX_RECORD_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The row is too big: {} bytes, the maximum is {} bytes", record.len(), MAX_RECORD_SIZE),
+            ));
         }
+
+        Ok(Cell {
+            row_id: row_data.id,
+            record,
+        })
     }
 
-    pub fn get_last_idx(&self) -> u32 {
-        self.last_idx
+    /// The method returns the position where the row with the id is or should be
+    pub fn get_row_position(&self, id: i64) -> Result<usize, usize> {
+        self.cells.binary_search_by_key(&id, |cell| cell.row_id)
     }
 
-    pub fn get_num_rows(&self) -> usize {
-        self.num_rows
+    /// The method inserts the row keeping the order of ids.
+    /// Returns false if there is not enough space in the page
+    pub fn insert_row(&mut self, row_data: &RowData) -> io::Result<bool> {
+        let cell = Self::create_cell(row_data)?;
+
+        if self.used_size + cell.get_size() > PAGE_SIZE {
+            return Ok(false);
+        }
+
+        let cell_num = match self.get_row_position(row_data.id) {
+            Ok(_) => return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("The row with id {} already exists", row_data.id),
+            )),
+            Err(cell_num) => cell_num,
+        };
+
+        self.used_size += cell.get_size();
+        self.cells.insert(cell_num, cell);
+
+        Ok(true)
+    }
+
+    /// The method rewrites the row in place, the id of the row must stay the same.
+    /// Returns false if there is not enough space in the page
+    pub fn update_row(&mut self, cell_num: usize, row_data: &RowData) -> io::Result<bool> {
+        let cell = Self::create_cell(row_data)?;
+        let new_used_size = self.used_size - self.cells[cell_num].get_size() + cell.get_size();
+
+        if new_used_size > PAGE_SIZE {
+            return Ok(false);
+        }
+
+        self.used_size = new_used_size;
+        self.cells[cell_num] = cell;
+
+        Ok(true)
+    }
+
+    pub fn remove_row(&mut self, cell_num: usize) {
+        let cell = self.cells.remove(cell_num);
+        self.used_size -= cell.get_size();
+    }
+
+    /// The method moves the second half of the cells to the new page
+    pub fn split(&mut self) -> Page {
+        let mut new_page = Page::new(self.table_id);
+        new_page.cells = self.cells.split_off(self.cells.len() / 2);
+        new_page.used_size += new_page.cells.iter().map(Cell::get_size).sum::<usize>();
+
+        self.used_size -= new_page.used_size - PAGE_HEADER_SIZE;
+
+        new_page
+    }
+
+    pub fn find_row(&self, id: i64) -> Option<usize> {
+        self.get_row_position(id).ok()
+    }
+
+    pub fn get_row(&self, cell_num: usize) -> RowData {
+        let cell = &self.cells[cell_num];
+
+        // The records are either serialized by the page or checked when the page is read
+        RowData::deserialize(cell.row_id, &cell.record).expect("The record is valid")
     }
 
-    pub fn get_destination(&self) -> &[u8] {
-        &self.destination
+    pub fn get_table_id(&self) -> u32 {
+        self.table_id
+    }
+
+    pub fn get_first_idx(&self) -> Option<i64> {
+        self.cells.first().map(|cell| cell.row_id)
+    }
+
+    pub fn get_last_idx(&self) -> Option<i64> {
+        self.cells.last().map(|cell| cell.row_id)
+    }
+
+    pub fn get_num_rows(&self) -> usize {
+        self.cells.len()
     }
 }
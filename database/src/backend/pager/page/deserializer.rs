@@ -1,32 +1,58 @@
+use std::io;
+
 pub trait Deserializer<T> {
-    fn deserialize(data: &[u8], offset: usize, length: usize) -> T;
+    fn deserialize(data: &[u8], offset: usize, length: usize) -> io::Result<T>;
+}
+
+/// The method returns the bytes in the range, the lengths read from the file aren't trusted
+fn get_bytes(data: &[u8], offset: usize, length: usize) -> io::Result<&[u8]> {
+    offset
+        .checked_add(length)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("The range {}..{} is out of bounds, the data length is {}", offset, offset.saturating_add(length), data.len()),
+        ))
+}
+
+fn get_array<const N: usize>(data: &[u8], offset: usize, length: usize) -> io::Result<[u8; N]> {
+    get_bytes(data, offset, length)?.try_into().map_err(|_| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Expected {} bytes, got {}", N, length),
+    ))
+}
+
+pub struct U16Deserializer;
+
+impl Deserializer<u16> for U16Deserializer {
+    fn deserialize(data: &[u8], offset: usize, length: usize) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(get_array(data, offset, length)?))
+    }
 }
 
 pub struct U32Deserializer;
 
 impl Deserializer<u32> for U32Deserializer {
-    fn deserialize(data: &[u8], offset: usize, length: usize) -> u32 {
-        if offset + length <= data.len() {
-            let bytes = &data[offset..offset + length];
+    fn deserialize(data: &[u8], offset: usize, length: usize) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(get_array(data, offset, length)?))
+    }
+}
 
-            return u32::from_be_bytes(bytes.try_into().expect("Slice with incorrect length"));
-        }
+pub struct I64Deserializer;
 
-        panic!("The specific range is out of bounds");
+impl Deserializer<i64> for I64Deserializer {
+    fn deserialize(data: &[u8], offset: usize, length: usize) -> io::Result<i64> {
+        Ok(i64::from_be_bytes(get_array(data, offset, length)?))
     }
 }
 
 pub struct UTF8Deserializer;
 
 impl Deserializer<String> for UTF8Deserializer {
-    fn deserialize(data: &[u8], offset: usize, length: usize) -> String {
-        if offset + length <= data.len() {
-            let bytes = &data[offset..offset + length];
-
-            // TODO Check if we can here avoid reallocating the data
-            return String::from_utf8(bytes.to_vec()).expect("Expect valid UTF-8");
-        }
+    fn deserialize(data: &[u8], offset: usize, length: usize) -> io::Result<String> {
+        let bytes = get_bytes(data, offset, length)?;
 
-        panic!("The specific range is out of bounds");
+        // TODO Check if we can here avoid reallocating the data
+        String::from_utf8(bytes.to_vec()).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
     }
-}
\ No newline at end of file
+}
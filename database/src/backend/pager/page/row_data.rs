@@ -1,45 +1,110 @@
 use std::io;
+use crate::value::Value;
+use super::deserializer::{
+    Deserializer,
+    I64Deserializer,
+    U32Deserializer,
+    UTF8Deserializer,
+};
 
-pub const COLUMN_ID_SIZE: usize = size_of::<u32>();
-pub const COLUMN_EMAIL_SIZE: usize = 256;
-pub const COLUMN_USERNAME_SIZE: usize = 32;
+pub const ROW_ID_SIZE: usize = size_of::<i64>();
+const VALUE_TAG_SIZE: usize = size_of::<u8>();
+const INTEGER_SIZE: usize = size_of::<i64>();
+const REAL_SIZE: usize = size_of::<f64>();
+const TEXT_LENGTH_SIZE: usize = size_of::<u32>();
 
+// Every value in the record starts with the tag describing its type
+const NULL_TAG: u8 = 0;
+const INTEGER_TAG: u8 = 1;
+const TEXT_TAG: u8 = 2;
+const REAL_TAG: u8 = 3;
+
+#[derive(Debug, Clone)]
 pub struct RowData {
-    pub id: u32,
-    pub email: String,
-    pub user_name: String,
+    pub id: i64,
+    pub values: Vec<Value>,
 }
 
 impl RowData {
-    pub fn new(
-        id: u32,
-        email: &String,
-        user_name: &String,
-    ) -> io::Result<RowData> {
-        if email.len() > COLUMN_EMAIL_SIZE {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "The user's mail is too long",
-            ));
+    pub fn new(id: i64, values: Vec<Value>) -> RowData {
+        RowData {
+            id,
+            values,
         }
+    }
 
-        if user_name.len() > COLUMN_USERNAME_SIZE {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "The username is too long",
-            ));
-        }
+    /// The method serializes the values into the record:
+    /// the tag of every value is followed by its data
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut result = Vec::new();
 
-        Ok(
-            RowData {
-                id,
-                email: email.clone(),
-                user_name: user_name.clone(),
+        for value in self.values.iter() {
+            match value {
+                Value::Null => result.push(NULL_TAG),
+                Value::Integer(value) => {
+                    result.push(INTEGER_TAG);
+                    result.extend(value.to_be_bytes());
+                },
+                Value::Real(value) => {
+                    result.push(REAL_TAG);
+                    result.extend(value.to_be_bytes());
+                },
+                Value::Text(value) => {
+                    result.push(TEXT_TAG);
+                    result.extend((value.len() as u32).to_be_bytes());
+                    result.extend(value.as_bytes());
+                },
             }
-        )
+        }
+
+        result
     }
 
-    pub fn print(&self) {
-        println!("Id: {}, email: {}, user name: {}", self.id, self.email, self.user_name);
+    /// The record could be read from the corrupt file, so the unknown tags
+    /// and the lengths out of the record bounds are reported as errors
+    pub fn deserialize(id: i64, data: &[u8]) -> io::Result<RowData> {
+        let mut values = Vec::new();
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let tag = data[offset];
+            offset += VALUE_TAG_SIZE;
+
+            let value = match tag {
+                NULL_TAG => Value::Null,
+                INTEGER_TAG => {
+                    let value = I64Deserializer::deserialize(data, offset, INTEGER_SIZE)?;
+                    offset += INTEGER_SIZE;
+
+                    Value::Integer(value)
+                },
+                REAL_TAG => {
+                    let bits = I64Deserializer::deserialize(data, offset, REAL_SIZE)?;
+                    offset += REAL_SIZE;
+
+                    Value::Real(f64::from_bits(bits as u64))
+                },
+                TEXT_TAG => {
+                    let length = U32Deserializer::deserialize(data, offset, TEXT_LENGTH_SIZE)? as usize;
+                    offset += TEXT_LENGTH_SIZE;
+
+                    let value = UTF8Deserializer::deserialize(data, offset, length)?;
+                    offset += length;
+
+                    Value::Text(value)
+                },
+                _ => return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unknown value tag {} in the row {}", tag, id),
+                )),
+            };
+
+            values.push(value);
+        }
+
+        Ok(RowData {
+            id,
+            values,
+        })
     }
 }
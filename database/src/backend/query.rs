@@ -0,0 +1,645 @@
+pub mod join_order;
+pub mod subquery;
+pub mod cte;
+pub mod compound;
+
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::io;
+use std::time::Instant;
+use super::operator::{find_column_source, BoxedOperator, PlanNode, QueryContext, Row, Source};
+use super::operator::aggregate::Aggregate;
+use super::operator::filter::Filter;
+use super::operator::join::{Join, JoinAlgorithm};
+use super::operator::limit::Limit;
+use super::operator::profile::Profile;
+use super::operator::project::Project;
+use super::operator::sort::Sort;
+use super::operator::window::Window;
+use super::pager::Pager;
+use super::pager::page::row_data::RowData;
+use super::sorter::SortOrder;
+use super::table::Table;
+use super::table::statistics::{DEFAULT_EQUAL_SELECTIVITY, DEFAULT_RANGE_SELECTIVITY};
+use crate::session::Session;
+use crate::statement::expression::{BinaryOperator, EmptyRow, Expression, RowSource};
+use crate::statement::select_statement::{JoinConstraint, JoinType, OrderingTerm, ResultColumn, SelectStatement, TableReference};
+use crate::value::Value;
+use join_order::{JoinMethod, JoinPlanner, JoinStep, PlannedCondition};
+use cte::CteScope;
+use subquery::{bind_clauses, bind_outer_columns, DerivedTable};
+
+/// The method returns the numbers of the sources the expression depends on
+pub fn get_source_nums(expression: &Expression, sources: &[Source]) -> io::Result<BTreeSet<usize>> {
+    if let Expression::Column { table_name, column_name } = expression {
+        return Ok(BTreeSet::from([find_column_source(sources, table_name.as_deref(), column_name)?]));
+    }
+
+    // The subqueries could use the columns of any source, so they are evaluated on the joined rows
+    if expression.has_subquery() {
+        return Ok((0..sources.len()).collect());
+    }
+
+    let mut source_nums = BTreeSet::new();
+
+    for child in expression.get_children() {
+        source_nums.extend(get_source_nums(child, sources)?);
+    }
+
+    Ok(source_nums)
+}
+
+pub fn find_table<'a>(tables: &'a [Table], table_name: &str) -> io::Result<&'a Table> {
+    tables
+        .iter()
+        .find(|table| table.get_name() == table_name)
+        .ok_or_else(|| io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No such table: {}", table_name),
+        ))
+}
+
+/// The method returns the sources of the table references, the derived tables
+/// are materialized in the order of the references. The tables of WITH hide the tables of the database
+pub fn get_sources<'a>(
+    tables: &'a [Table],
+    derived_tables: &'a [DerivedTable],
+    ctes: Option<&'a CteScope<'a>>,
+    pager: &'a Pager,
+    select_statement: &'a SelectStatement,
+) -> io::Result<Vec<Source<'a>>> {
+    let mut derived_tables = derived_tables.iter();
+
+    let mut get_source = |table_reference: &'a TableReference| -> io::Result<Source<'a>> {
+        let derived_table = match table_reference.is_derived() {
+            true => Some(derived_tables.next().expect("The derived tables are materialized before the query")),
+            false => ctes.and_then(|ctes| ctes.find(&table_reference.table_name)),
+        };
+
+        let (table, pager) = match derived_table {
+            Some(derived_table) => (&derived_table.table, &derived_table.pager),
+            None => (find_table(tables, &table_reference.table_name)?, pager),
+        };
+
+        Ok(Source {
+            table,
+            pager,
+            name: table_reference.get_name(),
+            merged_columns: Vec::new(),
+        })
+    };
+
+    let mut sources = vec![get_source(&select_statement.from)?];
+
+    for join in select_statement.joins.iter() {
+        let mut source = get_source(&join.table)?;
+
+        if let Some(JoinConstraint::Using(column_names)) = &join.constraint {
+            for column_name in column_names.iter() {
+                if !source.table.has_column(column_name) || find_column_source(&sources, None, column_name).is_err() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Cannot join using column {} - column not present in both tables", column_name),
+                    ));
+                }
+
+                source.merged_columns.push(column_name.clone());
+            }
+        }
+
+        sources.push(source);
+    }
+
+    Ok(sources)
+}
+
+/// The method returns the result columns with their names, * is expanded to the columns of the tables
+pub fn get_result_columns(sources: &[Source], columns: &[ResultColumn]) -> io::Result<Vec<(String, Expression)>> {
+    let get_source_columns = |source: &Source, with_merged_columns: bool| -> Vec<(String, Expression)> {
+        source.table.get_definition().columns
+            .iter()
+            .filter(|column| with_merged_columns || !source.merged_columns.contains(&column.name))
+            .map(|column| (
+                column.name.clone(),
+                Expression::Column { table_name: Some(source.name.to_string()), column_name: column.name.clone() },
+            ))
+            .collect()
+    };
+
+    let mut result_columns = Vec::new();
+
+    for column in columns.iter() {
+        match column {
+            ResultColumn::All => {
+                for source in sources.iter() {
+                    result_columns.extend(get_source_columns(source, false));
+                }
+            },
+            ResultColumn::TableAll(table_name) => {
+                let source = sources
+                    .iter()
+                    .find(|source| source.name == table_name)
+                    .ok_or_else(|| io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("No such table: {}", table_name),
+                    ))?;
+
+                result_columns.extend(get_source_columns(source, true));
+            },
+            ResultColumn::Expression { expression, alias } => {
+                result_columns.push((alias.clone().unwrap_or_else(|| expression.to_string()), expression.clone()));
+            },
+        }
+    }
+
+    Ok(result_columns)
+}
+
+/// The term of ORDER BY or GROUP BY could refer to the result column by its name or position,
+/// such term is replaced by the expression of the result column
+fn resolve_result_column(clause: &str, term_num: usize, expression: &Expression, result_columns: &[(String, Expression)]) -> io::Result<Expression> {
+    match expression {
+        Expression::Literal(Value::Integer(position)) => {
+            match usize::try_from(*position).ok().filter(|position| (1..=result_columns.len()).contains(position)) {
+                Some(position) => Ok(result_columns[position - 1].1.clone()),
+                None => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} term {} out of range - should be between 1 and {}", clause, term_num + 1, result_columns.len()),
+                )),
+            }
+        },
+        Expression::Column { table_name: None, column_name } => Ok(result_columns
+            .iter()
+            .find(|(name, _)| name == column_name)
+            .map_or_else(|| expression.clone(), |(_, expression)| expression.clone())),
+        expression => Ok(expression.clone()),
+    }
+}
+
+/// The ORDER BY terms with the result columns replaced by their expressions
+pub fn resolve_order_by(order_by: &[OrderingTerm], result_columns: &[(String, Expression)]) -> io::Result<Vec<OrderingTerm>> {
+    order_by
+        .iter()
+        .enumerate()
+        .map(|(term_num, ordering_term)| Ok(OrderingTerm {
+            expression: resolve_result_column("ORDER BY", term_num, &ordering_term.expression, result_columns)?,
+            ..ordering_term.clone()
+        }))
+        .collect()
+}
+
+/// The GROUP BY terms with the result columns replaced by their expressions, unlike ORDER BY
+/// the column of the sources is preferred to the result column with the same name as in SQLite.
+/// The groups can't be made by the aggregates
+pub fn resolve_group_by(group_by: &[Expression], sources: &[Source], result_columns: &[(String, Expression)]) -> io::Result<Vec<Expression>> {
+    let mut resolved_group_by = Vec::new();
+
+    for (term_num, expression) in group_by.iter().enumerate() {
+        let expression = match expression {
+            Expression::Column { table_name: None, column_name } if sources.iter().any(|source| source.table.has_column(column_name)) => {
+                expression.clone()
+            },
+            expression => resolve_result_column("GROUP BY", term_num, expression, result_columns)?,
+        };
+
+        if !expression.get_aggregates()?.is_empty() || !expression.get_windows()?.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("GROUP BY term {} can't use the aggregate or window functions", term_num + 1),
+            ));
+        }
+
+        resolved_group_by.push(expression);
+    }
+
+    Ok(resolved_group_by)
+}
+
+/// The method evaluates LIMIT or OFFSET, the negative value means there is no limit
+pub fn evaluate_limit(session: &Session, expression: &Option<Expression>) -> io::Result<Option<usize>> {
+    let Some(expression) = expression else {
+        return Ok(None);
+    };
+
+    match expression.evaluate(&EmptyRow { session })? {
+        Value::Integer(value) => Ok(usize::try_from(value).ok()),
+        value => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("datatype mismatch: LIMIT and OFFSET must be integers, got {}", value.to_sql()),
+        )),
+    }
+}
+
+/// The method combines the expressions with AND
+pub fn conjoin(expressions: Vec<Expression>) -> Option<Expression> {
+    expressions
+        .into_iter()
+        .reduce(|left, right| Expression::Binary(Box::new(left), BinaryOperator::And, Box::new(right)))
+}
+
+/// The way the operators of the query are used
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlanMode {
+    Execute,
+    // The operators are described together with the estimated numbers of their rows
+    Explain,
+    // The operators are also measured while the query is run
+    Analyze,
+}
+
+/// The method wraps the operator to show its estimate, the operator is returned as is unless the query is explained
+fn profile<'a, T: 'a>(mode: PlanMode, operator: BoxedOperator<'a, T>, estimated_rows: f64) -> BoxedOperator<'a, T> {
+    match mode {
+        PlanMode::Execute => operator,
+        PlanMode::Explain | PlanMode::Analyze => Box::new(Profile::new(operator, estimated_rows, mode == PlanMode::Analyze)),
+    }
+}
+
+/// The method passes the rows of the operator to the sink, the operator is closed even if it fails
+fn drain(root: &mut BoxedOperator<RowData>, mut sink: impl FnMut(RowData)) -> io::Result<usize> {
+    let mut num_rows = 0;
+
+    root.open()?;
+
+    let result = loop {
+        match root.next() {
+            Ok(Some(row_data)) => {
+                sink(row_data);
+                num_rows += 1;
+            },
+            Ok(None) => break Ok(num_rows),
+            Err(err) => break Err(err),
+        }
+    };
+
+    root.close();
+
+    result
+}
+
+/// The method evaluates the expression which doesn't depend on the tables, it could run the subqueries
+pub fn evaluate_constant(tables: &[Table], pager: &Pager, session: &Session, expression: &Expression) -> io::Result<Value> {
+    let context = QueryContext::new(tables, pager, session, None, Vec::new(), Vec::new(), None);
+
+    context.evaluate(expression, &Row { sources: Vec::new(), aggregate_values: Vec::new(), window_values: Vec::new() })
+}
+
+/// The method describes how UPDATE or DELETE finds the rows of the table
+pub fn explain_table_scan(description: String, tables: &[Table], table: &Table, pager: &Pager, session: &Session, where_clause: &Option<Expression>) -> Vec<String> {
+    let sources = vec![Source { table, pager, name: table.get_name(), merged_columns: Vec::new() }];
+    let context = QueryContext::new(tables, pager, session, None, sources, Vec::new(), None);
+
+    let access_path = table.plan_scan(pager, where_clause, &[]);
+    let scan = profile(
+        PlanMode::Explain,
+        context.create_scan(0, access_path.scan_plan, false, where_clause.clone()),
+        access_path.num_rows,
+    );
+
+    PlanNode::new(description, vec![scan.explain()]).to_lines()
+}
+
+/// The planned SELECT, the operators share the context owned by the query
+pub struct Query<'a> {
+    context: QueryContext<'a>,
+    // The columns of the outer query are replaced by their values in the subquery
+    select_statement: Cow<'a, SelectStatement>,
+    result_columns: Vec<(String, Expression)>,
+    // The GROUP BY and ORDER BY terms with the result columns replaced by their expressions
+    group_by: Vec<Expression>,
+    order_by: Vec<OrderingTerm>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+impl<'a> Query<'a> {
+    /// The tables of WITH and the derived tables of FROM must be materialized before the query,
+    /// the subquery gets the row of the outer query its columns are evaluated against
+    pub fn new(
+        tables: &'a [Table],
+        derived_tables: &'a [DerivedTable],
+        pager: &'a Pager,
+        session: &'a Session,
+        select_statement: &'a SelectStatement,
+        ctes: Option<&'a CteScope<'a>>,
+        outer: Option<&'a dyn RowSource>,
+    ) -> io::Result<Self> {
+        let limit = evaluate_limit(session, &select_statement.limit)?;
+        let offset = evaluate_limit(session, &select_statement.offset)?;
+
+        let sources = get_sources(tables, derived_tables, ctes, pager, select_statement)?;
+        let mut result_columns = get_result_columns(&sources, &select_statement.columns)?;
+        let mut group_by = resolve_group_by(&select_statement.group_by, &sources, &result_columns)?;
+        let mut order_by = resolve_order_by(&select_statement.order_by, &result_columns)?;
+
+        let mut is_correlated = derived_tables.iter().any(|derived_table| derived_table.is_correlated)
+            || ctes.is_some_and(CteScope::is_correlated);
+        let mut select_statement = Cow::Borrowed(select_statement);
+
+        if let Some(outer) = outer {
+            let expressions = result_columns
+                .iter_mut()
+                .map(|(_, expression)| expression)
+                .chain(group_by.iter_mut())
+                .chain(order_by.iter_mut().map(|ordering_term| &mut ordering_term.expression));
+
+            for expression in expressions {
+                is_correlated |= bind_outer_columns(expression, &sources, outer)?;
+            }
+
+            let mut bound_statement = select_statement.clone().into_owned();
+
+            if bind_clauses(&mut bound_statement, &sources, outer)? {
+                is_correlated = true;
+                select_statement = Cow::Owned(bound_statement);
+            }
+        }
+
+        let mut aggregates: Vec<Expression> = Vec::new();
+
+        if select_statement.is_aggregate()? {
+            let expressions = result_columns
+                .iter()
+                .map(|(_, expression)| expression)
+                .chain(select_statement.having.iter())
+                .chain(order_by.iter().map(|ordering_term| &ordering_term.expression));
+
+            for expression in expressions {
+                for aggregate in expression.get_aggregates()? {
+                    if !aggregates.contains(aggregate) {
+                        aggregates.push(aggregate.clone());
+                    }
+                }
+            }
+        }
+
+        let mut windows: Vec<Expression> = Vec::new();
+
+        let expressions = result_columns
+            .iter()
+            .map(|(_, expression)| expression)
+            .chain(order_by.iter().map(|ordering_term| &ordering_term.expression));
+
+        for expression in expressions {
+            for window in expression.get_windows()? {
+                if !windows.contains(window) {
+                    windows.push(window.clone());
+                }
+            }
+        }
+
+        let mut context = QueryContext::new(tables, pager, session, ctes, sources, aggregates, outer);
+        context.windows = windows;
+
+        if is_correlated {
+            context.set_correlated();
+        }
+
+        Ok(Query {
+            context,
+            select_statement,
+            result_columns,
+            group_by,
+            order_by,
+            limit,
+            offset,
+        })
+    }
+
+    pub fn get_column_names(&self) -> Vec<String> {
+        self.result_columns.iter().map(|(column_name, _)| column_name.clone()).collect()
+    }
+
+    pub fn get_result_columns(&self) -> &[(String, Expression)] {
+        &self.result_columns
+    }
+
+    /// The method checks whether the subquery used the columns of the outer query
+    pub fn is_correlated(&self) -> bool {
+        self.context.is_correlated()
+    }
+
+    /// The method collects the conditions of WHERE and ON with the sources they depend on,
+    /// the conditions of USING are turned into the equalities
+    fn collect_conditions(&self) -> io::Result<Vec<PlannedCondition>> {
+        let sources = &self.context.sources;
+        let mut conditions = Vec::new();
+
+        for (join_num, join) in self.select_statement.joins.iter().enumerate() {
+            let source_num = join_num + 1;
+
+            let expressions = match &join.constraint {
+                Some(JoinConstraint::On(expression)) => expression.get_conjuncts().into_iter().cloned().collect(),
+                Some(JoinConstraint::Using(column_names)) => column_names
+                    .iter()
+                    .map(|column_name| {
+                        let left_source_num = find_column_source(&sources[..source_num], None, column_name)?;
+
+                        Ok(Expression::Binary(
+                            Box::new(Expression::Column {
+                                table_name: Some(sources[left_source_num].name.to_string()),
+                                column_name: column_name.clone(),
+                            }),
+                            BinaryOperator::Equal,
+                            Box::new(Expression::Column {
+                                table_name: Some(sources[source_num].name.to_string()),
+                                column_name: column_name.clone(),
+                            }),
+                        ))
+                    })
+                    .collect::<io::Result<Vec<Expression>>>()?,
+                None => Vec::new(),
+            };
+
+            for expression in expressions {
+                let source_nums = match expression.has_subquery() {
+                    // The subqueries of ON could use the columns of the joined tables only
+                    true => (0..=source_num).collect(),
+                    false => get_source_nums(&expression, sources)?,
+                };
+
+                if source_nums.iter().any(|&condition_source_num| condition_source_num > source_num) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("The ON clause references the tables which are joined later: {}", expression),
+                    ));
+                }
+
+                // The ON conditions of the INNER JOIN are the same as the WHERE conditions
+                conditions.push(PlannedCondition {
+                    expression,
+                    source_nums,
+                    left_join_source_num: Some(source_num).filter(|_| join.join_type == JoinType::Left),
+                });
+            }
+        }
+
+        if let Some(where_clause) = &self.select_statement.where_clause {
+            for expression in where_clause.get_conjuncts() {
+                conditions.push(PlannedCondition {
+                    expression: expression.clone(),
+                    source_nums: get_source_nums(expression, sources)?,
+                    left_join_source_num: None,
+                });
+            }
+        }
+
+        Ok(conditions)
+    }
+
+    /// The method builds the scan of the joined source unless its rows are looked up for every left row
+    fn create_join_algorithm(&self, mode: PlanMode, source_num: usize, method: JoinMethod, local_condition: Option<Expression>) -> JoinAlgorithm<'_> {
+        if let JoinMethod::IndexNestedLoop { column_name, left } = method {
+            return JoinAlgorithm::IndexNestedLoop {
+                column_name,
+                left,
+                condition: local_condition,
+            };
+        }
+
+        // The rows of the new source are read once
+        let source = &self.context.sources[source_num];
+        let access_path = source.table.plan_scan(source.pager, &local_condition, &[]);
+        let right_rows = profile(mode, self.context.create_scan(source_num, access_path.scan_plan, false, local_condition), access_path.num_rows);
+
+        match method {
+            JoinMethod::Hash { left, right } => JoinAlgorithm::Hash { left, right, right_rows },
+            _ => JoinAlgorithm::NestedLoop(right_rows),
+        }
+    }
+
+    /// The method builds the operators producing the result rows. The sources are joined
+    /// in the order chosen by the planner, the row ids of the rows of the first table in FROM are kept
+    pub fn plan(&self, mode: PlanMode) -> io::Result<BoxedOperator<'_, RowData>> {
+        let context = &self.context;
+        let is_aggregate = self.select_statement.is_aggregate()?;
+        let conditions = self.collect_conditions()?;
+
+        let left_join_source_nums = (1..)
+            .zip(self.select_statement.joins.iter())
+            .filter(|(_, join)| join.join_type == JoinType::Left)
+            .map(|(source_num, _)| source_num)
+            .collect();
+
+        // The aggregated rows and the rows of the windows are always sorted
+        let has_windows = !context.windows.is_empty();
+        let order_by: &[OrderingTerm] = if is_aggregate || has_windows { &[] } else { &self.order_by };
+
+        let planner = JoinPlanner::new(context, &conditions, left_join_source_nums, order_by);
+        let join_order = planner.plan()?;
+
+        // The joins keep the order of the rows of the first source, so it could be scanned in the required order
+        let first_source_num = join_order.first_source_num;
+        let scan_order_by = planner.get_scan_order_by(first_source_num)?;
+
+        let (local_conditions, _) = planner.get_step_conditions(&BTreeSet::new(), first_source_num);
+        let local_condition = conjoin(local_conditions);
+
+        let first_source = &context.sources[first_source_num];
+        let access_path = first_source.table.plan_scan(first_source.pager, &local_condition, scan_order_by);
+        let is_sorted = self.order_by.is_empty() || (access_path.order.is_some() && !scan_order_by.is_empty());
+
+        let mut num_rows = access_path.num_rows;
+        let scan = context.create_scan(first_source_num, access_path.scan_plan, access_path.order.unwrap_or(false), local_condition);
+        let mut root = profile(mode, scan, num_rows);
+        let mut joined_source_nums = BTreeSet::from([first_source_num]);
+
+        for JoinStep { source_num, method, num_rows: num_joined_rows } in join_order.steps {
+            let (local_conditions, join_conditions) = planner.get_step_conditions(&joined_source_nums, source_num);
+            let algorithm = self.create_join_algorithm(mode, source_num, method, conjoin(local_conditions));
+
+            let join_type = match source_num {
+                0 => JoinType::Inner,
+                _ => self.select_statement.joins[source_num - 1].join_type,
+            };
+
+            num_rows = num_joined_rows;
+            root = profile(mode, Box::new(Join::new(context, root, source_num, join_type, algorithm, conjoin(join_conditions))), num_rows);
+            joined_source_nums.insert(source_num);
+        }
+
+        let remaining_conditions: Vec<Expression> = conditions
+            .iter()
+            .filter(|condition| planner.is_remaining(condition))
+            .map(|condition| condition.expression.clone())
+            .collect();
+
+        let num_remaining_conditions = remaining_conditions.len() as i32;
+
+        if let Some(condition) = conjoin(remaining_conditions) {
+            num_rows *= DEFAULT_RANGE_SELECTIVITY.powi(num_remaining_conditions);
+            root = profile(mode, Box::new(Filter::new(context, root, condition)), num_rows);
+        }
+
+        if is_aggregate {
+            // There is one group without GROUP BY even if there are no rows
+            num_rows = match self.group_by.is_empty() {
+                true => 1.0,
+                false => (num_rows * DEFAULT_EQUAL_SELECTIVITY).max(1.0).min(num_rows),
+            };
+            root = profile(mode, Box::new(Aggregate::new(context, root, self.group_by.clone())), num_rows);
+
+            if let Some(having) = &self.select_statement.having {
+                num_rows *= DEFAULT_RANGE_SELECTIVITY;
+                root = profile(mode, Box::new(Filter::new(context, root, having.clone())), num_rows);
+            }
+        }
+
+        if has_windows {
+            root = profile(mode, Box::new(Window::new(context, root)), num_rows);
+        }
+
+        // The sort keys follow the values of the result columns
+        let mut expressions: Vec<Expression> = self.result_columns.iter().map(|(_, expression)| expression.clone()).collect();
+
+        if !is_sorted {
+            expressions.extend(self.order_by.iter().map(|ordering_term| ordering_term.expression.clone()));
+        }
+
+        let mut root: BoxedOperator<RowData> = profile(mode, Box::new(Project::new(context, root, expressions)), num_rows);
+
+        if !is_sorted {
+            let sort_orders = self.order_by
+                .iter()
+                .map(|ordering_term| SortOrder {
+                    descending: ordering_term.descending,
+                    nulls_first: ordering_term.is_nulls_first(),
+                })
+                .collect();
+
+            root = profile(mode, Box::new(Sort::new(root, sort_orders)), num_rows);
+        }
+
+        if self.limit.is_some() || self.offset.is_some() {
+            let offset = self.offset.unwrap_or(0);
+
+            num_rows = (num_rows - offset as f64).max(0.0).min(self.limit.map_or(f64::INFINITY, |limit| limit as f64));
+            root = profile(mode, Box::new(Limit::new(root, self.limit.unwrap_or(usize::MAX), offset)), num_rows);
+        }
+
+        Ok(root)
+    }
+
+    /// The method runs the query and passes the result rows to the sink one by one
+    pub fn run(&self, sink: impl FnMut(RowData)) -> io::Result<usize> {
+        drain(&mut self.plan(PlanMode::Execute)?, sink)
+    }
+
+    /// The method describes the operators chosen for the query, the query is not run
+    pub fn explain(&self) -> io::Result<Vec<String>> {
+        Ok(self.plan(PlanMode::Explain)?.explain().to_lines())
+    }
+
+    /// The method runs the query and describes the operators with the numbers of the rows
+    /// they actually returned and the time spent in them, the result rows are discarded
+    pub fn analyze(&self) -> io::Result<Vec<String>> {
+        let start = Instant::now();
+        let mut root = self.plan(PlanMode::Analyze)?;
+        let num_rows = drain(&mut root, |_| {})?;
+
+        let mut lines = root.explain().to_lines();
+        lines.push(format!("Returned {} rows in {:.3} ms", num_rows, start.elapsed().as_secs_f64() * 1000.0));
+
+        Ok(lines)
+    }
+}
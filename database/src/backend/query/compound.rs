@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+use std::io;
+use super::cte::CteScope;
+use super::subquery::{create_derived_table, get_derived_column_names, with_query, DerivedTable};
+use crate::backend::pager::Pager;
+use crate::backend::table::Table;
+use crate::session::Session;
+use crate::statement::expression::RowSource;
+use crate::statement::select_statement::{CompoundOperator, SelectStatement, DERIVED_TABLE_NAME};
+use crate::value::Value;
+
+pub fn column_count_error(operator: CompoundOperator) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("SELECTs to the left and right of {} do not have the same number of result columns", operator),
+    )
+}
+
+/// The method combines the rows by the operator, the rows of the result are distinct
+/// for all the operators except UNION ALL. NULLs are equal to each other
+pub fn combine_rows(operator: CompoundOperator, left: Vec<Vec<Value>>, right: Vec<Vec<Value>>) -> Vec<Vec<Value>> {
+    let mut added_rows = HashSet::new();
+
+    match operator {
+        CompoundOperator::UnionAll => left.into_iter().chain(right).collect(),
+        CompoundOperator::Union => left
+            .into_iter()
+            .chain(right)
+            .filter(|values| added_rows.insert(values.clone()))
+            .collect(),
+        CompoundOperator::Intersect | CompoundOperator::Except => {
+            let right: HashSet<Vec<Value>> = right.into_iter().collect();
+            let is_kept = operator == CompoundOperator::Intersect;
+
+            left
+                .into_iter()
+                .filter(|values| right.contains(values) == is_kept && added_rows.insert(values.clone()))
+                .collect()
+        },
+    }
+}
+
+/// The method runs the selects of the compound select and combines their rows from left to right,
+/// the columns are named after the columns of the first select
+pub fn materialize_compound(
+    tables: &[Table],
+    pager: &Pager,
+    session: &Session,
+    select_statement: &SelectStatement,
+    ctes: Option<&CteScope>,
+    outer: Option<&dyn RowSource>,
+) -> io::Result<DerivedTable> {
+    let run_select = |select_statement: &SelectStatement| {
+        with_query(tables, pager, session, select_statement, ctes, outer, |query| {
+            let mut rows = Vec::new();
+            query.run(|row_data| rows.push(row_data.values))?;
+
+            Ok((get_derived_column_names(query), rows, query.is_correlated()))
+        })
+    };
+
+    let (column_names, mut rows, mut is_correlated) = run_select(&select_statement.get_first_select())?;
+
+    for (operator, compound_select) in select_statement.compound.iter() {
+        let (compound_column_names, compound_rows, is_compound_correlated) = run_select(compound_select)?;
+
+        if compound_column_names.len() != column_names.len() {
+            return Err(column_count_error(*operator));
+        }
+
+        rows = combine_rows(*operator, rows, compound_rows);
+        is_correlated |= is_compound_correlated;
+    }
+
+    create_derived_table(DERIVED_TABLE_NAME, column_names, rows, is_correlated)
+}
@@ -0,0 +1,198 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io;
+use std::mem;
+use super::evaluate_limit;
+use super::compound::column_count_error;
+use super::subquery::{create_derived_table, get_derived_column_names, with_query, DerivedTable};
+use crate::backend::pager::Pager;
+use crate::backend::table::Table;
+use crate::session::Session;
+use crate::statement::expression::RowSource;
+use crate::statement::select_statement::{CommonTableExpression, CompoundOperator, SelectStatement};
+use crate::value::Value;
+
+/// The tables of the WITH clauses visible to the query, the tables of the inner
+/// WITH clauses hide the outer tables with the same names
+pub struct CteScope<'a> {
+    tables: Vec<DerivedTable>,
+    parent: Option<&'a CteScope<'a>>,
+}
+
+impl CteScope<'_> {
+    pub fn find(&self, table_name: &str) -> Option<&DerivedTable> {
+        self.tables
+            .iter()
+            .find(|derived_table| derived_table.table.get_name() == table_name)
+            .or_else(|| self.parent.and_then(|parent| parent.find(table_name)))
+    }
+
+    /// The method checks whether any table uses the columns of the outer query
+    pub fn is_correlated(&self) -> bool {
+        self.tables.iter().any(|derived_table| derived_table.is_correlated)
+            || self.parent.is_some_and(CteScope::is_correlated)
+    }
+}
+
+/// The rows of the select with the number of its columns
+struct SelectResult {
+    num_columns: usize,
+    rows: Vec<Vec<Value>>,
+    is_correlated: bool,
+}
+
+fn run_select(
+    tables: &[Table],
+    pager: &Pager,
+    session: &Session,
+    select_statement: &SelectStatement,
+    ctes: &CteScope,
+    outer: Option<&dyn RowSource>,
+) -> io::Result<SelectResult> {
+    with_query(tables, pager, session, select_statement, Some(ctes), outer, |query| {
+        let mut rows = Vec::new();
+        query.run(|row_data| rows.push(row_data.values))?;
+
+        Ok(SelectResult {
+            num_columns: query.get_column_names().len(),
+            rows,
+            is_correlated: query.is_correlated(),
+        })
+    })
+}
+
+/// The method materializes the tables of the WITH clause of the statement,
+/// every table could read the tables defined before it
+pub fn materialize_ctes<'a>(
+    tables: &[Table],
+    pager: &Pager,
+    session: &Session,
+    select_statement: &SelectStatement,
+    ctes: Option<&'a CteScope<'a>>,
+    outer: Option<&dyn RowSource>,
+) -> io::Result<Option<CteScope<'a>>> {
+    let Some(with_clause) = &select_statement.with_clause else {
+        return Ok(None);
+    };
+
+    let mut scope = CteScope {
+        tables: Vec::new(),
+        parent: ctes,
+    };
+
+    for cte in with_clause.tables.iter() {
+        if scope.tables.iter().any(|derived_table| derived_table.table.get_name() == cte.table_name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Duplicate WITH table name: {}", cte.table_name),
+            ));
+        }
+
+        let derived_table = materialize_cte(tables, pager, session, cte, with_clause.recursive, &scope, outer)?;
+        scope.tables.push(derived_table);
+    }
+
+    Ok(Some(scope))
+}
+
+/// The recursive select is run on the rows added by the previous step until it adds no rows,
+/// UNION skips the rows which are already in the table
+fn materialize_cte(
+    tables: &[Table],
+    pager: &Pager,
+    session: &Session,
+    cte: &CommonTableExpression,
+    recursive: bool,
+    scope: &CteScope,
+    outer: Option<&dyn RowSource>,
+) -> io::Result<DerivedTable> {
+    let select_statement = &cte.select_statement;
+    let recursive_select = cte.get_recursive_select().filter(|_| recursive);
+
+    // LIMIT and OFFSET of the recursive table are applied to all its rows
+    let initial_select = match recursive_select {
+        Some(_) => Cow::Owned(SelectStatement {
+            compound: select_statement.compound[..select_statement.compound.len() - 1].to_vec(),
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+            ..select_statement.clone()
+        }),
+        None => Cow::Borrowed(select_statement),
+    };
+
+    let (column_names, mut rows, mut is_correlated) = with_query(tables, pager, session, &initial_select, Some(scope), outer, |query| {
+        let mut rows = Vec::new();
+        query.run(|row_data| rows.push(row_data.values))?;
+
+        Ok((get_derived_column_names(query), rows, query.is_correlated()))
+    })?;
+
+    let column_names = if cte.column_names.is_empty() {
+        column_names
+    } else if cte.column_names.len() == column_names.len() {
+        cte.column_names.clone()
+    } else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("The table {} has {} values for {} columns", cte.table_name, column_names.len(), cte.column_names.len()),
+        ));
+    };
+
+    if let Some((operator, recursive_select)) = recursive_select {
+        let union_all = match operator {
+            CompoundOperator::UnionAll => true,
+            CompoundOperator::Union => false,
+            _ => return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The recursive select of {} must be joined by UNION or UNION ALL", cte.table_name),
+            )),
+        };
+
+        if !select_statement.order_by.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("ORDER BY is not supported by the recursive table {}", cte.table_name),
+            ));
+        }
+
+        let limit = evaluate_limit(session, &select_statement.limit)?;
+        let offset = evaluate_limit(session, &select_statement.offset)?.unwrap_or(0);
+
+        let mut added_rows = HashSet::new();
+
+        if !union_all {
+            rows.retain(|values| added_rows.insert(values.clone()));
+        }
+
+        let mut new_rows = rows.clone();
+
+        while !new_rows.is_empty() && limit.is_none_or(|limit| rows.len() < offset + limit) {
+            // The recursive select reads the rows added by the previous step instead of the whole table
+            let working_table = create_derived_table(&cte.table_name, column_names.clone(), mem::take(&mut new_rows), false)?;
+            let step_scope = CteScope { tables: vec![working_table], parent: Some(scope) };
+            let result = run_select(tables, pager, session, recursive_select, &step_scope, outer)?;
+
+            if result.num_columns != column_names.len() {
+                return Err(column_count_error(operator));
+            }
+
+            is_correlated |= result.is_correlated;
+            new_rows = result.rows;
+
+            if !union_all {
+                new_rows.retain(|values| added_rows.insert(values.clone()));
+            }
+
+            rows.extend(new_rows.iter().cloned());
+        }
+
+        rows = rows
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+    }
+
+    create_derived_table(&cte.table_name, column_names, rows, is_correlated)
+}
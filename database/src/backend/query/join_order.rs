@@ -0,0 +1,304 @@
+use std::collections::BTreeSet;
+use std::io;
+use super::{conjoin, get_source_nums};
+use crate::backend::operator::{find_column_source, QueryContext};
+use crate::backend::sorter::estimate_sort_cost;
+use crate::backend::table::{ROW_LOOKUP_COST, statistics::{DEFAULT_EQUAL_SELECTIVITY, DEFAULT_RANGE_SELECTIVITY}};
+use crate::statement::expression::{BinaryOperator, Expression};
+use crate::statement::select_statement::OrderingTerm;
+
+/// The orders of more sources are not compared, the sources are joined in the order of FROM
+const MAX_REORDERED_SOURCES: usize = 8;
+
+/// The condition of WHERE or ON with the sources it depends on
+pub struct PlannedCondition {
+    pub expression: Expression,
+    pub source_nums: BTreeSet<usize>,
+    // The right source of the LEFT JOIN if the condition is in its ON clause
+    pub left_join_source_num: Option<usize>,
+}
+
+/// The way the source is joined to the joined sources
+#[derive(Debug, Clone, PartialEq)]
+pub enum JoinMethod {
+    // The rows are looked up by the column for every left row
+    IndexNestedLoop {
+        column_name: String,
+        left: Expression,
+    },
+    // The rows are put into the hash table by the values of the right expression
+    Hash {
+        left: Expression,
+        right: Expression,
+    },
+    NestedLoop,
+}
+
+/// The source joined to the joined sources
+#[derive(Clone)]
+pub struct JoinStep {
+    pub source_num: usize,
+    pub method: JoinMethod,
+    // The estimated number of the rows after the join
+    pub num_rows: f64,
+}
+
+/// The order of the sources with the ways they are joined
+pub struct JoinOrder {
+    pub first_source_num: usize,
+    pub steps: Vec<JoinStep>,
+}
+
+/// The estimates of the joined sources
+#[derive(Clone)]
+struct PartialPlan {
+    cost: f64,
+    num_rows: f64,
+    first_source_num: usize,
+    steps: Vec<JoinStep>,
+}
+
+/// The planner choosing the join order and the join methods by their estimated costs
+pub struct JoinPlanner<'a> {
+    context: &'a QueryContext<'a>,
+    conditions: &'a [PlannedCondition],
+    // The right sources of the LEFT JOINs
+    left_join_source_nums: BTreeSet<usize>,
+    // The ORDER BY terms which could be satisfied by the scan of one source
+    order_by: &'a [OrderingTerm],
+}
+
+impl<'a> JoinPlanner<'a> {
+    pub fn new(context: &'a QueryContext<'a>, conditions: &'a [PlannedCondition], left_join_source_nums: BTreeSet<usize>, order_by: &'a [OrderingTerm]) -> Self {
+        JoinPlanner {
+            context,
+            conditions,
+            left_join_source_nums,
+            order_by,
+        }
+    }
+
+    /// The method returns the local conditions of the source and the conditions which are checked
+    /// when it's joined to the joined sources. The WHERE conditions must not be checked
+    /// while the LEFT JOIN is done, since they would keep the rows without the matching rows
+    pub fn get_step_conditions(&self, joined_source_nums: &BTreeSet<usize>, source_num: usize) -> (Vec<Expression>, Vec<Expression>) {
+        let mut local_conditions = Vec::new();
+        let mut join_conditions = Vec::new();
+
+        for condition in self.conditions.iter() {
+            let is_placed = match condition.left_join_source_num {
+                Some(left_join_source_num) => left_join_source_num == source_num,
+                None => {
+                    condition.source_nums.contains(&source_num)
+                        && !self.left_join_source_nums.contains(&source_num)
+                        && condition.source_nums.iter().all(|condition_source_num| {
+                            *condition_source_num == source_num || joined_source_nums.contains(condition_source_num)
+                        })
+                },
+            };
+
+            if !is_placed {
+                continue;
+            }
+
+            if condition.source_nums.iter().all(|&condition_source_num| condition_source_num == source_num) {
+                local_conditions.push(condition.expression.clone());
+            } else {
+                join_conditions.push(condition.expression.clone());
+            }
+        }
+
+        (local_conditions, join_conditions)
+    }
+
+    /// The WHERE conditions without the sources and the conditions on the sources of the LEFT JOINs
+    /// are checked after all the joins, the sources are joined in the order of FROM in such case
+    pub fn is_remaining(&self, condition: &PlannedCondition) -> bool {
+        condition.left_join_source_num.is_none()
+            && condition.source_nums.last().is_none_or(|source_num| self.left_join_source_nums.contains(source_num))
+    }
+
+    /// The ORDER BY terms could be satisfied by the scan of the first source if they refer to it only
+    pub fn get_scan_order_by(&self, source_num: usize) -> io::Result<&'a [OrderingTerm]> {
+        for ordering_term in self.order_by.iter() {
+            if get_source_nums(&ordering_term.expression, &self.context.sources)?.iter().any(|&term_source_num| term_source_num != source_num) {
+                return Ok(&[]);
+            }
+        }
+
+        Ok(self.order_by)
+    }
+
+    fn estimate_distinct_count(&self, expression: &Expression) -> Option<f64> {
+        let Expression::Column { table_name, column_name } = expression else {
+            return None;
+        };
+
+        let source_num = find_column_source(&self.context.sources, table_name.as_deref(), column_name).ok()?;
+
+        let source = &self.context.sources[source_num];
+
+        Some(source.table.estimate_distinct_count(source.pager, column_name))
+    }
+
+    /// The equal values of the columns are expected to be spread evenly
+    fn estimate_join_selectivity(&self, condition: &Expression) -> f64 {
+        let Expression::Binary(left, BinaryOperator::Equal, right) = condition else {
+            return DEFAULT_RANGE_SELECTIVITY;
+        };
+
+        match (self.estimate_distinct_count(left), self.estimate_distinct_count(right)) {
+            (Some(left_count), Some(right_count)) => 1.0 / left_count.max(right_count),
+            (Some(distinct_count), None) | (None, Some(distinct_count)) => 1.0 / distinct_count,
+            (None, None) => DEFAULT_EQUAL_SELECTIVITY,
+        }
+    }
+
+    /// The method finds the equalities of the expression on the joined sources and the expression on the new source
+    fn find_equi_join_conditions<'c>(&self, joined_source_nums: &BTreeSet<usize>, source_num: usize, conditions: &'c [Expression]) -> io::Result<Vec<(&'c Expression, &'c Expression)>> {
+        let mut equi_join_conditions = Vec::new();
+
+        for condition in conditions.iter() {
+            let Expression::Binary(left, BinaryOperator::Equal, right) = condition else {
+                continue;
+            };
+
+            let left_source_nums = get_source_nums(left, &self.context.sources)?;
+            let right_source_nums = get_source_nums(right, &self.context.sources)?;
+
+            let is_joined = |source_nums: &BTreeSet<usize>| !source_nums.is_empty() && source_nums.is_subset(joined_source_nums);
+            let is_new = |source_nums: &BTreeSet<usize>| source_nums.len() == 1 && source_nums.contains(&source_num);
+
+            if is_joined(&left_source_nums) && is_new(&right_source_nums) {
+                equi_join_conditions.push((left.as_ref(), right.as_ref()));
+            } else if is_joined(&right_source_nums) && is_new(&left_source_nums) {
+                equi_join_conditions.push((right.as_ref(), left.as_ref()));
+            }
+        }
+
+        Ok(equi_join_conditions)
+    }
+
+    /// The method estimates the cost of reading the first source, the rows must be sorted
+    /// if they are not read in the required order
+    fn plan_first_source(&self, source_num: usize) -> io::Result<PartialPlan> {
+        let (local_conditions, _) = self.get_step_conditions(&BTreeSet::new(), source_num);
+        let scan_order_by = self.get_scan_order_by(source_num)?;
+
+        let source = &self.context.sources[source_num];
+        let access_path = source.table.plan_scan(source.pager, &conjoin(local_conditions), scan_order_by);
+        let is_sorted = self.order_by.is_empty() || (!scan_order_by.is_empty() && access_path.order.is_some());
+
+        Ok(PartialPlan {
+            cost: access_path.cost + if is_sorted { 0.0 } else { estimate_sort_cost(access_path.num_rows) },
+            num_rows: access_path.num_rows,
+            first_source_num: source_num,
+            steps: Vec::new(),
+        })
+    }
+
+    /// The method chooses the cheapest way to join the source to the joined sources
+    fn plan_join(&self, partial_plan: &PartialPlan, source_num: usize) -> io::Result<PartialPlan> {
+        let mut joined_source_nums: BTreeSet<usize> = partial_plan.steps.iter().map(|step| step.source_num).collect();
+        joined_source_nums.insert(partial_plan.first_source_num);
+
+        let (local_conditions, join_conditions) = self.get_step_conditions(&joined_source_nums, source_num);
+
+        let table = self.context.sources[source_num].table;
+        let pager = self.context.sources[source_num].pager;
+        let access_path = table.plan_scan(pager, &conjoin(local_conditions), &[]);
+
+        let num_left_rows = partial_plan.num_rows;
+        let mut methods = vec![(JoinMethod::NestedLoop, access_path.cost + num_left_rows * access_path.num_rows)];
+
+        for (left, right) in self.find_equi_join_conditions(&joined_source_nums, source_num, &join_conditions)? {
+            methods.push((
+                JoinMethod::Hash { left: left.clone(), right: right.clone() },
+                access_path.cost + access_path.num_rows + num_left_rows,
+            ));
+
+            if let Expression::Column { column_name, .. } = right {
+                if table.can_lookup(column_name) {
+                    let num_rows = table.estimate_num_rows(pager);
+                    let num_key_rows = num_rows / table.estimate_distinct_count(pager, column_name);
+
+                    methods.push((
+                        JoinMethod::IndexNestedLoop { column_name: column_name.clone(), left: left.clone() },
+                        num_left_rows * (num_rows.max(2.0).log2() + num_key_rows * ROW_LOOKUP_COST),
+                    ));
+                }
+            }
+        }
+
+        let (method, cost) = methods
+            .into_iter()
+            .min_by(|(_, left_cost), (_, right_cost)| left_cost.total_cmp(right_cost))
+            .unwrap();
+
+        let selectivity: f64 = join_conditions.iter().map(|condition| self.estimate_join_selectivity(condition)).product();
+        let mut num_rows = num_left_rows * access_path.num_rows * selectivity;
+
+        // The LEFT JOIN keeps all the left rows
+        if self.left_join_source_nums.contains(&source_num) {
+            num_rows = num_rows.max(num_left_rows);
+        }
+
+        let mut steps = partial_plan.steps.clone();
+        steps.push(JoinStep { source_num, method, num_rows });
+
+        Ok(PartialPlan {
+            cost: partial_plan.cost + cost,
+            num_rows,
+            first_source_num: partial_plan.first_source_num,
+            steps,
+        })
+    }
+
+    /// The method chooses the cheapest order of the sources comparing the plans of all the subsets
+    /// of the sources, the sources are always joined one by one. The sources of the LEFT JOINs
+    /// are joined in the order of FROM
+    pub fn plan(&self) -> io::Result<JoinOrder> {
+        let num_sources = self.context.sources.len();
+
+        let partial_plan = if self.left_join_source_nums.is_empty() && (2..=MAX_REORDERED_SOURCES).contains(&num_sources) {
+            let mut best_plans: Vec<Option<PartialPlan>> = vec![None; 1 << num_sources];
+
+            for source_num in 0..num_sources {
+                best_plans[1 << source_num] = Some(self.plan_first_source(source_num)?);
+            }
+
+            // The subsets are always processed before their supersets
+            for source_set in 1..(1 << num_sources) {
+                let Some(partial_plan) = best_plans[source_set].take() else {
+                    continue;
+                };
+
+                for source_num in (0..num_sources).filter(|source_num| source_set & (1 << source_num) == 0) {
+                    let joined_plan = self.plan_join(&partial_plan, source_num)?;
+                    let joined_set = source_set | (1 << source_num);
+
+                    if best_plans[joined_set].as_ref().is_none_or(|best_plan| joined_plan.cost < best_plan.cost) {
+                        best_plans[joined_set] = Some(joined_plan);
+                    }
+                }
+
+                best_plans[source_set] = Some(partial_plan);
+            }
+
+            best_plans.pop().flatten().unwrap()
+        } else {
+            let mut partial_plan = self.plan_first_source(0)?;
+
+            for source_num in 1..num_sources {
+                partial_plan = self.plan_join(&partial_plan, source_num)?;
+            }
+
+            partial_plan
+        };
+
+        Ok(JoinOrder {
+            first_source_num: partial_plan.first_source_num,
+            steps: partial_plan.steps,
+        })
+    }
+}
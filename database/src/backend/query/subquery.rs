@@ -0,0 +1,210 @@
+use std::io;
+use std::iter;
+use super::Query;
+use super::compound::materialize_compound;
+use super::cte::{materialize_ctes, CteScope};
+use crate::backend::operator::{find_column_source, Source};
+use crate::backend::pager::Pager;
+use crate::backend::pager::page::row_data::RowData;
+use crate::backend::table::Table;
+use crate::session::Session;
+use crate::statement::create_table_statement::{ColumnDefinition, ColumnType, CreateTableStatement};
+use crate::statement::expression::{Expression, RowSource, SubqueryResult};
+use crate::statement::select_statement::{JoinConstraint, SelectStatement, NO_TABLE_NAME};
+use crate::value::Value;
+
+/// The derived table is the only table of its pager
+const DERIVED_TABLE_ID: u32 = 1;
+
+/// The table of the subquery in FROM, its rows are stored in the temporary pager
+pub struct DerivedTable {
+    pub table: Table,
+    pub pager: Pager,
+    // The subquery uses the columns of the outer query
+    pub is_correlated: bool,
+}
+
+/// The method materializes the tables of WITH, the derived tables of FROM
+/// and the rows of the compound select, then plans the query and passes it to the function
+pub fn with_query<T>(
+    tables: &[Table],
+    pager: &Pager,
+    session: &Session,
+    select_statement: &SelectStatement,
+    ctes: Option<&CteScope>,
+    outer: Option<&dyn RowSource>,
+    f: impl FnOnce(&Query) -> io::Result<T>,
+) -> io::Result<T> {
+    let scope = materialize_ctes(tables, pager, session, select_statement, ctes, outer)?;
+    let ctes = scope.as_ref().or(ctes);
+
+    // The combined rows of the compound select are ordered and limited by the query reading them
+    if !select_statement.compound.is_empty() {
+        let derived_tables = vec![materialize_compound(tables, pager, session, select_statement, ctes, outer)?];
+        let compound_query = select_statement.get_compound_query();
+
+        return f(&Query::new(tables, &derived_tables, pager, session, &compound_query, ctes, outer)?);
+    }
+
+    let derived_tables = materialize_derived_tables(tables, pager, session, select_statement, ctes, outer)?;
+
+    f(&Query::new(tables, &derived_tables, pager, session, select_statement, ctes, outer)?)
+}
+
+/// The method runs the subqueries in FROM and stores their rows, the derived tables
+/// are returned in the order of the table references
+fn materialize_derived_tables(
+    tables: &[Table],
+    pager: &Pager,
+    session: &Session,
+    select_statement: &SelectStatement,
+    ctes: Option<&CteScope>,
+    outer: Option<&dyn RowSource>,
+) -> io::Result<Vec<DerivedTable>> {
+    let table_references = iter::once(&select_statement.from).chain(select_statement.joins.iter().map(|join| &join.table));
+    let mut derived_tables = Vec::new();
+
+    for table_reference in table_references {
+        let derived_table = match &table_reference.subquery {
+            Some(subquery) => with_query(tables, pager, session, subquery, ctes, outer, |query| {
+                let mut rows = Vec::new();
+                query.run(|row_data| rows.push(row_data.values))?;
+
+                create_derived_table(table_reference.get_name(), get_derived_column_names(query), rows, query.is_correlated())
+            })?,
+            // The columns of SELECT without FROM are evaluated against the single empty row
+            None if table_reference.is_no_table() => create_derived_table(NO_TABLE_NAME, Vec::new(), vec![Vec::new()], false)?,
+            None => continue,
+        };
+
+        derived_tables.push(derived_table);
+    }
+
+    Ok(derived_tables)
+}
+
+/// The method stores the rows in the temporary pager, the row ids keep the order of the rows
+pub fn create_derived_table(table_name: &str, column_names: Vec<String>, rows: Vec<Vec<Value>>, is_correlated: bool) -> io::Result<DerivedTable> {
+    let mut pager = Pager::temporary();
+
+    for (row_num, values) in rows.into_iter().enumerate() {
+        pager.insert_row(DERIVED_TABLE_ID, &RowData::new(row_num as i64 + 1, values))?;
+    }
+
+    // The rows are stored as they are, so the column types are never applied
+    let columns = column_names
+        .into_iter()
+        .map(|name| ColumnDefinition {
+            name,
+            column_type: ColumnType::Text,
+            primary_key: false,
+            autoincrement: false,
+            unique: false,
+            not_null: false,
+            check: None,
+            default: None,
+            references: None,
+        })
+        .collect();
+
+    let definition = CreateTableStatement {
+        table_name: table_name.to_string(),
+        if_not_exists: false,
+        columns,
+        constraints: Vec::new(),
+    };
+
+    Ok(DerivedTable {
+        table: Table::new(DERIVED_TABLE_ID, definition, &pager)?,
+        pager,
+        is_correlated,
+    })
+}
+
+/// The columns of the tables keep their names without the table names,
+/// the duplicate names get the numbers
+pub fn get_derived_column_names(query: &Query) -> Vec<String> {
+    let mut column_names: Vec<String> = Vec::new();
+
+    for (name, expression) in query.get_result_columns() {
+        let name = match expression {
+            Expression::Column { column_name, .. } if *name == expression.to_string() => column_name.clone(),
+            _ => name.clone(),
+        };
+
+        let mut unique_name = name.clone();
+        let mut duplicate_num = 0;
+
+        while column_names.contains(&unique_name) {
+            duplicate_num += 1;
+            unique_name = format!("{}:{}", name, duplicate_num);
+        }
+
+        column_names.push(unique_name);
+    }
+
+    column_names
+}
+
+/// The method runs the subquery of the expression evaluated against the outer row.
+/// The result is returned together with the flag telling whether it depends on the outer row
+pub fn run_subquery(
+    tables: &[Table],
+    pager: &Pager,
+    session: &Session,
+    subquery: &SelectStatement,
+    ctes: Option<&CteScope>,
+    outer: &dyn RowSource,
+) -> io::Result<(SubqueryResult, bool)> {
+    with_query(tables, pager, session, subquery, ctes, Some(outer), |query| {
+        let mut values = Vec::new();
+        query.run(|row_data| values.extend(row_data.values.into_iter().next()))?;
+
+        Ok((SubqueryResult::new(query.get_column_names().len(), values), query.is_correlated()))
+    })
+}
+
+/// The method replaces the columns which are not found in the sources by their values
+/// in the outer row, so the planner sees them as constants. The subqueries of the expression
+/// find the outer columns themselves. Returns whether any column was replaced
+pub fn bind_outer_columns(expression: &mut Expression, sources: &[Source], outer: &dyn RowSource) -> io::Result<bool> {
+    if let Expression::Column { table_name, column_name } = expression {
+        return match find_column_source(sources, table_name.as_deref(), column_name) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                *expression = Expression::Literal(outer.get_column_value(table_name.as_deref(), column_name)?);
+                Ok(true)
+            },
+            result => result.map(|_| false),
+        };
+    }
+
+    let mut is_bound = false;
+
+    for child in expression.get_children_mut() {
+        is_bound |= bind_outer_columns(child, sources, outer)?;
+    }
+
+    Ok(is_bound)
+}
+
+/// The method binds the outer columns in the clauses evaluated against the rows of the sources
+pub fn bind_clauses(select_statement: &mut SelectStatement, sources: &[Source], outer: &dyn RowSource) -> io::Result<bool> {
+    let join_conditions = select_statement.joins.iter_mut().filter_map(|join| match &mut join.constraint {
+        Some(JoinConstraint::On(expression)) => Some(expression),
+        _ => None,
+    });
+
+    let expressions = select_statement.where_clause
+        .iter_mut()
+        .chain(select_statement.group_by.iter_mut())
+        .chain(select_statement.having.iter_mut())
+        .chain(join_conditions);
+
+    let mut is_bound = false;
+
+    for expression in expressions {
+        is_bound |= bind_outer_columns(expression, sources, outer)?;
+    }
+
+    Ok(is_bound)
+}
@@ -0,0 +1,271 @@
+use std::iter;
+use crate::statement::Statement;
+use crate::statement::create_table_statement::{CreateTableStatement, TableConstraint};
+use crate::statement::create_trigger_statement::{CreateTriggerStatement, TriggerEvent};
+use crate::statement::expression::Expression;
+use crate::statement::insert_statement::{InsertSource, InsertStatement, UpsertAction};
+use crate::statement::select_statement::{JoinConstraint, ResultColumn, SelectStatement};
+
+/// The renaming of the table or of its column by ALTER TABLE, the definitions,
+/// the views and the triggers referring to them are changed the same way
+pub enum Rename<'a> {
+    Table {
+        old_name: &'a str,
+        new_name: &'a str,
+    },
+    Column {
+        table_name: &'a str,
+        old_name: &'a str,
+        new_name: &'a str,
+    },
+}
+
+impl Rename<'_> {
+    fn get_table_name(&self) -> &str {
+        match self {
+            Rename::Table { old_name, .. } => old_name,
+            Rename::Column { table_name, .. } => table_name,
+        }
+    }
+
+    fn rename_table(&self, table_name: &mut String) {
+        if let Rename::Table { old_name, new_name } = self {
+            if table_name == old_name {
+                *table_name = new_name.to_string();
+            }
+        }
+    }
+
+    fn rename_columns(&self, column_names: &mut [String]) {
+        if let Rename::Column { old_name, new_name, .. } = self {
+            for column_name in column_names.iter_mut().filter(|column_name| column_name == old_name) {
+                *column_name = new_name.to_string();
+            }
+        }
+    }
+
+    /// The method renames the table and its columns in the definition of the table
+    /// and in the foreign keys of the other tables referring to it
+    pub fn rename_definition(&self, definition: &mut CreateTableStatement) {
+        let is_renamed_table = definition.table_name == self.get_table_name();
+
+        if is_renamed_table {
+            self.rename_table(&mut definition.table_name);
+
+            for column in definition.columns.iter_mut() {
+                self.rename_columns(std::slice::from_mut(&mut column.name));
+
+                if let Some(check) = &mut column.check {
+                    self.rename_expression(check, &[self.get_table_name().to_string()], true);
+                }
+            }
+        }
+
+        let mut foreign_keys: Vec<_> = definition.columns
+            .iter_mut()
+            .filter_map(|column| column.references.as_mut())
+            .collect();
+
+        for constraint in definition.constraints.iter_mut() {
+            match constraint {
+                TableConstraint::PrimaryKey(column_names) | TableConstraint::Unique(column_names) if is_renamed_table => {
+                    self.rename_columns(column_names);
+                },
+                TableConstraint::Check(check) if is_renamed_table => {
+                    self.rename_expression(check, &[self.get_table_name().to_string()], true);
+                },
+                TableConstraint::ForeignKey(foreign_key) => foreign_keys.push(foreign_key),
+                _ => {},
+            }
+        }
+
+        for foreign_key in foreign_keys {
+            if is_renamed_table {
+                self.rename_columns(&mut foreign_key.columns);
+            }
+
+            if foreign_key.parent_table == self.get_table_name() {
+                self.rename_columns(&mut foreign_key.parent_columns);
+                self.rename_table(&mut foreign_key.parent_table);
+            }
+        }
+    }
+
+    /// The trigger of the renamed table refers to its columns by NEW and OLD
+    pub fn rename_trigger(&self, trigger: &mut CreateTriggerStatement) {
+        let is_renamed_table = trigger.table_name == self.get_table_name();
+        let qualifiers = if is_renamed_table { vec!["new".to_string(), "old".to_string()] } else { Vec::new() };
+
+        if is_renamed_table {
+            self.rename_table(&mut trigger.table_name);
+
+            if let TriggerEvent::Update(column_names) = &mut trigger.event {
+                self.rename_columns(column_names);
+            }
+        }
+
+        if let Some(when) = &mut trigger.when {
+            self.rename_expression(when, &qualifiers, false);
+        }
+
+        for statement in trigger.statements.iter_mut() {
+            self.rename_statement(statement, &qualifiers);
+        }
+    }
+
+    fn rename_statement(&self, statement: &mut Statement, qualifiers: &[String]) {
+        // The values and the SELECT of INSERT can't refer to the columns of its table
+        let is_insert = matches!(statement, Statement::Insert(_));
+
+        let (table_name, column_names) = match statement {
+            Statement::Select(select_statement) => return self.rename_select_in(select_statement, qualifiers),
+            Statement::Insert(insert_statement) => (&mut insert_statement.table_name, insert_statement.columns.iter_mut().flatten().collect()),
+            Statement::Update(update_statement) => (
+                &mut update_statement.table_name,
+                update_statement.assignments.iter_mut().map(|(column_name, _)| column_name).collect(),
+            ),
+            Statement::Delete(delete_statement) => (&mut delete_statement.table_name, Vec::new()),
+            _ => return,
+        };
+
+        let is_renamed_table = table_name == self.get_table_name();
+        let reads_table = is_renamed_table && !is_insert;
+        let mut qualifiers = qualifiers.to_vec();
+
+        if reads_table {
+            qualifiers.push(table_name.clone());
+        }
+
+        if is_renamed_table {
+            self.rename_table(table_name);
+
+            for column_name in column_names {
+                self.rename_columns(std::slice::from_mut(column_name));
+            }
+        }
+
+        // DO UPDATE of the upsert reads the row of the table and the inserted row as "excluded"
+        if let Statement::Insert(insert_statement) = statement {
+            let mut upsert_qualifiers = qualifiers.clone();
+
+            if is_renamed_table {
+                upsert_qualifiers.extend([self.get_table_name().to_string(), "excluded".to_string()]);
+            }
+
+            for upsert in insert_statement.upserts.iter_mut() {
+                if is_renamed_table {
+                    self.rename_columns(&mut upsert.target);
+
+                    if let UpsertAction::Update { assignments, .. } = &mut upsert.action {
+                        for (column_name, _) in assignments.iter_mut() {
+                            self.rename_columns(std::slice::from_mut(column_name));
+                        }
+                    }
+                }
+
+                for expression in upsert.get_expressions_mut() {
+                    self.rename_expression(expression, &upsert_qualifiers, is_renamed_table);
+                }
+            }
+        }
+
+        let expressions = match statement {
+            Statement::Insert(InsertStatement { source: InsertSource::Values(rows), .. }) => rows.iter_mut().flatten().collect(),
+            Statement::Insert(_) => Vec::new(),
+            _ => statement.get_expressions_mut(),
+        };
+
+        for expression in expressions {
+            self.rename_expression(expression, &qualifiers, reads_table);
+        }
+
+        if let Some(select_statement) = statement.get_select_mut() {
+            self.rename_select_in(select_statement, &qualifiers);
+        }
+    }
+
+    pub fn rename_select(&self, select_statement: &mut SelectStatement) {
+        self.rename_select_in(select_statement, &[]);
+    }
+
+    /// The qualifiers are the names under which the queries see the renamed table,
+    /// the subqueries could refer to the table of the enclosing query by them
+    fn rename_select_in(&self, select_statement: &mut SelectStatement, outer_qualifiers: &[String]) {
+        if let Some(with_clause) = &mut select_statement.with_clause {
+            for cte in with_clause.tables.iter_mut() {
+                self.rename_select_in(&mut cte.select_statement, outer_qualifiers);
+            }
+        }
+
+        let mut qualifiers = outer_qualifiers.to_vec();
+        let mut reads_table = false;
+
+        let table_references = iter::once(&mut select_statement.from).chain(select_statement.joins.iter_mut().map(|join| &mut join.table));
+
+        for table_reference in table_references {
+            if let Some(subquery) = &mut table_reference.subquery {
+                self.rename_select_in(subquery, outer_qualifiers);
+            } else if table_reference.table_name == self.get_table_name() {
+                reads_table = true;
+                qualifiers.push(table_reference.alias.clone().unwrap_or_else(|| table_reference.table_name.clone()));
+                self.rename_table(&mut table_reference.table_name);
+            }
+        }
+
+        for (_, compound_select) in select_statement.compound.iter_mut() {
+            self.rename_select_in(compound_select, outer_qualifiers);
+        }
+
+        if reads_table {
+            for join in select_statement.joins.iter_mut() {
+                if let Some(JoinConstraint::Using(column_names)) = &mut join.constraint {
+                    self.rename_columns(column_names);
+                }
+            }
+        }
+
+        for column in select_statement.columns.iter_mut() {
+            if let ResultColumn::TableAll(table_name) = column {
+                if qualifiers.contains(table_name) {
+                    self.rename_table(table_name);
+                }
+            }
+        }
+
+        for expression in select_statement.get_expressions_mut() {
+            self.rename_expression(expression, &qualifiers, reads_table);
+        }
+    }
+
+    /// The unqualified columns belong to the renamed table if the query reads it
+    fn rename_expression(&self, expression: &mut Expression, qualifiers: &[String], reads_table: bool) {
+        match expression {
+            Expression::Column { table_name, column_name } => {
+                let is_renamed_table = match table_name {
+                    Some(table_name) => qualifiers.contains(table_name),
+                    None => reads_table,
+                };
+
+                if !is_renamed_table {
+                    return;
+                }
+
+                if let Some(table_name) = table_name {
+                    self.rename_table(table_name);
+                }
+
+                self.rename_columns(std::slice::from_mut(column_name));
+            },
+            Expression::Subquery(subquery) | Expression::Exists(subquery) => self.rename_select_in(subquery, qualifiers),
+            Expression::InSubquery { expression, subquery, .. } => {
+                self.rename_expression(expression, qualifiers, reads_table);
+                self.rename_select_in(subquery, qualifiers);
+            },
+            _ => {
+                for child in expression.get_children_mut() {
+                    self.rename_expression(child, qualifiers, reads_table);
+                }
+            },
+        }
+    }
+}
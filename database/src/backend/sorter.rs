@@ -0,0 +1,358 @@
+use std::cmp::Ordering;
+use std::env;
+use std::fmt;
+use std::fs::{remove_file, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{self, AtomicUsize};
+use super::pager::page::row_data::RowData;
+use crate::value::Value;
+
+/// The memory the sorter could use before the sorted rows are spilled to a temporary file
+pub const SORT_MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+
+// The number of the temporary files created by the process, it makes the file names unique
+static RUN_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The direction of one sort key
+#[derive(Debug, Clone, Copy)]
+pub struct SortOrder {
+    pub descending: bool,
+    pub nulls_first: bool,
+}
+
+impl SortOrder {
+    fn compare(&self, left: &Value, right: &Value) -> Ordering {
+        match (left.is_null(), right.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) if self.nulls_first => Ordering::Less,
+            (true, false) => Ordering::Greater,
+            (false, true) if self.nulls_first => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) if self.descending => right.cmp(left),
+            (false, false) => left.cmp(right),
+        }
+    }
+}
+
+/// The sort order is printed like the ordering term, the default place of NULLs is omitted
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.descending, self.nulls_first) {
+            (false, true) => write!(f, "ASC"),
+            (false, false) => write!(f, "ASC NULLS LAST"),
+            (true, false) => write!(f, "DESC"),
+            (true, true) => write!(f, "DESC NULLS FIRST"),
+        }
+    }
+}
+
+fn compare_keys(sort_orders: &[SortOrder], left: &[Value], right: &[Value]) -> Ordering {
+    sort_orders
+        .iter()
+        .zip(left.iter().zip(right.iter()))
+        .map(|(sort_order, (left, right))| sort_order.compare(left, right))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// The row with its sort keys, the keys are evaluated only once
+struct SortItem {
+    keys: Vec<Value>,
+    row_data: RowData,
+}
+
+impl SortItem {
+    fn get_memory_size(&self) -> usize {
+        let values_size = |values: &[Value]| -> usize {
+            values
+                .iter()
+                .map(|value| size_of::<Value>() + match value {
+                    Value::Text(text) => text.len(),
+                    _ => 0,
+                })
+                .sum()
+        };
+
+        size_of::<SortItem>() + values_size(&self.keys) + values_size(&self.row_data.values)
+    }
+
+    /// The item is written as the row id, the row record and the keys record,
+    /// each record is prefixed by its length
+    fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        let record = self.row_data.serialize();
+        let keys_record = RowData::new(0, self.keys.clone()).serialize();
+
+        writer.write_all(&self.row_data.id.to_be_bytes())?;
+        writer.write_all(&(record.len() as u32).to_be_bytes())?;
+        writer.write_all(&record)?;
+        writer.write_all(&(keys_record.len() as u32).to_be_bytes())?;
+        writer.write_all(&keys_record)?;
+
+        Ok(())
+    }
+
+    /// The method returns None at the end of the file
+    fn read(reader: &mut impl Read) -> io::Result<Option<Self>> {
+        let mut id_bytes = [0u8; size_of::<i64>()];
+
+        match reader.read_exact(&mut id_bytes) {
+            Ok(()) => {},
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+
+        let read_record = |reader: &mut dyn Read| -> io::Result<Vec<u8>> {
+            let mut length_bytes = [0u8; size_of::<u32>()];
+            reader.read_exact(&mut length_bytes)?;
+
+            let mut record = vec![0u8; u32::from_be_bytes(length_bytes) as usize];
+            reader.read_exact(&mut record)?;
+
+            Ok(record)
+        };
+
+        let record = read_record(reader)?;
+        let keys_record = read_record(reader)?;
+
+        Ok(Some(SortItem {
+            keys: RowData::deserialize(0, &keys_record)?.values,
+            row_data: RowData::deserialize(i64::from_be_bytes(id_bytes), &record)?,
+        }))
+    }
+}
+
+/// The temporary file with the sorted part of the rows, it's removed when it's not needed
+struct RunFile {
+    path: PathBuf,
+}
+
+impl RunFile {
+    fn create(items: &[SortItem]) -> io::Result<Self> {
+        let run_num = RUN_FILE_COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+        let run_file = RunFile {
+            path: env::temp_dir().join(format!("rustqlight_sort_{}_{}.tmp", process::id(), run_num)),
+        };
+
+        let mut writer = BufWriter::new(File::create(&run_file.path)?);
+
+        for item in items.iter() {
+            item.write(&mut writer)?;
+        }
+
+        writer.flush()?;
+
+        Ok(run_file)
+    }
+}
+
+impl Drop for RunFile {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
+
+struct RunReader {
+    // The file is kept to be removed after the reading
+    _run_file: RunFile,
+    reader: BufReader<File>,
+    head: Option<SortItem>,
+}
+
+impl RunReader {
+    fn new(run_file: RunFile) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(&run_file.path)?);
+        let head = SortItem::read(&mut reader)?;
+
+        Ok(RunReader {
+            _run_file: run_file,
+            reader,
+            head,
+        })
+    }
+}
+
+/// The estimated cost of sorting the rows, the cost of reading one row of the table is 1
+pub fn estimate_sort_cost(num_rows: f64) -> f64 {
+    num_rows * num_rows.max(2.0).log2()
+}
+
+/// The external merge sort: the rows are sorted in memory while they fit into the limit,
+/// then the sorted runs are written to the temporary files and merged
+pub struct Sorter {
+    sort_orders: Vec<SortOrder>,
+    memory_limit: usize,
+    items: Vec<SortItem>,
+    used_memory: usize,
+    run_files: Vec<RunFile>,
+}
+
+impl Sorter {
+    pub fn new(sort_orders: Vec<SortOrder>, memory_limit: usize) -> Self {
+        Sorter {
+            sort_orders,
+            memory_limit,
+            items: Vec::new(),
+            used_memory: 0,
+            run_files: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, keys: Vec<Value>, row_data: RowData) -> io::Result<()> {
+        let item = SortItem { keys, row_data };
+
+        self.used_memory += item.get_memory_size();
+        self.items.push(item);
+
+        if self.used_memory > self.memory_limit {
+            self.spill()?;
+        }
+
+        Ok(())
+    }
+
+    fn sort_items(&mut self) {
+        // The sort is stable, so the equal rows keep their order
+        let sort_orders = &self.sort_orders;
+        self.items.sort_by(|left, right| compare_keys(sort_orders, &left.keys, &right.keys));
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.sort_items();
+        self.run_files.push(RunFile::create(&self.items)?);
+
+        self.items.clear();
+        self.used_memory = 0;
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<SortedRows> {
+        if self.run_files.is_empty() {
+            self.sort_items();
+
+            return Ok(SortedRows {
+                sort_orders: self.sort_orders,
+                items: self.items.into_iter(),
+                run_readers: Vec::new(),
+            });
+        }
+
+        if !self.items.is_empty() {
+            self.spill()?;
+        }
+
+        let run_readers = self.run_files
+            .into_iter()
+            .map(RunReader::new)
+            .collect::<io::Result<Vec<RunReader>>>()?;
+
+        Ok(SortedRows {
+            sort_orders: self.sort_orders,
+            items: Vec::new().into_iter(),
+            run_readers,
+        })
+    }
+}
+
+/// The sorted rows, either from memory or merged from the temporary files
+pub struct SortedRows {
+    sort_orders: Vec<SortOrder>,
+    items: std::vec::IntoIter<SortItem>,
+    run_readers: Vec<RunReader>,
+}
+
+impl SortedRows {
+    fn next_merged(&mut self) -> io::Result<Option<RowData>> {
+        // The first run wins the tie, since the runs keep the order of the rows
+        let mut min_run_num: Option<usize> = None;
+
+        for (run_num, run_reader) in self.run_readers.iter().enumerate() {
+            let Some(head) = &run_reader.head else {
+                continue;
+            };
+
+            let is_less = min_run_num.is_none_or(|min_run_num| {
+                let min_head = self.run_readers[min_run_num].head.as_ref().unwrap();
+                compare_keys(&self.sort_orders, &head.keys, &min_head.keys).is_lt()
+            });
+
+            if is_less {
+                min_run_num = Some(run_num);
+            }
+        }
+
+        let Some(min_run_num) = min_run_num else {
+            return Ok(None);
+        };
+
+        let run_reader = &mut self.run_readers[min_run_num];
+        let next_head = SortItem::read(&mut run_reader.reader)?;
+        let item = std::mem::replace(&mut run_reader.head, next_head).unwrap();
+
+        Ok(Some(item.row_data))
+    }
+}
+
+impl Iterator for SortedRows {
+    type Item = io::Result<RowData>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.run_readers.is_empty() {
+            return self.items.next().map(|item| Ok(item.row_data));
+        }
+
+        self.next_merged().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort(values: &[Value], sort_order: SortOrder, memory_limit: usize) -> (Vec<i64>, usize) {
+        let mut sorter = Sorter::new(vec![sort_order], memory_limit);
+
+        for (row_num, value) in values.iter().enumerate() {
+            sorter.push(vec![value.clone()], RowData::new(row_num as i64, vec![value.clone()])).unwrap();
+        }
+
+        let num_runs = sorter.run_files.len();
+        let row_ids = sorter
+            .finish()
+            .unwrap()
+            .map(|row_data| row_data.unwrap().id)
+            .collect();
+
+        (row_ids, num_runs)
+    }
+
+    #[test]
+    fn it_should_sort_in_memory_and_with_temporary_files() {
+        let values: Vec<Value> = (0..200).map(|value| Value::Integer((value * 37) % 101)).collect();
+        let sort_order = SortOrder { descending: false, nulls_first: true };
+
+        let (memory_row_ids, memory_num_runs) = sort(&values, sort_order, SORT_MEMORY_LIMIT);
+        let (external_row_ids, external_num_runs) = sort(&values, sort_order, 1024);
+
+        assert_eq!(memory_num_runs, 0);
+        assert!(external_num_runs > 1);
+        assert_eq!(memory_row_ids, external_row_ids);
+
+        let mut expected_row_ids: Vec<i64> = (0..200).collect();
+        expected_row_ids.sort_by_key(|&row_id| (values[row_id as usize].clone(), row_id));
+        assert_eq!(external_row_ids, expected_row_ids);
+    }
+
+    #[test]
+    fn it_should_place_nulls() {
+        let values = vec![Value::Integer(2), Value::Null, Value::Integer(1)];
+
+        let (row_ids, _) = sort(&values, SortOrder { descending: true, nulls_first: false }, SORT_MEMORY_LIMIT);
+        assert_eq!(row_ids, vec![0, 2, 1]);
+
+        let (row_ids, _) = sort(&values, SortOrder { descending: true, nulls_first: true }, 64);
+        assert_eq!(row_ids, vec![1, 0, 2]);
+    }
+}
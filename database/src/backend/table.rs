@@ -1,89 +1,1178 @@
-mod indexer;
+pub mod index;
+pub mod statistics;
 
-use std::{io, process};
-use super::pager::page::{
-    PAGE_SIZE,
-    row_data::RowData,
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io;
+use std::ops::Bound;
+use super::cursor::{Cursor, CursorPosition};
+use super::pager::Pager;
+use super::pager::page::row_data::RowData;
+use super::sorter::estimate_sort_cost;
+use index::{Index, IndexRange, AUTO_INDEX_PREFIX};
+use statistics::{ColumnStatistics, TableStatistics, DEFAULT_EQUAL_SELECTIVITY, DEFAULT_RANGE_SELECTIVITY};
+use crate::statement::{
+    select_statement::OrderingTerm,
+    update_statement::UpdateStatement,
+    create_table_statement::{ColumnDefinition, ColumnType, CreateTableStatement, ForeignKey, TableConstraint},
+    create_index_statement::CreateIndexStatement,
+    expression::{BinaryOperator, Expression, RowSource},
 };
-use indexer::Indexer;
-use crate::backend::pager::Pager;
-use crate::statement::Statement;
-use crate::enums::Statements;
-use crate::parser::ParsedStatement;
+use crate::session::Session;
+use crate::value::Value;
 
-pub const TABLE_MAX_PAGES: usize = 100;
+/// The hidden column with the row id
+pub const ROW_ID_COLUMN: &str = "rowid";
+
+/// The cost of reading the row by its id relative to reading the next row of the table
+pub const ROW_LOOKUP_COST: f64 = 2.0;
 
 pub struct Table {
-    pager: Pager,
-    indexer: Indexer,
-    max_num_rows: usize,
+    id: u32,
+    definition: CreateTableStatement,
+    // The INTEGER PRIMARY KEY column which is an alias for the row id
+    row_id_column: Option<usize>,
+    // The biggest row id ever used, it's tracked for the AUTOINCREMENT tables only
+    sequence: Option<i64>,
+    indexes: Vec<Index>,
+    statistics: Option<TableStatistics>,
+}
+
+/// The way the rows are read from the table
+pub enum ScanPlan<'a> {
+    RowId(i64),
+    Index(&'a Index, IndexRange),
+    Table,
+}
+
+/// The scan plan with its estimates
+pub struct AccessPath<'a> {
+    pub scan_plan: ScanPlan<'a>,
+    // The direction of the scan if the rows are read in the required order, otherwise they must be sorted
+    pub order: Option<bool>,
+    // The estimated cost of reading the rows, the cost of reading one row of the table is 1
+    pub cost: f64,
+    // The estimated number of the rows matching the condition
+    pub num_rows: f64,
+}
+
+/// The row of the table the expressions are evaluated against
+struct TableRow<'a> {
+    table: &'a Table,
+    row_data: &'a RowData,
+    session: &'a Session,
+}
+
+impl RowSource for TableRow<'_> {
+    fn get_column_value(&self, table_name: Option<&str>, column_name: &str) -> io::Result<Value> {
+        if table_name.is_some_and(|table_name| table_name != self.table.get_name()) {
+            return Err(no_such_column_error(&format!("{}.{}", table_name.unwrap(), column_name)));
+        }
+
+        self.table.get_column_value(self.row_data, column_name)
+    }
+
+    fn get_session(&self) -> &Session {
+        self.session
+    }
+}
+
+fn no_such_column_error(column_name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("No such column: {}", column_name),
+    )
 }
 
 impl Table {
-    pub fn new(pager: Pager) -> Self {
-        let last_saved_index = pager.get_last_saved_index().unwrap_or(0);
-        Table {
-            pager,
-            indexer: Indexer::new(Some(last_saved_index)),
-            max_num_rows: PAGE_SIZE * TABLE_MAX_PAGES,
+    /// The method checks the definition and builds the indexes for the PRIMARY KEY
+    /// and UNIQUE constraints from the rows already stored in the pager
+    pub fn new(id: u32, definition: CreateTableStatement, pager: &Pager) -> io::Result<Self> {
+        let sequence = definition.columns.iter().any(|column| column.autoincrement).then_some(0);
+
+        let mut table = Table {
+            id,
+            definition,
+            row_id_column: None,
+            sequence,
+            indexes: Vec::new(),
+            statistics: None,
+        };
+
+        table.check_definition()?;
+        table.row_id_column = table.find_row_id_column();
+
+        // The row id is unique by itself, so its alias doesn't need an index
+        let row_id_column_set = table.row_id_column.map(|column_idx| vec![table.definition.columns[column_idx].name.clone()]);
+
+        let unique_column_sets = table
+            .get_unique_column_sets()
+            .into_iter()
+            .filter(|columns| Some(columns) != row_id_column_set.as_ref());
+
+        for (auto_index_num, columns) in unique_column_sets.enumerate() {
+            let create_index_statement = CreateIndexStatement {
+                index_name: format!("{}{}_{}", AUTO_INDEX_PREFIX, table.get_name(), auto_index_num + 1),
+                table_name: table.get_name().to_string(),
+                columns,
+                unique: true,
+                if_not_exists: false,
+            };
+
+            table.create_index(pager, &create_index_statement)?;
+        }
+
+        Ok(table)
+    }
+
+    fn check_definition(&self) -> io::Result<()> {
+        let columns = &self.definition.columns;
+
+        for (column_num, column) in columns.iter().enumerate() {
+            if columns[..column_num].iter().any(|prev_column| prev_column.name == column.name) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Duplicate column name: {}", column.name),
+                ));
+            }
+        }
+
+        let num_primary_keys = columns.iter().filter(|column| column.primary_key).count()
+            + self.definition.constraints
+                .iter()
+                .filter(|constraint| matches!(constraint, TableConstraint::PrimaryKey(_)))
+                .count();
+
+        if columns.iter().any(|column| column.autoincrement && column.column_type != ColumnType::Integer) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "AUTOINCREMENT is only allowed on an INTEGER PRIMARY KEY",
+            ));
+        }
+
+        if num_primary_keys > 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The table {} has more than one primary key", self.get_name()),
+            ));
+        }
+
+        for constraint in self.definition.constraints.iter() {
+            if let TableConstraint::PrimaryKey(column_names) | TableConstraint::Unique(column_names) = constraint {
+                self.get_column_idxs(column_names)?;
+            }
+        }
+
+        for foreign_key in self.definition.get_foreign_keys() {
+            self.get_column_idxs(&foreign_key.columns)?;
+        }
+
+        Ok(())
+    }
+
+    /// The primary key of the single INTEGER column is an alias for the row id
+    fn find_row_id_column(&self) -> Option<usize> {
+        let mut primary_key_columns: Vec<&str> = self.definition.columns
+            .iter()
+            .filter(|column| column.primary_key)
+            .map(|column| column.name.as_str())
+            .collect();
+
+        for constraint in self.definition.constraints.iter() {
+            if let TableConstraint::PrimaryKey(column_names) = constraint {
+                primary_key_columns.extend(column_names.iter().map(String::as_str));
+            }
+        }
+
+        let [column_name] = primary_key_columns.as_slice() else {
+            return None;
+        };
+
+        self.find_column_idx(column_name)
+            .filter(|&column_idx| self.definition.columns[column_idx].column_type == ColumnType::Integer)
+    }
+
+    /// The method returns the columns of the PRIMARY KEY and UNIQUE constraints,
+    /// the primary key goes first
+    fn get_unique_column_sets(&self) -> Vec<Vec<String>> {
+        let mut primary_key_sets = Vec::new();
+        let mut unique_sets = Vec::new();
+
+        for column in self.definition.columns.iter() {
+            if column.primary_key {
+                primary_key_sets.push(vec![column.name.clone()]);
+            }
+
+            if column.unique {
+                unique_sets.push(vec![column.name.clone()]);
+            }
+        }
+
+        for constraint in self.definition.constraints.iter() {
+            match constraint {
+                TableConstraint::PrimaryKey(column_names) => primary_key_sets.push(column_names.clone()),
+                TableConstraint::Unique(column_names) => unique_sets.push(column_names.clone()),
+                TableConstraint::Check(_) | TableConstraint::ForeignKey(_) => {},
+            }
+        }
+
+        primary_key_sets.extend(unique_sets);
+
+        primary_key_sets
+    }
+
+    /// The method returns the columns of the parent key referenced by the foreign key in the order
+    /// of the child columns. The parent key must be the primary key or have the unique constraint
+    pub fn get_parent_key(&self, foreign_key: &ForeignKey) -> Option<Vec<String>> {
+        let unique_column_sets = self.get_unique_column_sets();
+
+        if foreign_key.parent_columns.is_empty() {
+            let has_primary_key = self.definition.columns.iter().any(|column| column.primary_key)
+                || self.definition.constraints.iter().any(|constraint| matches!(constraint, TableConstraint::PrimaryKey(_)));
+
+            return unique_column_sets
+                .into_iter()
+                .next()
+                .filter(|primary_key| has_primary_key && primary_key.len() == foreign_key.columns.len());
+        }
+
+        let is_unique = unique_column_sets.iter().any(|column_names| {
+            column_names.len() == foreign_key.parent_columns.len()
+                && column_names.iter().all(|column_name| foreign_key.parent_columns.contains(column_name))
+        });
+
+        is_unique.then(|| foreign_key.parent_columns.clone())
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.definition.table_name
+    }
+
+    pub fn get_definition(&self) -> &CreateTableStatement {
+        &self.definition
+    }
+
+    /// Only the parts of the definition which don't change the rows and the indexes
+    /// may be changed, like the foreign keys referring to the other tables
+    pub fn get_definition_mut(&mut self) -> &mut CreateTableStatement {
+        &mut self.definition
+    }
+
+    pub fn get_sequence(&self) -> Option<i64> {
+        self.sequence
+    }
+
+    /// The method restores the biggest used row id of the AUTOINCREMENT table
+    pub fn set_sequence(&mut self, sequence: i64) {
+        if let Some(cur_sequence) = &mut self.sequence {
+            *cur_sequence = sequence;
+        }
+    }
+
+    pub fn get_statistics(&self) -> Option<&TableStatistics> {
+        self.statistics.as_ref()
+    }
+
+    pub fn set_statistics(&mut self, statistics: TableStatistics) {
+        self.statistics = Some(statistics);
+    }
+
+    /// The method gathers the statistics of the columns, they are used to choose the query plans
+    pub fn analyze(&mut self, pager: &Pager) -> io::Result<()> {
+        let rows = self.get_all_rows(pager)?;
+        let mut columns = BTreeMap::new();
+
+        for column in self.definition.columns.iter() {
+            let values = rows
+                .iter()
+                .map(|row_data| self.get_column_value(row_data, &column.name))
+                .collect::<io::Result<Vec<Value>>>()?;
+
+            columns.insert(column.name.clone(), ColumnStatistics::new(values));
+        }
+
+        self.statistics = Some(TableStatistics {
+            row_count: rows.len(),
+            columns,
+        });
+
+        Ok(())
+    }
+
+    fn update_sequence(&mut self, row_id: i64) {
+        if let Some(sequence) = &mut self.sequence {
+            *sequence = (*sequence).max(row_id);
+        }
+    }
+
+    fn get_row_id_column_name(&self) -> &str {
+        match self.row_id_column {
+            Some(column_idx) => &self.definition.columns[column_idx].name,
+            None => ROW_ID_COLUMN,
+        }
+    }
+
+    fn is_row_id_column(&self, column_name: &str) -> bool {
+        match self.find_column_idx(column_name) {
+            Some(column_idx) => Some(column_idx) == self.row_id_column,
+            None => column_name == ROW_ID_COLUMN,
+        }
+    }
+
+    /// The method converts the value to the row id, it must be an integer
+    fn to_row_id(&self, value: Value) -> io::Result<i64> {
+        let row_id = match &value {
+            Value::Null => None,
+            Value::Integer(row_id) => Some(*row_id),
+            Value::Real(real) if real.fract() == 0.0 => Some(*real as i64),
+            Value::Real(_) => None,
+            Value::Text(text) => text.trim().parse::<i64>().ok(),
+        };
+
+        row_id.ok_or_else(|| self.datatype_mismatch_error(self.get_row_id_column_name(), &value))
+    }
+
+    fn row_id_constraint_error(&self, row_id: i64) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("UNIQUE constraint failed: {}.{} = {}", self.get_name(), self.get_row_id_column_name(), row_id),
+        )
+    }
+
+    /// The new row id is greater than all the ids in the table. For the AUTOINCREMENT table
+    /// it's also greater than all the ids ever used, so the ids of the deleted rows are not reused
+    fn get_next_row_id(&self, pager: &Pager) -> io::Result<i64> {
+        let max_row_id = pager
+            .get_last_row_id(self.id)
+            .unwrap_or(0)
+            .max(self.sequence.unwrap_or(0));
+
+        max_row_id.checked_add(1).ok_or_else(|| io::Error::new(
+            io::ErrorKind::OutOfMemory,
+            format!("The table {} is full", self.get_name()),
+        ))
+    }
+
+    fn find_column_idx(&self, column_name: &str) -> Option<usize> {
+        self.definition.columns.iter().position(|column| column.name == column_name)
+    }
+
+    /// The method returns the position of the column in the row, there is no position
+    /// for the hidden row id column
+    pub fn find_column(&self, column_name: &str) -> io::Result<Option<usize>> {
+        match self.find_column_idx(column_name) {
+            Some(column_idx) => Ok(Some(column_idx)),
+            None if column_name == ROW_ID_COLUMN => Ok(None),
+            None => Err(no_such_column_error(column_name)),
+        }
+    }
+
+    /// The method checks whether the table has the column or it's the row id
+    pub fn has_column(&self, column_name: &str) -> bool {
+        self.find_column_idx(column_name).is_some() || column_name == ROW_ID_COLUMN
+    }
+
+    pub fn get_column_value(&self, row_data: &RowData, column_name: &str) -> io::Result<Value> {
+        match self.find_column_idx(column_name) {
+            Some(column_idx) => Ok(row_data.values[column_idx].clone()),
+            None if column_name == ROW_ID_COLUMN => Ok(Value::Integer(row_data.id)),
+            None => Err(no_such_column_error(column_name)),
         }
     }
 
-    fn insert_new_row(&mut self, new_row_data: &RowData) -> io::Result<usize> {
-        self.pager.insert_new_row(new_row_data)
+    /// The method checks whether the rows with the column value could be found without the full scan
+    pub fn can_lookup(&self, column_name: &str) -> bool {
+        self.is_row_id_column(column_name)
+            || self.indexes.iter().any(|index| index.get_columns().first().is_some_and(|index_column| index_column == column_name))
+    }
+
+    fn get_column_idx(&self, column_name: &str) -> io::Result<usize> {
+        self.find_column_idx(column_name).ok_or_else(|| {
+            no_such_column_error(&format!("{}.{}", self.get_name(), column_name))
+        })
+    }
+
+    fn get_column_idxs(&self, column_names: &[String]) -> io::Result<Vec<usize>> {
+        column_names.iter().map(|column_name| self.get_column_idx(column_name)).collect()
+    }
+
+    /// The method describes the column values of the row, like users.email = 'a@x.com'
+    fn describe_values(&self, row_data: &RowData, column_idxs: &[usize]) -> String {
+        column_idxs
+            .iter()
+            .map(|&column_idx| format!(
+                "{}.{} = {}",
+                self.get_name(),
+                self.definition.columns[column_idx].name,
+                row_data.values[column_idx].to_sql(),
+            ))
+            .collect::<Vec<String>>()
+            .join(", ")
     }
 
-    fn select(&self) -> io::Result<usize> {
-        self.pager.select_all();
-        Ok(0)
+    fn unique_constraint_error(&self, index: &Index, row_data: &RowData) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("UNIQUE constraint failed: {}", self.describe_values(row_data, index.get_column_idxs())),
+        )
     }
 
-    fn execute_statement(&mut self, statement: &Statement) -> io::Result<usize>{
-        match statement {
-            Statement::Insert(insert_statement) => {
-                let new_row = RowData::new(
-                    self.indexer.get_next_index(),
-                    &insert_statement.email,
-                    &insert_statement.user_name,
-                )?;
+    fn datatype_mismatch_error(&self, column_name: &str, value: &Value) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("datatype mismatch: {}.{} = {}", self.get_name(), column_name, value.to_sql()),
+        )
+    }
 
-                self.insert_new_row(&new_row)
+    /// The method converts the value to the column type if it's possible
+    fn apply_affinity(&self, column: &ColumnDefinition, value: Value) -> io::Result<Value> {
+        match (column.column_type, value) {
+            (ColumnType::Integer, Value::Text(text)) => match text.trim().parse::<i64>() {
+                Ok(integer) => Ok(Value::Integer(integer)),
+                Err(_) => Err(self.datatype_mismatch_error(&column.name, &Value::Text(text))),
+            },
+            (ColumnType::Integer, Value::Real(real)) if real.fract() == 0.0 && real.abs() < i64::MAX as f64 => {
+                Ok(Value::Integer(real as i64))
             },
-            Statement::Select(_select_statement) => {
-                self.select()
+            (ColumnType::Real, Value::Integer(integer)) => Ok(Value::Real(integer as f64)),
+            (ColumnType::Real, Value::Text(text)) => match text.trim().parse::<f64>() {
+                Ok(real) => Ok(Value::Real(real)),
+                Err(_) => Err(self.datatype_mismatch_error(&column.name, &Value::Text(text))),
             },
+            (ColumnType::Text, value @ (Value::Integer(_) | Value::Real(_))) => Ok(Value::Text(value.to_string())),
+            (_, value) => Ok(value),
         }
     }
 
-    fn execute_meta_command(
-        &mut self,
-        parsed_meta_command: &Option<String>,
-    ) -> io::Result<usize> {
-        let meta_command = parsed_meta_command.as_ref().unwrap();
+    /// The method converts the values to the column types
+    pub fn apply_affinities(&self, row_data: &mut RowData) -> io::Result<()> {
+        for (column_idx, column) in self.definition.columns.iter().enumerate() {
+            let value = std::mem::replace(&mut row_data.values[column_idx], Value::Null);
+            row_data.values[column_idx] = self.apply_affinity(column, value)?;
+        }
 
-        match meta_command.as_str() {
-            ".exit" => {
-                if let Err(err) = self.pager.save() {
-                    println!("Can't dump table to the file: {:?}", err);
-                }
+        Ok(())
+    }
 
-                process::exit(0);
-            },
-            _ => {
-                println!("The meta command was not implemented yet");
-                Ok(0)
+    /// The method converts the values to the column types and checks
+    /// the NOT NULL and CHECK constraints
+    fn check_row(&self, session: &Session, row_data: &mut RowData) -> io::Result<()> {
+        self.apply_affinities(row_data)?;
+        self.check_constraints(session, row_data)
+    }
+
+    /// The method checks the NOT NULL and CHECK constraints of the row
+    pub fn check_constraints(&self, session: &Session, row_data: &RowData) -> io::Result<()> {
+        for (column_idx, column) in self.definition.columns.iter().enumerate() {
+            if (column.not_null || column.primary_key) && row_data.values[column_idx].is_null() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("NOT NULL constraint failed: {}", self.describe_values(row_data, &[column_idx])),
+                ));
+            }
+        }
+
+        let table_checks = self.definition.constraints.iter().filter_map(|constraint| match constraint {
+            TableConstraint::Check(check) => Some(check),
+            _ => None,
+        });
+
+        let checks = self.definition.columns
+            .iter()
+            .filter_map(|column| column.check.as_ref())
+            .chain(table_checks);
+
+        // The CHECK constraint fails only if it's false, NULL means the value is unknown
+        for check in checks {
+            let table_row = TableRow { table: self, row_data, session };
+            let result = check.evaluate(&table_row)?;
+
+            if !result.is_null() && !result.is_true() {
+                let column_idxs: Vec<usize> = check
+                    .get_column_names()
+                    .into_iter()
+                    .filter_map(|column_name| self.find_column_idx(column_name))
+                    .collect();
+
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "CHECK constraint failed: {} ({}): {}",
+                        self.get_name(),
+                        check,
+                        self.describe_values(row_data, &column_idxs),
+                    ),
+                ));
             }
         }
+
+        Ok(())
     }
 
-    pub fn execute(&mut self, parsed_statement: ParsedStatement) -> io::Result<usize> {
-        match parsed_statement.statement_type {
-            Statements::Statement => {
-                self.execute_statement(parsed_statement.statement.as_ref().unwrap())
-            },
-            Statements::MetaCommand => {
-                self.execute_meta_command(&parsed_statement.meta_command)
+    /// The method checks that the number of the INSERT values matches the number of the columns
+    pub fn check_num_values(&self, column_names: &Option<Vec<String>>, num_values: usize) -> io::Result<()> {
+        match column_names {
+            Some(column_names) if column_names.len() != num_values => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} values for {} columns", num_values, column_names.len()),
+            )),
+            None if num_values != self.definition.columns.len() => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "The table {} has {} columns but {} values were supplied",
+                    self.get_name(),
+                    self.definition.columns.len(),
+                    num_values,
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The method returns the positions of the INSERT columns in the row,
+    /// there is no position for the hidden row id column
+    pub fn get_insert_positions(&self, column_names: &[String]) -> io::Result<Vec<Option<usize>>> {
+        let mut positions = Vec::with_capacity(column_names.len());
+
+        for column_name in column_names.iter() {
+            if self.find_column_idx(column_name).is_none() && column_name == ROW_ID_COLUMN {
+                positions.push(None);
+                continue;
+            }
+
+            let column_idx = self.get_column_idx(column_name)?;
+
+            if positions.contains(&Some(column_idx)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("The column {} is set more than once", self.definition.columns[column_idx].name),
+                ));
+            }
+
+            positions.push(Some(column_idx));
+        }
+
+        Ok(positions)
+    }
+
+    /// The method inserts the row with the values of all the columns checking the constraints.
+    /// The row id is generated if it's NULL. Returns the id of the new row
+    pub fn insert_row(&mut self, pager: &mut Pager, session: &Session, row_id: Value, values: Vec<Value>) -> io::Result<i64> {
+        let new_row_data = self.prepare_row(pager, row_id, values)?;
+        self.check_constraints(session, &new_row_data)?;
+
+        self.write_row(pager, new_row_data)
+    }
+
+    /// The method evaluates the expression against the row of the table
+    pub fn evaluate(&self, session: &Session, row_data: &RowData, expression: &Expression) -> io::Result<Value> {
+        expression.evaluate(&TableRow { table: self, row_data, session })
+    }
+
+    /// The method finds the row id of the new row and converts its values to the column types.
+    /// The row id is generated if it's NULL
+    pub fn prepare_row(&self, pager: &Pager, row_id: Value, mut values: Vec<Value>) -> io::Result<RowData> {
+        // The value of the INTEGER PRIMARY KEY column is the row id
+        let row_id = match self.row_id_column {
+            Some(column_idx) if row_id.is_null() => values[column_idx].clone(),
+            _ => row_id,
+        };
+
+        let row_id = match row_id {
+            Value::Null => self.get_next_row_id(pager)?,
+            row_id => self.to_row_id(row_id)?,
+        };
+
+        if let Some(column_idx) = self.row_id_column {
+            values[column_idx] = Value::Integer(row_id);
+        }
+
+        let mut new_row_data = RowData::new(row_id, values);
+        self.apply_affinities(&mut new_row_data)?;
+
+        Ok(new_row_data)
+    }
+
+    /// The method returns the ids of the rows which have the same row id or the same values
+    /// of the unique indexes as the new row, together with the columns of the conflicting keys
+    pub fn find_conflicts(&self, pager: &Pager, row_data: &RowData) -> Vec<(Vec<String>, i64)> {
+        let mut conflicts = Vec::new();
+
+        if pager.find_row(self.id, row_data.id).is_some() {
+            let column_name = self.row_id_column.map_or(ROW_ID_COLUMN, |column_idx| &self.definition.columns[column_idx].name);
+            conflicts.push((vec![column_name.to_string()], row_data.id));
+        }
+
+        for index in self.indexes.iter() {
+            if let Some(row_id) = index.find_conflict(row_data) {
+                conflicts.push((index.get_columns().clone(), row_id));
+            }
+        }
+
+        conflicts
+    }
+
+    /// The target of ON CONFLICT must be the INTEGER PRIMARY KEY or the columns of the unique index
+    pub fn is_conflict_target(&self, column_names: &[String]) -> bool {
+        let is_same_columns = |key_columns: &[String]| {
+            key_columns.len() == column_names.len() && key_columns.iter().all(|column_name| column_names.contains(column_name))
+        };
+
+        let is_row_id_target = self.row_id_column
+            .is_some_and(|column_idx| is_same_columns(std::slice::from_ref(&self.definition.columns[column_idx].name)));
+
+        is_row_id_target || self.indexes.iter().any(|index| index.is_unique() && is_same_columns(index.get_columns()))
+    }
+
+    /// The method stores the prepared row if it doesn't conflict with the other rows
+    pub fn write_row(&mut self, pager: &mut Pager, new_row_data: RowData) -> io::Result<i64> {
+        let row_id = new_row_data.id;
+
+        if pager.find_row(self.id, row_id).is_some() {
+            return Err(self.row_id_constraint_error(row_id));
+        }
+
+        for index in self.indexes.iter() {
+            if index.find_conflict(&new_row_data).is_some() {
+                return Err(self.unique_constraint_error(index, &new_row_data));
+            }
+        }
+
+        pager.insert_row(self.id, &new_row_data)?;
+
+        for index in self.indexes.iter_mut() {
+            index.insert(&new_row_data);
+        }
+
+        self.update_sequence(row_id);
+
+        Ok(row_id)
+    }
+
+    pub fn get_all_rows(&self, pager: &Pager) -> io::Result<Vec<RowData>> {
+        let mut rows = Vec::with_capacity(pager.get_num_rows(self.id));
+        let mut cursor = Cursor::new(pager, self.id, CursorPosition::Start)?;
+
+        while let Some(row_data) = cursor.get_row() {
+            rows.push(row_data);
+            cursor.cursor_advance()?;
+        }
+
+        Ok(rows)
+    }
+
+    /// The method returns the column, the operator and the value if the expression
+    /// compares a column with a literal
+    fn get_column_constraint(expression: &Expression) -> Option<(&str, BinaryOperator, &Value)> {
+        match expression {
+            Expression::Binary(left, operator, right) => match (left.as_ref(), right.as_ref()) {
+                (Expression::Column { column_name, .. }, Expression::Literal(value)) => {
+                    Some((column_name, *operator, value))
+                },
+                (Expression::Literal(value), Expression::Column { column_name, .. }) => {
+                    Some((column_name, operator.get_swapped(), value))
+                },
+                _ => None,
             },
+            _ => None,
+        }
+    }
+
+    /// The method returns the column constraints which could be used by the indexes.
+    /// The comparisons with NULL are never true, only IS could find NULLs
+    fn get_index_constraints(where_clause: &Expression) -> Vec<(&str, BinaryOperator, &Value)> {
+        where_clause
+            .get_conjuncts()
+            .into_iter()
+            .filter_map(Self::get_column_constraint)
+            .filter_map(|(column_name, operator, value)| match operator {
+                BinaryOperator::Is => Some((column_name, BinaryOperator::Equal, value)),
+                _ if value.is_null() => None,
+                _ => Some((column_name, operator, value)),
+            })
+            .collect()
+    }
+
+    /// The method builds the range of the index: the values of the longest prefix
+    /// of the equal columns and the bounds of the next column
+    fn build_index_range(index: &Index, constraints: &[(&str, BinaryOperator, &Value)]) -> IndexRange {
+        let mut equal_values = Vec::new();
+
+        for index_column in index.get_columns() {
+            let equal_constraint = constraints.iter().find(|(column_name, operator, _)| {
+                column_name == index_column && *operator == BinaryOperator::Equal
+            });
+
+            match equal_constraint {
+                Some((_, _, value)) => equal_values.push((*value).clone()),
+                None => break,
+            }
+        }
+
+        let mut lower_bound = Bound::Unbounded;
+        let mut upper_bound = Bound::Unbounded;
+
+        if let Some(range_column) = index.get_columns().get(equal_values.len()) {
+            for (column_name, operator, value) in constraints.iter() {
+                if column_name != range_column {
+                    continue;
+                }
+
+                match operator {
+                    BinaryOperator::Greater => lower_bound = Bound::Excluded((*value).clone()),
+                    BinaryOperator::GreaterOrEqual => lower_bound = Bound::Included((*value).clone()),
+                    BinaryOperator::Less => upper_bound = Bound::Excluded((*value).clone()),
+                    BinaryOperator::LessOrEqual => upper_bound = Bound::Included((*value).clone()),
+                    _ => {},
+                }
+            }
+        }
+
+        IndexRange {
+            equal_values,
+            lower_bound,
+            upper_bound,
+        }
+    }
+
+    pub fn estimate_num_rows(&self, pager: &Pager) -> f64 {
+        pager.get_num_rows(self.id) as f64
+    }
+
+    /// The column has no duplicates if it's the row id or the only column of a unique index
+    fn is_unique_column(&self, column_name: &str) -> bool {
+        self.is_row_id_column(column_name)
+            || self.indexes.iter().any(|index| index.is_unique() && index.get_columns().as_slice() == [column_name])
+    }
+
+    /// The estimated number of the different values in the column
+    pub fn estimate_distinct_count(&self, pager: &Pager, column_name: &str) -> f64 {
+        let num_rows = self.estimate_num_rows(pager).max(1.0);
+
+        if self.is_unique_column(column_name) {
+            return num_rows;
+        }
+
+        match self.statistics.as_ref().and_then(|statistics| statistics.get_distinct_count(column_name)) {
+            Some(distinct_count) => (distinct_count as f64).clamp(1.0, num_rows),
+            None => (1.0 / DEFAULT_EQUAL_SELECTIVITY).min(num_rows),
+        }
+    }
+
+    /// The share of the rows with the value in the column
+    fn estimate_equal(&self, pager: &Pager, column_name: &str, value: &Value) -> f64 {
+        if !value.is_null() && self.is_unique_column(column_name) {
+            return 1.0 / self.estimate_num_rows(pager).max(1.0);
+        }
+
+        self.statistics
+            .as_ref()
+            .and_then(|statistics| statistics.estimate_equal(column_name, value))
+            .unwrap_or(DEFAULT_EQUAL_SELECTIVITY)
+    }
+
+    /// The share of the rows with the values of the column between the bounds
+    fn estimate_range(&self, column_name: &str, lower_bound: &Bound<Value>, upper_bound: &Bound<Value>) -> f64 {
+        let estimated_range = self.statistics
+            .as_ref()
+            .and_then(|statistics| statistics.estimate_range(column_name, lower_bound, upper_bound));
+
+        // Every bound narrows down the rows
+        estimated_range.unwrap_or_else(|| {
+            [lower_bound, upper_bound]
+                .iter()
+                .filter(|bound| !matches!(bound, Bound::Unbounded))
+                .fold(1.0, |share, _| share * DEFAULT_RANGE_SELECTIVITY)
+        })
+    }
+
+    /// The estimated share of the rows for which the condition is true
+    pub fn estimate_selectivity(&self, pager: &Pager, condition: &Option<Expression>) -> f64 {
+        let Some(condition) = condition else {
+            return 1.0;
+        };
+
+        condition
+            .get_conjuncts()
+            .into_iter()
+            .map(|conjunct| match Self::get_column_constraint(conjunct) {
+                Some((column_name, BinaryOperator::Equal | BinaryOperator::Is, value)) => self.estimate_equal(pager, column_name, value),
+                Some((column_name, BinaryOperator::NotEqual | BinaryOperator::IsNot, value)) => 1.0 - self.estimate_equal(pager, column_name, value),
+                Some((_, _, Value::Null)) => 0.0,
+                Some((column_name, operator, value)) => {
+                    let bound = |is_included| if is_included { Bound::Included(value.clone()) } else { Bound::Excluded(value.clone()) };
+
+                    match operator {
+                        BinaryOperator::Greater | BinaryOperator::GreaterOrEqual => {
+                            self.estimate_range(column_name, &bound(operator == BinaryOperator::GreaterOrEqual), &Bound::Unbounded)
+                        },
+                        BinaryOperator::Less | BinaryOperator::LessOrEqual => {
+                            self.estimate_range(column_name, &Bound::Unbounded, &bound(operator == BinaryOperator::LessOrEqual))
+                        },
+                        _ => DEFAULT_RANGE_SELECTIVITY,
+                    }
+                },
+                None => DEFAULT_RANGE_SELECTIVITY,
+            })
+            .product()
+    }
+
+    /// The estimated share of the rows found by the index range
+    fn estimate_index_range(&self, pager: &Pager, index: &Index, index_range: &IndexRange) -> f64 {
+        let columns = index.get_columns();
+
+        let equal_share: f64 = columns
+            .iter()
+            .zip(index_range.equal_values.iter())
+            .map(|(column_name, value)| self.estimate_equal(pager, column_name, value))
+            .product();
+
+        let range_share = match columns.get(index_range.equal_values.len()) {
+            Some(column_name) => self.estimate_range(column_name, &index_range.lower_bound, &index_range.upper_bound),
+            None => 1.0,
+        };
+
+        equal_share * range_share
+    }
+
+    /// The method returns the row id if the where clause requires the exact one
+    fn find_row_id_lookup(&self, where_clause: &Expression) -> Option<i64> {
+        where_clause
+            .get_conjuncts()
+            .into_iter()
+            .filter_map(Self::get_column_constraint)
+            .find_map(|(column_name, operator, value)| match (operator, value) {
+                (BinaryOperator::Equal, Value::Integer(row_id)) if self.is_row_id_column(column_name) => Some(*row_id),
+                _ => None,
+            })
+    }
+
+    /// The method returns the column names if the ordering terms are plain columns sorted
+    /// in the same direction with NULLs as the smallest values, like the indexes keep them
+    fn get_columns_ordering<'o>(&self, order_by: &'o [OrderingTerm]) -> Option<(Vec<&'o str>, bool)> {
+        let descending = order_by.first()?.descending;
+        let mut column_names = Vec::with_capacity(order_by.len());
+
+        for ordering_term in order_by.iter() {
+            let Expression::Column { column_name, .. } = &ordering_term.expression else {
+                return None;
+            };
+
+            if ordering_term.descending != descending || ordering_term.is_nulls_first() == descending {
+                return None;
+            }
+
+            column_names.push(column_name.as_str());
+        }
+
+        Some((column_names, descending))
+    }
+
+    /// The method checks that the index scan returns the rows in the order of the columns.
+    /// The columns compared with the constants don't change the order
+    fn is_ordered_by_index(index: &Index, num_equal_columns: usize, column_names: &[&str]) -> bool {
+        let (equal_columns, range_columns) = index.get_columns().split_at(num_equal_columns);
+
+        let order_columns: Vec<&str> = column_names
+            .iter()
+            .copied()
+            .filter(|column_name| !equal_columns.iter().any(|equal_column| equal_column == column_name))
+            .collect();
+
+        order_columns.len() <= range_columns.len()
+            && order_columns.iter().zip(range_columns.iter()).all(|(order_column, range_column)| order_column == range_column)
+    }
+
+    /// The method chooses the cheapest way to read the rows matching the where clause.
+    /// The rows which are not read in the required order must be sorted, so the sort is added to the cost
+    pub fn plan_scan(&self, pager: &Pager, where_clause: &Option<Expression>, order_by: &[OrderingTerm]) -> AccessPath<'_> {
+        let num_rows = self.estimate_num_rows(pager);
+        let num_matching_rows = num_rows * self.estimate_selectivity(pager, where_clause);
+        let columns_ordering = self.get_columns_ordering(order_by);
+
+        let get_index_order = |index: &Index, num_equal_columns: usize| -> Option<bool> {
+            if order_by.is_empty() {
+                return Some(false);
+            }
+
+            columns_ordering
+                .as_ref()
+                .filter(|(column_names, _)| Self::is_ordered_by_index(index, num_equal_columns, column_names))
+                .map(|(_, descending)| *descending)
+        };
+
+        let mut access_paths = Vec::new();
+
+        if let Some(row_id) = where_clause.as_ref().and_then(|where_clause| self.find_row_id_lookup(where_clause)) {
+            access_paths.push(AccessPath {
+                scan_plan: ScanPlan::RowId(row_id),
+                order: Some(false),
+                cost: ROW_LOOKUP_COST,
+                num_rows: num_matching_rows.min(1.0),
+            });
+        }
+
+        // The table is stored in the order of the row ids
+        let table_order = match columns_ordering.as_ref().map(|(column_names, descending)| (column_names.as_slice(), descending)) {
+            _ if order_by.is_empty() => Some(false),
+            Some(([column_name], descending)) if self.is_row_id_column(column_name) => Some(*descending),
+            _ => None,
+        };
+
+        access_paths.push(AccessPath {
+            scan_plan: ScanPlan::Table,
+            order: table_order,
+            cost: num_rows,
+            num_rows: num_matching_rows,
+        });
+
+        let constraints = where_clause.as_ref().map_or_else(Vec::new, Self::get_index_constraints);
+
+        for index in self.indexes.iter() {
+            let index_range = Self::build_index_range(index, &constraints);
+            let order = get_index_order(index, index_range.equal_values.len());
+
+            let is_whole_index = index_range.equal_values.is_empty()
+                && matches!((&index_range.lower_bound, &index_range.upper_bound), (Bound::Unbounded, Bound::Unbounded));
+
+            // The whole index is read only to get the rows in the required order
+            if is_whole_index && (order_by.is_empty() || order.is_none()) {
+                continue;
+            }
+
+            let num_index_rows = num_rows * self.estimate_index_range(pager, index, &index_range);
+
+            access_paths.push(AccessPath {
+                scan_plan: ScanPlan::Index(index, index_range),
+                order,
+                cost: num_rows.max(2.0).log2() + num_index_rows * ROW_LOOKUP_COST,
+                num_rows: num_matching_rows,
+            });
+        }
+
+        let sort_cost = estimate_sort_cost(num_matching_rows);
+        let get_total_cost = |access_path: &AccessPath| {
+            access_path.cost + if order_by.is_empty() || access_path.order.is_some() { 0.0 } else { sort_cost }
+        };
+
+        access_paths
+            .into_iter()
+            .min_by(|left, right| get_total_cost(left).total_cmp(&get_total_cost(right)))
+            .expect("The table could always be scanned")
+    }
+
+    pub fn read_rows(&self, pager: &Pager, scan_plan: ScanPlan, reverse: bool) -> io::Result<Vec<RowData>> {
+        let mut rows: Vec<RowData> = match scan_plan {
+            ScanPlan::RowId(row_id) => pager.get_row_by_id(self.id, row_id).into_iter().collect(),
+            ScanPlan::Index(index, index_scan) => index
+                .scan(&index_scan)
+                .into_iter()
+                .filter_map(|row_id| pager.get_row_by_id(self.id, row_id))
+                .collect(),
+            ScanPlan::Table => self.get_all_rows(pager)?,
+        };
+
+        if reverse {
+            rows.reverse();
+        }
+
+        Ok(rows)
+    }
+
+    /// The index scan only narrows down the rows, the whole condition is checked anyway
+    fn filter_rows(&self, session: &Session, rows: Vec<RowData>, where_clause: &Option<Expression>) -> io::Result<Vec<RowData>> {
+        let Some(where_clause) = where_clause else {
+            return Ok(rows);
+        };
+
+        let mut filtered_rows = Vec::new();
+
+        for row_data in rows {
+            if where_clause.evaluate(&TableRow { table: self, row_data: &row_data, session })?.is_true() {
+                filtered_rows.push(row_data);
+            }
+        }
+
+        Ok(filtered_rows)
+    }
+
+    /// The method returns the rows matching the where clause using the row id
+    /// or an index if it's possible
+    fn find_rows(&self, pager: &Pager, session: &Session, where_clause: &Option<Expression>) -> io::Result<Vec<RowData>> {
+        let access_path = self.plan_scan(pager, where_clause, &[]);
+        let rows = self.read_rows(pager, access_path.scan_plan, false)?;
+
+        self.filter_rows(session, rows, where_clause)
+    }
+
+    /// The method checks the assigned columns of UPDATE, None is the row id which could be changed as well
+    pub fn get_update_positions(&self, update_statement: &UpdateStatement) -> io::Result<Vec<Option<usize>>> {
+        update_statement.assignments
+            .iter()
+            .map(|(column_name, _)| match self.find_column_idx(column_name) {
+                Some(column_idx) => Ok(Some(column_idx)),
+                None if column_name == ROW_ID_COLUMN => Ok(None),
+                None => Err(no_such_column_error(&format!("{}.{}", self.get_name(), column_name))),
+            })
+            .collect()
+    }
+
+    fn is_row_id_changed(&self, positions: &[Option<usize>]) -> bool {
+        positions
+            .iter()
+            .any(|column_idx| column_idx.is_none() || *column_idx == self.row_id_column)
+    }
+
+    /// The method returns the row with the values assigned to the columns in the order of the positions,
+    /// the constraints are not checked
+    pub fn get_updated_row(&self, positions: &[Option<usize>], old_row: &RowData, values: Vec<Value>) -> io::Result<RowData> {
+        let mut new_row = old_row.clone();
+
+        for (column_idx, value) in positions.iter().zip(values) {
+            match column_idx {
+                Some(column_idx) => new_row.values[*column_idx] = value,
+                None => new_row.id = self.to_row_id(value)?,
+            }
+        }
+
+        if let Some(column_idx) = self.row_id_column {
+            if self.is_row_id_changed(positions) && new_row.values[column_idx] != old_row.values[column_idx] {
+                new_row.id = self.to_row_id(new_row.values[column_idx].clone())?;
+            }
+
+            new_row.values[column_idx] = Value::Integer(new_row.id);
+        }
+
+        Ok(new_row)
+    }
+
+    /// The method assigns the new values to the columns of the rows, the values are evaluated
+    /// by the query for every old row in the order of the positions. Returns the new rows
+    pub fn update(&mut self, pager: &mut Pager, session: &Session, positions: &[Option<usize>], changes: Vec<(RowData, Vec<Value>)>) -> io::Result<Vec<RowData>> {
+        let is_row_id_changed = self.is_row_id_changed(positions);
+
+        let mut old_rows = Vec::with_capacity(changes.len());
+        let mut new_rows = Vec::with_capacity(changes.len());
+
+        for (old_row, values) in changes {
+            let mut new_row = self.get_updated_row(positions, &old_row, values)?;
+
+            self.check_row(session, &mut new_row)?;
+            old_rows.push(old_row);
+            new_rows.push(new_row);
+        }
+
+        // All the unique indexes are checked before any row is changed.
+        // The updated rows could swap their values, so only the conflicts with other rows matter
+        let updated_ids: HashSet<i64> = old_rows.iter().map(|row_data| row_data.id).collect();
+
+        if is_row_id_changed {
+            let mut new_ids = HashSet::new();
+
+            for new_row in new_rows.iter() {
+                let has_conflict = !new_ids.insert(new_row.id)
+                    || (!updated_ids.contains(&new_row.id) && pager.find_row(self.id, new_row.id).is_some());
+
+                if has_conflict {
+                    return Err(self.row_id_constraint_error(new_row.id));
+                }
+            }
+        }
+
+        for index in self.indexes.iter().filter(|index| index.is_unique()) {
+            let mut new_values = BTreeSet::new();
+
+            for new_row in new_rows.iter() {
+                let values: Vec<Value> = index
+                    .get_column_idxs()
+                    .iter()
+                    .map(|&column_idx| new_row.values[column_idx].clone())
+                    .collect();
+
+                let is_duplicate = !values.iter().any(Value::is_null) && !new_values.insert(values);
+
+                let has_conflict = is_duplicate || index
+                    .find_conflict(new_row)
+                    .is_some_and(|row_id| !updated_ids.contains(&row_id));
+
+                if has_conflict {
+                    return Err(self.unique_constraint_error(index, new_row));
+                }
+            }
+        }
+
+        // The rows with the changed ids are removed first, so the ids could be swapped as well
+        for (old_row, new_row) in old_rows.iter().zip(new_rows.iter()) {
+            if old_row.id != new_row.id {
+                pager.delete_row(self.id, old_row.id)?;
+            }
+        }
+
+        for (old_row, new_row) in old_rows.iter().zip(new_rows.iter()) {
+            for index in self.indexes.iter_mut() {
+                index.remove(old_row);
+                index.insert(new_row);
+            }
+
+            if old_row.id == new_row.id {
+                pager.update_row(self.id, new_row)?;
+            } else {
+                pager.insert_row(self.id, new_row)?;
+                self.update_sequence(new_row.id);
+            }
+        }
+
+        Ok(new_rows)
+    }
+
+    pub fn delete(&mut self, pager: &mut Pager, session: &Session, where_clause: &Option<Expression>) -> io::Result<usize> {
+        let rows = self.find_rows(pager, session, where_clause)?;
+
+        for row_data in rows.iter() {
+            self.delete_row(pager, row_data)?;
+        }
+
+        Ok(rows.len())
+    }
+
+    /// The method removes the row from the table and its indexes
+    pub fn delete_row(&mut self, pager: &mut Pager, row_data: &RowData) -> io::Result<()> {
+        for index in self.indexes.iter_mut() {
+            index.remove(row_data);
+        }
+
+        pager.delete_row(self.id, row_data.id)
+    }
+
+    pub fn find_index(&self, index_name: &str) -> Option<&Index> {
+        self.indexes.iter().find(|index| index.get_name() == index_name)
+    }
+
+    pub fn get_indexes(&self) -> &[Index] {
+        &self.indexes
+    }
+
+    pub fn create_index(&mut self, pager: &Pager, create_index_statement: &CreateIndexStatement) -> io::Result<()> {
+        let column_idxs = self.get_column_idxs(&create_index_statement.columns)?;
+        let mut index = Index::new(create_index_statement, column_idxs)?;
+
+        for row_data in self.get_all_rows(pager)? {
+            if index.find_conflict(&row_data).is_some() {
+                return Err(self.unique_constraint_error(&index, &row_data));
+            }
+
+            index.insert(&row_data);
         }
+
+        self.indexes.push(index);
+
+        Ok(())
+    }
+
+    pub fn drop_index(&mut self, index_name: &str) {
+        self.indexes.retain(|index| index.get_name() != index_name);
     }
 }
@@ -0,0 +1,204 @@
+use std::io;
+use std::ops::Bound;
+use b_tree::BTree;
+
+use crate::backend::pager::page::row_data::RowData;
+use crate::statement::create_index_statement::CreateIndexStatement;
+use crate::value::Value;
+
+const LEAF_DEGREE: usize = 32;
+const COMMON_DEGREE: usize = 32;
+
+pub const AUTO_INDEX_PREFIX: &str = "rustqlight_autoindex_";
+
+/// The part of the index key. Min and Max are never stored,
+/// they are used as bounds to scan all the keys with the same prefix
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IndexKeyPart {
+    Min,
+    Value(Value),
+    Max,
+}
+
+/// The indexed column values followed by the row id, so the keys are unique even for
+/// not unique indexes
+pub type IndexKey = Vec<IndexKeyPart>;
+
+/// The description of the index scan: the values of the leading columns
+/// and the bounds of the next column
+#[derive(Debug, Clone)]
+pub struct IndexRange {
+    pub equal_values: Vec<Value>,
+    pub lower_bound: Bound<Value>,
+    pub upper_bound: Bound<Value>,
+}
+
+pub struct Index {
+    name: String,
+    table_name: String,
+    columns: Vec<String>,
+    // The positions of the indexed columns in the row
+    column_idxs: Vec<usize>,
+    unique: bool,
+    tree: BTree<IndexKey, i64>,
+}
+
+impl Index {
+    pub fn new(statement: &CreateIndexStatement, column_idxs: Vec<usize>) -> io::Result<Self> {
+        let tree = BTree::new(LEAF_DEGREE, COMMON_DEGREE)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        Ok(Index {
+            name: statement.index_name.clone(),
+            table_name: statement.table_name.clone(),
+            columns: statement.columns.clone(),
+            column_idxs,
+            unique: statement.unique,
+            tree,
+        })
+    }
+
+    fn get_values(&self, row_data: &RowData) -> Vec<IndexKeyPart> {
+        self.column_idxs
+            .iter()
+            .map(|&column_idx| IndexKeyPart::Value(row_data.values[column_idx].clone()))
+            .collect()
+    }
+
+    fn build_key(&self, row_data: &RowData) -> IndexKey {
+        let mut key = self.get_values(row_data);
+        key.push(IndexKeyPart::Value(Value::Integer(row_data.id)));
+
+        key
+    }
+
+    pub fn insert(&mut self, row_data: &RowData) {
+        self.tree.insert(&self.build_key(row_data), &row_data.id);
+    }
+
+    pub fn remove(&mut self, row_data: &RowData) {
+        self.tree.remove(&self.build_key(row_data));
+    }
+
+    /// The method returns the id of another row with the same values in the unique index.
+    /// NULLs are never equal to each other, so they don't conflict
+    pub fn find_conflict(&self, row_data: &RowData) -> Option<i64> {
+        if !self.unique {
+            return None;
+        }
+
+        let mut lower_key = self.get_values(row_data);
+
+        if lower_key.contains(&IndexKeyPart::Value(Value::Null)) {
+            return None;
+        }
+
+        let mut upper_key = lower_key.clone();
+        lower_key.push(IndexKeyPart::Min);
+        upper_key.push(IndexKeyPart::Max);
+
+        self.tree
+            .range(lower_key..=upper_key)
+            .into_iter()
+            .map(|(_, row_id)| row_id)
+            .find(|&row_id| row_id != row_data.id)
+    }
+
+    /// The method returns the ids of the rows matching the scan in the index order
+    pub fn scan(&self, index_range: &IndexRange) -> Vec<i64> {
+        let prefix: Vec<IndexKeyPart> = index_range.equal_values
+            .iter()
+            .map(|value| IndexKeyPart::Value(value.clone()))
+            .collect();
+
+        let build_bound_key = |bound: &Bound<Value>, is_lower: bool| -> IndexKey {
+            let mut key = prefix.clone();
+
+            // Min is less than any stored key with the same prefix and Max is greater
+            match bound {
+                Bound::Included(value) | Bound::Excluded(value) => {
+                    key.push(IndexKeyPart::Value(value.clone()));
+
+                    let is_included = matches!(bound, Bound::Included(_));
+                    key.push(if is_included == is_lower { IndexKeyPart::Min } else { IndexKeyPart::Max });
+                },
+                Bound::Unbounded => {
+                    key.push(if is_lower { IndexKeyPart::Min } else { IndexKeyPart::Max });
+                },
+            }
+
+            key
+        };
+
+        let lower_key = build_bound_key(&index_range.lower_bound, true);
+        let upper_key = build_bound_key(&index_range.upper_bound, false);
+
+        if lower_key > upper_key {
+            return vec![];
+        }
+
+        self.tree
+            .range(lower_key..=upper_key)
+            .into_iter()
+            .map(|(_, row_id)| row_id)
+            .collect()
+    }
+
+    /// The method describes the constraints of the range on the index columns,
+    /// there is no description if the whole index is scanned
+    pub fn describe_range(&self, index_range: &IndexRange) -> Option<String> {
+        let mut constraints: Vec<String> = self.columns
+            .iter()
+            .zip(index_range.equal_values.iter())
+            .map(|(column_name, value)| format!("{}={}", column_name, value.to_sql()))
+            .collect();
+
+        if let Some(column_name) = self.columns.get(index_range.equal_values.len()) {
+            for (bound, included, excluded) in [(&index_range.lower_bound, ">=", ">"), (&index_range.upper_bound, "<=", "<")] {
+                match bound {
+                    Bound::Included(value) => constraints.push(format!("{}{}{}", column_name, included, value.to_sql())),
+                    Bound::Excluded(value) => constraints.push(format!("{}{}{}", column_name, excluded, value.to_sql())),
+                    Bound::Unbounded => {},
+                }
+            }
+        }
+
+        (!constraints.is_empty()).then(|| constraints.join(" AND "))
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_columns(&self) -> &Vec<String> {
+        &self.columns
+    }
+
+    pub fn get_column_idxs(&self) -> &[usize] {
+        &self.column_idxs
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    /// The indexes created for PRIMARY KEY and UNIQUE constraints are part of the table definition
+    pub fn is_auto_index(&self) -> bool {
+        self.name.starts_with(AUTO_INDEX_PREFIX)
+    }
+
+    /// The statement which creates the index again
+    pub fn get_statement(&self) -> CreateIndexStatement {
+        CreateIndexStatement {
+            index_name: self.name.clone(),
+            table_name: self.table_name.clone(),
+            columns: self.columns.clone(),
+            unique: self.unique,
+            if_not_exists: false,
+        }
+    }
+
+    pub fn to_sql(&self) -> String {
+        self.get_statement().to_sql()
+    }
+}
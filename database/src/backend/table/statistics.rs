@@ -0,0 +1,160 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::ops::Bound;
+use crate::parser::token_stream::TokenStream;
+use crate::parser::tokenizer::Token;
+use crate::session::Session;
+use crate::statement::expression::{EmptyRow, Expression};
+use crate::value::Value;
+
+/// The number of the histogram buckets, every bucket has about the same number of values
+const NUM_HISTOGRAM_BUCKETS: usize = 16;
+
+/// The selectivities which are used when there are no statistics
+pub const DEFAULT_EQUAL_SELECTIVITY: f64 = 0.1;
+pub const DEFAULT_RANGE_SELECTIVITY: f64 = 0.25;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStatistics {
+    pub distinct_count: usize,
+    pub null_count: usize,
+    // The biggest values of the buckets, the values are sorted
+    pub histogram: Vec<Value>,
+}
+
+/// The statistics gathered by ANALYZE
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableStatistics {
+    pub row_count: usize,
+    pub columns: BTreeMap<String, ColumnStatistics>,
+}
+
+impl ColumnStatistics {
+    pub fn new(mut values: Vec<Value>) -> Self {
+        let num_values = values.len();
+
+        values.retain(|value| !value.is_null());
+        values.sort();
+
+        let distinct_count = values.iter().collect::<HashSet<&Value>>().len();
+        let num_buckets = NUM_HISTOGRAM_BUCKETS.min(values.len());
+
+        let histogram = (1..=num_buckets)
+            .map(|bucket_num| values[(bucket_num * values.len()).div_ceil(num_buckets) - 1].clone())
+            .collect();
+
+        ColumnStatistics {
+            distinct_count,
+            null_count: num_values - values.len(),
+            histogram,
+        }
+    }
+
+    /// The histogram is stored as the list of SQL literals
+    pub fn histogram_to_sql(&self) -> String {
+        self.histogram.iter().map(Value::to_sql).collect::<Vec<String>>().join(", ")
+    }
+
+    pub fn parse_histogram(histogram_sql: &str) -> io::Result<Vec<Value>> {
+        let mut tokens = TokenStream::new(histogram_sql)?;
+        let mut histogram = Vec::new();
+
+        while tokens.peek().is_some() {
+            if !histogram.is_empty() {
+                tokens.expect(&Token::Comma)?;
+            }
+
+            histogram.push(Expression::parse(&mut tokens)?.evaluate(&EmptyRow { session: &Session::default() })?);
+        }
+
+        Ok(histogram)
+    }
+
+    /// The share of the values which are less than the value, the bucket with the value is counted as a half
+    fn get_position(&self, value: &Value) -> f64 {
+        let num_less = self.histogram.partition_point(|bound| bound < value);
+        let num_less_or_equal = self.histogram.partition_point(|bound| bound <= value);
+
+        (num_less + num_less_or_equal) as f64 / (2 * self.histogram.len()) as f64
+    }
+
+    fn get_not_null_share(&self, row_count: usize) -> f64 {
+        if row_count == 0 {
+            return 0.0;
+        }
+
+        1.0 - self.null_count as f64 / row_count as f64
+    }
+}
+
+impl TableStatistics {
+    /// The share of the rows with the value in the column
+    pub fn estimate_equal(&self, column_name: &str, value: &Value) -> Option<f64> {
+        let column = self.columns.get(column_name)?;
+
+        if self.row_count == 0 {
+            return Some(0.0);
+        }
+
+        if value.is_null() {
+            return Some(column.null_count as f64 / self.row_count as f64);
+        }
+
+        Some(column.get_not_null_share(self.row_count) / column.distinct_count.max(1) as f64)
+    }
+
+    /// The share of the rows with the values of the column between the bounds
+    pub fn estimate_range(&self, column_name: &str, lower_bound: &Bound<Value>, upper_bound: &Bound<Value>) -> Option<f64> {
+        let column = self.columns.get(column_name)?;
+
+        if column.histogram.is_empty() {
+            return Some(0.0);
+        }
+
+        let lower_position = match lower_bound {
+            Bound::Included(value) | Bound::Excluded(value) => column.get_position(value),
+            Bound::Unbounded => 0.0,
+        };
+        let upper_position = match upper_bound {
+            Bound::Included(value) | Bound::Excluded(value) => column.get_position(value),
+            Bound::Unbounded => 1.0,
+        };
+
+        // The range is never estimated as empty, since the histogram is only an approximation
+        let share = (upper_position - lower_position).max(1.0 / (2 * column.histogram.len()) as f64);
+
+        Some(share * column.get_not_null_share(self.row_count))
+    }
+
+    pub fn get_distinct_count(&self, column_name: &str) -> Option<usize> {
+        self.columns.get(column_name).map(|column| column.distinct_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_estimate_selectivity() {
+        let values: Vec<Value> = (1..=100).map(|value| Value::Integer(value % 50))
+            .chain((0..100).map(|_| Value::Null))
+            .collect();
+
+        let statistics = TableStatistics {
+            row_count: values.len(),
+            columns: BTreeMap::from([("value".to_string(), ColumnStatistics::new(values))]),
+        };
+
+        let column = &statistics.columns["value"];
+        assert_eq!((column.distinct_count, column.null_count, column.histogram.len()), (50, 100, 16));
+        assert_eq!(ColumnStatistics::parse_histogram(&column.histogram_to_sql()).unwrap(), column.histogram);
+
+        assert_eq!(statistics.estimate_equal("value", &Value::Integer(7)), Some(0.01));
+        assert_eq!(statistics.estimate_equal("value", &Value::Null), Some(0.5));
+        assert_eq!(statistics.estimate_equal("other", &Value::Integer(7)), None);
+
+        let share = statistics.estimate_range("value", &Bound::Excluded(Value::Integer(25)), &Bound::Unbounded).unwrap();
+        assert!((0.2..0.3).contains(&share));
+    }
+}
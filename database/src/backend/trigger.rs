@@ -0,0 +1,83 @@
+use std::io;
+use std::iter;
+use super::pager::page::row_data::RowData;
+use super::table::Table;
+use crate::statement::Statement;
+use crate::statement::expression::Expression;
+use crate::statement::select_statement::SelectStatement;
+
+/// The rows of the table changed by the statement which fired the trigger,
+/// INSERT has no OLD row and DELETE has no NEW row
+pub struct TriggerRows<'a> {
+    pub table: &'a Table,
+    pub old_row: Option<&'a RowData>,
+    pub new_row: Option<&'a RowData>,
+}
+
+impl TriggerRows<'_> {
+    /// The method replaces the columns of NEW and OLD in the statement of the trigger by their values
+    pub fn substitute(&self, statement: &mut Statement) -> io::Result<()> {
+        if let Some(select_statement) = statement.get_select_mut() {
+            self.substitute_select(select_statement)?;
+        }
+
+        for expression in statement.get_expressions_mut() {
+            self.substitute_expression(expression)?;
+        }
+
+        Ok(())
+    }
+
+    fn substitute_select(&self, select_statement: &mut SelectStatement) -> io::Result<()> {
+        if let Some(with_clause) = &mut select_statement.with_clause {
+            for cte in with_clause.tables.iter_mut() {
+                self.substitute_select(&mut cte.select_statement)?;
+            }
+        }
+
+        let table_references = iter::once(&mut select_statement.from).chain(select_statement.joins.iter_mut().map(|join| &mut join.table));
+
+        for table_reference in table_references {
+            if let Some(subquery) = &mut table_reference.subquery {
+                self.substitute_select(subquery)?;
+            }
+        }
+
+        for (_, compound_select) in select_statement.compound.iter_mut() {
+            self.substitute_select(compound_select)?;
+        }
+
+        for expression in select_statement.get_expressions_mut() {
+            self.substitute_expression(expression)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn substitute_expression(&self, expression: &mut Expression) -> io::Result<()> {
+        match expression {
+            Expression::Column { table_name: Some(table_name), column_name } if table_name == "new" || table_name == "old" => {
+                let row_data = if table_name == "new" { self.new_row } else { self.old_row };
+
+                let Some(row_data) = row_data else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("There is no {} row in the trigger of the table {}", table_name.to_uppercase(), self.table.get_name()),
+                    ));
+                };
+
+                *expression = Expression::Literal(self.table.get_column_value(row_data, column_name)?);
+                Ok(())
+            },
+            Expression::Subquery(subquery) | Expression::Exists(subquery) => self.substitute_select(subquery),
+            Expression::InSubquery { expression, subquery, .. } => {
+                self.substitute_expression(expression)?;
+                self.substitute_select(subquery)
+            },
+            _ => expression
+                .get_children_mut()
+                .into_iter()
+                .try_for_each(|child| self.substitute_expression(child)),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::io;
+use std::iter;
+use crate::statement::create_view_statement::CreateViewStatement;
+use crate::statement::select_statement::SelectStatement;
+
+/// The method replaces the references to the views by the subqueries with their SELECTs,
+/// the tables of WITH hide the views with the same names
+pub fn expand_views(views: &[CreateViewStatement], select_statement: &mut SelectStatement) -> io::Result<()> {
+    expand_select(views, select_statement, &[], &mut Vec::new())
+}
+
+/// The names of the views being expanded are kept to find the views which read themselves
+fn expand_select(
+    views: &[CreateViewStatement],
+    select_statement: &mut SelectStatement,
+    cte_names: &[String],
+    expanding: &mut Vec<String>,
+) -> io::Result<()> {
+    let mut cte_names = cte_names.to_vec();
+
+    if let Some(with_clause) = &mut select_statement.with_clause {
+        // The recursive tables could read themselves and the tables after them
+        if with_clause.recursive {
+            cte_names.extend(with_clause.tables.iter().map(|cte| cte.table_name.clone()));
+        }
+
+        for cte in with_clause.tables.iter_mut() {
+            expand_select(views, &mut cte.select_statement, &cte_names, expanding)?;
+
+            if !with_clause.recursive {
+                cte_names.push(cte.table_name.clone());
+            }
+        }
+    }
+
+    let table_references = iter::once(&mut select_statement.from).chain(select_statement.joins.iter_mut().map(|join| &mut join.table));
+
+    for table_reference in table_references {
+        if let Some(subquery) = &mut table_reference.subquery {
+            expand_select(views, subquery, &cte_names, expanding)?;
+            continue;
+        }
+
+        if cte_names.contains(&table_reference.table_name) {
+            continue;
+        }
+
+        let Some(view) = views.iter().find(|view| view.view_name == table_reference.table_name) else {
+            continue;
+        };
+
+        if expanding.contains(&view.view_name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The view {} is circularly defined", view.view_name),
+            ));
+        }
+
+        // The SELECT of the view doesn't see the tables of WITH of the query
+        let mut subquery = view.get_select_statement();
+
+        expanding.push(view.view_name.clone());
+        expand_select(views, &mut subquery, &[], expanding)?;
+        expanding.pop();
+
+        // The columns are still qualified by the name of the view
+        table_reference.subquery = Some(Box::new(subquery));
+    }
+
+    for (_, compound_select) in select_statement.compound.iter_mut() {
+        expand_select(views, compound_select, &cte_names, expanding)?;
+    }
+
+    for expression in select_statement.get_expressions_mut() {
+        for subquery in expression.get_subqueries_mut() {
+            expand_select(views, subquery, &cte_names, expanding)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,461 @@
+pub mod compiler;
+pub mod instruction;
+
+use std::collections::VecDeque;
+use std::io;
+use super::cursor::{Cursor, CursorPosition};
+use super::pager::Pager;
+use super::pager::page::row_data::RowData;
+use super::sorter::{SortedRows, Sorter, SORT_MEMORY_LIMIT};
+use super::table::Table;
+use crate::session::Session;
+use crate::statement::expression::{EmptyRow, Expression};
+use crate::value::Value;
+use instruction::Instruction;
+
+/// The compiled statement, it could be run many times
+#[derive(Debug, Clone)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    pub num_registers: usize,
+    pub num_cursors: usize,
+    pub num_row_sets: usize,
+    // The statements changing the tables have no result columns
+    pub column_names: Vec<String>,
+    pub changed_table_name: Option<String>,
+}
+
+impl Program {
+    /// The method lists the instructions with their addresses and operands
+    pub fn explain(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("{:<4}  {:<13}  {:>4}  {:>4}  {:>4}  {}", "addr", "opcode", "p1", "p2", "p3", "p4"),
+            format!("{:-<4}  {:-<13}  {:->4}  {:->4}  {:->4}  {:-<4}", "", "", "", "", "", ""),
+        ];
+
+        for (address, instruction) in self.instructions.iter().enumerate() {
+            let (name, p1, p2, p3, p4) = instruction.get_operands();
+            let line = format!("{:<4}  {:<13}  {:>4}  {:>4}  {:>4}  {}", address, name, p1, p2, p3, p4);
+
+            lines.push(line.trim_end().to_string());
+        }
+
+        lines
+    }
+}
+
+/// The cursor keeps its current row, the rows of the table are found again by their ids,
+/// so the pager isn't borrowed between the instructions and the rows could be changed
+enum VmCursor {
+    Table {
+        table_id: u32,
+        row: Option<RowData>,
+    },
+    // The rows found in the index are read by their ids in the index order
+    Index {
+        table_id: u32,
+        row_ids: Vec<i64>,
+        position: usize,
+        row: Option<RowData>,
+    },
+    // The rows are pushed to the sorter until it's sorted, then they are read from the sorted rows
+    Sorter {
+        sorter: Option<Sorter>,
+        sorted_rows: Option<SortedRows>,
+        row: Option<RowData>,
+    },
+}
+
+impl VmCursor {
+    fn get_row(&self) -> Option<&RowData> {
+        match self {
+            VmCursor::Table { row, .. } | VmCursor::Index { row, .. } | VmCursor::Sorter { row, .. } => row.as_ref(),
+        }
+    }
+
+    fn get_table_id(&self) -> io::Result<u32> {
+        match self {
+            VmCursor::Table { table_id, .. } | VmCursor::Index { table_id, .. } => Ok(*table_id),
+            VmCursor::Sorter { .. } => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The sorter cursor doesn't belong to a table",
+            )),
+        }
+    }
+}
+
+/// The interpreter of the program, the program is run until the next result row
+pub struct VirtualMachine<'a> {
+    program: &'a Program,
+    pager: &'a mut Pager,
+    tables: &'a mut [Table],
+    session: &'a mut Session,
+    address: usize,
+    registers: Vec<Value>,
+    cursors: Vec<Option<VmCursor>>,
+    row_sets: Vec<VecDeque<i64>>,
+    num_changes: usize,
+    is_halted: bool,
+}
+
+impl<'a> VirtualMachine<'a> {
+    pub fn new(program: &'a Program, pager: &'a mut Pager, tables: &'a mut [Table], session: &'a mut Session) -> Self {
+        let mut vm = VirtualMachine {
+            program,
+            pager,
+            tables,
+            session,
+            address: 0,
+            registers: Vec::new(),
+            cursors: Vec::new(),
+            row_sets: Vec::new(),
+            num_changes: 0,
+            is_halted: false,
+        };
+        vm.reset();
+
+        vm
+    }
+
+    /// The method prepares the program to be run from the start again
+    pub fn reset(&mut self) {
+        self.address = 0;
+        self.registers = vec![Value::Null; self.program.num_registers];
+        self.cursors = (0..self.program.num_cursors).map(|_| None).collect();
+        self.row_sets = vec![VecDeque::new(); self.program.num_row_sets];
+        self.num_changes = 0;
+        self.is_halted = false;
+    }
+
+    /// The number of the rows inserted or deleted since the program was started
+    pub fn get_num_changes(&self) -> usize {
+        self.num_changes
+    }
+
+    /// The method runs the program until the next result row, there is no row once the program halts.
+    /// The program halts on the error as well
+    pub fn step(&mut self) -> io::Result<Option<RowData>> {
+        if self.is_halted {
+            return Ok(None);
+        }
+
+        let result = self.run();
+
+        if !matches!(result, Ok(Some(_))) {
+            self.is_halted = true;
+            // The temporary files of the sorters are removed with the cursors
+            self.cursors.iter_mut().for_each(|cursor| *cursor = None);
+        }
+
+        result
+    }
+
+    fn find_table(tables: &mut [Table], table_id: u32) -> io::Result<&mut Table> {
+        tables
+            .iter_mut()
+            .find(|table| table.get_id() == table_id)
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound,
+                "The table was dropped since the statement was prepared",
+            ))
+    }
+
+    fn get_cursor(&mut self, cursor_num: usize) -> io::Result<&mut VmCursor> {
+        self.cursors[cursor_num].as_mut().ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("The cursor {} is not open", cursor_num),
+        ))
+    }
+
+    /// The method skips the ids of the rows which don't exist anymore
+    fn read_index_row(pager: &Pager, table_id: u32, row_ids: &[i64], position: &mut usize) -> Option<RowData> {
+        while let Some(&row_id) = row_ids.get(*position) {
+            if let Some(row_data) = pager.get_row_by_id(table_id, row_id) {
+                return Some(row_data);
+            }
+
+            *position += 1;
+        }
+
+        None
+    }
+
+    /// The method moves the cursor to the first or the last row, returns whether there is one
+    fn rewind(&mut self, cursor_num: usize, to_last: bool) -> io::Result<bool> {
+        let pager: &Pager = self.pager;
+
+        match self.cursors[cursor_num].as_mut() {
+            Some(VmCursor::Table { table_id, row }) => {
+                let cursor_position = if to_last { CursorPosition::End } else { CursorPosition::Start };
+                *row = Cursor::new(pager, *table_id, cursor_position)?.get_row();
+
+                Ok(row.is_some())
+            },
+            Some(VmCursor::Index { table_id, row_ids, position, row }) if !to_last => {
+                *position = 0;
+                *row = Self::read_index_row(pager, *table_id, row_ids, position);
+
+                Ok(row.is_some())
+            },
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The cursor {} can't be rewound", cursor_num),
+            )),
+        }
+    }
+
+    /// The method moves the cursor to the next or the previous row, returns whether there is one
+    fn advance(&mut self, cursor_num: usize, backward: bool) -> io::Result<bool> {
+        let pager: &Pager = self.pager;
+
+        match self.cursors[cursor_num].as_mut() {
+            Some(VmCursor::Table { table_id, row }) => {
+                let mut cursor = row.as_ref().and_then(|row_data| Cursor::seek(pager, *table_id, row_data.id));
+
+                if let Some(cursor) = cursor.as_mut() {
+                    match backward {
+                        true => cursor.cursor_retreat()?,
+                        false => cursor.cursor_advance()?,
+                    }
+                }
+
+                *row = cursor.and_then(|cursor| cursor.get_row());
+
+                Ok(row.is_some())
+            },
+            Some(VmCursor::Index { table_id, row_ids, position, row }) if !backward => {
+                *position += 1;
+                *row = Self::read_index_row(pager, *table_id, row_ids, position);
+
+                Ok(row.is_some())
+            },
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The cursor {} can't be moved", cursor_num),
+            )),
+        }
+    }
+
+    fn get_sorted_rows(&mut self, cursor_num: usize) -> io::Result<(&mut SortedRows, &mut Option<RowData>)> {
+        match self.get_cursor(cursor_num)? {
+            VmCursor::Sorter { sorted_rows: Some(sorted_rows), row, .. } => Ok((sorted_rows, row)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The cursor {} is not a sorted sorter", cursor_num),
+            )),
+        }
+    }
+
+    fn get_count(&self, register: usize) -> i64 {
+        match self.registers[register] {
+            Value::Integer(count) => count,
+            _ => 0,
+        }
+    }
+
+    fn run(&mut self) -> io::Result<Option<RowData>> {
+        let program = self.program;
+
+        loop {
+            let instruction = &program.instructions[self.address];
+            self.address += 1;
+
+            match instruction {
+                Instruction::Goto { target } => self.address = *target,
+                Instruction::Halt => return Ok(None),
+                Instruction::OpenRead { cursor, table_id, .. } | Instruction::OpenWrite { cursor, table_id, .. } => {
+                    Self::find_table(self.tables, *table_id)?;
+                    self.cursors[*cursor] = Some(VmCursor::Table { table_id: *table_id, row: None });
+                },
+                Instruction::OpenIndex { cursor, table_id, index_name, index_range, reverse } => {
+                    let index = Self::find_table(self.tables, *table_id)?
+                        .find_index(index_name)
+                        .ok_or_else(|| io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("The index {} was dropped since the statement was prepared", index_name),
+                        ))?;
+
+                    let mut row_ids = index.scan(index_range);
+
+                    if *reverse {
+                        row_ids.reverse();
+                    }
+
+                    self.cursors[*cursor] = Some(VmCursor::Index { table_id: *table_id, row_ids, position: 0, row: None });
+                },
+                Instruction::SorterOpen { cursor, sort_orders } => {
+                    self.cursors[*cursor] = Some(VmCursor::Sorter {
+                        sorter: Some(Sorter::new(sort_orders.clone(), SORT_MEMORY_LIMIT)),
+                        sorted_rows: None,
+                        row: None,
+                    });
+                },
+                Instruction::Rewind { cursor, target } | Instruction::Last { cursor, target } => {
+                    if !self.rewind(*cursor, matches!(instruction, Instruction::Last { .. }))? {
+                        self.address = *target;
+                    }
+                },
+                Instruction::Next { cursor, target } | Instruction::Prev { cursor, target } => {
+                    if self.advance(*cursor, matches!(instruction, Instruction::Prev { .. }))? {
+                        self.address = *target;
+                    }
+                },
+                Instruction::SeekRowId { cursor, register, target } => {
+                    let row_id = match self.registers[*register] {
+                        Value::Integer(row_id) => Some(row_id),
+                        _ => None,
+                    };
+
+                    let table_id = self.get_cursor(*cursor)?.get_table_id()?;
+                    let row_data = row_id.and_then(|row_id| self.pager.get_row_by_id(table_id, row_id));
+                    let is_found = row_data.is_some();
+
+                    self.cursors[*cursor] = Some(VmCursor::Table { table_id, row: row_data });
+
+                    if !is_found {
+                        self.address = *target;
+                    }
+                },
+                Instruction::Column { cursor, column, register } => {
+                    let value = self.get_cursor(*cursor)?
+                        .get_row()
+                        .and_then(|row_data| row_data.values.get(*column).cloned())
+                        .unwrap_or(Value::Null);
+
+                    self.registers[*register] = value;
+                },
+                Instruction::RowId { cursor, register } => {
+                    let value = self.get_cursor(*cursor)?
+                        .get_row()
+                        .map_or(Value::Null, |row_data| Value::Integer(row_data.id));
+
+                    self.registers[*register] = value;
+                },
+                Instruction::Integer { value, register } => self.registers[*register] = Value::Integer(*value),
+                Instruction::Real { value, register } => self.registers[*register] = Value::Real(*value),
+                Instruction::String { value, register } => self.registers[*register] = Value::Text(value.clone()),
+                Instruction::Null { register } => self.registers[*register] = Value::Null,
+                Instruction::Unary { operator, operand, register } => {
+                    self.registers[*register] = Expression::evaluate_unary(*operator, self.registers[*operand].clone())?;
+                },
+                Instruction::Binary { operator, left, right, register } => {
+                    let left_value = self.registers[*left].clone();
+                    let right_value = self.registers[*right].clone();
+
+                    self.registers[*register] = Expression::evaluate_binary(left_value, *operator, right_value)?;
+                },
+                Instruction::Function { name, first_argument, num_arguments, register } => {
+                    let function = Expression::Function {
+                        name: name.clone(),
+                        arguments: self.registers[*first_argument..*first_argument + *num_arguments]
+                            .iter()
+                            .cloned()
+                            .map(Expression::Literal)
+                            .collect(),
+                    };
+
+                    self.registers[*register] = function.evaluate(&EmptyRow { session: self.session })?;
+                },
+                Instruction::Cast { column_type, register } => {
+                    let value = std::mem::replace(&mut self.registers[*register], Value::Null);
+                    self.registers[*register] = Expression::cast(value, *column_type);
+                },
+                Instruction::IfNot { register, target } => {
+                    if !self.registers[*register].is_true() {
+                        self.address = *target;
+                    }
+                },
+                Instruction::IfPos { register, target } => {
+                    let count = self.get_count(*register);
+
+                    if count > 0 {
+                        self.registers[*register] = Value::Integer(count - 1);
+                        self.address = *target;
+                    }
+                },
+                Instruction::DecrJumpZero { register, target } => {
+                    let count = self.get_count(*register) - 1;
+                    self.registers[*register] = Value::Integer(count);
+
+                    if count == 0 {
+                        self.address = *target;
+                    }
+                },
+                Instruction::ResultRow { first, num_values } => {
+                    let row_id = self.get_count(*first);
+                    let values = self.registers[*first + 1..*first + 1 + *num_values].to_vec();
+
+                    return Ok(Some(RowData::new(row_id, values)));
+                },
+                Instruction::SorterInsert { cursor, first, num_values, num_keys } => {
+                    let row_id = self.get_count(*first);
+                    let values = self.registers[*first + 1..*first + 1 + *num_values].to_vec();
+                    let keys = self.registers[*first + 1 + *num_values..*first + 1 + *num_values + *num_keys].to_vec();
+
+                    match self.get_cursor(*cursor)? {
+                        VmCursor::Sorter { sorter: Some(sorter), .. } => sorter.push(keys, RowData::new(row_id, values))?,
+                        _ => return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("The cursor {} is not an open sorter", cursor),
+                        )),
+                    }
+                },
+                Instruction::SorterSort { cursor, target } => {
+                    if let VmCursor::Sorter { sorter, sorted_rows, .. } = self.get_cursor(*cursor)? {
+                        if let Some(sorter) = sorter.take() {
+                            *sorted_rows = Some(sorter.finish()?);
+                        }
+                    }
+
+                    let (sorted_rows, row) = self.get_sorted_rows(*cursor)?;
+                    *row = sorted_rows.next().transpose()?;
+
+                    if row.is_none() {
+                        self.address = *target;
+                    }
+                },
+                Instruction::SorterNext { cursor, target } => {
+                    let (sorted_rows, row) = self.get_sorted_rows(*cursor)?;
+                    *row = sorted_rows.next().transpose()?;
+
+                    if row.is_some() {
+                        self.address = *target;
+                    }
+                },
+                Instruction::RowSetAdd { row_set, register } => {
+                    let row_id = self.get_count(*register);
+                    self.row_sets[*row_set].push_back(row_id);
+                },
+                Instruction::RowSetRead { row_set, register, target } => {
+                    match self.row_sets[*row_set].pop_front() {
+                        Some(row_id) => self.registers[*register] = Value::Integer(row_id),
+                        None => self.address = *target,
+                    }
+                },
+                Instruction::Insert { cursor, first, num_values } => {
+                    let table_id = self.get_cursor(*cursor)?.get_table_id()?;
+                    let row_id = self.registers[*first].clone();
+                    let values = self.registers[*first + 1..*first + 1 + *num_values].to_vec();
+
+                    let table = Self::find_table(self.tables, table_id)?;
+                    self.session.last_insert_rowid = table.insert_row(self.pager, self.session, row_id, values)?;
+                    self.num_changes += 1;
+                },
+                Instruction::Delete { cursor } => {
+                    let (table_id, row_data) = match self.get_cursor(*cursor)? {
+                        VmCursor::Table { table_id, row } => (*table_id, row.take()),
+                        _ => (0, None),
+                    };
+
+                    let row_data = row_data.ok_or_else(|| io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("The cursor {} has no row to delete", cursor),
+                    ))?;
+
+                    Self::find_table(self.tables, table_id)?.delete_row(self.pager, &row_data)?;
+                    self.num_changes += 1;
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,583 @@
+use std::io;
+use super::Program;
+use super::instruction::Instruction;
+use crate::backend::operator::{find_column_source, Source};
+use crate::backend::pager::Pager;
+use crate::backend::query::{evaluate_limit, find_table, get_result_columns, get_sources, resolve_order_by};
+use crate::backend::sorter::SortOrder;
+use crate::backend::table::{ScanPlan, Table};
+use crate::session::Session;
+use crate::statement::Statement;
+use crate::statement::delete_statement::DeleteStatement;
+use crate::statement::expression::{BinaryOperator, Expression};
+use crate::statement::insert_statement::{ConflictResolution, InsertSource, InsertStatement};
+use crate::statement::select_statement::SelectStatement;
+use crate::value::Value;
+
+/// The statements the compiler doesn't support are run by the operators and the tables
+fn unsupported_error(description: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("The bytecode compiler doesn't support {}", description),
+    )
+}
+
+/// The loop over the rows of the cursor, the jumps are set when the end of the loop is emitted
+struct ScanLoop {
+    cursor: usize,
+    // The address of the first instruction of the loop body
+    body: usize,
+    // There is no step for the row found by its id
+    step: Option<Instruction>,
+    // The jumps to the next row
+    continue_jumps: Vec<usize>,
+    // The jumps to the end of the loop
+    exit_jumps: Vec<usize>,
+}
+
+/// The values of the result row: the expressions on the current row of the table
+/// or the values of the sorted row
+enum ResultRowColumns<'e> {
+    Expressions(&'e [Expression]),
+    Sorted(usize),
+}
+
+/// The compiler of the statements to the programs of the virtual machine,
+/// the access paths are chosen by the planner of the tables
+pub struct Compiler<'a> {
+    tables: &'a [Table],
+    pager: &'a Pager,
+    session: &'a Session,
+    instructions: Vec<Instruction>,
+    num_registers: usize,
+    num_cursors: usize,
+    num_row_sets: usize,
+    // The columns of the sources are read from their cursors
+    sources: Vec<Source<'a>>,
+    source_cursors: Vec<usize>,
+}
+
+impl<'a> Compiler<'a> {
+    pub fn new(tables: &'a [Table], pager: &'a Pager, session: &'a Session) -> Self {
+        Compiler {
+            tables,
+            pager,
+            session,
+            instructions: Vec::new(),
+            num_registers: 0,
+            num_cursors: 0,
+            num_row_sets: 0,
+            sources: Vec::new(),
+            source_cursors: Vec::new(),
+        }
+    }
+
+    /// INSERT, DELETE and SELECT from one table without aggregates are compiled,
+    /// the other statements return the Unsupported error
+    pub fn compile(mut self, statement: &'a Statement) -> io::Result<Program> {
+        if !statement.get_returning().is_empty() {
+            return Err(unsupported_error("RETURNING"));
+        }
+
+        let (column_names, changed_table_name) = match statement {
+            Statement::Select(select_statement) => (self.compile_select(select_statement)?, None),
+            Statement::Insert(insert_statement) => {
+                self.compile_insert(insert_statement)?;
+                (Vec::new(), Some(insert_statement.table_name.clone()))
+            },
+            Statement::Delete(delete_statement) => {
+                self.compile_delete(delete_statement)?;
+                (Vec::new(), Some(delete_statement.table_name.clone()))
+            },
+            _ => return Err(unsupported_error("this statement")),
+        };
+
+        Ok(Program {
+            instructions: self.instructions,
+            num_registers: self.num_registers,
+            num_cursors: self.num_cursors,
+            num_row_sets: self.num_row_sets,
+            column_names,
+            changed_table_name,
+        })
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.instructions.push(instruction);
+        self.instructions.len() - 1
+    }
+
+    /// The method sets the jumps to the address of the next instruction
+    fn patch_jumps(&mut self, jumps: &[usize]) {
+        let address = self.instructions.len();
+
+        for &jump in jumps.iter() {
+            self.instructions[jump].set_target(address);
+        }
+    }
+
+    fn allocate_registers(&mut self, num_registers: usize) -> usize {
+        self.num_registers += num_registers;
+        self.num_registers - num_registers
+    }
+
+    fn allocate_cursor(&mut self) -> usize {
+        self.num_cursors += 1;
+        self.num_cursors - 1
+    }
+
+    fn add_source(&mut self, source: Source<'a>) -> usize {
+        let cursor = self.allocate_cursor();
+
+        self.sources.push(source);
+        self.source_cursors.push(cursor);
+
+        cursor
+    }
+
+    fn compile_literal(&mut self, value: &Value, register: usize) {
+        self.emit(match value {
+            Value::Null => Instruction::Null { register },
+            Value::Integer(value) => Instruction::Integer { value: *value, register },
+            Value::Real(value) => Instruction::Real { value: *value, register },
+            Value::Text(value) => Instruction::String { value: value.clone(), register },
+        });
+    }
+
+    /// The method emits the instructions which put the value of the expression to the register,
+    /// the operands are put to the new registers
+    fn compile_expression(&mut self, expression: &Expression, register: usize) -> io::Result<()> {
+        match expression {
+            Expression::Literal(value) => self.compile_literal(value, register),
+            Expression::Column { table_name, column_name } => {
+                if self.sources.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("The column {} can't be used in a constant expression", column_name),
+                    ));
+                }
+
+                let source_num = find_column_source(&self.sources, table_name.as_deref(), column_name)?;
+                let cursor = self.source_cursors[source_num];
+
+                match self.sources[source_num].table.find_column(column_name)? {
+                    Some(column) => self.emit(Instruction::Column { cursor, column, register }),
+                    None => self.emit(Instruction::RowId { cursor, register }),
+                };
+            },
+            Expression::Unary(operator, operand) => {
+                self.compile_expression(operand, register)?;
+                self.emit(Instruction::Unary { operator: *operator, operand: register, register });
+            },
+            Expression::Binary(left, operator, right) => {
+                let right_register = self.allocate_registers(1);
+
+                self.compile_expression(left, register)?;
+                self.compile_expression(right, right_register)?;
+                self.emit(Instruction::Binary { operator: *operator, left: register, right: right_register, register });
+            },
+            Expression::Function { name, arguments } => {
+                let first_argument = self.allocate_registers(arguments.len());
+
+                for (argument_num, argument) in arguments.iter().enumerate() {
+                    self.compile_expression(argument, first_argument + argument_num)?;
+                }
+
+                self.emit(Instruction::Function {
+                    name: name.clone(),
+                    first_argument,
+                    num_arguments: arguments.len(),
+                    register,
+                });
+            },
+            Expression::Aggregate { .. } => return Err(unsupported_error("aggregate functions")),
+            Expression::Case { operand, when_clauses, else_clause } => {
+                let operand_register = match operand {
+                    Some(operand) => {
+                        let operand_register = self.allocate_registers(1);
+                        self.compile_expression(operand, operand_register)?;
+
+                        Some(operand_register)
+                    },
+                    None => None,
+                };
+
+                let mut end_jumps = Vec::new();
+
+                for (condition, result) in when_clauses.iter() {
+                    let condition_register = self.allocate_registers(1);
+                    self.compile_expression(condition, condition_register)?;
+
+                    // The comparison with NULL is NULL, so the NULL operand matches nothing
+                    if let Some(operand_register) = operand_register {
+                        self.emit(Instruction::Binary {
+                            operator: BinaryOperator::Equal,
+                            left: operand_register,
+                            right: condition_register,
+                            register: condition_register,
+                        });
+                    }
+
+                    let next_jump = self.emit(Instruction::IfNot { register: condition_register, target: 0 });
+
+                    self.compile_expression(result, register)?;
+                    end_jumps.push(self.emit(Instruction::Goto { target: 0 }));
+                    self.patch_jumps(&[next_jump]);
+                }
+
+                match else_clause {
+                    Some(else_clause) => self.compile_expression(else_clause, register)?,
+                    None => self.compile_literal(&Value::Null, register),
+                }
+
+                self.patch_jumps(&end_jumps);
+            },
+            Expression::Cast { expression, column_type } => {
+                self.compile_expression(expression, register)?;
+                self.emit(Instruction::Cast { column_type: *column_type, register });
+            },
+            Expression::Subquery(_) | Expression::Exists(_) | Expression::InSubquery { .. } => return Err(unsupported_error("subqueries")),
+            Expression::InList { .. } => return Err(unsupported_error("IN lists")),
+            Expression::Window { .. } => return Err(unsupported_error("window functions")),
+        }
+
+        Ok(())
+    }
+
+    /// The method opens the cursor for the scan and emits the start of the loop over its rows
+    fn begin_scan(&mut self, cursor: usize, table: &Table, scan_plan: ScanPlan, reverse: bool) -> ScanLoop {
+        let table_id = table.get_id();
+        let table_name = table.get_name().to_string();
+
+        let (step, exit_jump) = match scan_plan {
+            ScanPlan::RowId(row_id) => {
+                let register = self.allocate_registers(1);
+
+                self.emit(Instruction::OpenRead { cursor, table_id, table_name });
+                self.emit(Instruction::Integer { value: row_id, register });
+
+                (None, self.emit(Instruction::SeekRowId { cursor, register, target: 0 }))
+            },
+            ScanPlan::Index(index, index_range) => {
+                self.emit(Instruction::OpenIndex {
+                    cursor,
+                    table_id,
+                    index_name: index.get_name().to_string(),
+                    index_range,
+                    reverse,
+                });
+
+                (Some(Instruction::Next { cursor, target: 0 }), self.emit(Instruction::Rewind { cursor, target: 0 }))
+            },
+            ScanPlan::Table if reverse => {
+                self.emit(Instruction::OpenRead { cursor, table_id, table_name });
+
+                (Some(Instruction::Prev { cursor, target: 0 }), self.emit(Instruction::Last { cursor, target: 0 }))
+            },
+            ScanPlan::Table => {
+                self.emit(Instruction::OpenRead { cursor, table_id, table_name });
+
+                (Some(Instruction::Next { cursor, target: 0 }), self.emit(Instruction::Rewind { cursor, target: 0 }))
+            },
+        };
+
+        ScanLoop {
+            cursor,
+            body: self.instructions.len(),
+            step,
+            continue_jumps: Vec::new(),
+            exit_jumps: vec![exit_jump],
+        }
+    }
+
+    /// The rows which don't match the condition are skipped
+    fn compile_condition(&mut self, condition: &Option<Expression>, scan_loop: &mut ScanLoop) -> io::Result<()> {
+        if let Some(condition) = condition {
+            let register = self.allocate_registers(1);
+
+            self.compile_expression(condition, register)?;
+            scan_loop.continue_jumps.push(self.emit(Instruction::IfNot { register, target: 0 }));
+        }
+
+        Ok(())
+    }
+
+    /// The method emits the step to the next row and the end of the loop
+    fn end_scan(&mut self, scan_loop: ScanLoop) {
+        self.patch_jumps(&scan_loop.continue_jumps);
+
+        if let Some(mut step) = scan_loop.step {
+            step.set_target(scan_loop.body);
+            self.emit(step);
+        }
+
+        self.patch_jumps(&scan_loop.exit_jumps);
+    }
+
+    /// The method emits the result row, the rows are skipped until the offset is reached
+    /// and the program is stopped when the limit is reached
+    fn compile_result_row(
+        &mut self,
+        columns: ResultRowColumns,
+        limits: (Option<usize>, Option<usize>),
+        scan_loop: &mut ScanLoop,
+        halt_jumps: &mut Vec<usize>,
+    ) -> io::Result<()> {
+        let (limit_register, offset_register) = limits;
+
+        if let Some(register) = offset_register {
+            scan_loop.continue_jumps.push(self.emit(Instruction::IfPos { register, target: 0 }));
+        }
+
+        let num_values = match columns {
+            ResultRowColumns::Expressions(expressions) => expressions.len(),
+            ResultRowColumns::Sorted(num_values) => num_values,
+        };
+
+        let first = self.allocate_registers(1 + num_values);
+        self.emit(Instruction::RowId { cursor: scan_loop.cursor, register: first });
+
+        match columns {
+            ResultRowColumns::Expressions(expressions) => {
+                for (column_num, expression) in expressions.iter().enumerate() {
+                    self.compile_expression(expression, first + 1 + column_num)?;
+                }
+            },
+            ResultRowColumns::Sorted(num_values) => {
+                for column in 0..num_values {
+                    self.emit(Instruction::Column { cursor: scan_loop.cursor, column, register: first + 1 + column });
+                }
+            },
+        }
+
+        self.emit(Instruction::ResultRow { first, num_values });
+
+        if let Some(register) = limit_register {
+            halt_jumps.push(self.emit(Instruction::DecrJumpZero { register, target: 0 }));
+        }
+
+        Ok(())
+    }
+
+    /// SELECT from one table is compiled to the loop over its rows, the rows are put to the sorter
+    /// unless they are read in the required order
+    fn compile_select(&mut self, select_statement: &'a SelectStatement) -> io::Result<Vec<String>> {
+        if !select_statement.joins.is_empty() {
+            return Err(unsupported_error("joins"));
+        }
+
+        if select_statement.from.subquery.is_some() {
+            return Err(unsupported_error("subqueries in FROM"));
+        }
+
+        if select_statement.from.is_no_table() {
+            return Err(unsupported_error("SELECT without FROM"));
+        }
+
+        if select_statement.with_clause.is_some() {
+            return Err(unsupported_error("common table expressions"));
+        }
+
+        if !select_statement.compound.is_empty() {
+            return Err(unsupported_error("compound queries"));
+        }
+
+        if select_statement.is_aggregate()? {
+            return Err(unsupported_error("aggregate queries"));
+        }
+
+        let limit = evaluate_limit(self.session, &select_statement.limit)?;
+        let offset = evaluate_limit(self.session, &select_statement.offset)?;
+
+        let sources = get_sources(self.tables, &[], None, self.pager, select_statement)?;
+        let result_columns = get_result_columns(&sources, &select_statement.columns)?;
+        let order_by = resolve_order_by(&select_statement.order_by, &result_columns)?;
+
+        let table = sources[0].table;
+        let cursor = self.add_source(sources.into_iter().next().unwrap());
+
+        let access_path = table.plan_scan(self.pager, &select_statement.where_clause, &order_by);
+        let is_sorted = order_by.is_empty() || access_path.order.is_some();
+
+        let mut halt_jumps = Vec::new();
+
+        let limit_register = match limit {
+            Some(limit) => {
+                let register = self.allocate_registers(1);
+
+                self.emit(Instruction::Integer { value: limit as i64, register });
+                halt_jumps.push(self.emit(Instruction::IfNot { register, target: 0 }));
+
+                Some(register)
+            },
+            None => None,
+        };
+
+        let offset_register = match offset.filter(|&offset| offset > 0) {
+            Some(offset) => {
+                let register = self.allocate_registers(1);
+                self.emit(Instruction::Integer { value: offset as i64, register });
+
+                Some(register)
+            },
+            None => None,
+        };
+
+        let sorter_cursor = match is_sorted {
+            true => None,
+            false => {
+                let sorter_cursor = self.allocate_cursor();
+                let sort_orders = order_by
+                    .iter()
+                    .map(|ordering_term| SortOrder {
+                        descending: ordering_term.descending,
+                        nulls_first: ordering_term.is_nulls_first(),
+                    })
+                    .collect();
+
+                self.emit(Instruction::SorterOpen { cursor: sorter_cursor, sort_orders });
+
+                Some(sorter_cursor)
+            },
+        };
+
+        let expressions: Vec<Expression> = result_columns.iter().map(|(_, expression)| expression.clone()).collect();
+        let mut scan_loop = self.begin_scan(cursor, table, access_path.scan_plan, access_path.order.unwrap_or(false));
+
+        self.compile_condition(&select_statement.where_clause, &mut scan_loop)?;
+
+        match sorter_cursor {
+            None => {
+                let columns = ResultRowColumns::Expressions(&expressions);
+                self.compile_result_row(columns, (limit_register, offset_register), &mut scan_loop, &mut halt_jumps)?;
+                self.end_scan(scan_loop);
+            },
+            Some(sorter_cursor) => {
+                // The row id and the values of the result columns are followed by the sort keys
+                let first = self.allocate_registers(1 + expressions.len() + order_by.len());
+                self.emit(Instruction::RowId { cursor, register: first });
+
+                for (expression_num, expression) in expressions.iter().chain(order_by.iter().map(|term| &term.expression)).enumerate() {
+                    self.compile_expression(expression, first + 1 + expression_num)?;
+                }
+
+                self.emit(Instruction::SorterInsert {
+                    cursor: sorter_cursor,
+                    first,
+                    num_values: expressions.len(),
+                    num_keys: order_by.len(),
+                });
+                self.end_scan(scan_loop);
+
+                let exit_jump = self.emit(Instruction::SorterSort { cursor: sorter_cursor, target: 0 });
+                let mut sorted_loop = ScanLoop {
+                    cursor: sorter_cursor,
+                    body: self.instructions.len(),
+                    step: Some(Instruction::SorterNext { cursor: sorter_cursor, target: 0 }),
+                    continue_jumps: Vec::new(),
+                    exit_jumps: vec![exit_jump],
+                };
+
+                let columns = ResultRowColumns::Sorted(expressions.len());
+                self.compile_result_row(columns, (limit_register, offset_register), &mut sorted_loop, &mut halt_jumps)?;
+                self.end_scan(sorted_loop);
+            },
+        }
+
+        self.patch_jumps(&halt_jumps);
+        self.emit(Instruction::Halt);
+
+        Ok(result_columns.into_iter().map(|(column_name, _)| column_name).collect())
+    }
+
+    /// The values are put to the registers in the order of the table columns,
+    /// the missing columns get their defaults. Only the single row is compiled
+    fn compile_insert(&mut self, insert_statement: &InsertStatement) -> io::Result<()> {
+        if insert_statement.conflict_resolution != ConflictResolution::Abort || !insert_statement.upserts.is_empty() {
+            return Err(unsupported_error("INSERT with the conflict resolution"));
+        }
+
+        let table = find_table(self.tables, &insert_statement.table_name)?;
+        let num_columns = table.get_definition().columns.len();
+
+        let values: &[Expression] = match &insert_statement.source {
+            InsertSource::Values(rows) if rows.len() == 1 => &rows[0],
+            InsertSource::DefaultValues => &[],
+            _ => return Err(unsupported_error("INSERT of several rows or of the rows of SELECT")),
+        };
+
+        if !values.is_empty() {
+            table.check_num_values(&insert_statement.columns, values.len())?;
+        }
+
+        let positions = match &insert_statement.columns {
+            Some(column_names) => table.get_insert_positions(column_names)?,
+            None => (0..num_columns).map(Some).collect(),
+        };
+
+        let cursor = self.allocate_cursor();
+        self.emit(Instruction::OpenWrite { cursor, table_id: table.get_id(), table_name: table.get_name().to_string() });
+
+        // The row id is generated if it's NULL
+        let first = self.allocate_registers(1 + num_columns);
+        self.emit(Instruction::Null { register: first });
+
+        let mut is_set = vec![false; num_columns];
+
+        for (expression, position) in values.iter().zip(positions) {
+            match position {
+                Some(column_idx) => {
+                    self.compile_expression(expression, first + 1 + column_idx)?;
+                    is_set[column_idx] = true;
+                },
+                None => self.compile_expression(expression, first)?,
+            }
+        }
+
+        for (column_idx, column) in table.get_definition().columns.iter().enumerate() {
+            if is_set[column_idx] {
+                continue;
+            }
+
+            match &column.default {
+                Some(default) => self.compile_expression(default, first + 1 + column_idx)?,
+                None => self.compile_literal(&Value::Null, first + 1 + column_idx),
+            }
+        }
+
+        self.emit(Instruction::Insert { cursor, first, num_values: num_columns });
+        self.emit(Instruction::Halt);
+
+        Ok(())
+    }
+
+    /// The ids of the matching rows are collected first, so the scan isn't affected by the deletes
+    fn compile_delete(&mut self, delete_statement: &DeleteStatement) -> io::Result<()> {
+        let table = find_table(self.tables, &delete_statement.table_name)?;
+        let cursor = self.add_source(Source { table, pager: self.pager, name: table.get_name(), merged_columns: Vec::new() });
+
+        let row_set = self.num_row_sets;
+        self.num_row_sets += 1;
+
+        let row_id_register = self.allocate_registers(1);
+        let access_path = table.plan_scan(self.pager, &delete_statement.where_clause, &[]);
+        let mut scan_loop = self.begin_scan(cursor, table, access_path.scan_plan, false);
+
+        self.compile_condition(&delete_statement.where_clause, &mut scan_loop)?;
+        self.emit(Instruction::RowId { cursor, register: row_id_register });
+        self.emit(Instruction::RowSetAdd { row_set, register: row_id_register });
+        self.end_scan(scan_loop);
+
+        let write_cursor = self.allocate_cursor();
+        self.emit(Instruction::OpenWrite { cursor: write_cursor, table_id: table.get_id(), table_name: table.get_name().to_string() });
+
+        let loop_start = self.emit(Instruction::RowSetRead { row_set, register: row_id_register, target: 0 });
+        self.emit(Instruction::SeekRowId { cursor: write_cursor, register: row_id_register, target: loop_start });
+        self.emit(Instruction::Delete { cursor: write_cursor });
+        self.emit(Instruction::Goto { target: loop_start });
+        self.patch_jumps(&[loop_start]);
+        self.emit(Instruction::Halt);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use crate::backend::sorter::SortOrder;
+use crate::backend::table::index::IndexRange;
+use crate::statement::create_table_statement::ColumnType;
+use crate::statement::expression::{BinaryOperator, UnaryOperator};
+
+/// The instruction of the virtual machine. The registers and the cursors are referred to
+/// by their numbers, the jumps are done to the addresses of the instructions
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    Goto { target: usize },
+    Halt,
+    OpenRead { cursor: usize, table_id: u32, table_name: String },
+    OpenWrite { cursor: usize, table_id: u32, table_name: String },
+    // The cursor reads the rows of the table found in the index range in the index order
+    OpenIndex { cursor: usize, table_id: u32, index_name: String, index_range: IndexRange, reverse: bool },
+    SorterOpen { cursor: usize, sort_orders: Vec<SortOrder> },
+    // The cursor is moved to the first or the last row, the jump is done if there are no rows
+    Rewind { cursor: usize, target: usize },
+    Last { cursor: usize, target: usize },
+    // The cursor is moved to the next or the previous row, the jump is done if there is one
+    Next { cursor: usize, target: usize },
+    Prev { cursor: usize, target: usize },
+    // The cursor is moved to the row with the id in the register, the jump is done if there is no such row
+    SeekRowId { cursor: usize, register: usize, target: usize },
+    Column { cursor: usize, column: usize, register: usize },
+    RowId { cursor: usize, register: usize },
+    Integer { value: i64, register: usize },
+    Real { value: f64, register: usize },
+    String { value: String, register: usize },
+    Null { register: usize },
+    Unary { operator: UnaryOperator, operand: usize, register: usize },
+    Binary { operator: BinaryOperator, left: usize, right: usize, register: usize },
+    // The arguments are in the consecutive registers
+    Function { name: String, first_argument: usize, num_arguments: usize, register: usize },
+    Cast { column_type: ColumnType, register: usize },
+    // The jump is done unless the value is true, so NULL jumps as well
+    IfNot { register: usize, target: usize },
+    // The counter is decremented and the jump is done if it was positive
+    IfPos { register: usize, target: usize },
+    // The counter is decremented and the jump is done if it reaches zero
+    DecrJumpZero { register: usize, target: usize },
+    // The row id is in the first register, the values are in the next ones
+    ResultRow { first: usize, num_values: usize },
+    // The row id and the values are followed by the sort keys
+    SorterInsert { cursor: usize, first: usize, num_values: usize, num_keys: usize },
+    // The rows are sorted and the cursor is moved to the first one, the jump is done if there are no rows
+    SorterSort { cursor: usize, target: usize },
+    SorterNext { cursor: usize, target: usize },
+    RowSetAdd { row_set: usize, register: usize },
+    // The row id is taken from the set, the jump is done if the set is empty
+    RowSetRead { row_set: usize, register: usize, target: usize },
+    // The row id is in the first register, the values are in the next ones
+    Insert { cursor: usize, first: usize, num_values: usize },
+    // The current row of the cursor is deleted
+    Delete { cursor: usize },
+}
+
+impl Instruction {
+    /// The method returns the name of the instruction with its operands: three numbers and a text,
+    /// the operands which are not used are zeros and an empty text
+    pub fn get_operands(&self) -> (&'static str, i64, i64, i64, String) {
+        let number = |value: usize| value as i64;
+
+        match self {
+            Instruction::Goto { target } => ("Goto", 0, number(*target), 0, String::new()),
+            Instruction::Halt => ("Halt", 0, 0, 0, String::new()),
+            Instruction::OpenRead { cursor, table_id, table_name } => ("OpenRead", number(*cursor), *table_id as i64, 0, table_name.clone()),
+            Instruction::OpenWrite { cursor, table_id, table_name } => ("OpenWrite", number(*cursor), *table_id as i64, 0, table_name.clone()),
+            Instruction::OpenIndex { cursor, table_id, index_name, reverse, .. } => {
+                ("OpenIndex", number(*cursor), *table_id as i64, *reverse as i64, index_name.clone())
+            },
+            Instruction::SorterOpen { cursor, sort_orders } => {
+                let sort_orders: Vec<String> = sort_orders.iter().map(SortOrder::to_string).collect();
+
+                ("SorterOpen", number(*cursor), number(sort_orders.len()), 0, sort_orders.join(", "))
+            },
+            Instruction::Rewind { cursor, target } => ("Rewind", number(*cursor), number(*target), 0, String::new()),
+            Instruction::Last { cursor, target } => ("Last", number(*cursor), number(*target), 0, String::new()),
+            Instruction::Next { cursor, target } => ("Next", number(*cursor), number(*target), 0, String::new()),
+            Instruction::Prev { cursor, target } => ("Prev", number(*cursor), number(*target), 0, String::new()),
+            Instruction::SeekRowId { cursor, register, target } => ("SeekRowId", number(*cursor), number(*target), number(*register), String::new()),
+            Instruction::Column { cursor, column, register } => ("Column", number(*cursor), number(*column), number(*register), String::new()),
+            Instruction::RowId { cursor, register } => ("RowId", number(*cursor), number(*register), 0, String::new()),
+            Instruction::Integer { value, register } => ("Integer", *value, number(*register), 0, String::new()),
+            Instruction::Real { value, register } => ("Real", 0, number(*register), 0, value.to_string()),
+            Instruction::String { value, register } => ("String", number(value.len()), number(*register), 0, value.clone()),
+            Instruction::Null { register } => ("Null", 0, number(*register), 0, String::new()),
+            Instruction::Unary { operator, operand, register } => {
+                let name = match operator {
+                    UnaryOperator::Not => "Not",
+                    UnaryOperator::Minus => "Negative",
+                };
+
+                (name, number(*operand), number(*register), 0, String::new())
+            },
+            Instruction::Binary { operator, left, right, register } => ("Binary", number(*left), number(*right), number(*register), operator.to_string()),
+            Instruction::Function { name, first_argument, num_arguments, register } => {
+                ("Function", number(*first_argument), number(*num_arguments), number(*register), name.clone())
+            },
+            Instruction::Cast { column_type, register } => ("Cast", number(*register), 0, 0, column_type.to_string()),
+            Instruction::IfNot { register, target } => ("IfNot", number(*register), number(*target), 0, String::new()),
+            Instruction::IfPos { register, target } => ("IfPos", number(*register), number(*target), 0, String::new()),
+            Instruction::DecrJumpZero { register, target } => ("DecrJumpZero", number(*register), number(*target), 0, String::new()),
+            Instruction::ResultRow { first, num_values } => ("ResultRow", number(*first), number(*num_values), 0, String::new()),
+            Instruction::SorterInsert { cursor, first, num_values, num_keys } => {
+                ("SorterInsert", number(*cursor), number(*first), number(*num_values), format!("keys={}", num_keys))
+            },
+            Instruction::SorterSort { cursor, target } => ("SorterSort", number(*cursor), number(*target), 0, String::new()),
+            Instruction::SorterNext { cursor, target } => ("SorterNext", number(*cursor), number(*target), 0, String::new()),
+            Instruction::RowSetAdd { row_set, register } => ("RowSetAdd", number(*row_set), number(*register), 0, String::new()),
+            Instruction::RowSetRead { row_set, register, target } => ("RowSetRead", number(*row_set), number(*target), number(*register), String::new()),
+            Instruction::Insert { cursor, first, num_values } => ("Insert", number(*cursor), number(*first), number(*num_values), String::new()),
+            Instruction::Delete { cursor } => ("Delete", number(*cursor), 0, 0, String::new()),
+        }
+    }
+
+    /// The method sets the address of the jump, it's used when the jump is emitted before its target
+    pub fn set_target(&mut self, address: usize) {
+        match self {
+            Instruction::Goto { target }
+            | Instruction::Rewind { target, .. }
+            | Instruction::Last { target, .. }
+            | Instruction::Next { target, .. }
+            | Instruction::Prev { target, .. }
+            | Instruction::SeekRowId { target, .. }
+            | Instruction::IfNot { target, .. }
+            | Instruction::IfPos { target, .. }
+            | Instruction::DecrJumpZero { target, .. }
+            | Instruction::SorterSort { target, .. }
+            | Instruction::SorterNext { target, .. }
+            | Instruction::RowSetRead { target, .. } => *target = address,
+            instruction => unreachable!("The instruction {:?} doesn't jump", instruction),
+        }
+    }
+}
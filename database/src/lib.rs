@@ -2,5 +2,7 @@ mod parser;
 mod enums;
 mod backend;
 mod statement;
+mod value;
+mod session;
 
 pub mod process;
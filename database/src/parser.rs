@@ -1,11 +1,30 @@
+pub mod tokenizer;
+pub mod token_stream;
+
 use std::io::{ self, Write };
 
 use crate::statement::{
     Statement,
     insert_statement::InsertStatement,
     select_statement::SelectStatement,
+    update_statement::UpdateStatement,
+    delete_statement::DeleteStatement,
+    create_table_statement::CreateTableStatement,
+    drop_table_statement::DropTableStatement,
+    alter_table_statement::AlterTableStatement,
+    create_index_statement::CreateIndexStatement,
+    drop_index_statement::DropIndexStatement,
+    create_view_statement::CreateViewStatement,
+    drop_view_statement::DropViewStatement,
+    create_trigger_statement::CreateTriggerStatement,
+    drop_trigger_statement::DropTriggerStatement,
+    analyze_statement::AnalyzeStatement,
+    pragma_statement::PragmaStatement,
+    explain_statement::ExplainStatement,
+    transaction_statement::TransactionStatement,
 };
 use crate::enums::Statements;
+use token_stream::TokenStream;
 
 pub struct ParsedStatement {
     pub statement_type: Statements,
@@ -15,42 +34,81 @@ pub struct ParsedStatement {
 }
 
 impl ParsedStatement {
-    fn prepare_statement(statement_str: &str) -> Option<Statement> {
-        let words: Vec<&str> = statement_str.split_whitespace().collect();
-        let words_count = words.len();
+    /// The method parses the statement, the tokens after it are left in the stream
+    pub fn parse_statement(tokens: &mut TokenStream) -> io::Result<Statement> {
+        let statement = if tokens.is_keyword("insert") || tokens.is_keyword("replace") {
+            Statement::Insert(InsertStatement::new(tokens)?)
+        } else if SelectStatement::is_nth_select(tokens, 0) {
+            Statement::Select(Box::new(SelectStatement::new(tokens)?))
+        } else if tokens.is_keyword("update") {
+            Statement::Update(UpdateStatement::new(tokens)?)
+        } else if tokens.is_keyword("delete") {
+            Statement::Delete(DeleteStatement::new(tokens)?)
+        } else if tokens.is_keyword("create") && tokens.is_nth_keyword(1, "table") {
+            Statement::CreateTable(CreateTableStatement::new(tokens)?)
+        } else if tokens.is_keyword("create") && tokens.is_nth_keyword(1, "view") {
+            Statement::CreateView(Box::new(CreateViewStatement::new(tokens)?))
+        } else if tokens.is_keyword("create") && tokens.is_nth_keyword(1, "trigger") {
+            Statement::CreateTrigger(Box::new(CreateTriggerStatement::new(tokens)?))
+        } else if tokens.is_keyword("create") {
+            Statement::CreateIndex(CreateIndexStatement::new(tokens)?)
+        } else if tokens.is_keyword("drop") && tokens.is_nth_keyword(1, "table") {
+            Statement::DropTable(DropTableStatement::new(tokens)?)
+        } else if tokens.is_keyword("drop") && tokens.is_nth_keyword(1, "view") {
+            Statement::DropView(DropViewStatement::new(tokens)?)
+        } else if tokens.is_keyword("drop") && tokens.is_nth_keyword(1, "trigger") {
+            Statement::DropTrigger(DropTriggerStatement::new(tokens)?)
+        } else if tokens.is_keyword("drop") {
+            Statement::DropIndex(DropIndexStatement::new(tokens)?)
+        } else if tokens.is_keyword("alter") {
+            Statement::AlterTable(AlterTableStatement::new(tokens)?)
+        } else if tokens.is_keyword("analyze") {
+            Statement::Analyze(AnalyzeStatement::new(tokens)?)
+        } else if tokens.is_keyword("pragma") {
+            Statement::Pragma(PragmaStatement::new(tokens)?)
+        } else if tokens.is_keyword("explain") {
+            Statement::Explain(ExplainStatement::new(tokens)?)
+        } else if ["begin", "commit", "end", "rollback", "savepoint", "release"].iter().any(|keyword| tokens.is_keyword(keyword)) {
+            Statement::Transaction(TransactionStatement::new(tokens)?)
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unrecognized statement",
+            ));
+        };
 
-        if words_count >= 3 && words[0] == "insert" {
-            let insert_statement = InsertStatement::new(&words);
-            return Some(Statement::Insert(insert_statement));
-        }
+        Ok(statement)
+    }
 
-        if words_count >= 3 && words[0] == "select" {
-            let select_statement = SelectStatement::new(&words);
-            return Some(Statement::Select(select_statement));
-        }
+    fn prepare_statement(statement_str: &str) -> io::Result<Statement> {
+        let mut tokens = TokenStream::new(statement_str)?;
+        let statement = Self::parse_statement(&mut tokens)?;
 
-        None
+        tokens.expect_end()?;
+
+        Ok(statement)
     }
 
-    pub fn new(buffer: &Box<String>) -> Self {
+    pub fn new(buffer: &str) -> io::Result<Self> {
         if buffer.starts_with('.') {
-            ParsedStatement {
+            Ok(ParsedStatement {
                 statement_type: Statements::MetaCommand,
                 statement: None,
-                meta_command: Some((**buffer).clone()),
-            }
+                meta_command: Some(buffer.to_string()),
+            })
         } else {
-            ParsedStatement {
+            Ok(ParsedStatement {
                 statement_type: Statements::Statement,
-                statement: ParsedStatement::prepare_statement(buffer),
+                statement: Some(ParsedStatement::prepare_statement(buffer)?),
                 meta_command: None,
-            }
+            })
         }
     }
 }
 
 struct InputBuffer {
-    buffer: Option<Box<String>>,
+    // None means the input was closed
+    buffer: Option<String>,
 }
 
 fn print_prompt() {
@@ -61,14 +119,16 @@ fn print_prompt() {
 fn read_input() -> InputBuffer {
     let mut input = String::new();
 
-    io::stdin()
+    let bytes_read = io::stdin()
         .read_line(&mut input)
         .expect("Failed to read line");
 
-    input = input.trim().to_string();
+    if bytes_read == 0 {
+        return InputBuffer { buffer: None };
+    }
 
     InputBuffer {
-        buffer: Some(Box::new(input)),
+        buffer: Some(input.trim().to_string()),
     }
 }
 
@@ -78,27 +138,10 @@ fn get_input() -> InputBuffer {
     read_input()
 }
 
-pub fn parse_with_prompt() -> Option<ParsedStatement> {
+/// The function returns None when there is no more input
+pub fn parse_with_prompt() -> Option<io::Result<ParsedStatement>> {
     let input_buffer = get_input();
 
-    match &input_buffer.buffer {
-        Some(buffer) => {
-            let parsed_statement = ParsedStatement::new(buffer);
-
-            if parsed_statement.statement_type == Statements::Statement {
-                match &parsed_statement.statement {
-                    Some(_) => Some(parsed_statement),
-                    None => None,
-                }
-            } else {
-                Some(parsed_statement)
-            }
-        },
-        None => {
-            println!("Wrong way");
-
-            None
-        }
-    }
+    input_buffer.buffer.map(|buffer| ParsedStatement::new(&buffer))
 }
 
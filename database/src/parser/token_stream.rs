@@ -0,0 +1,172 @@
+use std::io;
+use super::tokenizer::{Token, Tokenizer};
+
+/// Keywords which could not be used as identifiers without quotes
+const RESERVED_KEYWORDS: &[&str] = &[
+    "and", "as", "asc", "between", "by", "case", "cast", "check", "constraint", "create", "cross",
+    "default", "delete", "desc", "distinct", "drop", "else", "end", "except", "exists", "from", "group",
+    "having", "if", "in", "index", "inner", "insert", "intersect", "into", "is", "join", "left", "limit", "not", "null",
+    "offset", "on", "or", "order", "outer", "primary", "returning", "select", "set", "table", "then", "union", "unique",
+    "update", "using", "values", "when", "where", "with",
+];
+
+pub struct TokenStream {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl TokenStream {
+    pub fn new(statement_str: &str) -> io::Result<Self> {
+        Ok(TokenStream {
+            tokens: Tokenizer::new(statement_str).tokenize()?,
+            position: 0,
+        })
+    }
+
+    pub fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    pub fn peek_nth(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.position + offset)
+    }
+
+    pub fn next_token(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+
+        if token.is_some() {
+            self.position += 1;
+        }
+
+        token
+    }
+
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.is_nth_keyword(0, keyword)
+    }
+
+    pub fn is_nth_keyword(&self, offset: usize, keyword: &str) -> bool {
+        matches!(self.peek_nth(offset), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    pub fn consume_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.position += 1;
+            return true;
+        }
+
+        false
+    }
+
+    pub fn expect_keyword(&mut self, keyword: &str) -> io::Result<()> {
+        if self.consume_keyword(keyword) {
+            return Ok(());
+        }
+
+        Err(self.unexpected_token_error(&keyword.to_uppercase()))
+    }
+
+    pub fn consume(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            return true;
+        }
+
+        false
+    }
+
+    pub fn expect(&mut self, expected: &Token) -> io::Result<()> {
+        if self.consume(expected) {
+            return Ok(());
+        }
+
+        Err(self.unexpected_token_error(&expected.to_string()))
+    }
+
+    /// The method reads an identifier. Identifiers are case-insensitive, so they are lowercased.
+    /// The quoted identifier could be a keyword
+    pub fn identifier(&mut self) -> io::Result<String> {
+        match self.peek() {
+            Some(Token::Word(word)) if !Self::is_reserved(word) => {
+                let identifier = word.to_lowercase();
+                self.position += 1;
+
+                Ok(identifier)
+            }
+            Some(Token::QuotedIdentifier(identifier)) => {
+                let identifier = identifier.to_lowercase();
+                self.position += 1;
+
+                Ok(identifier)
+            }
+            _ => Err(self.unexpected_token_error("identifier")),
+        }
+    }
+
+    /// The method checks whether the next token could be read as an identifier
+    pub fn is_identifier(&self) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) => !Self::is_reserved(word),
+            Some(Token::QuotedIdentifier(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// The method reads a comma separated list of identifiers wrapped into parentheses
+    pub fn identifier_list(&mut self) -> io::Result<Vec<String>> {
+        self.expect(&Token::LeftParen)?;
+
+        let mut identifiers = vec![self.identifier()?];
+
+        while self.consume(&Token::Comma) {
+            identifiers.push(self.identifier()?);
+        }
+
+        self.expect(&Token::RightParen)?;
+
+        Ok(identifiers)
+    }
+
+    /// The method checks that the whole statement was parsed, the trailing semicolon is optional
+    pub fn expect_end(&mut self) -> io::Result<()> {
+        self.consume(&Token::Semicolon);
+
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.unexpected_token_error("end of statement")),
+        }
+    }
+
+    pub fn is_reserved(word: &str) -> bool {
+        RESERVED_KEYWORDS.contains(&word.to_lowercase().as_str())
+    }
+
+    /// The method writes the identifier back as SQL, the identifier which isn't
+    /// a plain word or is a keyword is quoted, so it's read back the same way
+    pub fn quote_identifier(identifier: &str) -> String {
+        let mut chars = identifier.chars();
+
+        let is_plain = chars.next().is_some_and(|first| first.is_alphabetic() || first == '_')
+            && chars.all(|cur_char| cur_char.is_alphanumeric() || cur_char == '_')
+            && !Self::is_reserved(identifier);
+
+        match is_plain {
+            true => identifier.to_string(),
+            false => Token::QuotedIdentifier(identifier.to_string()).to_string(),
+        }
+    }
+
+    /// The identifiers are quoted if needed and separated by commas
+    pub fn quote_identifiers(identifiers: &[String]) -> String {
+        identifiers.iter().map(|identifier| Self::quote_identifier(identifier)).collect::<Vec<String>>().join(", ")
+    }
+
+    pub fn unexpected_token_error(&self, expected: &str) -> io::Error {
+        let message = match self.peek() {
+            Some(token) => format!("Syntax error near \"{}\": expected {}", token, expected),
+            None => format!("Syntax error: unexpected end of statement, expected {}", expected),
+        };
+
+        io::Error::new(io::ErrorKind::InvalidInput, message)
+    }
+}
@@ -0,0 +1,244 @@
+use std::fmt;
+use std::io;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    // Keywords and identifiers
+    Word(String),
+    // The identifier in double quotes or backticks, it's never a keyword
+    QuotedIdentifier(String),
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    LeftParen,
+    RightParen,
+    Comma,
+    Semicolon,
+    Asterisk,
+    Dot,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
+    Concat,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::QuotedIdentifier(identifier) => write!(f, "\"{}\"", identifier.replace('"', "\"\"")),
+            Token::Integer(value) => write!(f, "{}", value),
+            Token::Real(value) => write!(f, "{}", value),
+            Token::Text(value) => write!(f, "'{}'", value),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+            Token::Semicolon => write!(f, ";"),
+            Token::Asterisk => write!(f, "*"),
+            Token::Dot => write!(f, "."),
+            Token::Equal => write!(f, "="),
+            Token::NotEqual => write!(f, "!="),
+            Token::Less => write!(f, "<"),
+            Token::LessOrEqual => write!(f, "<="),
+            Token::Greater => write!(f, ">"),
+            Token::GreaterOrEqual => write!(f, ">="),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Slash => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::Concat => write!(f, "||"),
+        }
+    }
+}
+
+pub struct Tokenizer<'input> {
+    chars: Peekable<Chars<'input>>,
+}
+
+impl<'input> Tokenizer<'input> {
+    pub fn new(input: &'input str) -> Self {
+        Tokenizer {
+            chars: input.chars().peekable(),
+        }
+    }
+
+    pub fn tokenize(mut self) -> io::Result<Vec<Token>> {
+        let mut tokens = Vec::new();
+
+        while let Some(&cur_char) = self.chars.peek() {
+            if cur_char.is_whitespace() {
+                self.chars.next();
+                continue;
+            }
+
+            let token = match cur_char {
+                '\'' => Token::Text(self.read_quoted('\'')?),
+                '"' | '`' => Token::QuotedIdentifier(self.read_quoted(cur_char)?),
+                '0'..='9' => self.read_number()?,
+                _ if cur_char.is_alphabetic() || cur_char == '_' => self.read_word(),
+                _ => self.read_symbol()?,
+            };
+
+            tokens.push(token);
+        }
+
+        Ok(tokens)
+    }
+
+    /// The method reads a quoted string, the doubled quote is an escaped quote
+    fn read_quoted(&mut self, quote: char) -> io::Result<String> {
+        // Skip the opening quote
+        self.chars.next();
+
+        let mut result = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some(cur_char) if cur_char == quote => {
+                    if self.chars.peek() == Some(&quote) {
+                        self.chars.next();
+                        result.push(quote);
+                    } else {
+                        return Ok(result);
+                    }
+                }
+                Some(cur_char) => result.push(cur_char),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Unterminated quoted string",
+                    ))
+                }
+            }
+        }
+    }
+
+    fn read_digits(&mut self, number: &mut String) {
+        while let Some(&cur_char) = self.chars.peek() {
+            if !cur_char.is_ascii_digit() {
+                break;
+            }
+
+            number.push(cur_char);
+            self.chars.next();
+        }
+    }
+
+    /// The number is an integer unless it has the fractional part or the exponent
+    fn read_number(&mut self) -> io::Result<Token> {
+        let mut number = String::new();
+        self.read_digits(&mut number);
+
+        let mut is_real = false;
+
+        if self.next_if_eq('.') {
+            is_real = true;
+            number.push('.');
+            self.read_digits(&mut number);
+        }
+
+        if self.chars.peek().is_some_and(|cur_char| cur_char.eq_ignore_ascii_case(&'e')) {
+            is_real = true;
+            number.push('e');
+            self.chars.next();
+
+            if let Some(sign) = self.chars.next_if(|cur_char| *cur_char == '+' || *cur_char == '-') {
+                number.push(sign);
+            }
+
+            self.read_digits(&mut number);
+        }
+
+        if is_real {
+            return number.parse::<f64>().map(Token::Real).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Malformed number {}", number),
+                )
+            });
+        }
+
+        number.parse::<i64>().map(Token::Integer).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The number {} is too big", number),
+            )
+        })
+    }
+
+    fn read_word(&mut self) -> Token {
+        let mut word = String::new();
+
+        while let Some(&cur_char) = self.chars.peek() {
+            if !cur_char.is_alphanumeric() && cur_char != '_' {
+                break;
+            }
+
+            word.push(cur_char);
+            self.chars.next();
+        }
+
+        Token::Word(word)
+    }
+
+    fn read_symbol(&mut self) -> io::Result<Token> {
+        let cur_char = self.chars.next().unwrap();
+
+        let token = match cur_char {
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ',' => Token::Comma,
+            ';' => Token::Semicolon,
+            '*' => Token::Asterisk,
+            '.' => Token::Dot,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '/' => Token::Slash,
+            '%' => Token::Percent,
+            '=' => {
+                // Both = and == are the equality
+                self.next_if_eq('=');
+                Token::Equal
+            }
+            '!' if self.next_if_eq('=') => Token::NotEqual,
+            '|' if self.next_if_eq('|') => Token::Concat,
+            '<' => {
+                if self.next_if_eq('=') {
+                    Token::LessOrEqual
+                } else if self.next_if_eq('>') {
+                    Token::NotEqual
+                } else {
+                    Token::Less
+                }
+            }
+            '>' => {
+                if self.next_if_eq('=') {
+                    Token::GreaterOrEqual
+                } else {
+                    Token::Greater
+                }
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unexpected character: {}", cur_char),
+                ))
+            }
+        };
+
+        Ok(token)
+    }
+
+    fn next_if_eq(&mut self, expected: char) -> bool {
+        self.chars.next_if_eq(&expected).is_some()
+    }
+}
@@ -1,25 +1,37 @@
 use crate::parser;
-use crate::backend::{ db_open, Table };
+use crate::backend::{ db_open, Database };
 
 pub struct Process {
-    table: Table,
+    database: Database,
+}
+
+impl Default for Process {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Process {
     pub fn new() -> Self {
         Process {
-            table: db_open(None), // TODO rewrite, using command line db path
+            database: db_open(None), // TODO rewrite, using command line db path
         }
     }
 
     pub fn run(&mut self) {
         loop {
             match parser::parse_with_prompt() {
-                Some(parsed_statement) => {
-                    let _ = self.table.execute(parsed_statement);
+                Some(Ok(parsed_statement)) => {
+                    if let Err(err) = self.database.execute(parsed_statement) {
+                        println!("Error: {}", err);
+                    }
+                },
+                Some(Err(err)) => {
+                    println!("{}", err);
                 },
+                // The input was closed, so we behave the same way as .exit
                 None => {
-                    println!("Unrecognized statement");
+                    self.database.exit();
                 }
             }
         }
@@ -0,0 +1,8 @@
+/// The state of the connection which is visible to the statements
+#[derive(Debug, Default)]
+pub struct Session {
+    // The id of the last row inserted by the INSERT statement, 0 if there were none
+    pub last_insert_rowid: i64,
+    // PRAGMA foreign_keys, the foreign keys are not checked by default
+    pub foreign_keys: bool,
+}
@@ -1,10 +1,105 @@
 pub mod insert_statement;
 pub mod select_statement;
+pub mod update_statement;
+pub mod delete_statement;
+pub mod create_table_statement;
+pub mod drop_table_statement;
+pub mod alter_table_statement;
+pub mod create_index_statement;
+pub mod drop_index_statement;
+pub mod create_view_statement;
+pub mod drop_view_statement;
+pub mod create_trigger_statement;
+pub mod drop_trigger_statement;
+pub mod analyze_statement;
+pub mod pragma_statement;
+pub mod explain_statement;
+pub mod transaction_statement;
+pub mod expression;
+pub mod window;
 
-use insert_statement::InsertStatement;
-use select_statement::SelectStatement;
+use insert_statement::{InsertSource, InsertStatement, Upsert};
+use select_statement::{ResultColumn, SelectStatement};
+use update_statement::UpdateStatement;
+use delete_statement::DeleteStatement;
+use create_table_statement::CreateTableStatement;
+use drop_table_statement::DropTableStatement;
+use alter_table_statement::AlterTableStatement;
+use create_index_statement::CreateIndexStatement;
+use drop_index_statement::DropIndexStatement;
+use create_view_statement::CreateViewStatement;
+use drop_view_statement::DropViewStatement;
+use create_trigger_statement::CreateTriggerStatement;
+use drop_trigger_statement::DropTriggerStatement;
+use expression::Expression;
+use analyze_statement::AnalyzeStatement;
+use pragma_statement::PragmaStatement;
+use explain_statement::ExplainStatement;
+use transaction_statement::TransactionStatement;
 
+#[derive(Debug, Clone)]
 pub enum Statement {
     Insert(InsertStatement),
-    Select(SelectStatement),
+    Select(Box<SelectStatement>),
+    Update(UpdateStatement),
+    Delete(DeleteStatement),
+    CreateTable(CreateTableStatement),
+    DropTable(DropTableStatement),
+    AlterTable(AlterTableStatement),
+    CreateIndex(CreateIndexStatement),
+    DropIndex(DropIndexStatement),
+    CreateView(Box<CreateViewStatement>),
+    DropView(DropViewStatement),
+    CreateTrigger(Box<CreateTriggerStatement>),
+    DropTrigger(DropTriggerStatement),
+    Analyze(AnalyzeStatement),
+    Pragma(PragmaStatement),
+    Explain(ExplainStatement),
+    Transaction(TransactionStatement),
+}
+
+impl Statement {
+    /// The method returns the expressions of INSERT, UPDATE and DELETE,
+    /// the expressions of SELECT are found within its subqueries too
+    pub fn get_expressions_mut(&mut self) -> Vec<&mut Expression> {
+        match self {
+            Statement::Insert(insert_statement) => {
+                let values: Vec<&mut Expression> = match &mut insert_statement.source {
+                    InsertSource::Values(rows) => rows.iter_mut().flatten().collect(),
+                    _ => Vec::new(),
+                };
+
+                values
+                    .into_iter()
+                    .chain(insert_statement.upserts.iter_mut().flat_map(Upsert::get_expressions_mut))
+                    .collect()
+            },
+            Statement::Update(update_statement) => update_statement.assignments
+                .iter_mut()
+                .map(|(_, expression)| expression)
+                .chain(update_statement.where_clause.iter_mut())
+                .collect(),
+            Statement::Delete(delete_statement) => delete_statement.where_clause.iter_mut().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The columns of RETURNING of INSERT, UPDATE or DELETE
+    pub fn get_returning(&self) -> &[ResultColumn] {
+        match self {
+            Statement::Insert(insert_statement) => &insert_statement.returning,
+            Statement::Update(update_statement) => &update_statement.returning,
+            Statement::Delete(delete_statement) => &delete_statement.returning,
+            _ => &[],
+        }
+    }
+
+    /// The method returns the SELECT statement or the SELECT of the rows of INSERT
+    pub fn get_select_mut(&mut self) -> Option<&mut SelectStatement> {
+        match self {
+            Statement::Select(select_statement) => Some(select_statement),
+            Statement::Insert(InsertStatement { source: InsertSource::Select(select_statement), .. }) => Some(select_statement),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,55 @@
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+use super::create_table_statement::ColumnDefinition;
+
+#[derive(Debug, Clone)]
+pub enum AlterTableAction {
+    RenameTable(String),
+    RenameColumn {
+        old_name: String,
+        new_name: String,
+    },
+    AddColumn(ColumnDefinition),
+    DropColumn(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct AlterTableStatement {
+    pub table_name: String,
+    pub action: AlterTableAction,
+}
+
+impl AlterTableStatement {
+    /// ALTER TABLE name {RENAME TO new_name | RENAME [COLUMN] column TO new_column
+    /// | ADD [COLUMN] column_definition | DROP [COLUMN] column}
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("alter")?;
+        tokens.expect_keyword("table")?;
+
+        let table_name = tokens.identifier()?;
+
+        let action = if tokens.consume_keyword("rename") {
+            if tokens.consume_keyword("to") {
+                AlterTableAction::RenameTable(tokens.identifier()?)
+            } else {
+                tokens.consume_keyword("column");
+
+                let old_name = tokens.identifier()?;
+                tokens.expect_keyword("to")?;
+
+                AlterTableAction::RenameColumn { old_name, new_name: tokens.identifier()? }
+            }
+        } else if tokens.consume_keyword("add") {
+            tokens.consume_keyword("column");
+            AlterTableAction::AddColumn(ColumnDefinition::parse(tokens)?)
+        } else if tokens.consume_keyword("drop") {
+            tokens.consume_keyword("column");
+            AlterTableAction::DropColumn(tokens.identifier()?)
+        } else {
+            return Err(tokens.unexpected_token_error("RENAME, ADD or DROP"));
+        };
+
+        Ok(AlterTableStatement { table_name, action })
+    }
+}
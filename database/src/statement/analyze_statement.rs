@@ -0,0 +1,24 @@
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+use crate::parser::tokenizer::Token;
+
+#[derive(Debug, Clone)]
+pub struct AnalyzeStatement {
+    // All the tables are analyzed if there is no name
+    pub table_name: Option<String>,
+}
+
+impl AnalyzeStatement {
+    /// ANALYZE [name]
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("analyze")?;
+
+        let table_name = match tokens.peek() {
+            None | Some(Token::Semicolon) => None,
+            Some(_) => Some(tokens.identifier()?),
+        };
+
+        Ok(AnalyzeStatement { table_name })
+    }
+}
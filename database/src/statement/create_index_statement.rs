@@ -0,0 +1,55 @@
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+
+#[derive(Debug, Clone)]
+pub struct CreateIndexStatement {
+    pub index_name: String,
+    pub table_name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+    pub if_not_exists: bool,
+}
+
+impl CreateIndexStatement {
+    /// CREATE [UNIQUE] INDEX [IF NOT EXISTS] name ON table (column, ...)
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("create")?;
+
+        let unique = tokens.consume_keyword("unique");
+
+        tokens.expect_keyword("index")?;
+
+        let if_not_exists = tokens.consume_keyword("if");
+        if if_not_exists {
+            tokens.expect_keyword("not")?;
+            tokens.expect_keyword("exists")?;
+        }
+
+        let index_name = tokens.identifier()?;
+
+        tokens.expect_keyword("on")?;
+
+        let table_name = tokens.identifier()?;
+        let columns = tokens.identifier_list()?;
+
+        Ok(CreateIndexStatement {
+            index_name,
+            table_name,
+            columns,
+            unique,
+            if_not_exists,
+        })
+    }
+
+    /// The method returns the SQL text which is stored in the schema to recreate the index
+    pub fn to_sql(&self) -> String {
+        format!(
+            "CREATE {}INDEX {} ON {} ({})",
+            if self.unique { "UNIQUE " } else { "" },
+            TokenStream::quote_identifier(&self.index_name),
+            TokenStream::quote_identifier(&self.table_name),
+            TokenStream::quote_identifiers(&self.columns),
+        )
+    }
+}
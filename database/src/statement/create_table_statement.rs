@@ -0,0 +1,445 @@
+use std::fmt;
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+use crate::parser::tokenizer::Token;
+use super::expression::Expression;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnType {
+    Integer,
+    Real,
+    Text,
+}
+
+impl ColumnType {
+    pub fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        if tokens.consume_keyword("integer") || tokens.consume_keyword("int") {
+            return Ok(ColumnType::Integer);
+        }
+
+        if tokens.consume_keyword("real") || tokens.consume_keyword("float") || tokens.consume_keyword("double") {
+            return Ok(ColumnType::Real);
+        }
+
+        if tokens.consume_keyword("text") {
+            return Ok(ColumnType::Text);
+        }
+
+        Err(tokens.unexpected_token_error("column type INTEGER, REAL or TEXT"))
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnType::Integer => write!(f, "INTEGER"),
+            ColumnType::Real => write!(f, "REAL"),
+            ColumnType::Text => write!(f, "TEXT"),
+        }
+    }
+}
+
+/// The action on the rows of the child table when the parent row is deleted or its key is updated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ForeignKeyAction {
+    // The statement fails if there are child rows, the deferred constraint is checked at commit
+    NoAction,
+    // The statement fails if there are child rows, the constraint is never deferred
+    Restrict,
+    SetNull,
+    Cascade,
+}
+
+impl ForeignKeyAction {
+    /// NO ACTION | RESTRICT | SET NULL | CASCADE
+    fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        if tokens.consume_keyword("no") {
+            tokens.expect_keyword("action")?;
+            return Ok(ForeignKeyAction::NoAction);
+        }
+
+        if tokens.consume_keyword("restrict") {
+            return Ok(ForeignKeyAction::Restrict);
+        }
+
+        if tokens.consume_keyword("set") {
+            tokens.expect_keyword("null")?;
+            return Ok(ForeignKeyAction::SetNull);
+        }
+
+        if tokens.consume_keyword("cascade") {
+            return Ok(ForeignKeyAction::Cascade);
+        }
+
+        Err(tokens.unexpected_token_error("NO ACTION, RESTRICT, SET NULL or CASCADE"))
+    }
+}
+
+impl fmt::Display for ForeignKeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForeignKeyAction::NoAction => write!(f, "NO ACTION"),
+            ForeignKeyAction::Restrict => write!(f, "RESTRICT"),
+            ForeignKeyAction::SetNull => write!(f, "SET NULL"),
+            ForeignKeyAction::Cascade => write!(f, "CASCADE"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignKey {
+    // The columns of the child table
+    pub columns: Vec<String>,
+    pub parent_table: String,
+    // The primary key of the parent table is referenced if there are no columns
+    pub parent_columns: Vec<String>,
+    pub on_delete: ForeignKeyAction,
+    pub on_update: ForeignKeyAction,
+    // DEFERRABLE INITIALLY DEFERRED, the constraint is checked at commit
+    pub deferred: bool,
+}
+
+impl ForeignKey {
+    /// REFERENCES parent [(column, ...)] [ON {DELETE | UPDATE} action] ...
+    /// [[NOT] DEFERRABLE [INITIALLY {DEFERRED | IMMEDIATE}]]
+    fn parse(tokens: &mut TokenStream, columns: Vec<String>) -> io::Result<Self> {
+        tokens.expect_keyword("references")?;
+
+        let parent_table = tokens.identifier()?;
+
+        let parent_columns = match tokens.peek() {
+            Some(Token::LeftParen) => tokens.identifier_list()?,
+            _ => Vec::new(),
+        };
+
+        let mut foreign_key = ForeignKey {
+            columns,
+            parent_table,
+            parent_columns,
+            on_delete: ForeignKeyAction::NoAction,
+            on_update: ForeignKeyAction::NoAction,
+            deferred: false,
+        };
+
+        while tokens.consume_keyword("on") {
+            if tokens.consume_keyword("delete") {
+                foreign_key.on_delete = ForeignKeyAction::parse(tokens)?;
+            } else {
+                tokens.expect_keyword("update")?;
+                foreign_key.on_update = ForeignKeyAction::parse(tokens)?;
+            }
+        }
+
+        let not_deferrable = tokens.is_keyword("not") && tokens.is_nth_keyword(1, "deferrable");
+
+        if not_deferrable {
+            tokens.expect_keyword("not")?;
+        }
+
+        if tokens.consume_keyword("deferrable") {
+            let deferred = if tokens.consume_keyword("initially") {
+                if tokens.consume_keyword("deferred") {
+                    true
+                } else {
+                    tokens.expect_keyword("immediate")?;
+                    false
+                }
+            } else {
+                false
+            };
+
+            foreign_key.deferred = deferred && !not_deferrable;
+        }
+
+        if !foreign_key.parent_columns.is_empty() && foreign_key.parent_columns.len() != foreign_key.columns.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "The foreign key on {} has {} columns but refers to {} columns of {}",
+                    foreign_key.columns.join(", "),
+                    foreign_key.columns.len(),
+                    foreign_key.parent_columns.len(),
+                    foreign_key.parent_table,
+                ),
+            ));
+        }
+
+        Ok(foreign_key)
+    }
+}
+
+impl fmt::Display for ForeignKey {
+    /// The clause is written without the child columns, they are written by the column or the table constraint
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "REFERENCES {}", TokenStream::quote_identifier(&self.parent_table))?;
+
+        if !self.parent_columns.is_empty() {
+            write!(f, " ({})", TokenStream::quote_identifiers(&self.parent_columns))?;
+        }
+
+        if self.on_delete != ForeignKeyAction::NoAction {
+            write!(f, " ON DELETE {}", self.on_delete)?;
+        }
+
+        if self.on_update != ForeignKeyAction::NoAction {
+            write!(f, " ON UPDATE {}", self.on_update)?;
+        }
+
+        if self.deferred {
+            write!(f, " DEFERRABLE INITIALLY DEFERRED")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ColumnDefinition {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub primary_key: bool,
+    pub autoincrement: bool,
+    pub unique: bool,
+    pub not_null: bool,
+    pub check: Option<Expression>,
+    pub default: Option<Expression>,
+    pub references: Option<ForeignKey>,
+}
+
+impl ColumnDefinition {
+    /// name type [PRIMARY KEY [AUTOINCREMENT] | UNIQUE | NOT NULL | CHECK (expression) | DEFAULT expression
+    /// | REFERENCES parent [(column)] ...] ...
+    pub fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        let mut column_definition = ColumnDefinition {
+            name: tokens.identifier()?,
+            column_type: ColumnType::parse(tokens)?,
+            primary_key: false,
+            autoincrement: false,
+            unique: false,
+            not_null: false,
+            check: None,
+            default: None,
+            references: None,
+        };
+
+        loop {
+            if tokens.consume_keyword("primary") {
+                tokens.expect_keyword("key")?;
+                column_definition.primary_key = true;
+                column_definition.autoincrement = tokens.consume_keyword("autoincrement");
+            } else if tokens.consume_keyword("unique") {
+                column_definition.unique = true;
+            } else if tokens.consume_keyword("not") {
+                tokens.expect_keyword("null")?;
+                column_definition.not_null = true;
+            } else if tokens.consume_keyword("check") {
+                column_definition.check = Some(parse_check_expression(tokens)?);
+            } else if tokens.consume_keyword("default") {
+                column_definition.default = Some(parse_default_expression(tokens)?);
+            } else if tokens.is_keyword("references") {
+                column_definition.references = Some(ForeignKey::parse(tokens, vec![column_definition.name.clone()])?);
+            } else {
+                return Ok(column_definition);
+            }
+        }
+    }
+}
+
+impl fmt::Display for ColumnDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", TokenStream::quote_identifier(&self.name), self.column_type)?;
+
+        if self.primary_key {
+            write!(f, " PRIMARY KEY")?;
+        }
+
+        if self.autoincrement {
+            write!(f, " AUTOINCREMENT")?;
+        }
+
+        if self.unique {
+            write!(f, " UNIQUE")?;
+        }
+
+        if self.not_null {
+            write!(f, " NOT NULL")?;
+        }
+
+        if let Some(check) = &self.check {
+            write!(f, " CHECK ({})", check)?;
+        }
+
+        if let Some(default) = &self.default {
+            write!(f, " DEFAULT ({})", default)?;
+        }
+
+        if let Some(references) = &self.references {
+            write!(f, " {}", references)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TableConstraint {
+    PrimaryKey(Vec<String>),
+    Unique(Vec<String>),
+    Check(Expression),
+    ForeignKey(ForeignKey),
+}
+
+impl TableConstraint {
+    /// [CONSTRAINT name] PRIMARY KEY (column, ...) | UNIQUE (column, ...) | CHECK (expression)
+    /// | FOREIGN KEY (column, ...) REFERENCES parent [(column, ...)] ...
+    fn parse(tokens: &mut TokenStream) -> io::Result<Option<Self>> {
+        // The constraint names are accepted but not used
+        if tokens.consume_keyword("constraint") {
+            tokens.identifier()?;
+        }
+
+        if tokens.consume_keyword("primary") {
+            tokens.expect_keyword("key")?;
+            return Ok(Some(TableConstraint::PrimaryKey(tokens.identifier_list()?)));
+        }
+
+        if tokens.consume_keyword("unique") {
+            return Ok(Some(TableConstraint::Unique(tokens.identifier_list()?)));
+        }
+
+        if tokens.consume_keyword("check") {
+            return Ok(Some(TableConstraint::Check(parse_check_expression(tokens)?)));
+        }
+
+        if tokens.consume_keyword("foreign") {
+            tokens.expect_keyword("key")?;
+
+            let columns = tokens.identifier_list()?;
+            return Ok(Some(TableConstraint::ForeignKey(ForeignKey::parse(tokens, columns)?)));
+        }
+
+        Ok(None)
+    }
+}
+
+impl fmt::Display for TableConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableConstraint::PrimaryKey(columns) => write!(f, "PRIMARY KEY ({})", TokenStream::quote_identifiers(columns)),
+            TableConstraint::Unique(columns) => write!(f, "UNIQUE ({})", TokenStream::quote_identifiers(columns)),
+            TableConstraint::Check(check) => write!(f, "CHECK ({})", check),
+            TableConstraint::ForeignKey(foreign_key) => write!(f, "FOREIGN KEY ({}) {}", TokenStream::quote_identifiers(&foreign_key.columns), foreign_key),
+        }
+    }
+}
+
+fn parse_check_expression(tokens: &mut TokenStream) -> io::Result<Expression> {
+    tokens.expect(&Token::LeftParen)?;
+    let check = Expression::parse(tokens)?;
+    tokens.expect(&Token::RightParen)?;
+
+    Ok(check)
+}
+
+/// The default is either a parenthesized expression or a literal, possibly negative
+fn parse_default_expression(tokens: &mut TokenStream) -> io::Result<Expression> {
+    if tokens.peek() == Some(&Token::LeftParen) {
+        return parse_check_expression(tokens);
+    }
+
+    let default = Expression::parse_unary(tokens)?;
+
+    if let Expression::Column { column_name, .. } = &default {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("The default value can't refer to the column {}", column_name),
+        ));
+    }
+
+    Ok(default)
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateTableStatement {
+    pub table_name: String,
+    pub if_not_exists: bool,
+    pub columns: Vec<ColumnDefinition>,
+    pub constraints: Vec<TableConstraint>,
+}
+
+impl CreateTableStatement {
+    /// CREATE TABLE [IF NOT EXISTS] name (column_definition, ..., [table_constraint, ...])
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("create")?;
+        tokens.expect_keyword("table")?;
+
+        let if_not_exists = tokens.consume_keyword("if");
+        if if_not_exists {
+            tokens.expect_keyword("not")?;
+            tokens.expect_keyword("exists")?;
+        }
+
+        let table_name = tokens.identifier()?;
+
+        tokens.expect(&Token::LeftParen)?;
+
+        let mut columns = Vec::new();
+        let mut constraints = Vec::new();
+
+        loop {
+            match TableConstraint::parse(tokens)? {
+                Some(constraint) => constraints.push(constraint),
+                // The columns must be defined before the table constraints
+                None if constraints.is_empty() => columns.push(ColumnDefinition::parse(tokens)?),
+                None => return Err(tokens.unexpected_token_error("table constraint")),
+            }
+
+            if !tokens.consume(&Token::Comma) {
+                break;
+            }
+        }
+
+        tokens.expect(&Token::RightParen)?;
+
+        if columns.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The table {} must have at least one column", table_name),
+            ));
+        }
+
+        Ok(CreateTableStatement {
+            table_name,
+            if_not_exists,
+            columns,
+            constraints,
+        })
+    }
+
+    /// The method returns the foreign keys of the columns and of the table constraints
+    pub fn get_foreign_keys(&self) -> Vec<&ForeignKey> {
+        let table_foreign_keys = self.constraints.iter().filter_map(|constraint| match constraint {
+            TableConstraint::ForeignKey(foreign_key) => Some(foreign_key),
+            _ => None,
+        });
+
+        self.columns
+            .iter()
+            .filter_map(|column| column.references.as_ref())
+            .chain(table_foreign_keys)
+            .collect()
+    }
+
+    /// The method returns the SQL text which is stored in the catalog to recreate the table
+    pub fn to_sql(&self) -> String {
+        let mut definitions: Vec<String> = self.columns
+            .iter()
+            .map(|column| column.to_string())
+            .collect();
+
+        definitions.extend(self.constraints.iter().map(|constraint| constraint.to_string()));
+
+        format!("CREATE TABLE {} ({})", TokenStream::quote_identifier(&self.table_name), definitions.join(", "))
+    }
+}
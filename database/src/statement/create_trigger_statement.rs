@@ -0,0 +1,199 @@
+use std::fmt;
+use std::io;
+
+use crate::parser::ParsedStatement;
+use crate::parser::token_stream::TokenStream;
+use crate::parser::tokenizer::Token;
+use super::Statement;
+use super::expression::Expression;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerTiming {
+    Before,
+    After,
+}
+
+impl fmt::Display for TriggerTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerTiming::Before => write!(f, "BEFORE"),
+            TriggerTiming::After => write!(f, "AFTER"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerEvent {
+    Insert,
+    // The trigger is fired by the UPDATE of any of the columns, by any UPDATE if there are none
+    Update(Vec<String>),
+    Delete,
+}
+
+impl TriggerEvent {
+    /// The method checks whether the trigger of this event is fired by the event of the statement,
+    /// the event of UPDATE has the assigned columns
+    pub fn is_fired_by(&self, event: &TriggerEvent) -> bool {
+        match (self, event) {
+            (TriggerEvent::Update(column_names), TriggerEvent::Update(assigned_column_names)) => {
+                column_names.is_empty() || column_names.iter().any(|column_name| assigned_column_names.contains(column_name))
+            },
+            (TriggerEvent::Insert, TriggerEvent::Insert) | (TriggerEvent::Delete, TriggerEvent::Delete) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for TriggerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerEvent::Insert => write!(f, "INSERT"),
+            TriggerEvent::Update(column_names) if column_names.is_empty() => write!(f, "UPDATE"),
+            TriggerEvent::Update(column_names) => write!(f, "UPDATE OF {}", TokenStream::quote_identifiers(column_names)),
+            TriggerEvent::Delete => write!(f, "DELETE"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateTriggerStatement {
+    pub trigger_name: String,
+    pub timing: TriggerTiming,
+    pub event: TriggerEvent,
+    pub table_name: String,
+    // The statements are run only for the rows matching the condition
+    pub when: Option<Expression>,
+    // The statements could read the changed row as NEW and the row before the change as OLD
+    pub statements: Vec<Statement>,
+    pub if_not_exists: bool,
+}
+
+impl CreateTriggerStatement {
+    /// CREATE TRIGGER [IF NOT EXISTS] name [BEFORE | AFTER] {INSERT | UPDATE [OF column, ...] | DELETE}
+    /// ON table [FOR EACH ROW] [WHEN expression] BEGIN statement; ... END
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("create")?;
+        tokens.expect_keyword("trigger")?;
+
+        let if_not_exists = tokens.consume_keyword("if");
+        if if_not_exists {
+            tokens.expect_keyword("not")?;
+            tokens.expect_keyword("exists")?;
+        }
+
+        let trigger_name = tokens.identifier()?;
+
+        let timing = if tokens.consume_keyword("after") {
+            TriggerTiming::After
+        } else {
+            tokens.consume_keyword("before");
+            TriggerTiming::Before
+        };
+
+        let event = if tokens.consume_keyword("insert") {
+            TriggerEvent::Insert
+        } else if tokens.consume_keyword("delete") {
+            TriggerEvent::Delete
+        } else {
+            tokens.expect_keyword("update")?;
+
+            let mut column_names = Vec::new();
+
+            if tokens.consume_keyword("of") {
+                column_names.push(tokens.identifier()?);
+
+                while tokens.consume(&Token::Comma) {
+                    column_names.push(tokens.identifier()?);
+                }
+            }
+
+            TriggerEvent::Update(column_names)
+        };
+
+        tokens.expect_keyword("on")?;
+
+        let table_name = tokens.identifier()?;
+
+        if tokens.consume_keyword("for") {
+            tokens.expect_keyword("each")?;
+            tokens.expect_keyword("row")?;
+        }
+
+        let when = if tokens.consume_keyword("when") {
+            Some(Expression::parse(tokens)?)
+        } else {
+            None
+        };
+
+        tokens.expect_keyword("begin")?;
+
+        let mut statements = Vec::new();
+
+        loop {
+            let statement = ParsedStatement::parse_statement(tokens)?;
+
+            if !matches!(statement, Statement::Insert(_) | Statement::Update(_) | Statement::Delete(_) | Statement::Select(_)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "The trigger could run INSERT, UPDATE, DELETE and SELECT statements only",
+                ));
+            }
+
+            if !statement.get_returning().is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "RETURNING can't be used in the trigger",
+                ));
+            }
+
+            statements.push(statement);
+            tokens.expect(&Token::Semicolon)?;
+
+            if tokens.consume_keyword("end") {
+                break;
+            }
+        }
+
+        Ok(CreateTriggerStatement {
+            trigger_name,
+            timing,
+            event,
+            table_name,
+            when,
+            statements,
+            if_not_exists,
+        })
+    }
+
+    /// The method returns the SQL text which is stored in the schema to recreate the trigger
+    pub fn to_sql(&self) -> String {
+        let mut sql = format!(
+            "CREATE TRIGGER {} {} {} ON {}",
+            TokenStream::quote_identifier(&self.trigger_name),
+            self.timing,
+            self.event,
+            TokenStream::quote_identifier(&self.table_name),
+        );
+
+        if let Some(when) = &self.when {
+            sql.push_str(&format!(" WHEN {}", when));
+        }
+
+        sql.push_str(" BEGIN");
+
+        for statement in self.statements.iter() {
+            let statement_sql = match statement {
+                Statement::Insert(insert_statement) => insert_statement.to_string(),
+                Statement::Update(update_statement) => update_statement.to_string(),
+                Statement::Delete(delete_statement) => delete_statement.to_string(),
+                Statement::Select(select_statement) => select_statement.to_string(),
+                _ => unreachable!("The trigger statements are checked by the parser"),
+            };
+
+            sql.push_str(&format!(" {};", statement_sql));
+        }
+
+        sql.push_str(" END");
+        sql
+    }
+}
@@ -0,0 +1,78 @@
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+use crate::parser::tokenizer::Token;
+use super::select_statement::{CommonTableExpression, ResultColumn, SelectStatement, WithClause};
+
+#[derive(Debug, Clone)]
+pub struct CreateViewStatement {
+    pub view_name: String,
+    // The names of the columns, the names of the result columns are used if there are none
+    pub column_names: Vec<String>,
+    pub select_statement: SelectStatement,
+    pub if_not_exists: bool,
+}
+
+impl CreateViewStatement {
+    /// CREATE VIEW [IF NOT EXISTS] name [(column, ...)] AS select_statement
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("create")?;
+        tokens.expect_keyword("view")?;
+
+        let if_not_exists = tokens.consume_keyword("if");
+        if if_not_exists {
+            tokens.expect_keyword("not")?;
+            tokens.expect_keyword("exists")?;
+        }
+
+        let view_name = tokens.identifier()?;
+
+        let column_names = match tokens.peek() {
+            Some(Token::LeftParen) => tokens.identifier_list()?,
+            _ => Vec::new(),
+        };
+
+        tokens.expect_keyword("as")?;
+
+        Ok(CreateViewStatement {
+            view_name,
+            column_names,
+            select_statement: SelectStatement::new(tokens)?,
+            if_not_exists,
+        })
+    }
+
+    /// The method returns the SQL text which is stored in the schema to recreate the view
+    pub fn to_sql(&self) -> String {
+        match self.column_names.is_empty() {
+            true => format!("CREATE VIEW {} AS {}", TokenStream::quote_identifier(&self.view_name), self.select_statement),
+            false => format!(
+                "CREATE VIEW {} ({}) AS {}",
+                TokenStream::quote_identifier(&self.view_name),
+                TokenStream::quote_identifiers(&self.column_names),
+                self.select_statement,
+            ),
+        }
+    }
+
+    /// The SELECT which replaces the references to the view, the columns are renamed
+    /// by the table of WITH: WITH view(column, ...) AS (select_statement) SELECT * FROM view
+    pub fn get_select_statement(&self) -> SelectStatement {
+        if self.column_names.is_empty() {
+            return self.select_statement.clone();
+        }
+
+        let mut select_statement = SelectStatement::from_table(&self.view_name, vec![ResultColumn::All], None);
+
+        select_statement.with_clause = Some(WithClause {
+            recursive: false,
+            tables: vec![CommonTableExpression {
+                table_name: self.view_name.clone(),
+                column_names: self.column_names.clone(),
+                select_statement: self.select_statement.clone(),
+            }],
+        });
+
+        select_statement
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt;
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+use super::expression::Expression;
+use super::select_statement::{format_returning, parse_returning, ResultColumn, SelectStatement};
+
+#[derive(Debug, Clone)]
+pub struct DeleteStatement {
+    pub table_name: String,
+    pub where_clause: Option<Expression>,
+    pub returning: Vec<ResultColumn>,
+}
+
+impl DeleteStatement {
+    /// DELETE FROM table [WHERE expression] [RETURNING result_column, ...]
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("delete")?;
+        tokens.expect_keyword("from")?;
+
+        let table_name = tokens.identifier()?;
+
+        let where_clause = if tokens.consume_keyword("where") {
+            Some(Expression::parse(tokens)?)
+        } else {
+            None
+        };
+
+        Ok(DeleteStatement {
+            table_name,
+            where_clause,
+            returning: parse_returning(tokens)?,
+        })
+    }
+
+    /// The SELECT which finds the deleted rows
+    pub fn get_select_statement(&self) -> SelectStatement {
+        SelectStatement::from_table(&self.table_name, vec![ResultColumn::All], self.where_clause.clone())
+    }
+}
+
+impl fmt::Display for DeleteStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DELETE FROM {}", TokenStream::quote_identifier(&self.table_name))?;
+
+        if let Some(where_clause) = &self.where_clause {
+            write!(f, " WHERE {}", where_clause)?;
+        }
+
+        write!(f, "{}", format_returning(&self.returning))
+    }
+}
@@ -0,0 +1,27 @@
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+
+#[derive(Debug, Clone)]
+pub struct DropIndexStatement {
+    pub index_name: String,
+    pub if_exists: bool,
+}
+
+impl DropIndexStatement {
+    /// DROP INDEX [IF EXISTS] name
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("drop")?;
+        tokens.expect_keyword("index")?;
+
+        let if_exists = tokens.consume_keyword("if");
+        if if_exists {
+            tokens.expect_keyword("exists")?;
+        }
+
+        Ok(DropIndexStatement {
+            index_name: tokens.identifier()?,
+            if_exists,
+        })
+    }
+}
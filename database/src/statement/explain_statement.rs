@@ -0,0 +1,47 @@
+use std::io;
+
+use super::Statement;
+use crate::parser::ParsedStatement;
+use crate::parser::token_stream::TokenStream;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExplainMode {
+    // The instructions of the compiled program are listed
+    Bytecode,
+    // The chosen operators are described, the statement is not executed
+    QueryPlan,
+    // The statement is executed and the operators are measured
+    Analyze,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExplainStatement {
+    pub mode: ExplainMode,
+    pub statement: Box<Statement>,
+}
+
+impl ExplainStatement {
+    /// EXPLAIN statement | EXPLAIN QUERY PLAN statement | EXPLAIN ANALYZE statement
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("explain")?;
+
+        let mode = if tokens.consume_keyword("analyze") {
+            ExplainMode::Analyze
+        } else if tokens.consume_keyword("query") {
+            tokens.expect_keyword("plan")?;
+
+            ExplainMode::QueryPlan
+        } else {
+            ExplainMode::Bytecode
+        };
+
+        if tokens.is_keyword("explain") {
+            return Err(tokens.unexpected_token_error("statement to explain"));
+        }
+
+        Ok(ExplainStatement {
+            mode,
+            statement: Box::new(ParsedStatement::parse_statement(tokens)?),
+        })
+    }
+}
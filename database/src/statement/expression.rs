@@ -0,0 +1,1371 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+use std::rc::Rc;
+
+use crate::parser::token_stream::TokenStream;
+use crate::parser::tokenizer::Token;
+use crate::session::Session;
+use crate::value::Value;
+use super::create_table_statement::ColumnType;
+use super::select_statement::SelectStatement;
+use super::window::{WindowDefinition, WindowFunction};
+
+/// Access to the column values of the row the expression is evaluated against
+pub trait RowSource {
+    fn get_column_value(&self, table_name: Option<&str>, column_name: &str) -> io::Result<Value>;
+
+    /// The session is used by the functions depending on the connection state
+    fn get_session(&self) -> &Session;
+
+    /// The aggregate functions could be evaluated only against the groups of rows
+    fn get_aggregate_value(&self, expression: &Expression) -> io::Result<Value> {
+        Err(aggregate_misuse_error(expression))
+    }
+
+    /// The window functions are computed over the rows of the query after they are grouped
+    fn get_window_value(&self, expression: &Expression) -> io::Result<Value> {
+        Err(window_misuse_error(expression))
+    }
+
+    /// The subqueries could be run only by the queries which have access to the tables
+    fn get_subquery_result(&self, subquery: &SelectStatement) -> io::Result<Rc<SubqueryResult>> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("The subquery can't be used here: ({})", subquery),
+        ))
+    }
+}
+
+/// The rows returned by the subquery, only their first values are used by the expressions
+pub struct SubqueryResult {
+    num_columns: usize,
+    values: Vec<Value>,
+    // The values are looked up by IN, NULLs are never equal to anything
+    value_set: HashSet<Value>,
+    has_null: bool,
+}
+
+impl SubqueryResult {
+    pub fn new(num_columns: usize, values: Vec<Value>) -> Self {
+        SubqueryResult {
+            num_columns,
+            value_set: values.iter().filter(|value| !value.is_null()).cloned().collect(),
+            has_null: values.iter().any(Value::is_null),
+            values,
+        }
+    }
+
+    /// The scalar subquery and the subquery of IN must return one column
+    fn check_num_columns(&self) -> io::Result<()> {
+        if self.num_columns != 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The subquery returns {} columns - expected 1", self.num_columns),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+pub fn aggregate_misuse_error(expression: &Expression) -> io::Error {
+    let Expression::Aggregate { function, .. } = expression else {
+        unreachable!("The expression {} is not an aggregate", expression);
+    };
+
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Misuse of aggregate function {}()", function),
+    )
+}
+
+pub fn window_misuse_error(expression: &Expression) -> io::Error {
+    let Expression::Window { function, .. } = expression else {
+        unreachable!("The expression {} is not a window function", expression);
+    };
+
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Misuse of window function {}()", function),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOperator {
+    Not,
+    Minus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOperator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    // The comparisons which treat NULLs as equal values
+    Is,
+    IsNot,
+    And,
+    Or,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Concat,
+}
+
+impl BinaryOperator {
+    pub fn is_arithmetic(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::Add
+                | BinaryOperator::Subtract
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::Remainder
+                | BinaryOperator::Concat
+        )
+    }
+
+    /// The method returns the operator which gives the same result with swapped operands
+    pub fn get_swapped(&self) -> Self {
+        match self {
+            BinaryOperator::Less => BinaryOperator::Greater,
+            BinaryOperator::LessOrEqual => BinaryOperator::GreaterOrEqual,
+            BinaryOperator::Greater => BinaryOperator::Less,
+            BinaryOperator::GreaterOrEqual => BinaryOperator::LessOrEqual,
+            _ => *self,
+        }
+    }
+
+    fn from_additive_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::Plus => Some(BinaryOperator::Add),
+            Token::Minus => Some(BinaryOperator::Subtract),
+            _ => None,
+        }
+    }
+
+    fn from_multiplicative_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::Asterisk => Some(BinaryOperator::Multiply),
+            Token::Slash => Some(BinaryOperator::Divide),
+            Token::Percent => Some(BinaryOperator::Remainder),
+            _ => None,
+        }
+    }
+
+    fn from_comparison_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::Equal => Some(BinaryOperator::Equal),
+            Token::NotEqual => Some(BinaryOperator::NotEqual),
+            Token::Less => Some(BinaryOperator::Less),
+            Token::LessOrEqual => Some(BinaryOperator::LessOrEqual),
+            Token::Greater => Some(BinaryOperator::Greater),
+            Token::GreaterOrEqual => Some(BinaryOperator::GreaterOrEqual),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self {
+            BinaryOperator::Equal => "=",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessOrEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterOrEqual => ">=",
+            BinaryOperator::Is => "IS",
+            BinaryOperator::IsNot => "IS NOT",
+            BinaryOperator::And => "AND",
+            BinaryOperator::Or => "OR",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Remainder => "%",
+            BinaryOperator::Concat => "||",
+        };
+
+        write!(f, "{}", operator)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    GroupConcat,
+}
+
+impl AggregateFunction {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "count" => Some(AggregateFunction::Count),
+            "sum" => Some(AggregateFunction::Sum),
+            "avg" => Some(AggregateFunction::Avg),
+            "min" => Some(AggregateFunction::Min),
+            "max" => Some(AggregateFunction::Max),
+            "group_concat" => Some(AggregateFunction::GroupConcat),
+            _ => None,
+        }
+    }
+
+    /// The method checks the number of arguments, COUNT(*) has no arguments
+    pub fn check_num_arguments(&self, num_arguments: usize) -> io::Result<()> {
+        let is_valid = match self {
+            AggregateFunction::Count => num_arguments <= 1,
+            AggregateFunction::GroupConcat => num_arguments == 1 || num_arguments == 2,
+            _ => num_arguments == 1,
+        };
+
+        if !is_valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Wrong number of arguments to function {}()", self),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Avg => "avg",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::GroupConcat => "group_concat",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Literal(Value),
+    Column {
+        table_name: Option<String>,
+        column_name: String,
+    },
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(Box<Expression>, BinaryOperator, Box<Expression>),
+    Function {
+        name: String,
+        arguments: Vec<Expression>,
+    },
+    // COUNT(*) is the aggregate without arguments
+    Aggregate {
+        function: AggregateFunction,
+        arguments: Vec<Expression>,
+        distinct: bool,
+    },
+    // The function computed over the rows of the partition the row belongs to
+    Window {
+        function: WindowFunction,
+        arguments: Vec<Expression>,
+        window: Box<WindowDefinition>,
+    },
+    // The CASE with the operand compares it with the WHEN values,
+    // otherwise the WHEN conditions are checked
+    Case {
+        operand: Option<Box<Expression>>,
+        when_clauses: Vec<(Expression, Expression)>,
+        else_clause: Option<Box<Expression>>,
+    },
+    Cast {
+        expression: Box<Expression>,
+        column_type: ColumnType,
+    },
+    // The first value of the first row of the subquery or NULL if there are no rows
+    Subquery(Box<SelectStatement>),
+    Exists(Box<SelectStatement>),
+    InList {
+        expression: Box<Expression>,
+        list: Vec<Expression>,
+        negated: bool,
+    },
+    InSubquery {
+        expression: Box<Expression>,
+        subquery: Box<SelectStatement>,
+        negated: bool,
+    },
+}
+
+impl Expression {
+    /// The method parses an expression using the SQL operator precedence:
+    /// OR < AND < NOT < comparison, IS, BETWEEN, IN < +, - < *, /, % < || < unary minus
+    pub fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        Self::parse_or(tokens)
+    }
+
+    fn parse_or(tokens: &mut TokenStream) -> io::Result<Self> {
+        let mut left = Self::parse_and(tokens)?;
+
+        while tokens.consume_keyword("or") {
+            let right = Self::parse_and(tokens)?;
+            left = Expression::Binary(Box::new(left), BinaryOperator::Or, Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_and(tokens: &mut TokenStream) -> io::Result<Self> {
+        let mut left = Self::parse_not(tokens)?;
+
+        while tokens.consume_keyword("and") {
+            let right = Self::parse_not(tokens)?;
+            left = Expression::Binary(Box::new(left), BinaryOperator::And, Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_not(tokens: &mut TokenStream) -> io::Result<Self> {
+        if tokens.consume_keyword("not") {
+            let operand = Self::parse_not(tokens)?;
+            return Ok(Expression::Unary(UnaryOperator::Not, Box::new(operand)));
+        }
+
+        Self::parse_comparison(tokens)
+    }
+
+    fn parse_comparison(tokens: &mut TokenStream) -> io::Result<Self> {
+        let left = Self::parse_additive(tokens)?;
+
+        if let Some(operator) = tokens.peek().and_then(BinaryOperator::from_comparison_token) {
+            tokens.next_token();
+            let right = Self::parse_additive(tokens)?;
+
+            return Ok(Expression::Binary(Box::new(left), operator, Box::new(right)));
+        }
+
+        if tokens.consume_keyword("is") {
+            let operator = if tokens.consume_keyword("not") { BinaryOperator::IsNot } else { BinaryOperator::Is };
+            let right = Self::parse_additive(tokens)?;
+
+            return Ok(Expression::Binary(Box::new(left), operator, Box::new(right)));
+        }
+
+        // x BETWEEN a AND b is the same as x >= a AND x <= b
+        if tokens.consume_keyword("between") {
+            let lower = Self::parse_additive(tokens)?;
+            tokens.expect_keyword("and")?;
+            let upper = Self::parse_additive(tokens)?;
+
+            return Ok(Expression::Binary(
+                Box::new(Expression::Binary(
+                    Box::new(left.clone()),
+                    BinaryOperator::GreaterOrEqual,
+                    Box::new(lower),
+                )),
+                BinaryOperator::And,
+                Box::new(Expression::Binary(
+                    Box::new(left),
+                    BinaryOperator::LessOrEqual,
+                    Box::new(upper),
+                )),
+            ));
+        }
+
+        let negated = tokens.is_keyword("not") && tokens.is_nth_keyword(1, "in");
+
+        if negated {
+            tokens.expect_keyword("not")?;
+        }
+
+        // x [NOT] IN (expression, ...) | x [NOT] IN (select_statement)
+        if tokens.consume_keyword("in") {
+            if SelectStatement::is_nth_select(tokens, 1) {
+                return Ok(Expression::InSubquery {
+                    expression: Box::new(left),
+                    subquery: Box::new(Self::parse_subquery(tokens)?),
+                    negated,
+                });
+            }
+
+            return Ok(Expression::InList {
+                expression: Box::new(left),
+                list: Self::parse_arguments(tokens)?,
+                negated,
+            });
+        }
+
+        Ok(left)
+    }
+
+    /// The parenthesized SELECT statement
+    fn parse_subquery(tokens: &mut TokenStream) -> io::Result<SelectStatement> {
+        tokens.expect(&Token::LeftParen)?;
+        let subquery = SelectStatement::new(tokens)?;
+        tokens.expect(&Token::RightParen)?;
+
+        Ok(subquery)
+    }
+
+    fn parse_additive(tokens: &mut TokenStream) -> io::Result<Self> {
+        let mut left = Self::parse_multiplicative(tokens)?;
+
+        while let Some(operator) = tokens.peek().and_then(BinaryOperator::from_additive_token) {
+            tokens.next_token();
+            let right = Self::parse_multiplicative(tokens)?;
+            left = Expression::Binary(Box::new(left), operator, Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_multiplicative(tokens: &mut TokenStream) -> io::Result<Self> {
+        let mut left = Self::parse_concat(tokens)?;
+
+        while let Some(operator) = tokens.peek().and_then(BinaryOperator::from_multiplicative_token) {
+            tokens.next_token();
+            let right = Self::parse_concat(tokens)?;
+            left = Expression::Binary(Box::new(left), operator, Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_concat(tokens: &mut TokenStream) -> io::Result<Self> {
+        let mut left = Self::parse_unary(tokens)?;
+
+        while tokens.consume(&Token::Concat) {
+            let right = Self::parse_unary(tokens)?;
+            left = Expression::Binary(Box::new(left), BinaryOperator::Concat, Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    pub fn parse_unary(tokens: &mut TokenStream) -> io::Result<Self> {
+        if tokens.consume(&Token::Minus) {
+            let operand = Self::parse_unary(tokens)?;
+            return Ok(Expression::Unary(UnaryOperator::Minus, Box::new(operand)));
+        }
+
+        // The unary plus doesn't change the value
+        if tokens.consume(&Token::Plus) {
+            return Self::parse_unary(tokens);
+        }
+
+        Self::parse_primary(tokens)
+    }
+
+    fn parse_primary(tokens: &mut TokenStream) -> io::Result<Self> {
+        if tokens.consume_keyword("null") {
+            return Ok(Expression::Literal(Value::Null));
+        }
+
+        if tokens.consume_keyword("case") {
+            return Self::parse_case(tokens);
+        }
+
+        // EXISTS (select_statement)
+        if tokens.consume_keyword("exists") {
+            return Ok(Expression::Exists(Box::new(Self::parse_subquery(tokens)?)));
+        }
+
+        // CAST(expression AS type)
+        if tokens.consume_keyword("cast") {
+            tokens.expect(&Token::LeftParen)?;
+            let expression = Self::parse(tokens)?;
+            tokens.expect_keyword("as")?;
+            let column_type = ColumnType::parse(tokens)?;
+            tokens.expect(&Token::RightParen)?;
+
+            return Ok(Expression::Cast {
+                expression: Box::new(expression),
+                column_type,
+            });
+        }
+
+        match tokens.peek() {
+            Some(Token::Integer(value)) => {
+                let value = *value;
+                tokens.next_token();
+
+                Ok(Expression::Literal(Value::Integer(value)))
+            }
+            Some(Token::Real(value)) => {
+                let value = *value;
+                tokens.next_token();
+
+                Ok(Expression::Literal(Value::Real(value)))
+            }
+            Some(Token::Text(value)) => {
+                let value = value.clone();
+                tokens.next_token();
+
+                Ok(Expression::Literal(Value::Text(value)))
+            }
+            Some(Token::LeftParen) if SelectStatement::is_nth_select(tokens, 1) => {
+                Ok(Expression::Subquery(Box::new(Self::parse_subquery(tokens)?)))
+            }
+            Some(Token::LeftParen) => {
+                tokens.next_token();
+                let expression = Self::parse(tokens)?;
+                tokens.expect(&Token::RightParen)?;
+
+                Ok(expression)
+            }
+            Some(Token::Word(_) | Token::QuotedIdentifier(_)) => {
+                let name = tokens.identifier()?;
+
+                if tokens.peek() == Some(&Token::LeftParen) {
+                    if let Some(function) = AggregateFunction::from_name(&name) {
+                        let aggregate = Self::parse_aggregate(tokens, function)?;
+
+                        return match tokens.is_keyword("over") {
+                            true => Self::parse_aggregate_window(tokens, aggregate),
+                            false => Ok(aggregate),
+                        };
+                    }
+
+                    if let Some(function) = WindowFunction::from_name(&name) {
+                        let arguments = Self::parse_arguments(tokens)?;
+                        function.check_num_arguments(arguments.len())?;
+
+                        return Self::parse_window(tokens, function, arguments);
+                    }
+
+                    return Ok(Expression::Function {
+                        name,
+                        arguments: Self::parse_arguments(tokens)?,
+                    });
+                }
+
+                if tokens.consume(&Token::Dot) {
+                    return Ok(Expression::Column {
+                        table_name: Some(name),
+                        column_name: tokens.identifier()?,
+                    });
+                }
+
+                Ok(Expression::Column {
+                    table_name: None,
+                    column_name: name,
+                })
+            }
+            _ => Err(tokens.unexpected_token_error("expression")),
+        }
+    }
+
+    /// CASE [operand] WHEN expression THEN expression ... [ELSE expression] END,
+    /// the CASE keyword is already consumed
+    fn parse_case(tokens: &mut TokenStream) -> io::Result<Self> {
+        let operand = if tokens.is_keyword("when") {
+            None
+        } else {
+            Some(Box::new(Self::parse(tokens)?))
+        };
+
+        let mut when_clauses = Vec::new();
+
+        while tokens.consume_keyword("when") {
+            let condition = Self::parse(tokens)?;
+            tokens.expect_keyword("then")?;
+            let result = Self::parse(tokens)?;
+
+            when_clauses.push((condition, result));
+        }
+
+        if when_clauses.is_empty() {
+            return Err(tokens.unexpected_token_error("WHEN"));
+        }
+
+        let else_clause = if tokens.consume_keyword("else") {
+            Some(Box::new(Self::parse(tokens)?))
+        } else {
+            None
+        };
+
+        tokens.expect_keyword("end")?;
+
+        Ok(Expression::Case {
+            operand,
+            when_clauses,
+            else_clause,
+        })
+    }
+
+    /// The method parses the parenthesized comma separated function arguments
+    fn parse_arguments(tokens: &mut TokenStream) -> io::Result<Vec<Self>> {
+        tokens.expect(&Token::LeftParen)?;
+
+        let mut arguments = Vec::new();
+
+        if tokens.consume(&Token::RightParen) {
+            return Ok(arguments);
+        }
+
+        loop {
+            arguments.push(Self::parse(tokens)?);
+
+            if !tokens.consume(&Token::Comma) {
+                break;
+            }
+        }
+
+        tokens.expect(&Token::RightParen)?;
+
+        Ok(arguments)
+    }
+
+    /// function([DISTINCT] expression, ...) or COUNT(*)
+    fn parse_aggregate(tokens: &mut TokenStream, function: AggregateFunction) -> io::Result<Self> {
+        if function == AggregateFunction::Count && tokens.peek_nth(1) == Some(&Token::Asterisk) {
+            tokens.expect(&Token::LeftParen)?;
+            tokens.expect(&Token::Asterisk)?;
+            tokens.expect(&Token::RightParen)?;
+
+            return Ok(Expression::Aggregate {
+                function,
+                arguments: Vec::new(),
+                distinct: false,
+            });
+        }
+
+        let distinct = tokens.is_nth_keyword(1, "distinct");
+
+        if distinct {
+            tokens.expect(&Token::LeftParen)?;
+            tokens.expect_keyword("distinct")?;
+
+            let argument = Self::parse(tokens)?;
+            tokens.expect(&Token::RightParen)?;
+
+            // DISTINCT makes no sense for the separator of GROUP_CONCAT, so it's allowed with one argument only
+            return Ok(Expression::Aggregate {
+                function,
+                arguments: vec![argument],
+                distinct,
+            });
+        }
+
+        let arguments = Self::parse_arguments(tokens)?;
+
+        if arguments.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Wrong number of arguments to function {}()", function),
+            ));
+        }
+
+        function.check_num_arguments(arguments.len())?;
+
+        Ok(Expression::Aggregate {
+            function,
+            arguments,
+            distinct,
+        })
+    }
+
+    /// function(expression, ...) OVER window_definition, the function is already parsed
+    fn parse_window(tokens: &mut TokenStream, function: WindowFunction, arguments: Vec<Expression>) -> io::Result<Self> {
+        if !tokens.is_keyword("over") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Misuse of window function {}(): OVER is expected", function),
+            ));
+        }
+
+        tokens.expect_keyword("over")?;
+
+        Ok(Expression::Window {
+            function,
+            arguments,
+            window: Box::new(WindowDefinition::parse(tokens)?),
+        })
+    }
+
+    /// aggregate OVER window_definition, the aggregate is computed over the frame of every row
+    fn parse_aggregate_window(tokens: &mut TokenStream, aggregate: Expression) -> io::Result<Self> {
+        let Expression::Aggregate { function, arguments, distinct } = aggregate else {
+            unreachable!("The expression {} is not an aggregate", aggregate);
+        };
+
+        if distinct {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "DISTINCT is not supported for window functions",
+            ));
+        }
+
+        Self::parse_window(tokens, WindowFunction::Aggregate(function), arguments)
+    }
+
+    fn evaluate_function(name: &str, arguments: &[Expression], row: &dyn RowSource) -> io::Result<Value> {
+        let check_num_arguments = |num_arguments: usize| {
+            if arguments.len() != num_arguments {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Wrong number of arguments to function {}()", name),
+                ));
+            }
+
+            Ok(())
+        };
+
+        match name {
+            "last_insert_rowid" => {
+                check_num_arguments(0)?;
+
+                Ok(Value::Integer(row.get_session().last_insert_rowid))
+            },
+            // The arguments are evaluated only until the first not NULL value
+            "coalesce" | "ifnull" => {
+                if name == "ifnull" {
+                    check_num_arguments(2)?;
+                } else if arguments.len() < 2 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Wrong number of arguments to function {}()", name),
+                    ));
+                }
+
+                for argument in arguments.iter() {
+                    let value = argument.evaluate(row)?;
+
+                    if !value.is_null() {
+                        return Ok(value);
+                    }
+                }
+
+                Ok(Value::Null)
+            },
+            "nullif" => {
+                check_num_arguments(2)?;
+
+                let value = arguments[0].evaluate(row)?;
+                let other_value = arguments[1].evaluate(row)?;
+
+                if !value.is_null() && value == other_value {
+                    return Ok(Value::Null);
+                }
+
+                Ok(value)
+            },
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("No such function: {}", name),
+            )),
+        }
+    }
+
+    pub fn evaluate(&self, row: &dyn RowSource) -> io::Result<Value> {
+        match self {
+            Expression::Literal(value) => Ok(value.clone()),
+            Expression::Column { table_name, column_name } => {
+                row.get_column_value(table_name.as_deref(), column_name)
+            }
+            Expression::Unary(operator, operand) => Self::evaluate_unary(*operator, operand.evaluate(row)?),
+            Expression::Binary(left, operator, right) => {
+                let left_value = left.evaluate(row)?;
+
+                // Short-circuit evaluation for the logical operators
+                match operator {
+                    BinaryOperator::And if Self::is_false(&left_value) => return Ok(Value::from(false)),
+                    BinaryOperator::Or if left_value.is_true() => return Ok(Value::from(true)),
+                    _ => {}
+                }
+
+                Self::evaluate_binary(left_value, *operator, right.evaluate(row)?)
+            }
+            Expression::Function { name, arguments } => Self::evaluate_function(name, arguments, row),
+            Expression::Aggregate { .. } => row.get_aggregate_value(self),
+            Expression::Window { .. } => row.get_window_value(self),
+            Expression::Case { operand, when_clauses, else_clause } => {
+                let operand_value = match operand {
+                    Some(operand) => Some(operand.evaluate(row)?),
+                    None => None,
+                };
+
+                for (condition, result) in when_clauses.iter() {
+                    let value = condition.evaluate(row)?;
+
+                    let is_matched = match &operand_value {
+                        Some(operand_value) => !operand_value.is_null() && *operand_value == value,
+                        None => value.is_true(),
+                    };
+
+                    if is_matched {
+                        return result.evaluate(row);
+                    }
+                }
+
+                match else_clause {
+                    Some(else_clause) => else_clause.evaluate(row),
+                    None => Ok(Value::Null),
+                }
+            }
+            Expression::Cast { expression, column_type } => Ok(Self::cast(expression.evaluate(row)?, *column_type)),
+            Expression::Subquery(subquery) => {
+                let result = row.get_subquery_result(subquery)?;
+                result.check_num_columns()?;
+
+                Ok(result.values.first().cloned().unwrap_or(Value::Null))
+            }
+            Expression::Exists(subquery) => Ok(Value::from(!row.get_subquery_result(subquery)?.values.is_empty())),
+            Expression::InList { expression, list, negated } => {
+                let value = expression.evaluate(row)?;
+                let mut is_found = false;
+                let mut has_null = false;
+
+                for item in list.iter() {
+                    let item_value = item.evaluate(row)?;
+
+                    if item_value.is_null() {
+                        has_null = true;
+                    } else if item_value == value {
+                        is_found = true;
+                        break;
+                    }
+                }
+
+                Self::evaluate_in(&value, list.is_empty(), is_found, has_null, *negated)
+            }
+            Expression::InSubquery { expression, subquery, negated } => {
+                let value = expression.evaluate(row)?;
+                let result = row.get_subquery_result(subquery)?;
+                result.check_num_columns()?;
+
+                let is_found = result.value_set.contains(&value);
+
+                Self::evaluate_in(&value, result.values.is_empty(), is_found, result.has_null, *negated)
+            }
+        }
+    }
+
+    /// IN is false for the empty list, otherwise it's unknown for NULL or if the value
+    /// isn't found in the list with NULLs, as any of them could be equal to the value
+    fn evaluate_in(value: &Value, is_empty: bool, is_found: bool, has_null: bool, negated: bool) -> io::Result<Value> {
+        let result = match is_empty {
+            true => Value::from(false),
+            false if value.is_null() => Value::Null,
+            false if is_found => Value::from(true),
+            false if has_null => Value::Null,
+            false => Value::from(false),
+        };
+
+        match negated {
+            true => Self::evaluate_unary(UnaryOperator::Not, result),
+            false => Ok(result),
+        }
+    }
+
+    pub fn evaluate_unary(operator: UnaryOperator, value: Value) -> io::Result<Value> {
+        match (operator, value) {
+            (_, Value::Null) => Ok(Value::Null),
+            (UnaryOperator::Not, value) => Ok(Value::from(!value.is_true())),
+            (UnaryOperator::Minus, Value::Integer(value)) => Ok(Value::Integer(-value)),
+            (UnaryOperator::Minus, Value::Real(value)) => Ok(Value::Real(-value)),
+            (UnaryOperator::Minus, value) => Self::evaluate_arithmetic(Value::Integer(0), BinaryOperator::Subtract, value),
+        }
+    }
+
+    /// The method applies the operator to the values of both operands
+    pub fn evaluate_binary(left_value: Value, operator: BinaryOperator, right_value: Value) -> io::Result<Value> {
+        if operator.is_arithmetic() {
+            return Self::evaluate_arithmetic(left_value, operator, right_value);
+        }
+
+        // The three-valued logic: NULL is an unknown value, so the result is unknown
+        // unless it's the same for any value
+        let result = match operator {
+            BinaryOperator::Is => left_value == right_value,
+            BinaryOperator::IsNot => left_value != right_value,
+            BinaryOperator::And if Self::is_false(&left_value) || Self::is_false(&right_value) => false,
+            BinaryOperator::Or if left_value.is_true() || right_value.is_true() => true,
+            _ if left_value.is_null() || right_value.is_null() => return Ok(Value::Null),
+            BinaryOperator::Equal => left_value == right_value,
+            BinaryOperator::NotEqual => left_value != right_value,
+            BinaryOperator::Less => left_value < right_value,
+            BinaryOperator::LessOrEqual => left_value <= right_value,
+            BinaryOperator::Greater => left_value > right_value,
+            BinaryOperator::GreaterOrEqual => left_value >= right_value,
+            BinaryOperator::And => true,
+            BinaryOperator::Or => false,
+            _ => unreachable!("The operator {} is arithmetic", operator),
+        };
+
+        Ok(Value::from(result))
+    }
+
+    /// The integer operations which overflow are computed with the reals,
+    /// the division by zero gives NULL
+    fn evaluate_arithmetic(left_value: Value, operator: BinaryOperator, right_value: Value) -> io::Result<Value> {
+        if left_value.is_null() || right_value.is_null() {
+            return Ok(Value::Null);
+        }
+
+        if operator == BinaryOperator::Concat {
+            return Ok(Value::Text(format!("{}{}", left_value, right_value)));
+        }
+
+        let result = match (left_value.to_numeric(), right_value.to_numeric()) {
+            (Value::Integer(left), Value::Integer(right)) => {
+                let result = match operator {
+                    BinaryOperator::Add => left.checked_add(right),
+                    BinaryOperator::Subtract => left.checked_sub(right),
+                    BinaryOperator::Multiply => left.checked_mul(right),
+                    BinaryOperator::Divide if right == 0 => return Ok(Value::Null),
+                    BinaryOperator::Divide => left.checked_div(right),
+                    BinaryOperator::Remainder if right == 0 => return Ok(Value::Null),
+                    BinaryOperator::Remainder => Some(left.wrapping_rem(right)),
+                    _ => unreachable!("The operator {} is not arithmetic", operator),
+                };
+
+                match result {
+                    Some(result) => Value::Integer(result),
+                    None => Self::evaluate_real_arithmetic(left as f64, operator, right as f64),
+                }
+            },
+            (left, right) => Self::evaluate_real_arithmetic(left.to_real(), operator, right.to_real()),
+        };
+
+        Ok(result)
+    }
+
+    fn evaluate_real_arithmetic(left: f64, operator: BinaryOperator, right: f64) -> Value {
+        match operator {
+            BinaryOperator::Add => Value::Real(left + right),
+            BinaryOperator::Subtract => Value::Real(left - right),
+            BinaryOperator::Multiply => Value::Real(left * right),
+            BinaryOperator::Divide if right == 0.0 => Value::Null,
+            BinaryOperator::Divide => Value::Real(left / right),
+            // The remainder is computed for the integer parts of the operands
+            BinaryOperator::Remainder if right as i64 == 0 => Value::Null,
+            BinaryOperator::Remainder => Value::Real((left as i64).wrapping_rem(right as i64) as f64),
+            _ => unreachable!("The operator {} is not arithmetic", operator),
+        }
+    }
+
+    /// The method converts the value to the type, the texts are converted using their longest numeric prefix
+    pub fn cast(value: Value, column_type: ColumnType) -> Value {
+        match (column_type, value) {
+            (_, Value::Null) => Value::Null,
+            (ColumnType::Text, value) => Value::Text(value.to_string()),
+            (ColumnType::Integer, Value::Integer(value)) => Value::Integer(value),
+            (ColumnType::Integer, Value::Real(value)) => Value::Integer(value as i64),
+            (ColumnType::Real, value @ (Value::Integer(_) | Value::Real(_))) => Value::Real(value.to_real()),
+            (column_type, Value::Text(text)) => {
+                let prefix = Value::get_numeric_prefix(&text);
+
+                match column_type {
+                    ColumnType::Integer => match prefix.parse::<i64>() {
+                        Ok(integer) => Value::Integer(integer),
+                        Err(_) => Value::Integer(prefix.parse::<f64>().map_or(0, |real| real as i64)),
+                    },
+                    _ => Value::Real(prefix.parse::<f64>().unwrap_or(0.0)),
+                }
+            },
+        }
+    }
+
+    /// The method returns the aggregate functions used in the expression, the nested aggregates are not allowed
+    pub fn get_aggregates(&self) -> io::Result<Vec<&Expression>> {
+        let mut aggregates = Vec::new();
+        self.collect_aggregates(&mut aggregates)?;
+
+        Ok(aggregates)
+    }
+
+    fn collect_aggregates<'a>(&'a self, aggregates: &mut Vec<&'a Expression>) -> io::Result<()> {
+        let Expression::Aggregate { arguments, .. } = self else {
+            for child in self.get_children() {
+                child.collect_aggregates(aggregates)?;
+            }
+
+            return Ok(());
+        };
+
+        for argument in arguments.iter() {
+            if let Some(Expression::Aggregate { function, .. }) = argument.get_aggregates()?.first() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Misuse of aggregate function {}()", function),
+                ));
+            }
+        }
+
+        if !aggregates.contains(&self) {
+            aggregates.push(self);
+        }
+
+        Ok(())
+    }
+
+    /// The method returns the window functions used in the expression, the nested window functions are not allowed
+    pub fn get_windows(&self) -> io::Result<Vec<&Expression>> {
+        let mut windows = Vec::new();
+        self.collect_windows(&mut windows)?;
+
+        Ok(windows)
+    }
+
+    fn collect_windows<'a>(&'a self, windows: &mut Vec<&'a Expression>) -> io::Result<()> {
+        let Expression::Window { function, .. } = self else {
+            for child in self.get_children() {
+                child.collect_windows(windows)?;
+            }
+
+            return Ok(());
+        };
+
+        for child in self.get_children() {
+            if !child.get_windows()?.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Misuse of window function {}()", function),
+                ));
+            }
+        }
+
+        if !windows.contains(&self) {
+            windows.push(self);
+        }
+
+        Ok(())
+    }
+
+    /// The method returns the direct subexpressions, the expressions of the subqueries
+    /// belong to the subqueries, so they are not included
+    pub fn get_children(&self) -> Vec<&Expression> {
+        match self {
+            Expression::Literal(_) | Expression::Column { .. } | Expression::Subquery(_) | Expression::Exists(_) => vec![],
+            Expression::Unary(_, operand) => vec![operand],
+            Expression::Binary(left, _, right) => vec![left, right],
+            Expression::Function { arguments, .. } | Expression::Aggregate { arguments, .. } => arguments.iter().collect(),
+            Expression::Window { arguments, window, .. } => arguments.iter().chain(window.get_expressions()).collect(),
+            Expression::Case { operand, when_clauses, else_clause } => operand
+                .iter()
+                .map(Box::as_ref)
+                .chain(when_clauses.iter().flat_map(|(condition, result)| [condition, result]))
+                .chain(else_clause.iter().map(Box::as_ref))
+                .collect(),
+            Expression::Cast { expression, .. } | Expression::InSubquery { expression, .. } => vec![expression],
+            Expression::InList { expression, list, .. } => std::iter::once(expression.as_ref()).chain(list.iter()).collect(),
+        }
+    }
+
+    /// The method returns the direct subexpressions which could be changed
+    pub fn get_children_mut(&mut self) -> Vec<&mut Expression> {
+        match self {
+            Expression::Literal(_) | Expression::Column { .. } | Expression::Subquery(_) | Expression::Exists(_) => vec![],
+            Expression::Unary(_, operand) => vec![operand],
+            Expression::Binary(left, _, right) => vec![left, right],
+            Expression::Function { arguments, .. } | Expression::Aggregate { arguments, .. } => arguments.iter_mut().collect(),
+            Expression::Window { arguments, window, .. } => arguments.iter_mut().chain(window.get_expressions_mut()).collect(),
+            Expression::Case { operand, when_clauses, else_clause } => operand
+                .iter_mut()
+                .map(Box::as_mut)
+                .chain(when_clauses.iter_mut().flat_map(|(condition, result)| [condition, result]))
+                .chain(else_clause.iter_mut().map(Box::as_mut))
+                .collect(),
+            Expression::Cast { expression, .. } | Expression::InSubquery { expression, .. } => vec![expression],
+            Expression::InList { expression, list, .. } => std::iter::once(expression.as_mut()).chain(list.iter_mut()).collect(),
+        }
+    }
+
+    /// The method returns the subqueries of the expression and of its subexpressions
+    pub fn get_subqueries_mut(&mut self) -> Vec<&mut SelectStatement> {
+        match self {
+            Expression::Subquery(subquery) | Expression::Exists(subquery) => vec![subquery],
+            Expression::InSubquery { expression, subquery, .. } => {
+                let mut subqueries = expression.get_subqueries_mut();
+                subqueries.push(subquery);
+
+                subqueries
+            },
+            _ => self.get_children_mut().into_iter().flat_map(Expression::get_subqueries_mut).collect(),
+        }
+    }
+
+    /// The method checks whether the expression runs the subqueries
+    pub fn has_subquery(&self) -> bool {
+        match self {
+            Expression::Subquery(_) | Expression::Exists(_) | Expression::InSubquery { .. } => true,
+            _ => self.get_children().into_iter().any(Expression::has_subquery),
+        }
+    }
+
+    fn is_false(value: &Value) -> bool {
+        !value.is_null() && !value.is_true()
+    }
+
+    /// The method splits the expression by the top level AND operators
+    pub fn get_conjuncts(&self) -> Vec<&Expression> {
+        match self {
+            Expression::Binary(left, BinaryOperator::And, right) => {
+                let mut conjuncts = left.get_conjuncts();
+                conjuncts.extend(right.get_conjuncts());
+
+                conjuncts
+            }
+            _ => vec![self],
+        }
+    }
+
+    /// The method returns the names of all the columns used in the expression
+    pub fn get_column_names(&self) -> Vec<&str> {
+        if let Expression::Column { column_name, .. } = self {
+            return vec![column_name];
+        }
+
+        let mut column_names = Vec::new();
+
+        for column_name in self.get_children().into_iter().flat_map(Expression::get_column_names) {
+            if !column_names.contains(&column_name) {
+                column_names.push(column_name);
+            }
+        }
+
+        column_names
+    }
+}
+
+impl fmt::Display for Expression {
+    /// The expression is written back as SQL, so it could be stored and parsed again
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The nested binary expressions are wrapped into parentheses to keep the precedence
+        let write_operand = |f: &mut fmt::Formatter<'_>, operand: &Expression| match operand {
+            Expression::Binary(..) | Expression::InList { .. } | Expression::InSubquery { .. } => write!(f, "({})", operand),
+            _ => write!(f, "{}", operand),
+        };
+
+        match self {
+            Expression::Literal(value) => write!(f, "{}", value.to_sql()),
+            Expression::Column { table_name: Some(table_name), column_name } => {
+                write!(f, "{}.{}", TokenStream::quote_identifier(table_name), TokenStream::quote_identifier(column_name))
+            }
+            Expression::Column { table_name: None, column_name } => write!(f, "{}", TokenStream::quote_identifier(column_name)),
+            Expression::Unary(UnaryOperator::Not, operand) => {
+                write!(f, "NOT ")?;
+                write_operand(f, operand)
+            }
+            // The double minus would start the comment
+            Expression::Unary(UnaryOperator::Minus, operand) if operand.to_string().starts_with('-') => write!(f, "-({})", operand),
+            Expression::Unary(UnaryOperator::Minus, operand) => {
+                write!(f, "-")?;
+                write_operand(f, operand)
+            }
+            Expression::Binary(left, operator, right) => {
+                write_operand(f, left)?;
+                write!(f, " {} ", operator)?;
+                write_operand(f, right)
+            }
+            Expression::Function { name, arguments } => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).collect();
+
+                write!(f, "{}({})", name, arguments.join(", "))
+            }
+            Expression::Aggregate { function, arguments, distinct } => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).collect();
+
+                match (arguments.is_empty(), distinct) {
+                    (true, _) => write!(f, "{}(*)", function),
+                    (false, true) => write!(f, "{}(DISTINCT {})", function, arguments.join(", ")),
+                    (false, false) => write!(f, "{}({})", function, arguments.join(", ")),
+                }
+            }
+            Expression::Window { function, arguments, window } => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).collect();
+
+                match (function, arguments.is_empty()) {
+                    (WindowFunction::Aggregate(_), true) => write!(f, "{}(*) OVER {}", function, window),
+                    _ => write!(f, "{}({}) OVER {}", function, arguments.join(", "), window),
+                }
+            }
+            Expression::Case { operand, when_clauses, else_clause } => {
+                write!(f, "CASE")?;
+
+                if let Some(operand) = operand {
+                    write!(f, " {}", operand)?;
+                }
+
+                for (condition, result) in when_clauses.iter() {
+                    write!(f, " WHEN {} THEN {}", condition, result)?;
+                }
+
+                if let Some(else_clause) = else_clause {
+                    write!(f, " ELSE {}", else_clause)?;
+                }
+
+                write!(f, " END")
+            }
+            Expression::Cast { expression, column_type } => write!(f, "CAST({} AS {})", expression, column_type),
+            Expression::Subquery(subquery) => write!(f, "({})", subquery),
+            Expression::Exists(subquery) => write!(f, "EXISTS ({})", subquery),
+            Expression::InList { expression, list, negated } => {
+                let list: Vec<String> = list.iter().map(|item| item.to_string()).collect();
+
+                write_operand(f, expression)?;
+                write!(f, " {}IN ({})", if *negated { "NOT " } else { "" }, list.join(", "))
+            }
+            Expression::InSubquery { expression, subquery, negated } => {
+                write_operand(f, expression)?;
+                write!(f, " {}IN ({})", if *negated { "NOT " } else { "" }, subquery)
+            }
+        }
+    }
+}
+
+/// The row source for the constant expressions like column defaults
+pub struct EmptyRow<'a> {
+    pub session: &'a Session,
+}
+
+impl RowSource for EmptyRow<'_> {
+    fn get_column_value(&self, _table_name: Option<&str>, column_name: &str) -> io::Result<Value> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("The column {} can't be used in a constant expression", column_name),
+        ))
+    }
+
+    fn get_session(&self) -> &Session {
+        self.session
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(expression_str: &str) -> Value {
+        let mut tokens = TokenStream::new(expression_str).unwrap();
+        let expression = Expression::parse(&mut tokens).unwrap();
+
+        expression.evaluate(&EmptyRow { session: &Session::default() }).unwrap()
+    }
+
+    #[test]
+    fn it_should_use_three_valued_logic() {
+        assert!(evaluate("null = null").is_null());
+        assert!(evaluate("1 < null").is_null());
+        assert!(evaluate("not null").is_null());
+        assert!(evaluate("null and 1").is_null());
+        assert!(evaluate("null or 0").is_null());
+        assert!(evaluate("2 between null and 3").is_null());
+
+        assert_eq!(evaluate("null and 0"), Value::Integer(0));
+        assert_eq!(evaluate("0 and null"), Value::Integer(0));
+        assert_eq!(evaluate("null or 1"), Value::Integer(1));
+        assert_eq!(evaluate("5 between null and 3"), Value::Integer(0));
+    }
+
+    #[test]
+    fn it_should_compare_nulls_with_is() {
+        assert_eq!(evaluate("null is null"), Value::Integer(1));
+        assert_eq!(evaluate("1 is null"), Value::Integer(0));
+        assert_eq!(evaluate("1 is not null"), Value::Integer(1));
+        assert_eq!(evaluate("'a' is 'a'"), Value::Integer(1));
+    }
+
+    #[test]
+    fn it_should_evaluate_null_functions() {
+        assert_eq!(evaluate("coalesce(null, null, 3, 4)"), Value::Integer(3));
+        assert!(evaluate("coalesce(null, null)").is_null());
+        assert_eq!(evaluate("ifnull(null, 'x')"), Value::Text("x".to_string()));
+        assert!(evaluate("nullif(1, 1)").is_null());
+        assert_eq!(evaluate("nullif(1, 2)"), Value::Integer(1));
+
+        let mut tokens = TokenStream::new("ifnull(1)").unwrap();
+        let expression = Expression::parse(&mut tokens).unwrap();
+        assert!(expression.evaluate(&EmptyRow { session: &Session::default() }).is_err());
+    }
+
+    #[test]
+    fn it_should_evaluate_arithmetic() {
+        assert_eq!(evaluate("1 + 2 * 3 - 4"), Value::Integer(3));
+        assert_eq!(evaluate("(1 + 2) * 3"), Value::Integer(9));
+        assert_eq!(evaluate("7 / 2"), Value::Integer(3));
+        assert_eq!(evaluate("7 / 2.0"), Value::Real(3.5));
+        assert_eq!(evaluate("-7 % 3"), Value::Integer(-1));
+        assert_eq!(evaluate("'3' + 4"), Value::Integer(7));
+        assert_eq!(evaluate("9223372036854775807 + 1"), Value::Real(9223372036854775808.0));
+        assert!(evaluate("1 / 0").is_null());
+        assert!(evaluate("1 + null").is_null());
+
+        assert_eq!(evaluate("'a' || 1 || 2.5"), Value::Text("a12.5".to_string()));
+        assert_eq!(evaluate("1 + 2 || 3"), Value::Integer(24));
+        assert!(evaluate("'a' || null").is_null());
+    }
+
+    #[test]
+    fn it_should_evaluate_case_and_cast() {
+        assert_eq!(evaluate("case when 1 > 2 then 'a' when 2 > 1 then 'b' else 'c' end"), Value::Text("b".to_string()));
+        assert_eq!(evaluate("case 2 when 1 then 'one' when 2 then 'two' end"), Value::Text("two".to_string()));
+        assert!(evaluate("case null when null then 1 end").is_null());
+
+        assert_eq!(evaluate("cast(' 12abc' as integer)"), Value::Integer(12));
+        assert_eq!(evaluate("cast('abc' as integer)"), Value::Integer(0));
+        assert_eq!(evaluate("cast('-2.5e1x' as real)"), Value::Real(-25.0));
+        assert_eq!(evaluate("cast(3.9 as integer)"), Value::Integer(3));
+        assert_eq!(evaluate("cast(3 as real)"), Value::Real(3.0));
+        assert_eq!(evaluate("cast(2.0 as text)"), Value::Text("2.0".to_string()));
+        assert!(evaluate("cast(null as text)").is_null());
+    }
+
+    #[test]
+    fn it_should_convert_numeric_texts_to_booleans() {
+        assert_eq!(evaluate("case when '0.5' then 1 else 0 end"), Value::Integer(1));
+        assert_eq!(evaluate("not '1e3'"), Value::Integer(0));
+        assert_eq!(evaluate("' 2abc' and 1"), Value::Integer(1));
+        assert_eq!(evaluate("'0.0' or 'abc'"), Value::Integer(0));
+    }
+
+    #[test]
+    fn it_should_compare_negative_zero() {
+        assert_eq!(evaluate("-0.0 = 0.0"), Value::Integer(1));
+        assert_eq!(evaluate("-0.0 < 0.0"), Value::Integer(0));
+        assert_eq!(evaluate("-0.0 = 0"), Value::Integer(1));
+        assert_eq!(evaluate("-0.0 in (0.0)"), Value::Integer(1));
+        assert_eq!(Value::Real(-0.0).cmp(&Value::Real(0.0)), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn it_should_evaluate_in_lists() {
+        assert_eq!(evaluate("2 in (1, 2, 3)"), Value::Integer(1));
+        assert_eq!(evaluate("'2' in (1, 2)"), Value::Integer(0));
+        assert_eq!(evaluate("4 not in (1, 2, 3)"), Value::Integer(1));
+        assert_eq!(evaluate("null in ()"), Value::Integer(0));
+        assert!(evaluate("null in (1)").is_null());
+        assert!(evaluate("4 in (1, null)").is_null());
+        assert!(evaluate("4 not in (1, null)").is_null());
+        assert_eq!(evaluate("1 in (1, null)"), Value::Integer(1));
+    }
+
+    #[test]
+    fn it_should_write_expressions_back_as_sql() {
+        let write = |expression_str: &str| {
+            let expression = Expression::parse(&mut TokenStream::new(expression_str).unwrap()).unwrap();
+            let sql = expression.to_string();
+
+            // The written expression is parsed back to the same one
+            assert_eq!(Expression::parse(&mut TokenStream::new(&sql).unwrap()).unwrap().to_string(), sql);
+
+            sql
+        };
+
+        assert_eq!(write("-(-a)"), "-(-a)");
+        assert_eq!(write("- -1"), "-(-1)");
+        assert_eq!(write("\"1\" + 1"), "\"1\" + 1");
+        assert_eq!(write("'it''s' || \"select\""), "'it''s' || \"select\"");
+        assert_eq!(evaluate(&write("- - 2")), Value::Integer(2));
+    }
+}
@@ -1,17 +1,293 @@
+use std::fmt;
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+use crate::parser::tokenizer::Token;
+use super::expression::Expression;
+use super::select_statement::{format_returning, parse_returning, ResultColumn, SelectStatement};
+use super::update_statement::{format_assignments, parse_assignments};
+
+/// The way INSERT resolves the conflicts with the constraints
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictResolution {
+    // The rows inserted by the statement are removed
+    Abort,
+    // The rows inserted by the statement before the failed one are kept
+    Fail,
+    // The row is skipped
+    Ignore,
+    // The conflicting rows are deleted before the row is inserted
+    Replace,
+    // The transaction is rolled back, the statement is aborted outside of the transaction
+    Rollback,
+}
+
+impl ConflictResolution {
+    /// ABORT | FAIL | IGNORE | REPLACE | ROLLBACK
+    fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        let resolutions = [
+            ("abort", ConflictResolution::Abort),
+            ("fail", ConflictResolution::Fail),
+            ("ignore", ConflictResolution::Ignore),
+            ("replace", ConflictResolution::Replace),
+            ("rollback", ConflictResolution::Rollback),
+        ];
+
+        for (keyword, resolution) in resolutions {
+            if tokens.consume_keyword(keyword) {
+                return Ok(resolution);
+            }
+        }
+
+        Err(tokens.unexpected_token_error("ABORT, FAIL, IGNORE, REPLACE or ROLLBACK"))
+    }
+}
+
+impl fmt::Display for ConflictResolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictResolution::Abort => write!(f, "ABORT"),
+            ConflictResolution::Fail => write!(f, "FAIL"),
+            ConflictResolution::Ignore => write!(f, "IGNORE"),
+            ConflictResolution::Replace => write!(f, "REPLACE"),
+            ConflictResolution::Rollback => write!(f, "ROLLBACK"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum UpsertAction {
+    Nothing,
+    // The columns of the inserted row are qualified by "excluded"
+    Update {
+        assignments: Vec<(String, Expression)>,
+        where_clause: Option<Expression>,
+    },
+}
+
+/// The ON CONFLICT clause of INSERT
+#[derive(Debug, Clone)]
+pub struct Upsert {
+    // The columns of the PRIMARY KEY or of the UNIQUE constraint, the clause handles
+    // the conflicts of all the constraints if there are no columns
+    pub target: Vec<String>,
+    pub action: UpsertAction,
+}
+
+impl Upsert {
+    /// ON CONFLICT [(column, ...)] DO {NOTHING | UPDATE SET column = expression, ... [WHERE expression]}
+    fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("on")?;
+        tokens.expect_keyword("conflict")?;
+
+        let target = if tokens.peek() == Some(&Token::LeftParen) {
+            tokens.identifier_list()?
+        } else {
+            Vec::new()
+        };
+
+        tokens.expect_keyword("do")?;
+
+        let action = if tokens.consume_keyword("nothing") {
+            UpsertAction::Nothing
+        } else {
+            tokens.expect_keyword("update")?;
+
+            let assignments = parse_assignments(tokens)?;
+
+            let where_clause = if tokens.consume_keyword("where") {
+                Some(Expression::parse(tokens)?)
+            } else {
+                None
+            };
+
+            UpsertAction::Update { assignments, where_clause }
+        };
+
+        Ok(Upsert { target, action })
+    }
+
+    /// The expressions of DO UPDATE refer to the columns of the table
+    /// and to the columns of the inserted row qualified by "excluded"
+    pub fn get_expressions_mut(&mut self) -> Vec<&mut Expression> {
+        match &mut self.action {
+            UpsertAction::Nothing => Vec::new(),
+            UpsertAction::Update { assignments, where_clause } => assignments
+                .iter_mut()
+                .map(|(_, expression)| expression)
+                .chain(where_clause.iter_mut())
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Upsert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ON CONFLICT")?;
+
+        if !self.target.is_empty() {
+            write!(f, " ({})", TokenStream::quote_identifiers(&self.target))?;
+        }
+
+        match &self.action {
+            UpsertAction::Nothing => write!(f, " DO NOTHING"),
+            UpsertAction::Update { assignments, where_clause } => {
+                write!(f, " DO UPDATE SET {}", format_assignments(assignments))?;
+
+                match where_clause {
+                    Some(where_clause) => write!(f, " WHERE {}", where_clause),
+                    None => Ok(()),
+                }
+            },
+        }
+    }
+}
+
+/// The rows inserted by the statement
+#[derive(Debug, Clone)]
+pub enum InsertSource {
+    // Every row has the same number of values
+    Values(Vec<Vec<Expression>>),
+    Select(Box<SelectStatement>),
+    // The single row with the defaults of all the columns
+    DefaultValues,
+}
+
+#[derive(Debug, Clone)]
 pub struct InsertStatement {
-    pub email: String,
-    pub user_name: String,
+    pub table_name: String,
+    // None means all the columns in the table order
+    pub columns: Option<Vec<String>>,
+    pub source: InsertSource,
+    pub conflict_resolution: ConflictResolution,
+    // The first clause matching the conflicting constraint is used
+    pub upserts: Vec<Upsert>,
+    pub returning: Vec<ResultColumn>,
 }
 
 impl InsertStatement {
-    pub fn new(words: &Vec<&str>) -> Self {
-        if words.len() < 2 {
-            panic!("Wrong number of arguments for Insert Statement");
+    /// {INSERT [OR resolution] | REPLACE} INTO table [(column, ...)]
+    /// {VALUES (expression, ...), ... | select | DEFAULT VALUES} [ON CONFLICT ...] ... [RETURNING result_column, ...]
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        let conflict_resolution = if tokens.consume_keyword("replace") {
+            ConflictResolution::Replace
+        } else {
+            tokens.expect_keyword("insert")?;
+
+            match tokens.consume_keyword("or") {
+                true => ConflictResolution::parse(tokens)?,
+                false => ConflictResolution::Abort,
+            }
+        };
+
+        tokens.expect_keyword("into")?;
+
+        let table_name = tokens.identifier()?;
+
+        let columns = if tokens.peek() == Some(&Token::LeftParen) {
+            Some(tokens.identifier_list()?)
+        } else {
+            None
+        };
+
+        let source = if SelectStatement::is_nth_select(tokens, 0) {
+            InsertSource::Select(Box::new(SelectStatement::new(tokens)?))
+        } else if columns.is_none() && tokens.consume_keyword("default") {
+            tokens.expect_keyword("values")?;
+            InsertSource::DefaultValues
+        } else {
+            tokens.expect_keyword("values")?;
+            InsertSource::Values(Self::parse_rows(tokens)?)
+        };
+
+        let mut upserts = Vec::new();
+
+        while tokens.is_keyword("on") {
+            upserts.push(Upsert::parse(tokens)?);
+        }
+
+        if !upserts.is_empty() && matches!(source, InsertSource::DefaultValues) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "ON CONFLICT can't be used with DEFAULT VALUES",
+            ));
+        }
+
+        Ok(InsertStatement {
+            table_name,
+            columns,
+            source,
+            conflict_resolution,
+            upserts,
+            returning: parse_returning(tokens)?,
+        })
+    }
+
+    /// (expression, ...), ...
+    fn parse_rows(tokens: &mut TokenStream) -> io::Result<Vec<Vec<Expression>>> {
+        let mut rows = Vec::new();
+
+        loop {
+            tokens.expect(&Token::LeftParen)?;
+
+            let mut values = vec![Expression::parse(tokens)?];
+
+            while tokens.consume(&Token::Comma) {
+                values.push(Expression::parse(tokens)?);
+            }
+
+            tokens.expect(&Token::RightParen)?;
+
+            if rows.first().is_some_and(|first_row: &Vec<Expression>| first_row.len() != values.len()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "All the rows of VALUES must have the same number of values",
+                ));
+            }
+
+            rows.push(values);
+
+            if !tokens.consume(&Token::Comma) {
+                return Ok(rows);
+            }
+        }
+    }
+}
+
+impl fmt::Display for InsertStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "INSERT")?;
+
+        if self.conflict_resolution != ConflictResolution::Abort {
+            write!(f, " OR {}", self.conflict_resolution)?;
         }
 
-        InsertStatement {
-            email: words[1].to_string(),
-            user_name: words[2].to_string(),
+        write!(f, " INTO {}", TokenStream::quote_identifier(&self.table_name))?;
+
+        if let Some(columns) = &self.columns {
+            write!(f, " ({})", TokenStream::quote_identifiers(columns))?;
         }
+
+        match &self.source {
+            InsertSource::Values(rows) => {
+                let rows: Vec<String> = rows
+                    .iter()
+                    .map(|values| {
+                        let values: Vec<String> = values.iter().map(Expression::to_string).collect();
+                        format!("({})", values.join(", "))
+                    })
+                    .collect();
+
+                write!(f, " VALUES {}", rows.join(", "))?;
+            },
+            InsertSource::Select(select_statement) => write!(f, " {}", select_statement)?,
+            InsertSource::DefaultValues => write!(f, " DEFAULT VALUES")?,
+        }
+
+        for upsert in self.upserts.iter() {
+            write!(f, " {}", upsert)?;
+        }
+
+        write!(f, "{}", format_returning(&self.returning))
     }
 }
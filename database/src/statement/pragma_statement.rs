@@ -0,0 +1,48 @@
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+use crate::parser::tokenizer::Token;
+
+#[derive(Debug, Clone)]
+pub struct PragmaStatement {
+    pub name: String,
+    // The pragma is read if there is no value. The words are lowercased
+    pub value: Option<String>,
+}
+
+impl PragmaStatement {
+    /// PRAGMA name [= value]
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("pragma")?;
+
+        let name = tokens.identifier()?;
+
+        if !tokens.consume(&Token::Equal) {
+            return Ok(PragmaStatement { name, value: None });
+        }
+
+        let value = match tokens.next_token() {
+            Some(Token::Word(word)) => word.to_lowercase(),
+            Some(Token::Integer(value)) => value.to_string(),
+            Some(Token::Text(value)) => value,
+            _ => return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The value of the pragma {} must be a word, a number or a string", name),
+            )),
+        };
+
+        Ok(PragmaStatement { name, value: Some(value) })
+    }
+
+    /// ON | TRUE | YES | 1 and OFF | FALSE | NO | 0
+    pub fn parse_flag(&self, value: &str) -> io::Result<bool> {
+        match value {
+            "on" | "true" | "yes" | "1" => Ok(true),
+            "off" | "false" | "no" | "0" => Ok(false),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The pragma {} expects ON or OFF, got {}", self.name, value),
+            )),
+        }
+    }
+}
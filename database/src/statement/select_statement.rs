@@ -1,7 +1,742 @@
-pub struct SelectStatement;
+use std::fmt;
+use std::io;
+use std::iter;
+
+use crate::parser::token_stream::TokenStream;
+use crate::parser::tokenizer::Token;
+use super::expression::Expression;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderingTerm {
+    pub expression: Expression,
+    pub descending: bool,
+    // None means the default: NULLs are the smallest values
+    pub nulls_first: Option<bool>,
+}
+
+impl OrderingTerm {
+    /// expression [ASC | DESC] [NULLS FIRST | NULLS LAST]
+    pub fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        let expression = Expression::parse(tokens)?;
+
+        let descending = if tokens.consume_keyword("desc") {
+            true
+        } else {
+            tokens.consume_keyword("asc");
+            false
+        };
+
+        let nulls_first = if tokens.consume_keyword("nulls") {
+            if tokens.consume_keyword("first") {
+                Some(true)
+            } else {
+                tokens.expect_keyword("last")?;
+                Some(false)
+            }
+        } else {
+            None
+        };
+
+        Ok(OrderingTerm {
+            expression,
+            descending,
+            nulls_first,
+        })
+    }
+
+    pub fn is_nulls_first(&self) -> bool {
+        self.nulls_first.unwrap_or(!self.descending)
+    }
+}
+
+impl fmt::Display for OrderingTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)?;
+
+        if self.descending {
+            write!(f, " DESC")?;
+        }
+
+        match self.nulls_first {
+            Some(true) => write!(f, " NULLS FIRST"),
+            Some(false) => write!(f, " NULLS LAST"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResultColumn {
+    // * is expanded to all the columns of all the tables
+    All,
+    // table.* is expanded to all the columns of the table
+    TableAll(String),
+    Expression {
+        expression: Expression,
+        alias: Option<String>,
+    },
+}
+
+impl ResultColumn {
+    /// * | table.* | expression [[AS] alias]
+    fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        if tokens.consume(&Token::Asterisk) {
+            return Ok(ResultColumn::All);
+        }
+
+        if tokens.peek_nth(1) == Some(&Token::Dot) && tokens.peek_nth(2) == Some(&Token::Asterisk) {
+            let table_name = tokens.identifier()?;
+            tokens.expect(&Token::Dot)?;
+            tokens.expect(&Token::Asterisk)?;
+
+            return Ok(ResultColumn::TableAll(table_name));
+        }
+
+        let expression = Expression::parse(tokens)?;
+
+        Ok(ResultColumn::Expression {
+            expression,
+            alias: parse_alias(tokens)?,
+        })
+    }
+}
+
+impl fmt::Display for ResultColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResultColumn::All => write!(f, "*"),
+            ResultColumn::TableAll(table_name) => write!(f, "{}.*", TokenStream::quote_identifier(table_name)),
+            ResultColumn::Expression { expression, alias: Some(alias) } => write!(f, "{} AS {}", expression, TokenStream::quote_identifier(alias)),
+            ResultColumn::Expression { expression, alias: None } => write!(f, "{}", expression),
+        }
+    }
+}
+
+/// [RETURNING result_column, ...], the columns of the rows changed by INSERT, UPDATE or DELETE
+pub fn parse_returning(tokens: &mut TokenStream) -> io::Result<Vec<ResultColumn>> {
+    if !tokens.consume_keyword("returning") {
+        return Ok(Vec::new());
+    }
+
+    let mut columns = vec![ResultColumn::parse(tokens)?];
+
+    while tokens.consume(&Token::Comma) {
+        columns.push(ResultColumn::parse(tokens)?);
+    }
+
+    Ok(columns)
+}
+
+pub fn format_returning(columns: &[ResultColumn]) -> String {
+    if columns.is_empty() {
+        return String::new();
+    }
+
+    let columns: Vec<String> = columns.iter().map(ResultColumn::to_string).collect();
+
+    format!(" RETURNING {}", columns.join(", "))
+}
+
+/// [[AS] alias], AS is optional before the alias
+fn parse_alias(tokens: &mut TokenStream) -> io::Result<Option<String>> {
+    let has_alias = tokens.consume_keyword("as") || tokens.is_identifier();
+
+    if has_alias {
+        return Ok(Some(tokens.identifier()?));
+    }
+
+    Ok(None)
+}
+
+/// The name of the derived table without an alias, it can't be used to qualify the columns
+pub const DERIVED_TABLE_NAME: &str = "(subquery)";
+
+/// The name of the table of SELECT without FROM, the table has a single row without columns
+pub const NO_TABLE_NAME: &str = "(no table)";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableReference {
+    pub table_name: String,
+    pub alias: Option<String>,
+    // The rows of the derived table are produced by the subquery
+    pub subquery: Option<Box<SelectStatement>>,
+}
+
+impl TableReference {
+    /// table [[AS] alias] | (select_statement) [[AS] alias]
+    fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        if tokens.consume(&Token::LeftParen) {
+            let subquery = SelectStatement::new(tokens)?;
+            tokens.expect(&Token::RightParen)?;
+
+            return Ok(TableReference {
+                table_name: DERIVED_TABLE_NAME.to_string(),
+                alias: parse_alias(tokens)?,
+                subquery: Some(Box::new(subquery)),
+            });
+        }
+
+        Ok(TableReference {
+            table_name: tokens.identifier()?,
+            alias: parse_alias(tokens)?,
+            subquery: None,
+        })
+    }
+
+    fn no_table() -> Self {
+        TableReference {
+            table_name: NO_TABLE_NAME.to_string(),
+            alias: None,
+            subquery: None,
+        }
+    }
+
+    /// The method returns the name the columns of the table are qualified with
+    pub fn get_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.table_name)
+    }
+
+    /// The method checks whether the table is the table of SELECT without FROM
+    pub fn is_no_table(&self) -> bool {
+        self.subquery.is_none() && self.table_name == NO_TABLE_NAME
+    }
+
+    /// The method checks whether the rows of the table are produced by the query, not read from the database
+    pub fn is_derived(&self) -> bool {
+        self.subquery.is_some() || self.is_no_table()
+    }
+}
+
+impl fmt::Display for TableReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.subquery {
+            Some(subquery) => write!(f, "({})", subquery)?,
+            None => write!(f, "{}", TokenStream::quote_identifier(&self.table_name))?,
+        }
+
+        match &self.alias {
+            Some(alias) => write!(f, " AS {}", TokenStream::quote_identifier(alias)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JoinType {
+    Inner,
+    Left,
+    Cross,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JoinConstraint {
+    On(Expression),
+    // The columns with the same names in both tables are equal
+    Using(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub join_type: JoinType,
+    pub table: TableReference,
+    pub constraint: Option<JoinConstraint>,
+}
+
+impl Join {
+    /// The method parses the join operator and the joined table if there is one:
+    /// , table | [INNER | LEFT [OUTER] | CROSS] JOIN table [ON expression | USING (column, ...)]
+    fn parse(tokens: &mut TokenStream) -> io::Result<Option<Self>> {
+        if tokens.consume(&Token::Comma) {
+            return Ok(Some(Join {
+                join_type: JoinType::Cross,
+                table: TableReference::parse(tokens)?,
+                constraint: None,
+            }));
+        }
+
+        let join_type = if tokens.consume_keyword("inner") {
+            tokens.expect_keyword("join")?;
+            JoinType::Inner
+        } else if tokens.consume_keyword("left") {
+            tokens.consume_keyword("outer");
+            tokens.expect_keyword("join")?;
+            JoinType::Left
+        } else if tokens.consume_keyword("cross") {
+            tokens.expect_keyword("join")?;
+            JoinType::Cross
+        } else if tokens.consume_keyword("join") {
+            JoinType::Inner
+        } else {
+            return Ok(None);
+        };
+
+        let table = TableReference::parse(tokens)?;
+
+        let constraint = if join_type == JoinType::Cross {
+            None
+        } else if tokens.consume_keyword("on") {
+            Some(JoinConstraint::On(Expression::parse(tokens)?))
+        } else if tokens.consume_keyword("using") {
+            Some(JoinConstraint::Using(tokens.identifier_list()?))
+        } else {
+            None
+        };
+
+        Ok(Some(Join {
+            join_type,
+            table,
+            constraint,
+        }))
+    }
+}
+
+impl fmt::Display for Join {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.join_type {
+            JoinType::Inner => write!(f, "JOIN {}", self.table)?,
+            JoinType::Left => write!(f, "LEFT JOIN {}", self.table)?,
+            JoinType::Cross => write!(f, "CROSS JOIN {}", self.table)?,
+        }
+
+        match &self.constraint {
+            Some(JoinConstraint::On(expression)) => write!(f, " ON {}", expression),
+            Some(JoinConstraint::Using(column_names)) => write!(f, " USING ({})", TokenStream::quote_identifiers(column_names)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommonTableExpression {
+    pub table_name: String,
+    // The names of the columns, the names of the result columns are used if there are none
+    pub column_names: Vec<String>,
+    // The last select of WITH RECURSIVE could read the rows added by the previous step
+    pub select_statement: SelectStatement,
+}
+
+impl CommonTableExpression {
+    /// table [(column, ...)] AS (select_statement)
+    fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        let table_name = tokens.identifier()?;
+
+        let column_names = match tokens.peek() {
+            Some(Token::LeftParen) => tokens.identifier_list()?,
+            _ => Vec::new(),
+        };
+
+        tokens.expect_keyword("as")?;
+        tokens.expect(&Token::LeftParen)?;
+
+        let select_statement = SelectStatement::new(tokens)?;
+        tokens.expect(&Token::RightParen)?;
+
+        Ok(CommonTableExpression {
+            table_name,
+            column_names,
+            select_statement,
+        })
+    }
+
+    /// The method returns the last select of the compound select if it reads the rows of the table itself
+    pub fn get_recursive_select(&self) -> Option<(CompoundOperator, &SelectStatement)> {
+        let (operator, recursive_select) = self.select_statement.compound.last()?;
+
+        let is_recursive = iter::once(&recursive_select.from)
+            .chain(recursive_select.joins.iter().map(|join| &join.table))
+            .any(|table_reference| table_reference.subquery.is_none() && table_reference.table_name == self.table_name);
+
+        is_recursive.then_some((*operator, recursive_select))
+    }
+}
+
+impl fmt::Display for CommonTableExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", TokenStream::quote_identifier(&self.table_name))?;
+
+        if !self.column_names.is_empty() {
+            write!(f, "({})", TokenStream::quote_identifiers(&self.column_names))?;
+        }
+
+        write!(f, " AS ({})", self.select_statement)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithClause {
+    // The tables could read their own rows
+    pub recursive: bool,
+    pub tables: Vec<CommonTableExpression>,
+}
+
+impl WithClause {
+    /// WITH [RECURSIVE] common_table_expression, ...
+    fn parse(tokens: &mut TokenStream) -> io::Result<Option<Self>> {
+        if !tokens.consume_keyword("with") {
+            return Ok(None);
+        }
+
+        let recursive = tokens.consume_keyword("recursive");
+        let mut tables = vec![CommonTableExpression::parse(tokens)?];
+
+        while tokens.consume(&Token::Comma) {
+            tables.push(CommonTableExpression::parse(tokens)?);
+        }
+
+        Ok(Some(WithClause { recursive, tables }))
+    }
+}
+
+impl fmt::Display for WithClause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.recursive {
+            true => write!(f, "WITH RECURSIVE {}", join_items(&self.tables)),
+            false => write!(f, "WITH {}", join_items(&self.tables)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompoundOperator {
+    Union,
+    UnionAll,
+    Intersect,
+    Except,
+}
+
+impl CompoundOperator {
+    /// UNION | UNION ALL | INTERSECT | EXCEPT
+    fn parse(tokens: &mut TokenStream) -> Option<Self> {
+        if tokens.consume_keyword("union") {
+            return match tokens.consume_keyword("all") {
+                true => Some(CompoundOperator::UnionAll),
+                false => Some(CompoundOperator::Union),
+            };
+        }
+
+        if tokens.consume_keyword("intersect") {
+            return Some(CompoundOperator::Intersect);
+        }
+
+        if tokens.consume_keyword("except") {
+            return Some(CompoundOperator::Except);
+        }
+
+        None
+    }
+}
+
+impl fmt::Display for CompoundOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompoundOperator::Union => write!(f, "UNION"),
+            CompoundOperator::UnionAll => write!(f, "UNION ALL"),
+            CompoundOperator::Intersect => write!(f, "INTERSECT"),
+            CompoundOperator::Except => write!(f, "EXCEPT"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectStatement {
+    pub with_clause: Option<WithClause>,
+    pub columns: Vec<ResultColumn>,
+    pub from: TableReference,
+    pub joins: Vec<Join>,
+    pub where_clause: Option<Expression>,
+    pub group_by: Vec<Expression>,
+    pub having: Option<Expression>,
+    // The selects combined with the rows of this select from left to right,
+    // ORDER BY, LIMIT and OFFSET are applied to the combined rows
+    pub compound: Vec<(CompoundOperator, SelectStatement)>,
+    pub order_by: Vec<OrderingTerm>,
+    pub limit: Option<Expression>,
+    pub offset: Option<Expression>,
+}
 
 impl SelectStatement {
-    pub fn new(_: &Vec<&str>) -> Self {
-        SelectStatement
+    /// [with_clause] select_core [compound_operator select_core ...]
+    /// [ORDER BY ordering_term, ...] [LIMIT expression [OFFSET expression]]
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        let with_clause = WithClause::parse(tokens)?;
+
+        let mut select_statement = Self::parse_core(tokens)?;
+        select_statement.with_clause = with_clause;
+
+        while let Some(operator) = CompoundOperator::parse(tokens) {
+            select_statement.compound.push((operator, Self::parse_core(tokens)?));
+        }
+
+        if tokens.consume_keyword("order") {
+            tokens.expect_keyword("by")?;
+
+            loop {
+                select_statement.order_by.push(OrderingTerm::parse(tokens)?);
+
+                if !tokens.consume(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+
+        if tokens.consume_keyword("limit") {
+            select_statement.limit = Some(Expression::parse(tokens)?);
+
+            if tokens.consume_keyword("offset") {
+                select_statement.offset = Some(Expression::parse(tokens)?);
+            } else if tokens.consume(&Token::Comma) {
+                // LIMIT offset, count
+                select_statement.offset = select_statement.limit.replace(Expression::parse(tokens)?);
+            }
+        }
+
+        Ok(select_statement)
+    }
+
+    /// SELECT result_column, ... [FROM table [join ...]] [WHERE expression] [GROUP BY expression, ... [HAVING expression]],
+    /// the columns of SELECT without FROM are evaluated once
+    fn parse_core(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("select")?;
+
+        let mut columns = vec![ResultColumn::parse(tokens)?];
+
+        while tokens.consume(&Token::Comma) {
+            columns.push(ResultColumn::parse(tokens)?);
+        }
+
+        let mut joins = Vec::new();
+
+        let from = if tokens.consume_keyword("from") {
+            let from = TableReference::parse(tokens)?;
+
+            while let Some(join) = Join::parse(tokens)? {
+                joins.push(join);
+            }
+
+            from
+        } else if columns.iter().any(|column| !matches!(column, ResultColumn::Expression { .. })) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "No tables specified",
+            ));
+        } else {
+            TableReference::no_table()
+        };
+
+        let where_clause = if tokens.consume_keyword("where") {
+            Some(Expression::parse(tokens)?)
+        } else {
+            None
+        };
+
+        let mut group_by = Vec::new();
+
+        if tokens.consume_keyword("group") {
+            tokens.expect_keyword("by")?;
+
+            loop {
+                group_by.push(Expression::parse(tokens)?);
+
+                if !tokens.consume(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+
+        let having = if tokens.consume_keyword("having") {
+            Some(Expression::parse(tokens)?)
+        } else {
+            None
+        };
+
+        Ok(SelectStatement {
+            with_clause: None,
+            columns,
+            from,
+            joins,
+            where_clause,
+            group_by,
+            having,
+            compound: Vec::new(),
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+        })
+    }
+
+    /// SELECT columns FROM table [WHERE expression], the query which finds the rows changed by UPDATE or DELETE
+    pub fn from_table(table_name: &str, columns: Vec<ResultColumn>, where_clause: Option<Expression>) -> Self {
+        SelectStatement {
+            with_clause: None,
+            columns,
+            from: TableReference {
+                table_name: table_name.to_string(),
+                alias: None,
+                subquery: None,
+            },
+            joins: Vec::new(),
+            where_clause,
+            group_by: Vec::new(),
+            having: None,
+            compound: Vec::new(),
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+        }
+    }
+
+    /// The method returns the first select of the compound select without WITH, ORDER BY and LIMIT
+    pub fn get_first_select(&self) -> Self {
+        SelectStatement {
+            with_clause: None,
+            compound: Vec::new(),
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+            ..self.clone()
+        }
+    }
+
+    /// SELECT * FROM (compound_select) [ORDER BY ...] [LIMIT ...], the query which orders
+    /// and limits the combined rows of the compound select
+    pub fn get_compound_query(&self) -> Self {
+        let compound_select = SelectStatement {
+            with_clause: None,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+            ..self.clone()
+        };
+
+        SelectStatement {
+            with_clause: None,
+            columns: vec![ResultColumn::All],
+            from: TableReference {
+                table_name: DERIVED_TABLE_NAME.to_string(),
+                alias: None,
+                subquery: Some(Box::new(compound_select)),
+            },
+            joins: Vec::new(),
+            where_clause: None,
+            group_by: Vec::new(),
+            having: None,
+            compound: Vec::new(),
+            order_by: self.order_by.clone(),
+            limit: self.limit.clone(),
+            offset: self.offset.clone(),
+        }
+    }
+
+    /// The method returns the expressions of this select, the expressions of WITH
+    /// and of the other selects of the compound select are not included
+    pub fn get_expressions_mut(&mut self) -> Vec<&mut Expression> {
+        let columns = self.columns.iter_mut().filter_map(|column| match column {
+            ResultColumn::Expression { expression, .. } => Some(expression),
+            _ => None,
+        });
+
+        let join_constraints = self.joins.iter_mut().filter_map(|join| match &mut join.constraint {
+            Some(JoinConstraint::On(expression)) => Some(expression),
+            _ => None,
+        });
+
+        columns
+            .chain(join_constraints)
+            .chain(self.where_clause.iter_mut())
+            .chain(self.group_by.iter_mut())
+            .chain(self.having.iter_mut())
+            .chain(self.order_by.iter_mut().map(|ordering_term| &mut ordering_term.expression))
+            .chain(self.limit.iter_mut())
+            .chain(self.offset.iter_mut())
+            .collect()
+    }
+
+    /// The method checks whether the statement starts at the token
+    pub fn is_nth_select(tokens: &TokenStream, offset: usize) -> bool {
+        tokens.is_nth_keyword(offset, "select") || tokens.is_nth_keyword(offset, "with")
+    }
+
+    /// The method checks whether the rows are combined into groups
+    pub fn is_aggregate(&self) -> io::Result<bool> {
+        if !self.group_by.is_empty() {
+            return Ok(true);
+        }
+
+        for column in self.columns.iter() {
+            if let ResultColumn::Expression { expression, .. } = column {
+                if !expression.get_aggregates()?.is_empty() {
+                    return Ok(true);
+                }
+            }
+        }
+
+        for ordering_term in self.order_by.iter() {
+            if !ordering_term.expression.get_aggregates()?.is_empty() {
+                return Ok(true);
+            }
+        }
+
+        match &self.having {
+            Some(having) if !having.get_aggregates()?.is_empty() => Ok(true),
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "A GROUP BY clause is required before HAVING",
+            )),
+            None => Ok(false),
+        }
+    }
+}
+
+/// The method joins the displayed items with commas
+fn join_items<T: fmt::Display>(items: &[T]) -> String {
+    items.iter().map(|item| item.to_string()).collect::<Vec<String>>().join(", ")
+}
+
+impl fmt::Display for SelectStatement {
+    /// The statement is written back as SQL, the subqueries are stored within the expressions
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(with_clause) = &self.with_clause {
+            write!(f, "{} ", with_clause)?;
+        }
+
+        write!(f, "SELECT {}", join_items(&self.columns))?;
+
+        if !self.from.is_no_table() {
+            write!(f, " FROM {}", self.from)?;
+        }
+
+        for join in self.joins.iter() {
+            write!(f, " {}", join)?;
+        }
+
+        if let Some(where_clause) = &self.where_clause {
+            write!(f, " WHERE {}", where_clause)?;
+        }
+
+        if !self.group_by.is_empty() {
+            write!(f, " GROUP BY {}", join_items(&self.group_by))?;
+        }
+
+        if let Some(having) = &self.having {
+            write!(f, " HAVING {}", having)?;
+        }
+
+        for (operator, select_statement) in self.compound.iter() {
+            write!(f, " {} {}", operator, select_statement)?;
+        }
+
+        if !self.order_by.is_empty() {
+            write!(f, " ORDER BY {}", join_items(&self.order_by))?;
+        }
+
+        if let Some(limit) = &self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+
+        if let Some(offset) = &self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+
+        Ok(())
     }
 }
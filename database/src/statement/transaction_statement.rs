@@ -0,0 +1,54 @@
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+
+#[derive(Debug, Clone)]
+pub enum TransactionStatement {
+    Begin,
+    Commit,
+    // The whole transaction is rolled back if there is no savepoint
+    Rollback(Option<String>),
+    Savepoint(String),
+    Release(String),
+}
+
+impl TransactionStatement {
+    /// BEGIN [DEFERRED | IMMEDIATE | EXCLUSIVE] [TRANSACTION] | {COMMIT | END} [TRANSACTION]
+    /// | ROLLBACK [TRANSACTION] [TO [SAVEPOINT] name] | SAVEPOINT name | RELEASE [SAVEPOINT] name
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        if tokens.consume_keyword("begin") {
+            // The database has the only connection, so the locking modes make no difference
+            let _ = tokens.consume_keyword("deferred") || tokens.consume_keyword("immediate") || tokens.consume_keyword("exclusive");
+            tokens.consume_keyword("transaction");
+
+            return Ok(TransactionStatement::Begin);
+        }
+
+        if tokens.consume_keyword("commit") || tokens.consume_keyword("end") {
+            tokens.consume_keyword("transaction");
+
+            return Ok(TransactionStatement::Commit);
+        }
+
+        if tokens.consume_keyword("rollback") {
+            tokens.consume_keyword("transaction");
+
+            if !tokens.consume_keyword("to") {
+                return Ok(TransactionStatement::Rollback(None));
+            }
+
+            tokens.consume_keyword("savepoint");
+
+            return Ok(TransactionStatement::Rollback(Some(tokens.identifier()?)));
+        }
+
+        if tokens.consume_keyword("savepoint") {
+            return Ok(TransactionStatement::Savepoint(tokens.identifier()?));
+        }
+
+        tokens.expect_keyword("release")?;
+        tokens.consume_keyword("savepoint");
+
+        Ok(TransactionStatement::Release(tokens.identifier()?))
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+use crate::parser::tokenizer::Token;
+use super::expression::Expression;
+use super::select_statement::{format_returning, parse_returning, ResultColumn, SelectStatement};
+
+#[derive(Debug, Clone)]
+pub struct UpdateStatement {
+    pub table_name: String,
+    pub assignments: Vec<(String, Expression)>,
+    pub where_clause: Option<Expression>,
+    pub returning: Vec<ResultColumn>,
+}
+
+impl UpdateStatement {
+    /// UPDATE table SET column = expression, ... [WHERE expression] [RETURNING result_column, ...]
+    pub fn new(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect_keyword("update")?;
+
+        let table_name = tokens.identifier()?;
+
+        let assignments = parse_assignments(tokens)?;
+
+        let where_clause = if tokens.consume_keyword("where") {
+            Some(Expression::parse(tokens)?)
+        } else {
+            None
+        };
+
+        Ok(UpdateStatement {
+            table_name,
+            assignments,
+            where_clause,
+            returning: parse_returning(tokens)?,
+        })
+    }
+
+    /// The SELECT which finds the updated rows, the new values of the assigned columns
+    /// follow the values of the row
+    pub fn get_select_statement(&self) -> SelectStatement {
+        let mut columns = vec![ResultColumn::All];
+
+        columns.extend(self.assignments.iter().map(|(_, expression)| ResultColumn::Expression {
+            expression: expression.clone(),
+            alias: None,
+        }));
+
+        SelectStatement::from_table(&self.table_name, columns, self.where_clause.clone())
+    }
+}
+
+/// SET column = expression, ...
+pub fn parse_assignments(tokens: &mut TokenStream) -> io::Result<Vec<(String, Expression)>> {
+    tokens.expect_keyword("set")?;
+
+    let mut assignments = Vec::new();
+
+    loop {
+        let column_name = tokens.identifier()?;
+        tokens.expect(&Token::Equal)?;
+        assignments.push((column_name, Expression::parse(tokens)?));
+
+        if !tokens.consume(&Token::Comma) {
+            return Ok(assignments);
+        }
+    }
+}
+
+pub fn format_assignments(assignments: &[(String, Expression)]) -> String {
+    assignments
+        .iter()
+        .map(|(column_name, expression)| format!("{} = {}", TokenStream::quote_identifier(column_name), expression))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+impl fmt::Display for UpdateStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UPDATE {} SET {}", TokenStream::quote_identifier(&self.table_name), format_assignments(&self.assignments))?;
+
+        if let Some(where_clause) = &self.where_clause {
+            write!(f, " WHERE {}", where_clause)?;
+        }
+
+        write!(f, "{}", format_returning(&self.returning))
+    }
+}
@@ -0,0 +1,316 @@
+use std::fmt;
+use std::io;
+
+use crate::parser::token_stream::TokenStream;
+use crate::parser::tokenizer::Token;
+use super::expression::{AggregateFunction, Expression};
+use super::select_statement::OrderingTerm;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowFunction {
+    RowNumber,
+    Rank,
+    DenseRank,
+    Lag,
+    Lead,
+    FirstValue,
+    LastValue,
+    // The aggregate function computed over the frame of every row
+    Aggregate(AggregateFunction),
+}
+
+impl WindowFunction {
+    /// The method finds the function which could be used only as a window function
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "row_number" => Some(WindowFunction::RowNumber),
+            "rank" => Some(WindowFunction::Rank),
+            "dense_rank" => Some(WindowFunction::DenseRank),
+            "lag" => Some(WindowFunction::Lag),
+            "lead" => Some(WindowFunction::Lead),
+            "first_value" => Some(WindowFunction::FirstValue),
+            "last_value" => Some(WindowFunction::LastValue),
+            _ => None,
+        }
+    }
+
+    pub fn check_num_arguments(&self, num_arguments: usize) -> io::Result<()> {
+        let is_valid = match self {
+            WindowFunction::RowNumber | WindowFunction::Rank | WindowFunction::DenseRank => num_arguments == 0,
+            // LAG(expression [, offset [, default]])
+            WindowFunction::Lag | WindowFunction::Lead => (1..=3).contains(&num_arguments),
+            WindowFunction::FirstValue | WindowFunction::LastValue => num_arguments == 1,
+            WindowFunction::Aggregate(function) => return function.check_num_arguments(num_arguments),
+        };
+
+        if !is_valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Wrong number of arguments to function {}()", self),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for WindowFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            WindowFunction::RowNumber => "row_number",
+            WindowFunction::Rank => "rank",
+            WindowFunction::DenseRank => "dense_rank",
+            WindowFunction::Lag => "lag",
+            WindowFunction::Lead => "lead",
+            WindowFunction::FirstValue => "first_value",
+            WindowFunction::LastValue => "last_value",
+            WindowFunction::Aggregate(function) => return write!(f, "{}", function),
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameUnits {
+    // The offsets are the numbers of the rows
+    Rows,
+    // The offsets are the differences of the ORDER BY values, the peers are always in the frame
+    Range,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameBound {
+    UnboundedPreceding,
+    Preceding(Expression),
+    CurrentRow,
+    Following(Expression),
+    UnboundedFollowing,
+}
+
+impl FrameBound {
+    /// UNBOUNDED PRECEDING | expression PRECEDING | CURRENT ROW | expression FOLLOWING | UNBOUNDED FOLLOWING
+    fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        if tokens.consume_keyword("unbounded") {
+            if tokens.consume_keyword("preceding") {
+                return Ok(FrameBound::UnboundedPreceding);
+            }
+
+            tokens.expect_keyword("following")?;
+            return Ok(FrameBound::UnboundedFollowing);
+        }
+
+        if tokens.consume_keyword("current") {
+            tokens.expect_keyword("row")?;
+            return Ok(FrameBound::CurrentRow);
+        }
+
+        let offset = Expression::parse(tokens)?;
+
+        if tokens.consume_keyword("preceding") {
+            return Ok(FrameBound::Preceding(offset));
+        }
+
+        tokens.expect_keyword("following")?;
+        Ok(FrameBound::Following(offset))
+    }
+
+    /// The bounds could be only in this order within the frame
+    fn get_position(&self) -> usize {
+        match self {
+            FrameBound::UnboundedPreceding => 0,
+            FrameBound::Preceding(_) => 1,
+            FrameBound::CurrentRow => 2,
+            FrameBound::Following(_) => 3,
+            FrameBound::UnboundedFollowing => 4,
+        }
+    }
+
+    pub fn get_offset(&self) -> Option<&Expression> {
+        match self {
+            FrameBound::Preceding(offset) | FrameBound::Following(offset) => Some(offset),
+            _ => None,
+        }
+    }
+
+    fn get_offset_mut(&mut self) -> Option<&mut Expression> {
+        match self {
+            FrameBound::Preceding(offset) | FrameBound::Following(offset) => Some(offset),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FrameBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameBound::UnboundedPreceding => write!(f, "UNBOUNDED PRECEDING"),
+            FrameBound::Preceding(offset) => write!(f, "{} PRECEDING", offset),
+            FrameBound::CurrentRow => write!(f, "CURRENT ROW"),
+            FrameBound::Following(offset) => write!(f, "{} FOLLOWING", offset),
+            FrameBound::UnboundedFollowing => write!(f, "UNBOUNDED FOLLOWING"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowFrame {
+    pub units: FrameUnits,
+    pub start: FrameBound,
+    pub end: FrameBound,
+}
+
+impl WindowFrame {
+    /// The frame of the window without the frame clause: the rows from the start
+    /// of the partition to the last peer of the current row
+    pub fn default_frame() -> Self {
+        WindowFrame {
+            units: FrameUnits::Range,
+            start: FrameBound::UnboundedPreceding,
+            end: FrameBound::CurrentRow,
+        }
+    }
+
+    /// {ROWS | RANGE} {BETWEEN frame_bound AND frame_bound | frame_bound}, the frame
+    /// ends with the current row if there is one bound only
+    fn parse(tokens: &mut TokenStream) -> io::Result<Option<Self>> {
+        let units = if tokens.consume_keyword("rows") {
+            FrameUnits::Rows
+        } else if tokens.consume_keyword("range") {
+            FrameUnits::Range
+        } else {
+            return Ok(None);
+        };
+
+        let (start, end) = if tokens.consume_keyword("between") {
+            let start = FrameBound::parse(tokens)?;
+            tokens.expect_keyword("and")?;
+
+            (start, FrameBound::parse(tokens)?)
+        } else {
+            (FrameBound::parse(tokens)?, FrameBound::CurrentRow)
+        };
+
+        let is_supported = start != FrameBound::UnboundedFollowing
+            && end != FrameBound::UnboundedPreceding
+            && start.get_position() <= end.get_position();
+
+        if !is_supported {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unsupported frame specification: BETWEEN {} AND {}", start, end),
+            ));
+        }
+
+        Ok(Some(WindowFrame { units, start, end }))
+    }
+}
+
+impl fmt::Display for WindowFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.units {
+            FrameUnits::Rows => write!(f, "ROWS BETWEEN {} AND {}", self.start, self.end),
+            FrameUnits::Range => write!(f, "RANGE BETWEEN {} AND {}", self.start, self.end),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowDefinition {
+    pub partition_by: Vec<Expression>,
+    pub order_by: Vec<OrderingTerm>,
+    pub frame: Option<WindowFrame>,
+}
+
+impl WindowDefinition {
+    /// ([PARTITION BY expression, ...] [ORDER BY ordering_term, ...] [frame])
+    pub fn parse(tokens: &mut TokenStream) -> io::Result<Self> {
+        tokens.expect(&Token::LeftParen)?;
+
+        let mut partition_by = Vec::new();
+
+        if tokens.consume_keyword("partition") {
+            tokens.expect_keyword("by")?;
+
+            loop {
+                partition_by.push(Expression::parse(tokens)?);
+
+                if !tokens.consume(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+
+        let mut order_by = Vec::new();
+
+        if tokens.consume_keyword("order") {
+            tokens.expect_keyword("by")?;
+
+            loop {
+                order_by.push(OrderingTerm::parse(tokens)?);
+
+                if !tokens.consume(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+
+        let frame = WindowFrame::parse(tokens)?;
+        tokens.expect(&Token::RightParen)?;
+
+        Ok(WindowDefinition {
+            partition_by,
+            order_by,
+            frame,
+        })
+    }
+
+    /// The expressions evaluated against the rows of the partition
+    pub fn get_expressions(&self) -> Vec<&Expression> {
+        let frame_offsets = self.frame
+            .iter()
+            .flat_map(|frame| [frame.start.get_offset(), frame.end.get_offset()])
+            .flatten();
+
+        self.partition_by
+            .iter()
+            .chain(self.order_by.iter().map(|ordering_term| &ordering_term.expression))
+            .chain(frame_offsets)
+            .collect()
+    }
+
+    pub fn get_expressions_mut(&mut self) -> Vec<&mut Expression> {
+        let frame_offsets = self.frame
+            .iter_mut()
+            .flat_map(|frame| [frame.start.get_offset_mut(), frame.end.get_offset_mut()])
+            .flatten();
+
+        self.partition_by
+            .iter_mut()
+            .chain(self.order_by.iter_mut().map(|ordering_term| &mut ordering_term.expression))
+            .chain(frame_offsets)
+            .collect()
+    }
+}
+
+impl fmt::Display for WindowDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut clauses = Vec::new();
+
+        if !self.partition_by.is_empty() {
+            let partition_by: Vec<String> = self.partition_by.iter().map(Expression::to_string).collect();
+            clauses.push(format!("PARTITION BY {}", partition_by.join(", ")));
+        }
+
+        if !self.order_by.is_empty() {
+            let order_by: Vec<String> = self.order_by.iter().map(OrderingTerm::to_string).collect();
+            clauses.push(format!("ORDER BY {}", order_by.join(", ")));
+        }
+
+        if let Some(frame) = &self.frame {
+            clauses.push(frame.to_string());
+        }
+
+        write!(f, "({})", clauses.join(" "))
+    }
+}
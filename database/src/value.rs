@@ -0,0 +1,193 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+impl Value {
+    /// The method converts the value to a boolean the same way SQLite does: non-zero numbers
+    /// are true, texts are converted to reals using their longest numeric prefix first
+    pub fn is_true(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Integer(value) => *value != 0,
+            Value::Real(value) => *value != 0.0,
+            Value::Text(value) => Self::get_numeric_prefix(value).parse::<f64>().is_ok_and(|value| value != 0.0),
+        }
+    }
+
+    /// The method returns the longest prefix of the text which looks like a number:
+    /// [sign] digits [. digits] [e [sign] digits]
+    pub fn get_numeric_prefix(text: &str) -> &str {
+        let text = text.trim_start();
+        let bytes = text.as_bytes();
+
+        let skip_digits = |mut position: usize| {
+            while position < bytes.len() && bytes[position].is_ascii_digit() {
+                position += 1;
+            }
+
+            position
+        };
+
+        let mut length = 0;
+
+        if matches!(bytes.first(), Some(b'+' | b'-')) {
+            length += 1;
+        }
+
+        length = skip_digits(length);
+
+        if bytes.get(length) == Some(&b'.') {
+            length = skip_digits(length + 1);
+        }
+
+        if matches!(bytes.get(length), Some(b'e' | b'E')) {
+            let exponent_start = if matches!(bytes.get(length + 1), Some(b'+' | b'-')) { length + 2 } else { length + 1 };
+            let exponent_end = skip_digits(exponent_start);
+
+            if exponent_end > exponent_start {
+                length = exponent_end;
+            }
+        }
+
+        &text[..length]
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// The method converts the value to a number, the texts which don't look like numbers are zeros
+    pub fn to_numeric(&self) -> Value {
+        match self {
+            Value::Text(text) => {
+                let text = text.trim();
+
+                if let Ok(integer) = text.parse::<i64>() {
+                    Value::Integer(integer)
+                } else if let Ok(real) = text.parse::<f64>() {
+                    Value::Real(real)
+                } else {
+                    Value::Integer(0)
+                }
+            },
+            _ => self.clone(),
+        }
+    }
+
+    /// The method returns the value as a float, it's used for the numbers only
+    pub fn to_real(&self) -> f64 {
+        match self.to_numeric() {
+            Value::Integer(value) => value as f64,
+            Value::Real(value) => value,
+            _ => 0.0,
+        }
+    }
+
+    /// The method returns the value as an SQL literal
+    pub fn to_sql(&self) -> String {
+        match self {
+            Value::Null => "NULL".to_string(),
+            Value::Integer(value) => value.to_string(),
+            Value::Real(_) => self.to_string(),
+            Value::Text(value) => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+
+    /// NULLs are sorted first, then numbers and then texts
+    fn get_type_order(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Integer(_) | Value::Real(_) => 1,
+            Value::Text(_) => 2,
+        }
+    }
+
+    /// The real number which has an exact integer value is returned as the integer
+    fn to_exact_integer(value: f64) -> Option<i64> {
+        if value.fract() == 0.0 && value >= i64::MIN as f64 && value < i64::MAX as f64 {
+            return Some(value as i64);
+        }
+
+        None
+    }
+
+    /// The negative zero is equal to the zero, as it's equal to the integer zero
+    fn normalize_zero(value: f64) -> f64 {
+        if value == 0.0 { 0.0 } else { value }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Integer(value as i64)
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Integer(left), Value::Integer(right)) => left.cmp(right),
+            (Value::Real(left), Value::Real(right)) => Self::normalize_zero(*left).total_cmp(&Self::normalize_zero(*right)),
+            (Value::Integer(left), Value::Real(right)) => match Self::to_exact_integer(*right) {
+                Some(right) => left.cmp(&right),
+                None => (*left as f64).total_cmp(right),
+            },
+            (Value::Real(_), Value::Integer(_)) => other.cmp(self).reverse(),
+            (Value::Text(left), Value::Text(right)) => left.cmp(right),
+            _ => self.get_type_order().cmp(&other.get_type_order()),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    /// The equal numbers get the same hash whether they are integers or reals
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_type_order().hash(state);
+
+        match self {
+            Value::Null => {},
+            Value::Integer(value) => value.hash(state),
+            Value::Real(value) => match Self::to_exact_integer(*value) {
+                Some(integer) => integer.hash(state),
+                None => value.to_bits().hash(state),
+            },
+            Value::Text(value) => value.hash(state),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::Integer(value) => write!(f, "{}", value),
+            // The reals are always written with the fractional part, so they could be told from the integers
+            Value::Real(value) if value.is_finite() && value.fract() == 0.0 && value.abs() < 1e15 => write!(f, "{:.1}", value),
+            Value::Real(value) if value.abs() >= 1e15 => write!(f, "{:e}", value),
+            Value::Real(value) => write!(f, "{}", value),
+            Value::Text(value) => write!(f, "{}", value),
+        }
+    }
+}